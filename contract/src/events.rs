@@ -0,0 +1,385 @@
+use std::fmt;
+
+use crate::*;
+
+/// Standard name and version used for all events emitted by the contract
+pub const KEYPOM_STANDARD_NAME: &str = "keypom";
+pub const KEYPOM_STANDARD_VERSION: &str = "1.0.0";
+
+/// Enum that represents the data type of the EventLog.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[serde(crate = "near_sdk::serde")]
+#[non_exhaustive]
+pub enum EventLogVariant {
+    DropCreation(Vec<DropCreationLog>),
+    DropUpdate(Vec<DropUpdateLog>),
+    AdminRefundDrop(Vec<AdminRefundDropLog>),
+    AssetSenderUpdate(Vec<AssetSenderUpdateLog>),
+    AccountCreationCostUpdate(Vec<AccountCreationCostUpdateLog>),
+    DropComplete(Vec<DropCompleteLog>),
+    PartnerFeeShare(Vec<PartnerFeeShareLog>),
+    Claim(Vec<ClaimLog>),
+    AdminChange(Vec<AdminChangeLog>),
+    BalanceLow(Vec<BalanceLowLog>),
+    SolvencyWarning(Vec<SolvencyWarningLog>),
+    FcReceiverReputationUpdate(Vec<FcReceiverReputationUpdateLog>),
+    AdminForceRefund(Vec<AdminForceRefundLog>),
+    KeyDeletion(Vec<KeyDeletionLog>),
+    DropOwnershipTransfer(Vec<DropOwnershipTransferLog>),
+    WrappedDeposit(Vec<WrappedDepositLog>),
+    AccessKeyCapacity(Vec<AccessKeyCapacityLog>),
+    DuplicateClaim(Vec<DuplicateClaimLog>),
+}
+
+/// Interface to capture data about an event
+///
+/// Arguments:
+/// * `standard`: name of standard e.g. keypom
+/// * `version`: e.g. 1.0.0
+/// * `event`: associated event data
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EventLog {
+    pub standard: String,
+    pub version: String,
+
+    // `flatten` to not have "event": {<EventLogVariant>} in the JSON, just have the contents of {<EventLogVariant>}.
+    #[serde(flatten)]
+    pub event: EventLogVariant,
+}
+
+impl fmt::Display for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "EVENT_JSON:{}",
+            &near_sdk::serde_json::to_string(self).map_err(|_| fmt::Error)?
+        ))
+    }
+}
+
+/// A new drop was created
+///
+/// Arguments
+/// * `owner_id`: the account that owns the drop
+/// * `drop_id`: the ID of the new drop
+/// * `drop_type`: one of `simple`, `nft`, `ft` or `fc`
+/// * `num_keys`: how many keys the drop was created with
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DropCreationLog {
+    pub owner_id: AccountId,
+    pub drop_id: DropId,
+    pub drop_type: String,
+    pub num_keys: u64,
+}
+
+/// Keys were deleted from a drop before all of their uses were claimed
+///
+/// Arguments
+/// * `owner_id`: the account that owns the drop
+/// * `drop_id`: the drop the keys were deleted from
+/// * `public_keys`: the keys that were deleted
+/// * `drop_removed`: whether the drop was removed along with its last keys
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct KeyDeletionLog {
+    pub owner_id: AccountId,
+    pub drop_id: DropId,
+    pub public_keys: Vec<PublicKey>,
+    pub drop_removed: bool,
+}
+
+/// A drop's config and / or metadata was updated by its owner
+///
+/// Arguments
+/// * `owner_id`: the account that owns the drop
+/// * `drop_id`: the drop that was updated
+/// * `config_updated`: whether or not the config was replaced
+/// * `metadata_updated`: whether or not the metadata was replaced or cleared
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DropUpdateLog {
+    pub owner_id: AccountId,
+    pub drop_id: DropId,
+    pub config_updated: bool,
+    pub metadata_updated: bool,
+}
+
+/// The account that NFTs or FTs must be sent from was changed by the drop owner
+///
+/// Arguments
+/// * `owner_id`: the account that owns the drop
+/// * `drop_id`: the drop that was updated
+/// * `old_sender_id`: the previous sender of the assets
+/// * `new_sender_id`: the account that assets must now be sent from
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetSenderUpdateLog {
+    pub owner_id: AccountId,
+    pub drop_id: DropId,
+    pub old_sender_id: AccountId,
+    pub new_sender_id: AccountId,
+}
+
+/// A drop was handed over to a new owner by its previous owner
+///
+/// Arguments
+/// * `drop_id`: the drop that was transferred
+/// * `old_owner_id`: the account that owned the drop before
+/// * `new_owner_id`: the account that now owns the drop and receives its refunds
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DropOwnershipTransferLog {
+    pub drop_id: DropId,
+    pub old_owner_id: AccountId,
+    pub new_owner_id: AccountId,
+}
+
+/// The contract owner forcibly removed a drop and refunded its funder
+///
+/// Arguments
+/// * `admin_id`: the contract owner that performed the refund
+/// * `funder_id`: the account whose balance was credited
+/// * `drop_id`: the drop that was removed
+/// * `keys_deleted`: how many keys were deleted along with the drop
+/// * `refund_amount`: the amount credited to the funder's balance
+/// * `registered_uses_left`: uses that still had assets registered and were not refunded
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AdminRefundDropLog {
+    pub admin_id: AccountId,
+    pub funder_id: AccountId,
+    pub drop_id: DropId,
+    pub keys_deleted: u64,
+    pub refund_amount: U128,
+    pub registered_uses_left: u64,
+}
+
+/// The contract owner changed the minimum balance needed to create a new account
+///
+/// Arguments
+/// * `old_cost`: the previous account creation cost
+/// * `new_cost`: the account creation cost now used by the contract
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountCreationCostUpdateLog {
+    pub old_cost: U128,
+    pub new_cost: U128,
+}
+
+/// A drop's last use was claimed (or the drop was deleted) with the stats of all its claims
+///
+/// Arguments
+/// * `owner_id`: the account that owns the drop
+/// * `drop_id`: the drop that finished
+/// * `completed`: true if every use was claimed, false if the drop was deleted first
+/// * `total_claims`: how many claims succeeded
+/// * `near_distributed`: total $NEAR sent to claimers
+/// * `accounts_created`: how many accounts were created by claims
+/// * `ft_delivered`: total FTs sent to claimers in the token's smallest units
+/// * `nft_delivered`: how many NFTs were sent to claimers
+/// * `first_claim_timestamp`: when the first successful claim resolved
+/// * `last_claim_timestamp`: when the last successful claim resolved
+/// * `duration`: time between the first and last successful claims (in nanoseconds)
+/// * `use_histogram`: successful claims for each use of the drop's keys, starting with the first use
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DropCompleteLog {
+    pub owner_id: AccountId,
+    pub drop_id: DropId,
+    pub completed: bool,
+    pub total_claims: u64,
+    pub near_distributed: U128,
+    pub accounts_created: u64,
+    pub ft_delivered: U128,
+    pub nft_delivered: u64,
+    pub first_claim_timestamp: Option<U64>,
+    pub last_claim_timestamp: Option<U64>,
+    pub duration: U64,
+    pub use_histogram: Vec<u64>,
+}
+
+/// A partner was credited its share of the fees paid for a drop
+///
+/// Arguments
+/// * `partner_id`: the partner whose balance was credited
+/// * `drop_id`: the drop the fees were paid for
+/// * `share_bps`: the partner's share locked in when the drop was created (in basis points)
+/// * `total_fees`: the drop and / or key fees paid
+/// * `partner_amount`: the amount credited to the partner. The rest goes to the fees collected.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PartnerFeeShareLog {
+    pub partner_id: AccountId,
+    pub drop_id: DropId,
+    pub share_bps: u16,
+    pub total_fees: U128,
+    pub partner_amount: U128,
+}
+
+/// A key was used to claim. Emitted when the claim is processed, before its transfers resolve.
+///
+/// Arguments
+/// * `drop_id`: the drop the key belongs to
+/// * `public_key`: the key that was used
+/// * `key_id`: the ID of the key within its drop
+/// * `account_id`: the account claiming (or being created)
+/// * `post_claim`: the drop's redirect and / or message for the wallet's success screen
+/// * `deposit_split`: beneficiaries that receive a share of the claim's $NEAR (in basis points)
+/// * `gas_rebate`: $NEAR sent to the new account on top of the deposit. Only set for `create_account_and_claim`.
+/// * `context_tag`: the label the funder set on the key when adding it (i.e where it was handed out)
+/// * `unverified`: whether the drop calls an FC receiver the contract owner hasn't allowed
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimLog {
+    pub drop_id: DropId,
+    pub public_key: PublicKey,
+    pub key_id: u64,
+    pub account_id: AccountId,
+    pub post_claim: Option<PostClaimInfo>,
+    pub deposit_split: Option<Vec<(AccountId, u16)>>,
+    pub gas_rebate: Option<U128>,
+    pub context_tag: Option<String>,
+    pub unverified: bool,
+}
+
+/// The contract owner called an owner-gated method. The same entry is kept in the admin changelog.
+///
+/// Arguments
+/// * `index`: position of the change among every change ever recorded
+/// * `timestamp`: when the method was called (in nanoseconds)
+/// * `method`: the method that was called
+/// * `args_hash`: sha256 of the raw args passed to the method
+/// * `actor`: the account that called the method
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AdminChangeLog {
+    pub index: u64,
+    pub timestamp: U64,
+    pub method: String,
+    pub args_hash: Base64VecU8,
+    pub actor: AccountId,
+}
+
+/// An operation left an account's balance below the alert threshold it set with `set_balance_alert`
+///
+/// Arguments
+/// * `account_id`: the account whose balance is low
+/// * `balance`: the balance left after the operation
+/// * `threshold`: the threshold set by the account
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BalanceLowLog {
+    pub account_id: AccountId,
+    pub balance: U128,
+    pub threshold: U128,
+}
+
+/// An operation left the contract owing more than it holds
+///
+/// Arguments
+/// * `liabilities`: user balances, key reserves and uncollected fees the contract owes
+/// * `available`: contract balance left after the storage staking requirement
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SolvencyWarningLog {
+    pub liabilities: U128,
+    pub available: U128,
+}
+
+/// The contract owner changed the reputation of an FC receiver
+///
+/// Arguments
+/// * `receiver_id`: the contract FC drops call
+/// * `old_reputation`: the reputation it had before
+/// * `new_reputation`: its reputation now. `unknown` means it was removed from the list.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FcReceiverReputationUpdateLog {
+    pub receiver_id: AccountId,
+    pub old_reputation: FcReceiverReputation,
+    pub new_reputation: FcReceiverReputation,
+}
+
+/// A step of a force refund on a stuck drop: announced by the contract owner, vetoed by the funder or executed
+///
+/// Arguments
+/// * `actor`: the account that took the step
+/// * `funder_id`: the drop's funder
+/// * `drop_id`: the drop being force refunded
+/// * `stage`: `announced`, `vetoed` or `executed`
+/// * `skip_asset_transfer`: whether the drop's NFTs or FTs are abandoned instead of being sent back
+/// * `executable_at`: when the timelock ends (in nanoseconds)
+/// * `registered_uses`: uses that still had assets registered at this step
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AdminForceRefundLog {
+    pub actor: AccountId,
+    pub funder_id: AccountId,
+    pub drop_id: DropId,
+    pub stage: ForceRefundStage,
+    pub skip_asset_transfer: bool,
+    pub executable_at: U64,
+    pub registered_uses: u64,
+}
+
+/// A step of a wNEAR balance top-up: the wNEAR was received and is being unwrapped, then either credited to the
+/// balance or returned to the sender if the unwrap failed
+///
+/// Arguments
+/// * `account_id`: the account topping up its balance
+/// * `wrap_contract`: the contract the wNEAR was sent from
+/// * `amount`: the amount of wNEAR sent
+/// * `stage`: `pending`, `credited` or `returned`
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WrappedDepositLog {
+    pub account_id: AccountId,
+    pub wrap_contract: AccountId,
+    pub amount: U128,
+    pub stage: WrappedDepositStage,
+}
+
+/// The access keys on the contract account went over 80% of the soft cap the owner set
+///
+/// Arguments
+/// * `active_access_keys`: access keys on the contract account once the batch that crossed the threshold resolved
+/// * `access_key_cap`: the cap set by the owner
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccessKeyCapacityLog {
+    pub active_access_keys: u64,
+    pub access_key_cap: u64,
+}
+
+/// A claim was retried with an idempotency key that was already used and wasn't executed again
+///
+/// Arguments
+/// * `public_key`: the key the claim was retried with
+/// * `drop_id`: the drop the original claim was for
+/// * `key_id`: the ID of the key within the drop
+/// * `account_id`: the account the original claim was sent to
+/// * `outcome`: one of `pending`, `succeeded` or `failed` depending on where the original claim is at
+/// * `claimed_at`: block timestamp at which the original claim was processed
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DuplicateClaimLog {
+    pub public_key: PublicKey,
+    pub drop_id: DropId,
+    pub key_id: u64,
+    pub account_id: AccountId,
+    pub outcome: ClaimOutcome,
+    pub claimed_at: u64,
+}
+
+/// Log an event in the NEP-297 format
+pub(crate) fn emit_event(event: EventLogVariant) {
+    let log = EventLog {
+        standard: KEYPOM_STANDARD_NAME.to_string(),
+        version: KEYPOM_STANDARD_VERSION.to_string(),
+        event,
+    };
+    env::log_str(&log.to_string());
+}
@@ -0,0 +1,120 @@
+use crate::*;
+
+/// Share of the access key cap (in percent) at which an `access_key_capacity` event is emitted
+const ACCESS_KEY_CAPACITY_WARNING_PERCENT: u64 = 80;
+
+/// Access keys on the contract account and the soft cap on them
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccessKeyCapacity {
+    pub active_access_keys: u64,
+    pub access_key_cap: Option<u64>,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Set the soft cap on the access keys the contract account can hold. Once the keys added plus the ones being
+        added would go over it, `create_drop` and `add_keys` fail until keys are cleaned up through claims,
+        `delete_keys`, `refund_expired_keys` or `compact_drop`. Passing in None removes the cap.
+    */
+    #[payable]
+    pub fn set_access_key_cap(&mut self, access_key_cap: Option<u64>) {
+        self.assert_owner_and_record("set_access_key_cap");
+        near_sdk::log!(
+            "Access key cap set to {:?}. Active access keys: {}",
+            access_key_cap,
+            self.active_access_keys
+        );
+        self.access_key_cap = access_key_cap;
+    }
+
+    #[private]
+    /*
+        Self callback counts the access keys added or deleted by each batch once they've resolved. A batch that
+        failed changed none of its keys since batches are applied atomically.
+    */
+    pub fn resolve_access_key_change(&mut self, batch_sizes: Vec<u64>, added: bool) {
+        let changed: u64 = batch_sizes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| matches!(env::promise_result(*i as u64), PromiseResult::Successful(_)))
+            .map(|(_, size)| size)
+            .sum();
+
+        if !added {
+            self.active_access_keys = self.active_access_keys.saturating_sub(changed);
+            return;
+        }
+
+        let previous = self.active_access_keys;
+        self.active_access_keys += changed;
+        if let Some(cap) = self.access_key_cap {
+            let threshold = cap * ACCESS_KEY_CAPACITY_WARNING_PERCENT;
+            if previous * 100 < threshold && self.active_access_keys * 100 >= threshold {
+                near_sdk::log!(
+                    "{} of the {} access key cap is in use",
+                    self.active_access_keys,
+                    cap
+                );
+                emit_event(EventLogVariant::AccessKeyCapacity(vec![
+                    AccessKeyCapacityLog {
+                        active_access_keys: self.active_access_keys,
+                        access_key_cap: cap,
+                    },
+                ]));
+            }
+        }
+    }
+
+    /// Returns how many access keys are on the contract account and the soft cap on them, if one is set
+    pub fn get_access_key_capacity(&self) -> AccessKeyCapacity {
+        AccessKeyCapacity {
+            active_access_keys: self.active_access_keys,
+            access_key_cap: self.access_key_cap,
+        }
+    }
+}
+
+impl Keypom {
+    /// New keys are rejected once they'd take the contract account over the access key cap
+    pub(crate) fn internal_check_access_key_capacity(&self, num_keys: usize) -> Result<(), String> {
+        match self.access_key_cap {
+            Some(cap) if self.active_access_keys + num_keys as u64 > cap => {
+                Err("contract key capacity reached, try again after cleanup".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /*
+        Count the keys in a set of add or delete key batches once they resolve. Each entry is a batch's promise
+        index and how many keys it adds or deletes. The batches are joined so that a single callback counts them.
+    */
+    pub(crate) fn internal_count_access_key_batches(
+        &self,
+        batches: &[(PromiseIndex, u64)],
+        added: bool,
+    ) {
+        if batches.is_empty() {
+            return;
+        }
+        let promises: Vec<PromiseIndex> = batches.iter().map(|(promise, _)| *promise).collect();
+        let batch_sizes: Vec<u64> = batches.iter().map(|(_, size)| *size).collect();
+        let joined = if promises.len() == 1 {
+            promises[0]
+        } else {
+            env::promise_and(&promises)
+        };
+        env::promise_then(
+            joined,
+            env::current_account_id(),
+            "resolve_access_key_change",
+            json!({ "batch_sizes": batch_sizes, "added": added })
+                .to_string()
+                .as_bytes(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_ACCESS_KEY_CHANGE,
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use crate::*;
+
+/// Most recent owner changes kept on-chain. Older entries are overwritten once the changelog is full.
+const MAX_ADMIN_CHANGES: u64 = 1_000;
+/// Maximum number of changes returned by `get_admin_changelog`
+const MAX_ADMIN_CHANGES_PER_PAGE: u64 = 100;
+
+/// An owner-gated method that was called on the contract
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AdminChange {
+    // Block timestamp of the call (in nanoseconds)
+    pub timestamp: u64,
+    // Method that was called (i.e `set_gas_price`)
+    pub method: String,
+    // sha256 of the raw args passed to the method
+    pub args_hash: CryptoHash,
+    // Account that called the method
+    pub actor: AccountId,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Ensure the owner is calling with exactly 1 yoctoNEAR attached and record the call in the admin changelog.
+        Every owner-gated method goes through this so that the change can't be made without also being recorded
+        or from a function call access key (which can't attach deposits).
+    */
+    pub(crate) fn assert_owner_and_record(&mut self, method: &str) {
+        assert_one_yocto();
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner_id,
+            "predecessor != owner"
+        );
+        self.internal_record_admin_change(method);
+    }
+
+    /// Append an owner change to the changelog, overwriting the oldest one if it's full, and emit it as an event
+    pub(crate) fn internal_record_admin_change(&mut self, method: &str) {
+        let index = self.admin_changes_recorded;
+        let change = AdminChange {
+            timestamp: env::block_timestamp(),
+            method: method.to_string(),
+            args_hash: env::sha256_array(&env::input().unwrap_or_default()),
+            actor: env::predecessor_account_id(),
+        };
+
+        if self.admin_changelog.len() < MAX_ADMIN_CHANGES {
+            self.admin_changelog.push(&change);
+        } else {
+            self.admin_changelog
+                .replace(index % MAX_ADMIN_CHANGES, &change);
+        }
+        self.admin_changes_recorded += 1;
+
+        emit_event(EventLogVariant::AdminChange(vec![admin_change_log(
+            index, change,
+        )]));
+    }
+
+    /*
+        Paginate through the owner changes still kept on-chain, oldest first. `from_index` counts every change
+        ever recorded so it stays valid as old entries are overwritten. Changes that have been overwritten are skipped.
+    */
+    pub fn get_admin_changelog(
+        &self,
+        from_index: Option<U64>,
+        limit: Option<u64>,
+    ) -> Vec<AdminChangeLog> {
+        let oldest_kept = self.admin_changes_recorded - self.admin_changelog.len();
+        let start = from_index.map(|i| i.0).unwrap_or(0).max(oldest_kept);
+        let limit = limit
+            .unwrap_or(MAX_ADMIN_CHANGES_PER_PAGE)
+            .min(MAX_ADMIN_CHANGES_PER_PAGE);
+        let end = start.saturating_add(limit).min(self.admin_changes_recorded);
+
+        (start..end)
+            .filter_map(|index| {
+                self.admin_changelog
+                    .get(index % MAX_ADMIN_CHANGES)
+                    .map(|change| admin_change_log(index, change))
+            })
+            .collect()
+    }
+
+    /// Total number of owner changes ever recorded, including the ones that have been overwritten
+    pub fn get_admin_changes_recorded(&self) -> u64 {
+        self.admin_changes_recorded
+    }
+}
+
+/// JSON representation of a change in the changelog
+fn admin_change_log(index: u64, change: AdminChange) -> AdminChangeLog {
+    AdminChangeLog {
+        index,
+        timestamp: U64(change.timestamp),
+        method: change.method,
+        args_hash: Base64VecU8(change.args_hash.to_vec()),
+        actor: change.actor,
+    }
+}
@@ -4,6 +4,12 @@ use crate::*;
 #[ext_contract(ext_linkdrop)]
 trait ExtLinkdrop {
     fn create_account(&mut self, new_account_id: AccountId, new_public_key: PublicKey) -> Promise;
+
+    fn create_account_advanced(
+        &mut self,
+        new_account_id: AccountId,
+        options: CreateAccountOptions,
+    ) -> Promise;
 }
 
 /// NFT contract
@@ -24,6 +30,12 @@ trait ExtFTContract {
     fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
 
     fn storage_balance_bounds(&self) -> StorageBalanceBounds;
+
+    fn ft_metadata(&self) -> FTMetadataInfo;
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance>;
+
+    fn ft_balance_of(&self, account_id: AccountId) -> U128;
 }
 
 #[ext_contract(ext_self)]
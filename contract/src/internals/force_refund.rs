@@ -0,0 +1,144 @@
+use crate::*;
+
+/// How long the funder has to veto a force refund once it's been announced (72 hours in nanoseconds)
+pub(crate) const ADMIN_FORCE_REFUND_TIMELOCK: u64 = 72 * 60 * 60 * 1_000_000_000;
+
+/// A force refund the contract owner announced and that can be executed once the timelock has passed
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingForceRefund {
+    // Whether the drop's NFTs or FTs are abandoned instead of being sent back
+    pub skip_asset_transfer: bool,
+    // When the refund was announced and when it can be executed (in nanoseconds)
+    pub announced_at: U64,
+    pub executable_at: U64,
+}
+
+/// Stage of a force refund reported in `admin_force_refund` events
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum ForceRefundStage {
+    Announced,
+    Vetoed,
+    Executed,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Unstick a drop whose assets can't be claimed or refunded, i.e because the token contract is dead. The first
+        call announces the refund and emits an event. The funder then has 72 hours to call `veto_admin_action`.
+        Calling it again with the same `skip_asset_transfer` once the timelock has passed executes it: every key is
+        deleted, the funder is refunded the $NEAR side of the drop and the drop is removed. If `skip_asset_transfer`
+        is true, the registered uses are zeroed and the NFTs or FTs are abandoned. Otherwise they're sent back first
+        as with `delete_drop`.
+    */
+    #[payable]
+    pub fn admin_force_refund(&mut self, drop_id: DropId, skip_asset_transfer: bool) {
+        self.assert_owner_and_record("admin_force_refund");
+
+        let mut drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        require!(
+            drop.state == DropState::Active,
+            "only active drops can be force refunded"
+        );
+        let now = env::block_timestamp();
+
+        let pending = match self.pending_force_refunds.get(&drop_id) {
+            Some(pending) => pending,
+            None => {
+                let pending = PendingForceRefund {
+                    skip_asset_transfer,
+                    announced_at: U64(now),
+                    executable_at: U64(now + ADMIN_FORCE_REFUND_TIMELOCK),
+                };
+                self.pending_force_refunds.insert(&drop_id, &pending);
+                near_sdk::log!(
+                    "Force refund of drop {} announced. Skip asset transfer: {}. Executable at {}",
+                    drop_id,
+                    skip_asset_transfer,
+                    format_iso_timestamp(pending.executable_at.0)
+                );
+                emit_force_refund_event(&drop, drop_id, &pending, ForceRefundStage::Announced);
+                return;
+            }
+        };
+        require!(
+            pending.skip_asset_transfer == skip_asset_transfer,
+            "skip_asset_transfer must match the announced force refund"
+        );
+        require!(
+            now >= pending.executable_at.0,
+            "force refund timelock hasn't passed yet"
+        );
+
+        self.pending_force_refunds.remove(&drop_id);
+        emit_force_refund_event(&drop, drop_id, &pending, ForceRefundStage::Executed);
+
+        if skip_asset_transfer {
+            // The assets can't be sent back so the uses registered for them are dropped
+            near_sdk::log!(
+                "Abandoning {} registered uses of drop {}",
+                drop.registered_uses,
+                drop_id
+            );
+            if let DropType::FungibleToken(data) = &mut drop.drop_type {
+                near_sdk::log!("Abandoning {} FT dust", data.dust.0);
+                let abandoned = data.balance_per_use.0 * drop.registered_uses as u128 + data.dust.0;
+                data.dust.0 = 0;
+                let contract_id = data.contract_id.clone();
+                self.internal_release_ft(&contract_id, abandoned);
+            }
+            if let DropType::NonFungibleToken(data) = &mut drop.drop_type {
+                near_sdk::log!("Abandoning {} NFTs", data.token_ids.len());
+                data.token_ids.clear();
+            }
+            drop.registered_uses = 0;
+        }
+        self.internal_delete_drop(drop_id, drop);
+    }
+
+    /// Veto a force refund the contract owner announced for one of the caller's drops
+    #[payable]
+    pub fn veto_admin_action(&mut self, drop_id: DropId) {
+        assert_one_yocto();
+
+        let drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        require!(
+            drop.owner_id == env::predecessor_account_id(),
+            "only drop funder can veto admin actions"
+        );
+        let pending = self
+            .pending_force_refunds
+            .remove(&drop_id)
+            .expect("no admin action pending for drop");
+
+        near_sdk::log!("Force refund of drop {} vetoed by the funder", drop_id);
+        emit_force_refund_event(&drop, drop_id, &pending, ForceRefundStage::Vetoed);
+    }
+
+    /// Returns the force refund announced for a drop, if there is one
+    pub fn get_pending_force_refund(&self, drop_id: DropId) -> Option<PendingForceRefund> {
+        self.pending_force_refunds.get(&drop_id)
+    }
+}
+
+fn emit_force_refund_event(
+    drop: &Drop,
+    drop_id: DropId,
+    pending: &PendingForceRefund,
+    stage: ForceRefundStage,
+) {
+    emit_event(EventLogVariant::AdminForceRefund(vec![
+        AdminForceRefundLog {
+            actor: env::predecessor_account_id(),
+            funder_id: drop.owner_id.clone(),
+            drop_id,
+            stage,
+            skip_asset_transfer: pending.skip_asset_transfer,
+            executable_at: pending.executable_at,
+            registered_uses: drop.registered_uses,
+        },
+    ]));
+}
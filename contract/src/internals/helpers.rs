@@ -17,6 +17,229 @@ pub(crate) fn yocto_to_near(yocto: u128) -> f64 {
     near
 }
 
+/// Determine the claim permission actually enforced for a drop given its config and optional FC data
+pub(crate) fn effective_claim_permission(
+    config: &Option<DropConfig>,
+    fc_data: Option<&FCData>,
+) -> ClaimPermissions {
+    // If GAS is specified for the function calls, the keys can only call `claim`
+    if fc_data
+        .and_then(|d| d.config.as_ref())
+        .and_then(|c| c.attached_gas)
+        .is_some()
+    {
+        return ClaimPermissions::Claim;
+    }
+
+    // If we have a config, use the config to determine what methods the access keys can call
+    config
+        .as_ref()
+        .and_then(|c| c.claim_permission.clone())
+        .unwrap_or(ClaimPermissions::Both)
+}
+
+/// Write the effective claim permission back into the config so that views always reflect what's enforced
+pub(crate) fn with_claim_permission(
+    config: Option<DropConfig>,
+    permission: &ClaimPermissions,
+) -> Option<DropConfig> {
+    match config {
+        Some(mut config) => {
+            config.claim_permission = Some(permission.clone());
+            Some(config)
+        }
+        // No config is the same as both methods being callable
+        None if *permission == ClaimPermissions::Both => None,
+        None => Some(DropConfig {
+            claim_permission: Some(permission.clone()),
+            ..Default::default()
+        }),
+    }
+}
+
+/// Seed for a new key's last used timestamp. Keys added at any point in a drop's life follow the same rule:
+/// 0 if they can be used right away or the current block timestamp if the throttle applies from addition.
+pub(crate) fn initial_last_used(config: &Option<DropConfig>) -> u64 {
+    let throttle_from_addition = config
+        .as_ref()
+        .filter(|c| c.throttle_timestamp.is_some())
+        .and_then(|c| c.throttle_from_addition)
+        .unwrap_or(false);
+
+    if throttle_from_addition {
+        env::block_timestamp()
+    } else {
+        0
+    }
+}
+
+/// Early bonus funds that were reserved when the drop was created but haven't been paid out
+pub(crate) fn unspent_early_bonus(drop: &Drop) -> Balance {
+    match drop.config.as_ref().and_then(|c| c.early_bonus) {
+        Some((num_bonus_claims, amount)) => {
+            num_bonus_claims.saturating_sub(drop.early_claims) as u128 * amount.0
+        }
+        None => 0,
+    }
+}
+
+/// Extra cost reserved for each paid claim when the drop delivers its balance through a wrap contract
+pub(crate) fn wrap_cost_per_use(config: &Option<DropConfig>) -> Balance {
+    match config.as_ref().and_then(|c| c.wrap_contract.as_ref()) {
+        Some(_) => WRAP_STORAGE_COST,
+        None => 0,
+    }
+}
+
+/// Extra cost reserved for each paid claim when claimers can choose an alternative payout token
+pub(crate) fn swap_cost_per_use(config: &Option<DropConfig>) -> Balance {
+    match config.as_ref().and_then(|c| c.alternative_payouts.as_ref()) {
+        Some(_) => SWAP_STORAGE_COST,
+        None => 0,
+    }
+}
+
+/// Storage reserved for each paid claim to cover the contract deployed to new accounts
+pub(crate) fn deploy_cost_per_use(config: &Option<DropConfig>) -> Balance {
+    match config.as_ref().and_then(|c| c.deploy.as_ref()) {
+        Some(deploy) => deploy.contract_bytes.0.len() as u128 * env::storage_byte_cost(),
+        None => 0,
+    }
+}
+
+/// $NEAR reserved for each paid claim to give accounts created with create_account_and_claim some GAS money
+pub(crate) fn gas_rebate_per_use(config: &Option<DropConfig>) -> Balance {
+    config
+        .as_ref()
+        .and_then(|c| c.gas_rebate)
+        .map_or(0, |rebate| rebate.0)
+}
+
+/// Everything reserved for each paid claim on top of the deposit per use
+pub(crate) fn extra_cost_per_use(config: &Option<DropConfig>) -> Balance {
+    wrap_cost_per_use(config)
+        + swap_cost_per_use(config)
+        + deploy_cost_per_use(config)
+        + gas_rebate_per_use(config)
+        + badge_cost_per_use(config)
+}
+
+/// If an FT or NFT drop has no assets registered for the next claim, describe the shortfall.
+/// Claims that are still resolving are included since they've already taken their asset.
+pub(crate) fn out_of_assets_message(drop: &Drop) -> Option<String> {
+    if drop.drop_type.behavior().registers_uses_with_keys() || drop.registered_uses >= 1 {
+        return None;
+    }
+    Some(format!(
+        "Drop temporarily out of assets. Shortfall: 1. Registered uses: {}. Claims still resolving: {}",
+        drop.registered_uses, drop.pending_claims
+    ))
+}
+
+/// Stop counting uses that were consumed or deleted. This saturates at 0 and logs rather than panicking
+/// since a claim that already went through shouldn't fail over the counter.
+pub(crate) fn release_registered_uses(drop: &mut Drop, num_uses: u64) {
+    if num_uses > drop.registered_uses {
+        near_sdk::log!(
+            "Releasing {} uses but only {} are registered. Setting registered uses to 0",
+            num_uses,
+            drop.registered_uses
+        );
+    }
+    drop.registered_uses = drop.registered_uses.saturating_sub(num_uses);
+}
+
+/// Whether the drop's start timestamp (if any) has been reached
+pub(crate) fn drop_has_started(drop: &Drop, current_timestamp: u64) -> bool {
+    current_timestamp
+        >= drop
+            .config
+            .as_ref()
+            .and_then(|c| c.start_timestamp)
+            .unwrap_or(current_timestamp)
+}
+
+/// Whether the drop's end timestamp (if any) has passed
+pub(crate) fn drop_has_ended(drop: &Drop, current_timestamp: u64) -> bool {
+    drop.config
+        .as_ref()
+        .and_then(|c| c.end_timestamp)
+        .is_some_and(|end| current_timestamp > end)
+}
+
+/// Uses per key set on the drop. Defaults to 1.
+pub(crate) fn drop_uses_per_key(config: &Option<DropConfig>) -> u64 {
+    config.as_ref().and_then(|c| c.uses_per_key).unwrap_or(1)
+}
+
+/// Uses a key was created with. Keys can override the drop's uses per key.
+pub(crate) fn key_uses_per_key(config: &Option<DropConfig>, key_info: &KeyInfo) -> u64 {
+    key_info
+        .config
+        .as_ref()
+        .and_then(|c| c.uses_per_key)
+        .unwrap_or_else(|| drop_uses_per_key(config))
+}
+
+/// Start timestamp of a key, which is its own if it overrides the drop's
+pub(crate) fn key_start_timestamp(drop: &Drop, key_info: &KeyInfo) -> Option<u64> {
+    key_info
+        .config
+        .as_ref()
+        .and_then(|c| c.start_timestamp)
+        .or_else(|| drop.config.as_ref().and_then(|c| c.start_timestamp))
+}
+
+/// Whether the key's start timestamp (if any) has been reached
+pub(crate) fn key_has_started(drop: &Drop, key_info: &KeyInfo, current_timestamp: u64) -> bool {
+    current_timestamp >= key_start_timestamp(drop, key_info).unwrap_or(current_timestamp)
+}
+
+/// End timestamp of a key, which is its own if it overrides the drop's
+pub(crate) fn key_end_timestamp(drop: &Drop, key_info: &KeyInfo) -> Option<u64> {
+    key_info
+        .config
+        .as_ref()
+        .and_then(|c| c.end_timestamp)
+        .or_else(|| drop.config.as_ref().and_then(|c| c.end_timestamp))
+}
+
+/// Whether the key's end timestamp (if any) has passed
+pub(crate) fn key_has_ended(drop: &Drop, key_info: &KeyInfo, current_timestamp: u64) -> bool {
+    key_end_timestamp(drop, key_info).is_some_and(|end| current_timestamp > end)
+}
+
+/// Whether the drop has reached its max number of total claims. Claims still resolving count towards the cap.
+pub(crate) fn drop_reached_max_claims(drop: &Drop) -> bool {
+    match drop.config.as_ref().and_then(|c| c.max_total_claims) {
+        Some(max_claims) => drop.total_claims + drop.pending_claims >= max_claims,
+        None => false,
+    }
+}
+
+/// Derive where a drop is in its lifecycle using the same checks that are enforced when claiming
+pub(crate) fn drop_status(drop: &Drop) -> DropStatus {
+    match drop.state {
+        DropState::Pending { .. } => return DropStatus::PendingStorage,
+        DropState::Cancelled => return DropStatus::Cancelled,
+        DropState::Failed => return DropStatus::Failed,
+        DropState::Deleting => return DropStatus::Deleting,
+        DropState::Active => {}
+    }
+
+    if drop.registered_uses < 1 {
+        DropStatus::Unfunded
+    } else if !drop_has_started(drop, env::block_timestamp()) {
+        DropStatus::NotStarted
+    } else if drop_has_ended(drop, env::block_timestamp()) {
+        DropStatus::Expired
+    } else if drop_reached_max_claims(drop) {
+        DropStatus::Exhausted
+    } else {
+        DropStatus::Active
+    }
+}
+
 impl Keypom {
     /// Used to calculate the base allowance needed given attached GAS
     pub(crate) fn calculate_base_allowance(&self, attached_gas: Gas) -> u128 {
@@ -58,11 +281,65 @@ impl Keypom {
         self.drop_ids_for_owner.insert(account_id, &drop_set);
     }
 
+    /// Whether `amount` can leave the contract while keeping its balance above the storage staking requirement and the operational buffer
+    pub(crate) fn internal_can_transfer(&self, amount: Balance) -> bool {
+        let storage_staked = Balance::from(env::storage_usage()) * env::storage_byte_cost();
+        env::account_balance() >= storage_staked + self.operational_buffer + amount
+    }
+
+    /*
+        Send `amount` to an account unless it would take the contract's balance below the storage staking
+        requirement and the operational buffer. In that case, the amount is credited to the account's balance
+        on the contract so that it can be withdrawn later. Returns the transfer if one was made.
+    */
+    pub(crate) fn internal_transfer_or_defer(
+        &mut self,
+        account_id: AccountId,
+        amount: Balance,
+    ) -> Option<Promise> {
+        if self.internal_can_transfer(amount) {
+            return Some(Promise::new(account_id).transfer(amount));
+        }
+
+        near_sdk::log!(
+            "Not enough free balance to send {} to {}. Crediting their balance instead.",
+            yocto_to_near(amount),
+            account_id
+        );
+        let mut cur_balance = self.user_balances.get(&account_id).unwrap_or(0);
+        cur_balance += amount;
+        self.internal_set_user_balance(&account_id, cur_balance);
+        None
+    }
+
+    /// Owner that refunds from a drop's callbacks go to. Drops can change hands while a promise is in flight so the
+    /// owner it was scheduled with is only used if the drop has since been removed.
+    pub(crate) fn internal_current_owner(&self, drop_id: DropId, owner_id: AccountId) -> AccountId {
+        self.drop_for_id
+            .get(&drop_id)
+            .map(|drop| drop.owner_id)
+            .unwrap_or(owner_id)
+    }
+
     //remove a drop ID for a funder (internal method_name and can't be called directly via CLI).
     pub(crate) fn internal_remove_drop_for_funder(
         &mut self,
         account_id: &AccountId,
         drop_id: &DropId,
+    ) {
+        self.internal_remove_drop_id_for_owner(account_id, drop_id);
+
+        //the drop is no longer part of its campaign either and doesn't need its FT registration refreshed or partner share
+        self.internal_remove_drop_from_campaign(drop_id);
+        self.last_ft_refresh.remove(drop_id);
+        self.partner_for_drop.remove(drop_id);
+    }
+
+    /// Remove a drop ID from the funder's set of drops without touching anything else linked to the drop
+    pub(crate) fn internal_remove_drop_id_for_owner(
+        &mut self,
+        account_id: &AccountId,
+        drop_id: &DropId,
     ) {
         //we get the set of drop IDs that the funder has
         let mut drop_set = self
@@ -83,6 +360,57 @@ impl Keypom {
         }
     }
 
+    /// Fees charged to an account as (drop fee, key fee). Fees set by the owner for a specific account take
+    /// precedence over the global fees, even if they're zero.
+    pub(crate) fn internal_fees_for_user(&self, account_id: &AccountId) -> (Balance, Balance) {
+        self.fees_per_user
+            .get(account_id)
+            .unwrap_or((self.drop_fee, self.key_fee))
+    }
+
+    /// Credit the funder back any per claim costs (wrapping, swapping or deploying) that were reserved but not needed by the claim method used
+    pub(crate) fn internal_refund_unused_claim_cost(
+        &mut self,
+        owner_id: &AccountId,
+        amount: Balance,
+    ) {
+        if amount == 0 {
+            return;
+        }
+
+        let mut user_balance = self.user_balances.get(owner_id).unwrap_or(0);
+        user_balance += amount;
+        self.internal_set_user_balance(owner_id, user_balance);
+    }
+
+    /// Resolve a pending claim once its promise has settled. Failed claims free up their spot under the max
+    /// number of total claims. If the drop was removed in the meantime, only its stats are updated.
+    pub(crate) fn internal_resolve_total_claims(
+        &mut self,
+        claim_guard: &ClaimGuard,
+        claim_succeeded: bool,
+        delivery: ClaimDelivery,
+    ) {
+        let drop_id = claim_guard.drop_id;
+        let drop_exists = if let Some(mut drop) = self.drop_for_id.get(&drop_id) {
+            drop.pending_claims = drop.pending_claims.saturating_sub(1);
+            if claim_succeeded {
+                drop.total_claims += 1;
+            }
+            self.drop_for_id.insert(&drop_id, &drop);
+            true
+        } else {
+            false
+        };
+        self.internal_resolve_claim_record(claim_guard, claim_succeeded);
+        self.internal_resolve_claim_stats(
+            drop_id,
+            drop_exists,
+            claim_guard.use_number,
+            Some(delivery).filter(|_| claim_succeeded),
+        );
+    }
+
     /// Internal function for executing the callback code either straight up or using `.then` for a passed in promise
     pub(crate) fn internal_execute(
         &mut self,
@@ -90,31 +418,48 @@ impl Keypom {
         drop_id: DropId,
         cur_key_info: KeyInfo,
         account_id: AccountId,
+        balance: Balance,
         storage_freed: u128,
-        token_id: Option<String>,
+        token_ids: Option<Vec<String>>,
         storage_for_longest: Option<u128>,
-        promise: Option<Promise>,
+        promise: Option<(Promise, PromiseTarget)>,
+        split: Vec<(AccountId, U128)>,
     ) {
+        // Reject a second claim for this use of the key before anything is scheduled. The callback clears it.
+        let claim_guard = ClaimGuard::new(drop_id, &cur_key_info, &drop_data.config);
+        self.internal_begin_claim_guard(&claim_guard);
+        // If the drop mints badges, the badge is minted once the claim resolves
+        let badge = self.internal_badge_for_claim(&drop_data, drop_id, &account_id, &cur_key_info);
         macro_rules! resolve_promise_or_call {
             ( $func:ident ( $($call:tt)* ) ) => {
-                if let Some(promise) = promise {
-                    promise.then(
+                if let Some((promise, target)) = promise {
+                    let claim = promise.then(
                         // Call on_claim_fc with all unspent GAS + min gas for on claim. No attached attached_deposit.
                         Self::ext(env::current_account_id())
                         .with_static_gas(MIN_GAS_FOR_ON_CLAIM)
                         .$func(
                             $($call)*
+                            // Use of the key being claimed
+                            claim_guard,
+                            // What the promise being resolved called so that failures can be recorded
+                            Some(target),
                             // Executing the function and treating it like a callback.
                             false,
                         )
                     );
+                    self.internal_mint_badge_after(claim, badge);
                 } else {
                     // We're not dealing with a promise so we simply execute the function.
-                    self.$func(
+                    let claim_succeeded = self.$func(
                         $($call)*
+                        claim_guard,
+                        None,
                         // Executing the function and treating it NOT like a callback.
                         true,
                     );
+                    if let Some(badge) = badge {
+                        self.internal_resolve_badge(claim_succeeded, badge);
+                    }
                 }
             }
         }
@@ -128,7 +473,7 @@ impl Keypom {
                     // Account ID that funded the linkdrop
                     drop_data.owner_id,
                     // Balance associated with the linkdrop
-                    U128(drop_data.deposit_per_use),
+                    U128(balance),
                     // How much storage was freed when the key was claimed
                     storage_freed,
                     // FC Data
@@ -148,17 +493,19 @@ impl Keypom {
                     // Account ID that funded the linkdrop
                     drop_data.owner_id,
                     // Balance associated with the linkdrop
-                    U128(drop_data.deposit_per_use),
+                    U128(balance),
                     // How much storage was freed when the key was claimed
                     storage_freed,
-                    // How much storage was prepaid to cover the longest token ID being inserted.
+                    // Drop ID
+                    drop_id,
+                    // Cost of the storage prepaid to cover the longest token IDs being inserted
                     storage_for_longest.expect("no storage for longest token Id found"),
                     // Sender of the NFT
                     data.sender_id,
                     // Contract where the NFT is stored
                     data.contract_id,
-                    // Token ID for the NFT
-                    token_id.expect("no token ID found"),
+                    // Token IDs for the NFTs
+                    token_ids.expect("no token ID found"),
                 ));
             }
             DropType::FungibleToken(data) => {
@@ -168,15 +515,18 @@ impl Keypom {
                     // Account ID that funded the linkdrop
                     drop_data.owner_id,
                     // Balance associated with the linkdrop
-                    U128(drop_data.deposit_per_use),
+                    U128(balance),
                     // How much storage was freed when the key was claimed
                     storage_freed,
+                    // Drop ID
+                    drop_id,
                     // FT Data to be used
                     data,
                 ));
             }
             DropType::Simple => {
-                promise.unwrap().then(
+                let (promise, target) = promise.unwrap();
+                let claim = promise.then(
                     // Call on_claim_simple with all unspent GAS + min gas for on claim. No attached attached_deposit.
                     Self::ext(env::current_account_id())
                         .with_static_gas(MIN_GAS_FOR_ON_CLAIM)
@@ -184,11 +534,20 @@ impl Keypom {
                             // Account ID that funded the linkdrop
                             drop_data.owner_id,
                             // Balance associated with the linkdrop
-                            U128(drop_data.deposit_per_use),
+                            U128(balance),
                             // How much storage was freed when the key was claimed
                             storage_freed,
+                            // Drop ID
+                            drop_id,
+                            // Use of the key being claimed
+                            claim_guard,
+                            // What the promise being resolved called
+                            target,
+                            // Beneficiaries whose transfers were joined onto the claim's promise
+                            split,
                         ),
                 );
+                self.internal_mint_badge_after(claim, badge);
             }
         };
     }
@@ -0,0 +1,49 @@
+use crate::*;
+
+/// Contract state as it was deployed before the claim, drop and owner features were added on top of it
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldKeypom {
+    pub owner_id: AccountId,
+    pub root_account: AccountId,
+    pub drop_id_for_pk: UnorderedMap<PublicKey, DropId>,
+    pub drop_for_id: LookupMap<DropId, Drop>,
+    pub drop_ids_for_owner: LookupMap<AccountId, UnorderedSet<DropId>>,
+    pub drop_fee: u128,
+    pub key_fee: u128,
+    pub fees_collected: u128,
+    pub fees_per_user: LookupMap<AccountId, (u128, u128)>,
+    pub user_balances: LookupMap<AccountId, Balance>,
+    pub next_drop_id: DropId,
+    pub yocto_per_gas: u128,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Read the state of a contract deployed before the current layout and carry it over. Everything added since
+        starts out the same as it would for a new contract. The running totals used by the solvency report start at
+        zero and only count balances and reserves that change from here on.
+    */
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old: OldKeypom = env::state_read().expect("No state to migrate");
+        near_sdk::log!(
+            "Migrating contract state. Next drop ID: {}",
+            old.next_drop_id
+        );
+
+        let mut contract = Self::new(old.root_account, old.owner_id);
+        contract.drop_id_for_pk = old.drop_id_for_pk;
+        contract.drop_for_id = old.drop_for_id;
+        contract.drop_ids_for_owner = old.drop_ids_for_owner;
+        contract.drop_fee = old.drop_fee;
+        contract.key_fee = old.key_fee;
+        contract.fees_collected = old.fees_collected;
+        contract.fees_per_user = old.fees_per_user;
+        contract.user_balances = old.user_balances;
+        contract.next_drop_id = old.next_drop_id;
+        contract.yocto_per_gas = old.yocto_per_gas;
+        contract
+    }
+}
@@ -1,9 +1,25 @@
+pub mod access_keys;
+pub mod changelog;
 pub mod ext_traits;
+pub mod force_refund;
 pub mod helpers;
+pub mod migrate;
 pub mod owner;
+mod prefixes;
+pub mod refund_vesting;
+pub mod solvency;
 pub mod storage;
+pub mod time;
+pub mod wrapped_deposits;
 
+pub use changelog::*;
 pub use ext_traits::*;
+pub use force_refund::*;
 pub(crate) use helpers::*;
+pub use migrate::*;
 pub use owner::*;
-pub use storage::*;
+pub(crate) use prefixes::*;
+pub use refund_vesting::*;
+pub(crate) use storage::*;
+pub(crate) use time::*;
+pub use wrapped_deposits::*;
@@ -2,50 +2,160 @@ use crate::*;
 
 #[near_bindgen]
 impl Keypom {
-    /// Set the desired linkdrop contract to interact with
-    pub fn set_root_account(&mut self, root_account: AccountId) {
-        assert_eq!(
-            env::predecessor_account_id(),
+    /// Transfer ownership of the contract. The new owner is the only account that can call the owner methods from then on.
+    #[payable]
+    pub fn set_owner(&mut self, new_owner: AccountId) {
+        self.assert_owner_and_record("set_owner");
+        near_sdk::log!(
+            "Transferring ownership from {} to {}",
             self.owner_id,
-            "predecessor != owner"
+            new_owner
         );
+        self.owner_id = new_owner;
+    }
+
+    /// Set the desired linkdrop contract to interact with
+    #[payable]
+    pub fn set_root_account(&mut self, root_account: AccountId) {
+        self.assert_owner_and_record("set_root_account");
         self.root_account = root_account;
     }
 
+    /// Register a root that is known to accept `create_account` calls from this contract
+    #[payable]
+    pub fn register_known_root(&mut self, root: AccountId) -> bool {
+        self.assert_owner_and_record("register_known_root");
+        self.known_roots.insert(&root)
+    }
+
+    /// Remove a root from the set of roots known to accept `create_account` calls
+    #[payable]
+    pub fn unregister_known_root(&mut self, root: AccountId) -> bool {
+        self.assert_owner_and_record("unregister_known_root");
+        self.known_roots.remove(&root)
+    }
+
     /// Set the desired linkdrop contract to interact with
+    #[payable]
     pub fn set_fees_per_user(&mut self, account_id: AccountId, drop_fee: U128, key_fee: U128) {
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.owner_id,
-            "predecessor != owner"
-        );
+        self.assert_owner_and_record("set_fees_per_user");
         self.fees_per_user
             .insert(&account_id, &(drop_fee.0, key_fee.0));
     }
 
     /// Set the desired linkdrop contract to interact with
+    #[payable]
     pub fn set_gas_price(&mut self, yocto_per_gas: u128) {
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.owner_id,
-            "predecessor != owner"
-        );
+        self.assert_owner_and_record("set_gas_price");
         self.yocto_per_gas = yocto_per_gas;
     }
 
-    /// Withdraw the fees collected to the passed in Account Id
-    pub fn withdraw_fees(&mut self, withdraw_to: AccountId) -> Promise {
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.owner_id,
-            "predecessor != owner"
+    /// Set the minimum balance that a new account must have when created
+    #[payable]
+    pub fn set_account_creation_cost(&mut self, cost: U128) {
+        self.assert_owner_and_record("set_account_creation_cost");
+        let old_cost = self.new_account_base;
+        self.new_account_base = cost.0;
+
+        emit_event(EventLogVariant::AccountCreationCostUpdate(vec![
+            AccountCreationCostUpdateLog {
+                old_cost: U128(old_cost),
+                new_cost: cost,
+            },
+        ]));
+    }
+
+    /// Set how long claim records for idempotency keys are kept before they can be swept
+    #[payable]
+    pub fn set_claim_record_ttl(&mut self, ttl: u64) {
+        self.assert_owner_and_record("set_claim_record_ttl");
+        self.claim_record_ttl = ttl;
+    }
+
+    /// Set how long before their end timestamp keys can be tightened with `tighten_expiring_keys`
+    #[payable]
+    pub fn set_expiring_key_window(&mut self, window: u64) {
+        self.assert_owner_and_record("set_expiring_key_window");
+        self.expiring_key_window = window;
+    }
+
+    /*
+        Last resort for bailing out a funder whose drop ended up in an inconsistent state.
+        Deletes every key in the drop, removes the drop and credits a best-effort refund to the
+        funder's balance. The refund consists of the storage freed, the allowance left on each key,
+        the access key storage, any unspent early bonus or compaction bounties and the deposits (along
+        with wrap and deploy costs) reserved for the remaining uses. Any NFTs or FTs still registered for the drop are NOT sent back.
+    */
+    #[payable]
+    pub fn admin_refund_drop(&mut self, drop_id: DropId) {
+        self.assert_owner_and_record("admin_refund_drop");
+
+        let drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        let RemovedDrop {
+            funder_id,
+            keys_to_delete,
+            refund: total_refund_amount,
+            registered_uses_left,
+        } = self.internal_remove_drop(drop_id, drop);
+        let num_keys = keys_to_delete.len() as u128;
+
+        near_sdk::log!(
+            "ADMIN REFUND: drop {} owned by {} removed. {} keys deleted. Refunded {} to the funder's balance. {} registered uses were not refunded",
+            drop_id,
+            funder_id,
+            num_keys,
+            yocto_to_near(total_refund_amount),
+            registered_uses_left
         );
+        emit_event(EventLogVariant::AdminRefundDrop(vec![AdminRefundDropLog {
+            admin_id: env::predecessor_account_id(),
+            funder_id,
+            drop_id,
+            keys_deleted: num_keys as u64,
+            refund_amount: U128(total_refund_amount),
+            registered_uses_left,
+        }]));
+
+        // Delete all the keys in a single batch
+        if !keys_to_delete.is_empty() {
+            let promise = env::promise_batch_create(&env::current_account_id());
+            for key in &keys_to_delete {
+                env::promise_batch_action_delete_key(promise, key);
+            }
+            self.internal_count_access_key_batches(
+                &[(promise, keys_to_delete.len() as u64)],
+                false,
+            );
+            env::promise_return(promise);
+        }
+    }
+
+    /// Set the balance kept on top of the storage staking requirement that outbound transfers can't dip into
+    #[payable]
+    pub fn set_operational_buffer(&mut self, buffer: U128) {
+        self.assert_owner_and_record("set_operational_buffer");
+        near_sdk::log!(
+            "Operational buffer changed from {} to {}",
+            yocto_to_near(self.operational_buffer),
+            yocto_to_near(buffer.0)
+        );
+        self.operational_buffer = buffer.0;
+    }
+
+    /// Withdraw the fees collected to the passed in Account Id. If the contract can't spare the amount without dipping
+    /// into its storage staking and operational buffer, the fees are credited to the account's balance instead.
+    #[payable]
+    pub fn withdraw_fees(&mut self, withdraw_to: AccountId) -> PromiseOrValue<bool> {
+        self.assert_owner_and_record("withdraw_fees");
         let amount = self.fees_collected;
         self.fees_collected = 0;
 
-        Promise::new(withdraw_to)
-            .transfer(amount)
-            .then(Self::ext(env::current_account_id()).on_withdraw_fees(amount))
+        match self.internal_transfer_or_defer(withdraw_to, amount) {
+            Some(promise) => PromiseOrValue::Promise(
+                promise.then(Self::ext(env::current_account_id()).on_withdraw_fees(amount)),
+            ),
+            None => PromiseOrValue::Value(false),
+        }
     }
 
     /// Callback for withdrawing fees on the contract
@@ -0,0 +1,101 @@
+use near_sdk::IntoStorageKey;
+
+use crate::*;
+
+/*
+    Storage prefixes for the collections of a single drop. Prefixes used to be derived from the drop ID and
+    owner which could collide (drop 1 owned by `2a.near` and drop 12 owned by `a.near` hash the same string)
+    and would be reused if a drop ID ever was. Every drop now takes a salt that is never handed out twice.
+*/
+pub(crate) struct DropStorageKeys {
+    salt: u64,
+}
+
+impl DropStorageKeys {
+    fn prefix(&self, kind: &str) -> CryptoHash {
+        // Old prefixes started with a digit, `allowlist-`, `metadata-` or `nft-` so salted ones can't match them
+        hash_account_id(&format!("salted-{}-{}", kind, self.salt))
+    }
+
+    pub(crate) fn pks(&self) -> StorageKey {
+        StorageKey::PksForDrop {
+            account_id_hash: self.prefix("pks"),
+        }
+    }
+
+    pub(crate) fn pk_for_key_id(&self) -> StorageKey {
+        StorageKey::PkForKeyId {
+            account_id_hash: self.prefix("key-ids"),
+        }
+    }
+
+    pub(crate) fn allowed_hashes(&self) -> StorageKey {
+        StorageKey::AllowedHashesForDrop {
+            account_id_hash: self.prefix("allowlist"),
+        }
+    }
+
+    pub(crate) fn claims_per_account(&self) -> StorageKey {
+        StorageKey::ClaimsPerAccountForDrop {
+            account_id_hash: self.prefix("account-claims"),
+        }
+    }
+
+    pub(crate) fn metadata(&self) -> StorageKey {
+        StorageKey::DropMetadata {
+            account_id_hash: self.prefix("metadata"),
+        }
+    }
+
+    pub(crate) fn token_ids(&self) -> StorageKey {
+        StorageKey::TokenIdsForDrop {
+            account_id_hash: self.prefix("nft"),
+        }
+    }
+
+    /*
+        Storage keys of the first entry each collection would write (following near-sdk's layout: UnorderedMap keys
+        under `k`, UnorderedSet elements under `e`, Vector elements by index and LazyOption at the prefix itself).
+        If any of them exist, something was left under the prefixes and the new drop would pick it up.
+    */
+    fn first_entries(&self) -> Vec<Vec<u8>> {
+        let first_index = 0u64.to_le_bytes();
+        let with_suffix = |key: StorageKey, suffix: &[u8]| {
+            let mut key = key.into_storage_key();
+            key.extend_from_slice(suffix);
+            key
+        };
+        vec![
+            with_suffix(self.pks(), &[&[b'k'][..], &first_index[..]].concat()),
+            with_suffix(self.pk_for_key_id(), &first_index),
+            with_suffix(
+                self.allowed_hashes(),
+                &[&[b'e'][..], &first_index[..]].concat(),
+            ),
+            with_suffix(
+                self.claims_per_account(),
+                &[&[b'k'][..], &first_index[..]].concat(),
+            ),
+            self.metadata().into_storage_key(),
+            with_suffix(self.token_ids(), &first_index),
+        ]
+    }
+}
+
+impl Keypom {
+    /// Take a fresh salt for a new drop's collections and make sure nothing is stored under its prefixes
+    pub(crate) fn internal_new_drop_storage_keys(&mut self) -> DropStorageKeys {
+        let keys = DropStorageKeys {
+            salt: self.next_storage_salt,
+        };
+        self.next_storage_salt += 1;
+
+        require!(
+            keys.first_entries()
+                .iter()
+                .all(|key| !env::storage_has_key(key)),
+            "storage prefix for the new drop is already in use"
+        );
+        keys
+    }
+}
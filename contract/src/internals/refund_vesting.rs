@@ -0,0 +1,222 @@
+use crate::*;
+
+/// How an account wants large drop refunds to be released to its balance
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RefundVestingConfig {
+    // How long a refund takes to vest in full (in nanoseconds)
+    pub duration_ns: U64,
+    // Refunds up to this amount are credited to the balance right away
+    pub threshold: U128,
+}
+
+/// Refunds being released linearly to an account's balance between `start` and `end`
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct RefundVesting {
+    pub start: u64,
+    pub end: u64,
+    // Total being vested and how much of it was already released to the balance
+    pub amount: Balance,
+    pub released: Balance,
+}
+
+/*
+    Vesting records are stored versioned so that their layout can change in an upgrade without migrating every
+    record. New versions are added as variants and converted into the latest `RefundVesting` when read.
+*/
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum VersionedRefundVesting {
+    V1(RefundVesting),
+}
+
+impl From<VersionedRefundVesting> for RefundVesting {
+    fn from(versioned: VersionedRefundVesting) -> Self {
+        match versioned {
+            VersionedRefundVesting::V1(vesting) => vesting,
+        }
+    }
+}
+
+impl RefundVesting {
+    /// Amount vested by `now`, including what was already released
+    fn vested(&self, now: u64) -> Balance {
+        if now >= self.end {
+            return self.amount;
+        }
+        if now <= self.start {
+            return 0;
+        }
+        // Split the amount so that multiplying by the elapsed time can't overflow
+        let duration = (self.end - self.start) as u128;
+        let elapsed = (now - self.start) as u128;
+        self.amount / duration * elapsed + self.amount % duration * elapsed / duration
+    }
+
+    /// Amount vested by `now` that hasn't been released yet
+    fn claimable(&self, now: u64) -> Balance {
+        self.vested(now) - self.released
+    }
+}
+
+/// Vesting schedule returned by views
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonRefundVesting {
+    pub start: U64,
+    pub end: U64,
+    pub amount: U128,
+    pub released: U128,
+    // What `claim_vested_refund` would release right now
+    pub claimable: U128,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Have refunds larger than `threshold` from deleting keys vest linearly over `duration_ns` instead of being
+        credited right away. Passing in None turns it off for future refunds. Refunds already vesting keep their
+        schedule. The setting's storage comes out of the account's balance and is credited back once it's cleared.
+    */
+    pub fn set_refund_vesting(&mut self, config: Option<RefundVestingConfig>) {
+        let account_id = env::predecessor_account_id();
+
+        let initial_storage = env::storage_usage();
+        match config {
+            Some(config) => {
+                require!(config.duration_ns.0 > 0, "duration_ns must be more than 0");
+                self.refund_vesting_configs.insert(&account_id, &config);
+            }
+            None => {
+                self.refund_vesting_configs.remove(&account_id);
+            }
+        }
+        let final_storage = env::storage_usage();
+
+        if final_storage > initial_storage {
+            let storage_cost =
+                Balance::from(final_storage - initial_storage) * env::storage_byte_cost();
+            self.internal_charge_balance(&account_id, storage_cost, 0);
+        } else if final_storage < initial_storage {
+            let storage_freed =
+                Balance::from(initial_storage - final_storage) * env::storage_byte_cost();
+            let balance = self.user_balances.get(&account_id).unwrap_or(0) + storage_freed;
+            self.internal_set_user_balance(&account_id, balance);
+        }
+    }
+
+    /// Release the part of the account's vesting refunds that has matured to its balance. Returns the amount released.
+    pub fn claim_vested_refund(&mut self) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let mut vesting: RefundVesting = self
+            .refund_vestings
+            .get(&account_id)
+            .expect("no refund vesting for account")
+            .into();
+
+        let initial_storage = env::storage_usage();
+        let claimable = vesting.claimable(env::block_timestamp());
+        vesting.released += claimable;
+        if vesting.released == vesting.amount {
+            self.refund_vestings.remove(&account_id);
+        } else {
+            self.refund_vestings
+                .insert(&account_id, &VersionedRefundVesting::V1(vesting));
+        }
+        self.total_vesting_refunds -= claimable;
+
+        // The record's storage is credited back once everything has been released
+        let storage_freed = Balance::from(initial_storage.saturating_sub(env::storage_usage()))
+            * env::storage_byte_cost();
+        let balance = self.user_balances.get(&account_id).unwrap_or(0) + claimable + storage_freed;
+        self.internal_set_user_balance(&account_id, balance);
+
+        near_sdk::log!(
+            "Released {} of vesting refunds. Storage freed: {}",
+            yocto_to_near(claimable),
+            yocto_to_near(storage_freed)
+        );
+        U128(claimable)
+    }
+
+    /// Returns how an account wants large refunds released, if it set it
+    pub fn get_refund_vesting_config(&self, account_id: AccountId) -> Option<RefundVestingConfig> {
+        self.refund_vesting_configs.get(&account_id)
+    }
+
+    /// Returns the account's vesting refunds along with what can be claimed right now
+    pub fn get_refund_vesting(&self, account_id: AccountId) -> Option<JsonRefundVesting> {
+        let vesting: RefundVesting = self.refund_vestings.get(&account_id)?.into();
+        Some(JsonRefundVesting {
+            start: U64(vesting.start),
+            end: U64(vesting.end),
+            amount: U128(vesting.amount),
+            released: U128(vesting.released),
+            claimable: U128(vesting.claimable(env::block_timestamp())),
+        })
+    }
+}
+
+impl Keypom {
+    /*
+        Credit a refund to the account's balance, or vest it if the account asked for refunds above a threshold to
+        vest. A new vesting refund restarts the schedule: whatever already matured is credited right away and the
+        rest is vested along with the new refund over the full duration from now. The record's storage is paid
+        out of the refund.
+    */
+    pub(crate) fn internal_credit_refund(&mut self, account_id: &AccountId, amount: Balance) {
+        let config = match self.refund_vesting_configs.get(account_id) {
+            Some(config) if amount > config.threshold.0 => config,
+            _ => {
+                let balance = self.user_balances.get(account_id).unwrap_or(0) + amount;
+                self.internal_set_user_balance(account_id, balance);
+                return;
+            }
+        };
+
+        let now = env::block_timestamp();
+        let (matured, unvested) = match self.refund_vestings.get(account_id) {
+            Some(old) => {
+                let old = RefundVesting::from(old);
+                (old.claimable(now), old.amount - old.vested(now))
+            }
+            None => (0, 0),
+        };
+        let mut vesting = RefundVesting {
+            start: now,
+            end: now.saturating_add(config.duration_ns.0),
+            amount: unvested + amount,
+            released: 0,
+        };
+
+        // Measure the record's storage so it can be paid for out of the refund
+        let initial_storage = env::storage_usage();
+        self.refund_vestings
+            .insert(account_id, &VersionedRefundVesting::V1(vesting.clone()));
+        let storage_cost = Balance::from(env::storage_usage().saturating_sub(initial_storage))
+            * env::storage_byte_cost();
+        // Only a new record takes storage. If the refund can't pay for it, it's credited right away instead.
+        if storage_cost >= amount {
+            self.refund_vestings.remove(account_id);
+            let balance = self.user_balances.get(account_id).unwrap_or(0) + amount;
+            self.internal_set_user_balance(account_id, balance);
+            return;
+        }
+        vesting.amount -= storage_cost;
+        self.refund_vestings
+            .insert(account_id, &VersionedRefundVesting::V1(vesting.clone()));
+
+        // The old record's matured part is credited and everything else it held is now in the new one
+        self.total_vesting_refunds = self.total_vesting_refunds + amount - storage_cost - matured;
+        near_sdk::log!(
+            "Vesting refund of {} until {}. Now vesting: {}. Released matured refunds: {}",
+            yocto_to_near(amount - storage_cost),
+            format_iso_timestamp(vesting.end),
+            yocto_to_near(vesting.amount),
+            yocto_to_near(matured)
+        );
+        if matured > 0 {
+            let balance = self.user_balances.get(account_id).unwrap_or(0) + matured;
+            self.internal_set_user_balance(account_id, balance);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::*;
+
+/// What the contract owes compared to the balance it has to pay it with
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SolvencyReport {
+    // Balance of the contract account
+    pub account_balance: U128,
+    // Part of the balance locked for the contract's storage
+    pub storage_staked: U128,
+    // Sum of every account's balance on the contract
+    pub user_balances: U128,
+    // Refunds still vesting for accounts that set up refund vesting
+    pub vesting_refunds: U128,
+    // Unspent allowance and deposits for the remaining uses of every key
+    pub key_reserves: U128,
+    // Fees the owner hasn't withdrawn yet
+    pub fees_collected: U128,
+    // Everything above that the contract owes
+    pub liabilities: U128,
+    // Whether the balance left after storage covers every liability
+    pub fully_backed: bool,
+}
+
+/*
+    NEAR the contract holds for a key: its unspent allowance plus the deposit for each of its remaining uses. Uses of
+    FC drops with no method still count a deposit so the reserve never understates what's owed. Other per-use costs
+    (FC attached deposits, asset storage and the extras from the drop config) aren't included.
+*/
+pub(crate) fn key_reserve(deposit_per_use: Balance, key_info: &KeyInfo) -> Balance {
+    key_info.allowance + deposit_per_use * key_info.remaining_uses as u128
+}
+
+impl Keypom {
+    /*
+        Set an account's balance and keep the running total of user balances in sync. Every change to a user
+        balance must go through this (or `internal_remove_user_balance`) so that the solvency report stays exact.
+    */
+    pub(crate) fn internal_set_user_balance(&mut self, account_id: &AccountId, balance: Balance) {
+        let old_balance = self.user_balances.insert(account_id, &balance).unwrap_or(0);
+        self.total_user_balances = self.total_user_balances.saturating_sub(old_balance) + balance;
+        self.internal_check_solvency();
+    }
+
+    /// Remove an account's balance entirely and return what it was
+    pub(crate) fn internal_remove_user_balance(&mut self, account_id: &AccountId) -> Balance {
+        let balance = self.user_balances.remove(account_id).unwrap_or(0);
+        self.total_user_balances = self.total_user_balances.saturating_sub(balance);
+        balance
+    }
+
+    /*
+        Insert a key into a drop's key map and keep the running total of key reserves in sync. Every change to a
+        key must go through this (or the remove and clear helpers below) so that the solvency report stays exact.
+    */
+    pub(crate) fn internal_insert_key_info(
+        &mut self,
+        pks: &mut UnorderedMap<PublicKey, KeyInfo>,
+        deposit_per_use: Balance,
+        public_key: &PublicKey,
+        key_info: &KeyInfo,
+    ) {
+        let old_reserve = pks
+            .insert(public_key, key_info)
+            .map(|old| key_reserve(deposit_per_use, &old))
+            .unwrap_or(0);
+        self.total_key_reserves = self.total_key_reserves.saturating_sub(old_reserve)
+            + key_reserve(deposit_per_use, key_info);
+    }
+
+    /// Remove a key from a drop's key map and release its reserve
+    pub(crate) fn internal_remove_key_info(
+        &mut self,
+        pks: &mut UnorderedMap<PublicKey, KeyInfo>,
+        deposit_per_use: Balance,
+        public_key: &PublicKey,
+    ) -> Option<KeyInfo> {
+        let key_info = pks.remove(public_key)?;
+        self.total_key_reserves = self
+            .total_key_reserves
+            .saturating_sub(key_reserve(deposit_per_use, &key_info));
+        Some(key_info)
+    }
+
+    /// Remove every key from a drop's key map and release their reserves
+    pub(crate) fn internal_clear_key_infos(
+        &mut self,
+        pks: &mut UnorderedMap<PublicKey, KeyInfo>,
+        deposit_per_use: Balance,
+    ) {
+        let reserves: Balance = pks
+            .values()
+            .map(|key_info| key_reserve(deposit_per_use, &key_info))
+            .sum();
+        pks.clear();
+        self.total_key_reserves = self.total_key_reserves.saturating_sub(reserves);
+    }
+
+    /// Emit a `solvency_warning` event if the contract now owes more than the balance it has left after storage
+    pub(crate) fn internal_check_solvency(&self) {
+        let report = self.get_solvency_report();
+        if !report.fully_backed {
+            emit_event(EventLogVariant::SolvencyWarning(vec![SolvencyWarningLog {
+                liabilities: report.liabilities,
+                available: U128(
+                    report
+                        .account_balance
+                        .0
+                        .saturating_sub(report.storage_staked.0),
+                ),
+            }]));
+        }
+    }
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Compare what the contract owes (every user balance, refunds still vesting, the reserves still held for keys
+        and the fees the owner hasn't withdrawn) against its balance left after the storage staking requirement. The totals are running
+        counters kept in sync with every balance and key change so this doesn't need to go through the drops.
+    */
+    pub fn get_solvency_report(&self) -> SolvencyReport {
+        let account_balance = env::account_balance();
+        let storage_staked = Balance::from(env::storage_usage()) * env::storage_byte_cost();
+        let liabilities = self.total_user_balances
+            + self.total_vesting_refunds
+            + self.total_key_reserves
+            + self.fees_collected;
+
+        SolvencyReport {
+            account_balance: U128(account_balance),
+            storage_staked: U128(storage_staked),
+            user_balances: U128(self.total_user_balances),
+            vesting_refunds: U128(self.total_vesting_refunds),
+            key_reserves: U128(self.total_key_reserves),
+            fees_collected: U128(self.fees_collected),
+            liabilities: U128(liabilities),
+            fully_backed: account_balance.saturating_sub(storage_staked) >= liabilities,
+        }
+    }
+}
@@ -16,26 +16,151 @@ impl Keypom {
         // Add the attached_deposit to their balance
         balance += attached_deposit;
         // Insert the balance back into the map for that account ID
-        self.user_balances
-            .insert(&env::predecessor_account_id(), &balance);
+        self.internal_set_user_balance(&env::predecessor_account_id(), balance);
     }
 
-    // Allows users to withdraw their balance
+    // Allows users to withdraw part of their balance or all of it if no amount is passed in
     #[payable]
-    pub fn withdraw_from_balance(&mut self) {
+    pub fn withdraw_from_balance(&mut self, amount: Option<U128>) {
+        assert_one_yocto();
+
         // the account to withdraw storage to is always the predecessor
         let owner_id = env::predecessor_account_id();
-        //get the amount that the user has by removing them from the map. If they're not in the map, default to 0
-        let amount = self.user_balances.remove(&owner_id).unwrap_or(0);
+        let balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        let amount = amount.map(|a| a.0).unwrap_or(balance);
+        require!(amount <= balance, "cannot withdraw more than the balance");
+        if amount == 0 {
+            return;
+        }
+
+        // The entry is removed once the balance is emptied to free up its storage
+        if amount == balance {
+            self.internal_remove_user_balance(&owner_id);
+        } else {
+            self.internal_set_user_balance(&owner_id, balance - amount);
+        }
+
+        //transfer the amount to the user. If the contract can't spare it yet, it stays in their balance.
+        if let Some(transfer) = self.internal_transfer_or_defer(owner_id.clone(), amount) {
+            transfer.then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MIN_GAS_FOR_RESOLVE_WITHDRAW)
+                    .resolve_withdraw(owner_id, U128(amount)),
+            );
+        }
+    }
 
-        //if that excess to withdraw is > 0, we transfer the amount to the user.
-        if amount > 0 {
-            Promise::new(owner_id.clone()).transfer(amount);
+    #[private]
+    /// Self callback credits the amount back to the balance if the withdrawal transfer failed. Returns whether it went through.
+    pub fn resolve_withdraw(&mut self, account_id: AccountId, amount: U128) -> bool {
+        if matches!(env::promise_result(0), PromiseResult::Successful(_)) {
+            return true;
         }
+
+        near_sdk::log!(
+            "Withdrawal of {} to {} failed. Crediting it back to the balance.",
+            yocto_to_near(amount.0),
+            account_id
+        );
+        let balance = self.user_balances.get(&account_id).unwrap_or(0) + amount.0;
+        self.internal_set_user_balance(&account_id, balance);
+        false
     }
 
     // Return the current balance for a given account
     pub fn get_user_balance(&self, account_id: AccountId) -> U128 {
         U128(self.user_balances.get(&account_id).unwrap_or(0))
     }
+
+    /// Emit a `balance_low` event whenever an operation leaves the caller's balance below the threshold. Passing in 0 clears the alert.
+    pub fn set_balance_alert(&mut self, threshold: U128) {
+        let account_id = env::predecessor_account_id();
+
+        // The alert's storage comes out of the account's balance and is credited back once it's cleared
+        let initial_storage = env::storage_usage();
+        if threshold.0 == 0 {
+            self.balance_alerts.remove(&account_id);
+        } else {
+            self.balance_alerts.insert(&account_id, &threshold.0);
+        }
+        let final_storage = env::storage_usage();
+
+        if final_storage > initial_storage {
+            let storage_cost =
+                Balance::from(final_storage - initial_storage) * env::storage_byte_cost();
+            self.internal_charge_balance(&account_id, storage_cost, 0);
+        } else if final_storage < initial_storage {
+            let storage_freed =
+                Balance::from(initial_storage - final_storage) * env::storage_byte_cost();
+            let balance = self.user_balances.get(&account_id).unwrap_or(0) + storage_freed;
+            self.internal_set_user_balance(&account_id, balance);
+        }
+    }
+
+    /// Return the balance alert threshold for a given account, if it set one
+    pub fn get_balance_alert(&self, account_id: AccountId) -> Option<U128> {
+        self.balance_alerts.get(&account_id).map(U128)
+    }
+
+    /// Emit a `balance_low` event if the account's balance was left below the threshold it set
+    pub(crate) fn internal_check_balance_alert(&self, account_id: &AccountId, balance: Balance) {
+        if let Some(threshold) = self.balance_alerts.get(account_id) {
+            if balance < threshold {
+                emit_event(EventLogVariant::BalanceLow(vec![BalanceLowLog {
+                    account_id: account_id.clone(),
+                    balance: U128(balance),
+                    threshold: U128(threshold),
+                }]));
+            }
+        }
+    }
+
+    /// Add the deposit attached to a call to the account's balance, creating its entry if it doesn't exist yet
+    pub(crate) fn internal_credit_attached_deposit(
+        &mut self,
+        account_id: &AccountId,
+        attached_deposit: Balance,
+    ) -> Balance {
+        let balance = self.user_balances.get(account_id).unwrap_or(0) + attached_deposit;
+        if attached_deposit > 0 {
+            self.internal_set_user_balance(account_id, balance);
+        }
+        balance
+    }
+
+    /// Take the required amount out of the account's balance and return what's left. Panics with the shortfall if it's not enough.
+    pub(crate) fn internal_charge_balance(
+        &mut self,
+        account_id: &AccountId,
+        required: Balance,
+        attached_deposit: Balance,
+    ) -> Balance {
+        let balance = self.user_balances.get(account_id).unwrap_or(0);
+        if balance < required {
+            env::panic_str(&insufficient_balance_message(
+                balance,
+                required,
+                attached_deposit,
+            ));
+        }
+        let remaining = balance - required;
+        self.internal_set_user_balance(account_id, remaining);
+        self.internal_check_balance_alert(account_id, remaining);
+        remaining
+    }
+}
+
+/// Error for a balance that can't cover a cost. The balance passed in already includes any deposit attached to the call.
+pub(crate) fn insufficient_balance_message(
+    balance: Balance,
+    required: Balance,
+    attached_deposit: Balance,
+) -> String {
+    format!(
+        "Insufficient balance, need {} more yoctoNEAR. Balance: {} (including {} attached), required: {}",
+        required - balance,
+        balance,
+        attached_deposit,
+        required
+    )
 }
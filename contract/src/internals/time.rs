@@ -0,0 +1,339 @@
+use crate::*;
+use near_sdk::serde::de::Error;
+use near_sdk::serde::Deserializer;
+use std::convert::TryFrom;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+const SECONDS_PER_MINUTE: u64 = 60;
+const SECONDS_PER_HOUR: u64 = 60 * SECONDS_PER_MINUTE;
+const SECONDS_PER_DAY: u64 = 24 * SECONDS_PER_HOUR;
+/// Timestamps and intervals further out than this are almost always in the wrong unit (i.e microseconds passed as nanoseconds)
+pub(crate) const MAX_TIME_HORIZON: u64 = 50 * 365 * SECONDS_PER_DAY * NANOS_PER_SECOND;
+
+/// A time passed into a config. Either nanoseconds (as a number or a string of digits) or an ISO-8601 string.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(untagged)]
+enum TimeInput {
+    Nanos(u64),
+    Text(String),
+}
+
+/// Deserialize a timestamp that's either nanoseconds since the Unix epoch or an ISO-8601 date (i.e "2023-01-31T18:00:00Z")
+pub(crate) fn deserialize_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    match Option::<TimeInput>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(TimeInput::Nanos(nanos)) => Ok(Some(nanos)),
+        Some(TimeInput::Text(text)) => match text.parse::<u64>() {
+            Ok(nanos) => Ok(Some(nanos)),
+            Err(_) => parse_iso_timestamp(&text)
+                .map(Some)
+                .map_err(D::Error::custom),
+        },
+    }
+}
+
+/// Deserialize an interval that's either in nanoseconds or an ISO-8601 duration (i.e "PT1H30M")
+pub(crate) fn deserialize_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    match Option::<TimeInput>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(TimeInput::Nanos(nanos)) => Ok(Some(nanos)),
+        Some(TimeInput::Text(text)) => match text.parse::<u64>() {
+            Ok(nanos) => Ok(Some(nanos)),
+            Err(_) => parse_iso_duration(&text)
+                .map(Some)
+                .map_err(D::Error::custom),
+        },
+    }
+}
+
+/// Days since the Unix epoch for a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u64, day: u64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let shifted_month = (month as i64 + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Date for a number of days since the Unix epoch as (year, month, day)
+fn civil_from_days(days: i64) -> (i64, u64, u64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u64;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u64;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u64) -> u64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parse a fixed width number out of a string
+fn parse_digits(s: &str, what: &str) -> Result<u64, String> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("invalid {} in timestamp", what));
+    }
+    s.parse()
+        .map_err(|_| format!("invalid {} in timestamp", what))
+}
+
+/*
+    Parse an ISO-8601 date with an optional time and UTC offset into nanoseconds since the Unix epoch.
+    Accepts "YYYY-MM-DD", "YYYY-MM-DDTHH:MM", "YYYY-MM-DDTHH:MM:SS" and fractional seconds, followed by
+    "Z" or an offset such as "+02:00". Times without an offset are read as UTC.
+*/
+pub(crate) fn parse_iso_timestamp(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    if s.len() < 10 || !s.is_char_boundary(10) {
+        return Err(format!("{} isn't an ISO-8601 timestamp", s));
+    }
+    let (date, rest) = s.split_at(10);
+    let date_parts: Vec<&str> = date.split('-').collect();
+    if date_parts.len() != 3 || date_parts[0].len() != 4 {
+        return Err(format!("{} isn't an ISO-8601 date", date));
+    }
+    let year = parse_digits(date_parts[0], "year")? as i64;
+    let month = parse_digits(date_parts[1], "month")?;
+    let day = parse_digits(date_parts[2], "day")?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err(format!("{} isn't a valid date", date));
+    }
+
+    let mut seconds = 0;
+    let mut nanos = 0;
+    let mut offset_seconds: i64 = 0;
+    if !rest.is_empty() {
+        let rest = rest
+            .strip_prefix('T')
+            .or_else(|| rest.strip_prefix(' '))
+            .ok_or_else(|| format!("{} isn't an ISO-8601 timestamp", s))?;
+
+        // Split the time from the UTC offset
+        let (time, offset) = match rest.find(['Z', '+', '-']) {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+        let time_parts: Vec<&str> = time.split(':').collect();
+        if time_parts.len() < 2 || time_parts.len() > 3 {
+            return Err(format!("{} isn't an ISO-8601 time", time));
+        }
+        let hour = parse_digits(time_parts[0], "hour")?;
+        let minute = parse_digits(time_parts[1], "minute")?;
+        let (second, fraction) = match time_parts.get(2) {
+            Some(part) => match part.split_once('.') {
+                Some((second, fraction)) => (parse_digits(second, "second")?, fraction),
+                None => (parse_digits(part, "second")?, ""),
+            },
+            None => (0, ""),
+        };
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(format!("{} isn't a valid time", time));
+        }
+        seconds = hour * SECONDS_PER_HOUR + minute * SECONDS_PER_MINUTE + second;
+        if !fraction.is_empty() {
+            // Only the first 9 digits are significant for nanoseconds
+            let digits = &fraction[..fraction.len().min(9)];
+            if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(format!("{} isn't a valid time", time));
+            }
+            nanos = parse_digits(digits, "fractional second")? * 10u64.pow(9 - digits.len() as u32);
+        }
+
+        match offset {
+            "" | "Z" => {}
+            _ => {
+                let sign = if offset.starts_with('-') { -1 } else { 1 };
+                let offset = offset[1..].replace(':', "");
+                if offset.len() != 4 || !offset.is_ascii() {
+                    return Err(format!("{} isn't a valid UTC offset", offset));
+                }
+                let hours = parse_digits(&offset[..2], "offset")?;
+                let minutes = parse_digits(&offset[2..], "offset")?;
+                offset_seconds =
+                    sign * (hours * SECONDS_PER_HOUR + minutes * SECONDS_PER_MINUTE) as i64;
+            }
+        }
+    }
+
+    let total_seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY as i64 + seconds as i64
+        - offset_seconds;
+    if total_seconds < 0 {
+        return Err(format!("{} is before the Unix epoch", s));
+    }
+    (total_seconds as u64)
+        .checked_mul(NANOS_PER_SECOND)
+        .and_then(|n| n.checked_add(nanos))
+        .ok_or_else(|| format!("{} is too far in the future", s))
+}
+
+/*
+    Parse an ISO-8601 duration into nanoseconds. Weeks, days, hours, minutes and (fractional) seconds are
+    supported, i.e "P1W", "P1DT12H" or "PT30M". Years and months are rejected since their length varies.
+*/
+pub(crate) fn parse_iso_duration(s: &str) -> Result<u64, String> {
+    let body = s
+        .trim()
+        .strip_prefix('P')
+        .ok_or_else(|| format!("{} isn't an ISO-8601 duration", s))?;
+    let (date, time) = match body.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, time),
+        Some(_) => return Err(format!("{} has no time after T", s)),
+        None => (body, ""),
+    };
+    if date.is_empty() && time.is_empty() {
+        return Err(format!("{} is an empty duration", s));
+    }
+
+    let mut nanos: u128 = 0;
+    for (part, in_time) in [(date, false), (time, true)] {
+        let mut number = String::new();
+        for c in part.chars() {
+            if c.is_ascii_digit() || c == '.' {
+                number.push(c);
+                continue;
+            }
+            let unit_seconds = match (c, in_time) {
+                ('W', false) => 7 * SECONDS_PER_DAY,
+                ('D', false) => SECONDS_PER_DAY,
+                ('H', true) => SECONDS_PER_HOUR,
+                ('M', true) => SECONDS_PER_MINUTE,
+                ('S', true) => 1,
+                ('Y', false) | ('M', false) => {
+                    return Err("durations in years or months aren't supported".to_string())
+                }
+                _ => return Err(format!("{} isn't an ISO-8601 duration", s)),
+            };
+            let (whole, fraction) = number.split_once('.').unwrap_or((&number, ""));
+            if whole.is_empty() || (!fraction.is_empty() && c != 'S') {
+                return Err(format!("{} isn't an ISO-8601 duration", s));
+            }
+            let whole = parse_digits(whole, "duration")? as u128;
+            nanos += whole * (unit_seconds * NANOS_PER_SECOND) as u128;
+            if !fraction.is_empty() {
+                let digits = &fraction[..fraction.len().min(9)];
+                nanos += (parse_digits(digits, "duration")? * 10u64.pow(9 - digits.len() as u32))
+                    as u128;
+            }
+            number.clear();
+        }
+        if !number.is_empty() {
+            return Err(format!("{} isn't an ISO-8601 duration", s));
+        }
+    }
+
+    u64::try_from(nanos).map_err(|_| format!("{} is too long", s))
+}
+
+/// Best effort UTC rendering of a timestamp in nanoseconds, i.e "2023-01-31T18:00:00Z"
+pub(crate) fn format_iso_timestamp(nanos: u64) -> String {
+    let total_seconds = nanos / NANOS_PER_SECOND;
+    let (year, month, day) = civil_from_days((total_seconds / SECONDS_PER_DAY) as i64);
+    let seconds = total_seconds % SECONDS_PER_DAY;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / SECONDS_PER_HOUR,
+        seconds % SECONDS_PER_HOUR / SECONDS_PER_MINUTE,
+        seconds % SECONDS_PER_MINUTE
+    )
+}
+
+/// Best effort rendering of an interval in nanoseconds as an ISO-8601 duration, i.e "P1DT12H"
+pub(crate) fn format_iso_duration(nanos: u64) -> String {
+    let total_seconds = nanos / NANOS_PER_SECOND;
+    let days = total_seconds / SECONDS_PER_DAY;
+    let hours = total_seconds % SECONDS_PER_DAY / SECONDS_PER_HOUR;
+    let minutes = total_seconds % SECONDS_PER_HOUR / SECONDS_PER_MINUTE;
+    let seconds = total_seconds % SECONDS_PER_MINUTE;
+    let sub_second = nanos % NANOS_PER_SECOND;
+
+    let mut duration = "P".to_string();
+    if days > 0 {
+        duration.push_str(&format!("{}D", days));
+    }
+    if hours > 0 || minutes > 0 || seconds > 0 || sub_second > 0 || days == 0 {
+        duration.push('T');
+        if hours > 0 {
+            duration.push_str(&format!("{}H", hours));
+        }
+        if minutes > 0 {
+            duration.push_str(&format!("{}M", minutes));
+        }
+        if sub_second > 0 {
+            let fraction = format!("{:09}", sub_second);
+            duration.push_str(&format!("{}.{}S", seconds, fraction.trim_end_matches('0')));
+        } else if seconds > 0 || (hours == 0 && minutes == 0) {
+            duration.push_str(&format!("{}S", seconds));
+        }
+    }
+    duration
+}
+
+/// The end timestamp must come after the start timestamp
+pub(crate) fn check_end_timestamp(config: &Option<DropConfig>) -> Result<(), String> {
+    let config = match config {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+    match (config.start_timestamp, config.end_timestamp) {
+        (Some(start), Some(end)) if end <= start => Err(format!(
+            "end_timestamp {} must be after start_timestamp {}",
+            format_iso_timestamp(end),
+            format_iso_timestamp(start)
+        )),
+        _ => Ok(()),
+    }
+}
+
+/*
+    Reject start timestamps and intervals more than MAX_TIME_HORIZON out, which usually means the value was
+    passed in the wrong unit. Drops that really need them can set `allow_distant_timestamps`.
+*/
+pub(crate) fn check_config_timestamps(config: &Option<DropConfig>) -> Result<(), String> {
+    let config = match config {
+        Some(config) if !config.allow_distant_timestamps.unwrap_or(false) => config,
+        _ => return Ok(()),
+    };
+
+    if let Some(start) = config.start_timestamp {
+        if start > env::block_timestamp().saturating_add(MAX_TIME_HORIZON) {
+            return Err(format!(
+                "start_timestamp {} is more than 50 years away. Make sure it's in nanoseconds or set allow_distant_timestamps.",
+                format_iso_timestamp(start)
+            ));
+        }
+    }
+    if let Some(interval) = config.throttle_timestamp {
+        if interval > MAX_TIME_HORIZON {
+            return Err(format!(
+                "throttle_timestamp {} is more than 50 years. Make sure it's in nanoseconds or set allow_distant_timestamps.",
+                format_iso_duration(interval)
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use crate::*;
+
+/// `msg` to pass to `ft_transfer_call` on the wrap contract to top up the sender's balance with wNEAR
+pub(crate) const ADD_TO_BALANCE_MSG: &str = "add_to_balance";
+
+/// Stage of a wNEAR top-up reported in `wrapped_deposit` events
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum WrappedDepositStage {
+    Pending,
+    Credited,
+    Returned,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /// Set the wrap contract (i.e wrap.near) whose tokens can top up balances. Passing in None stops accepting top-ups.
+    #[payable]
+    pub fn set_wrap_contract(&mut self, wrap_contract: Option<AccountId>) {
+        self.assert_owner_and_record("set_wrap_contract");
+        near_sdk::log!("Wrap contract set to {:?}", wrap_contract);
+        self.wrap_contract = wrap_contract;
+    }
+
+    #[private]
+    /*
+        Self callback credits the sender's balance once the wNEAR was unwrapped. If the unwrap failed, the wNEAR
+        is still held by this contract and the full amount is returned for the wrap contract to refund the sender.
+    */
+    pub fn resolve_wrapped_deposit(
+        &mut self,
+        wrap_contract: AccountId,
+        sender_id: AccountId,
+        amount: U128,
+    ) -> U128 {
+        self.internal_release_ft(&wrap_contract, amount.0);
+        let pending = self
+            .pending_wrapped_deposits
+            .get(&sender_id)
+            .unwrap_or(0)
+            .saturating_sub(amount.0);
+        if pending == 0 {
+            self.pending_wrapped_deposits.remove(&sender_id);
+        } else {
+            self.pending_wrapped_deposits.insert(&sender_id, &pending);
+        }
+
+        if !matches!(env::promise_result(0), PromiseResult::Successful(_)) {
+            near_sdk::log!(
+                "Unwrapping {} failed. Returning the wNEAR to {}",
+                yocto_to_near(amount.0),
+                sender_id
+            );
+            emit_wrapped_deposit_event(
+                sender_id,
+                wrap_contract,
+                amount,
+                WrappedDepositStage::Returned,
+            );
+            return amount;
+        }
+
+        let balance = self.user_balances.get(&sender_id).unwrap_or(0) + amount.0;
+        self.internal_set_user_balance(&sender_id, balance);
+        near_sdk::log!(
+            "Unwrapped {} and added it to the balance of {}",
+            yocto_to_near(amount.0),
+            sender_id
+        );
+        emit_wrapped_deposit_event(
+            sender_id,
+            wrap_contract,
+            amount,
+            WrappedDepositStage::Credited,
+        );
+        U128(0)
+    }
+
+    /// Returns the wrap contract whose tokens can top up balances, if one is set
+    pub fn get_wrap_contract(&self) -> Option<AccountId> {
+        self.wrap_contract.clone()
+    }
+
+    /// Returns the wNEAR an account sent to top up its balance that hasn't been unwrapped yet
+    pub fn get_pending_wrapped_deposit(&self, account_id: AccountId) -> U128 {
+        U128(self.pending_wrapped_deposits.get(&account_id).unwrap_or(0))
+    }
+}
+
+impl Keypom {
+    /*
+        Top up the sender's balance with wNEAR sent through `ft_transfer_call` with the `add_to_balance` msg. Tokens
+        from any other contract are returned in full. The wNEAR is unwrapped with `near_withdraw` and held as
+        pending until the $NEAR arrives so that it can't be spent before then. The pending entry's storage is held
+        by the contract until the unwrap resolves.
+    */
+    pub(crate) fn internal_add_wrapped_to_balance(
+        &mut self,
+        contract_id: AccountId,
+        sender_id: AccountId,
+        amount: U128,
+    ) -> PromiseOrValue<U128> {
+        if self.wrap_contract.as_ref() != Some(&contract_id) {
+            near_sdk::log!(
+                "Only the wrap contract's tokens can top up balances. Returning {} tokens",
+                amount.0
+            );
+            return PromiseOrValue::Value(amount);
+        }
+
+        let pending = self.pending_wrapped_deposits.get(&sender_id).unwrap_or(0) + amount.0;
+        self.pending_wrapped_deposits.insert(&sender_id, &pending);
+        // Count the wNEAR as held while it's unwrapped so that verified transfers don't mistake it for tokens sent to a drop
+        self.internal_hold_ft(&contract_id, amount.0);
+        emit_wrapped_deposit_event(
+            sender_id.clone(),
+            contract_id.clone(),
+            amount,
+            WrappedDepositStage::Pending,
+        );
+
+        PromiseOrValue::Promise(
+            Promise::new(contract_id.clone())
+                .function_call(
+                    "near_withdraw".to_string(),
+                    json!({ "amount": amount }).to_string().into_bytes(),
+                    1,
+                    GAS_FOR_NEAR_WITHDRAW,
+                )
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(MIN_GAS_FOR_RESOLVE_WRAPPED_DEPOSIT)
+                        .resolve_wrapped_deposit(contract_id, sender_id, amount),
+                ),
+        )
+    }
+}
+
+fn emit_wrapped_deposit_event(
+    account_id: AccountId,
+    wrap_contract: AccountId,
+    amount: U128,
+    stage: WrappedDepositStage,
+) {
+    emit_event(EventLogVariant::WrappedDeposit(vec![WrappedDepositLog {
+        account_id,
+        wrap_contract,
+        amount,
+        stage,
+    }]));
+}
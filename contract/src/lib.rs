@@ -1,12 +1,12 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::serde_json::json;
 use near_sdk::{
-    env, ext_contract, near_bindgen, promise_result_as_success, require, AccountId, Balance,
-    BorshStorageKey, CryptoHash, Gas, PanicOnDefault, Promise, PromiseOrValue, PromiseResult,
-    PublicKey,
+    assert_one_yocto, env, ext_contract, near_bindgen, promise_result_as_success, require,
+    AccountId, Balance, BorshStorageKey, CryptoHash, Gas, PanicOnDefault, Promise, PromiseIndex,
+    PromiseOrValue, PromiseResult, PublicKey,
 };
 
 /*
@@ -15,8 +15,9 @@ use near_sdk::{
 const ACCESS_KEY_STORAGE: u128 = 1_000_000_000_000_000_000_000; // 0.001 N
 
 /*
-    minimum amount of NEAR that a new account (with longest possible name) must have when created
+    default minimum amount of NEAR that a new account (with longest possible name) must have when created
     If this is less, it will throw a lack balance for state error (assuming you have the same account ID length)
+    The owner can change the amount actually used through `set_account_creation_cost` as the protocol config changes
 */
 const NEW_ACCOUNT_BASE: u128 = 2_840_000_000_000_000_000_000; // 0.00284 N
 
@@ -33,32 +34,115 @@ const MIN_GAS_FOR_SIMPLE_NFT_TRANSFER: Gas = Gas(10_000_000_000_000); // 10 TGas
 const MIN_GAS_FOR_RESOLVE_TRANSFER: Gas =
     Gas(15_000_000_000_000 + MIN_GAS_FOR_SIMPLE_NFT_TRANSFER.0); // 15 TGas + 10 TGas = 25 TGas
 
+// Each token of a pack beyond the first is transferred and may have to be sent back to the sender
+const GAS_PER_EXTRA_PACK_TOKEN: Gas = Gas(2 * MIN_GAS_FOR_SIMPLE_NFT_TRANSFER.0); // 2 * 10 TGas = 20 TGas
+
 // FTs
 // Actual amount of GAS to attach when querying the storage balance bounds. No unspent GAS will be attached on top of this (weight of 0)
 const GAS_FOR_STORAGE_BALANCE_BOUNDS: Gas = Gas(10_000_000_000_000); // 10 TGas
 const MIN_GAS_FOR_RESOLVE_STORAGE_CHECK: Gas = Gas(25_000_000_000_000); // 25 TGas
+                                                                        // Actual amount of GAS to attach when querying the FT metadata. No unspent GAS will be attached on top of this (weight of 0)
+const GAS_FOR_FT_METADATA: Gas = Gas(10_000_000_000_000); // 10 TGas
+                                                          // Actual amount of GAS to attach when querying this contract's storage balance on the FT contract. No unspent GAS will be attached on top of this (weight of 0)
+const GAS_FOR_STORAGE_BALANCE_OF: Gas = Gas(10_000_000_000_000); // 10 TGas
+const MIN_GAS_FOR_RESOLVE_FT_REGISTRATION: Gas =
+    Gas(10_000_000_000_000 + MIN_GAS_FOR_STORAGE_DEPOSIT.0); // 10 TGas + 5 TGas = 15 TGas
+                                                             // Actual amount of GAS to attach when querying this contract's FT balance to verify a transfer. No unspent GAS will be attached on top of this (weight of 0)
+const GAS_FOR_FT_BALANCE_OF: Gas = Gas(10_000_000_000_000); // 10 TGas
+const MIN_GAS_FOR_RESOLVE_RECEIVED_BALANCE: Gas = Gas(10_000_000_000_000); // 10 TGas
+                                                                           // Number of blocks after which a pending FT drop whose storage check hasn't resolved is assumed to have failed
+const PENDING_DROP_TIMEOUT_BLOCKS: u64 = 100;
 const MIN_GAS_FOR_FT_TRANSFER: Gas = Gas(5_000_000_000_000); // 5 TGas
 const MIN_GAS_FOR_STORAGE_DEPOSIT: Gas = Gas(5_000_000_000_000); // 5 TGas
 const MIN_GAS_FOR_RESOLVE_BATCH: Gas =
     Gas(13_000_000_000_000 + MIN_GAS_FOR_FT_TRANSFER.0 + MIN_GAS_FOR_STORAGE_DEPOSIT.0); // 13 TGas + 5 TGas + 5 TGas = 23 TGas
 
+// Wrapped NEAR
+const MIN_GAS_FOR_NEAR_DEPOSIT: Gas = Gas(5_000_000_000_000); // 5 TGas
+const MIN_GAS_FOR_ON_WRAP_DEPOSIT: Gas = Gas(10_000_000_000_000); // 10 TGas
+const GAS_FOR_NEAR_WITHDRAW: Gas = Gas(10_000_000_000_000); // 10 TGas
+const MIN_GAS_FOR_RESOLVE_WRAPPED_DEPOSIT: Gas = Gas(10_000_000_000_000); // 10 TGas
+
+// Extra GAS required by the claim when the deposit is wrapped before being sent to the claimer
+const GAS_FOR_WRAPPING: Gas = Gas(MIN_GAS_FOR_NEAR_DEPOSIT.0
+    + MIN_GAS_FOR_STORAGE_DEPOSIT.0
+    + MIN_GAS_FOR_FT_TRANSFER.0
+    + MIN_GAS_FOR_ON_WRAP_DEPOSIT.0); // 5 TGas + 5 TGas + 5 TGas + 10 TGas = 25 TGas
+
+// Storage registration for the claimer on the wrap contract plus the 1 yocto required by ft_transfer
+const WRAP_STORAGE_COST: u128 = 1_250_000_000_000_000_000_001; // 0.00125 N + 1 yocto
+
+// Swapping the claim balance into an alternative payout token
+const ONE_NEAR: u128 = 1_000_000_000_000_000_000_000_000; // 1 N
+const GAS_FOR_SWAP_TRANSFER_CALL: Gas = Gas(50_000_000_000_000); // 50 TGas
+const MIN_GAS_FOR_NEAR_WITHDRAW: Gas = Gas(5_000_000_000_000); // 5 TGas
+const MIN_GAS_FOR_ON_SWAP_PAYOUT: Gas = Gas(10_000_000_000_000 + MIN_GAS_FOR_NEAR_WITHDRAW.0); // 10 TGas + 5 TGas = 15 TGas
+
+// Extra GAS required by the claim when the drop allows alternative payouts
+const GAS_FOR_SWAPPING: Gas = Gas(MIN_GAS_FOR_STORAGE_DEPOSIT.0
+    + MIN_GAS_FOR_NEAR_DEPOSIT.0
+    + GAS_FOR_SWAP_TRANSFER_CALL.0
+    + MIN_GAS_FOR_ON_SWAP_PAYOUT.0); // 5 TGas + 5 TGas + 50 TGas + 15 TGas = 75 TGas
+
+// Storage registration for the claimer on the payout token plus the 1 yocto required by ft_transfer_call
+const SWAP_STORAGE_COST: u128 = 1_250_000_000_000_000_000_001; // 0.00125 N + 1 yocto
+
+// Deploying contracts to new accounts
+// Extra GAS on top of the create account GAS to deploy the contract. No unspent GAS will be attached on top of this (weight of 0)
+const GAS_FOR_DEPLOY_CONTRACT: Gas = Gas(20_000_000_000_000); // 20 TGas
+const GAS_FOR_INIT_METHOD: Gas = Gas(20_000_000_000_000); // 20 TGas
+const MIN_GAS_FOR_ON_ACCOUNT_DEPLOYED: Gas = Gas(5_000_000_000_000 + GAS_FOR_INIT_METHOD.0); // 5 TGas + 20 TGas = 25 TGas
+
+// Extra GAS required by create_account_and_claim when the drop deploys a contract to the new account
+const GAS_FOR_DEPLOYING: Gas = Gas(GAS_FOR_DEPLOY_CONTRACT.0 + MIN_GAS_FOR_ON_ACCOUNT_DEPLOYED.0); // 20 TGas + 25 TGas = 45 TGas
+
+// Checking a claimer's eligibility with the funder's predicate before claim
+const GAS_FOR_ELIGIBILITY_PREDICATE: Gas = Gas(10_000_000_000_000); // 10 TGas
+                                                                    // Minimum GAS for the callback that continues the claim. All unspent GAS will be attached on top of this (weight of 1)
+const MIN_GAS_FOR_ON_ELIGIBILITY_CHECKED: Gas = Gas(5_000_000_000_000); // 5 TGas
+
+// Extra GAS required by claim when the drop checks eligibility. The rest covers scheduling the calls.
+const GAS_FOR_ELIGIBILITY_CHECK: Gas = Gas(GAS_FOR_ELIGIBILITY_PREDICATE.0 + 10_000_000_000_000); // 10 TGas + 10 TGas = 20 TGas
+
+// Minting a badge on the owner registered badge minter for every successful claim
+const GAS_FOR_BADGE_MINT: Gas = Gas(10_000_000_000_000); // 10 TGas
+const MIN_GAS_FOR_ON_BADGE_MINTED: Gas = Gas(5_000_000_000_000); // 5 TGas
+                                                                 // GAS for the callback that mints the badge once the claim resolves. No unspent GAS will be attached on top of this (weight of 0)
+const MIN_GAS_FOR_ON_CLAIM_RESOLVED_FOR_BADGE: Gas =
+    Gas(5_000_000_000_000 + GAS_FOR_BADGE_MINT.0 + MIN_GAS_FOR_ON_BADGE_MINTED.0); // 5 TGas + 10 TGas + 5 TGas = 20 TGas
+                                                                                   // Deposit attached to each badge mint to cover the badge's storage on the minter
+const BADGE_MINT_DEPOSIT: u128 = 10_000_000_000_000_000_000_000; // 0.01 N
+
+// Extra GAS required by every claim when the drop mints badges. The rest covers scheduling the callback.
+const GAS_FOR_BADGES: Gas = Gas(MIN_GAS_FOR_ON_CLAIM_RESOLVED_FOR_BADGE.0 + 5_000_000_000_000); // 20 TGas + 5 TGas = 25 TGas
+
+// Minimum GAS for the callback that credits a withdrawal back to the balance if the transfer failed
+const MIN_GAS_FOR_RESOLVE_WITHDRAW: Gas = Gas(5_000_000_000_000); // 5 TGas
+
 // Specifies the GAS being attached from the wallet site
 const ATTACHED_GAS_FROM_WALLET: Gas = Gas(100_000_000_000_000); // 100 TGas
 
+// Most GAS a claim can attach since it's a single transaction
+const MAX_GAS_FOR_CLAIM: Gas = Gas(300_000_000_000_000); // 300 TGas
+
 // Specifies the amount of GAS to attach on top of the FC Gas if executing a regular function call in claim
 const GAS_OFFSET_IF_FC_EXECUTE: Gas = Gas(20_000_000_000_000); // 20 TGas
 
 // Actual amount of GAS to attach when creating a new account. No unspent GAS will be attached on top of this (weight of 0)
 const GAS_FOR_CREATE_ACCOUNT: Gas = Gas(28_000_000_000_000); // 28 TGas
 
-/// Both methods callable by the function call access key
-const ACCESS_KEY_BOTH_METHOD_NAMES: &str = "claim,create_account_and_claim";
+/// Maximum number of access keys added in a single promise batch
+const MAX_KEYS_PER_BATCH: usize = 50;
 
-/// Only the claim method_name is callable by the access key
-const ACCESS_KEY_CLAIM_METHOD_NAME: &str = "claim";
+// GAS kept after adding access keys for the rest of the call, storing any keys left as pending activation and the fees of
+// the callback and follow up receipts it creates. The GAS attached to those receipts is reserved on top of this.
+const GAS_RESERVED_AFTER_KEY_CREATION: Gas = Gas(30_000_000_000_000); // 30 TGas
 
-/// Only the create_account_and_claim method_name is callable by the access key
-const ACCESS_KEY_CREATE_ACCOUNT_METHOD_NAME: &str = "create_account_and_claim";
+// Minimum GAS attached to the self scheduled `finish_key_creation` call. Any unspent GAS is attached on top of this.
+const MIN_GAS_FOR_FINISH_KEY_CREATION: Gas = Gas(20_000_000_000_000); // 20 TGas
+
+// GAS attached to the callback that counts the access keys a batch actually added or deleted
+const GAS_FOR_RESOLVE_ACCESS_KEY_CHANGE: Gas = Gas(5_000_000_000_000); // 5 TGas
 
 /*
     FEES
@@ -68,15 +152,23 @@ const KEY_ADDITION_FEE: u128 = 5_000_000_000_000_000_000_000; // 0.005 N
 
 const GAS_FOR_PANIC_OFFSET: Gas = Gas(10_000_000_000_000); // 10 TGas
 
+mod events;
 mod internals;
 mod stage1;
 mod stage2;
 mod stage3;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(test)]
+mod tests;
 mod views;
 
+use events::*;
 use internals::*;
 use stage1::*;
 use stage2::*;
+use stage3::*;
+use views::*;
 
 #[derive(BorshSerialize, BorshStorageKey)]
 enum StorageKey {
@@ -89,6 +181,39 @@ enum StorageKey {
     TokenIdsForDrop { account_id_hash: CryptoHash },
     FeesPerUser,
     UserBalances,
+    ClaimRecords,
+    PkForKeyId { account_id_hash: CryptoHash },
+    KnownRoots,
+    AllowedHashesForDrop { account_id_hash: CryptoHash },
+    FcReceiverRestrictions,
+    FtMetadata,
+    RecentFailures,
+    SwapRoutes,
+    Campaigns,
+    CampaignForDrop,
+    LastFtRefresh,
+    PendingKeyActivations,
+    DropStats,
+    Partners,
+    PartnerForDrop,
+    DropIdsForAsset,
+    DropIdsForAssetInner { account_id_hash: CryptoHash },
+    AdminChangelog,
+    BalanceAlerts,
+    FcReceiverReputations,
+    RefundVestingConfigs,
+    RefundVestings,
+    ClaimsInFlight,
+    PendingForceRefunds,
+    FtHeld,
+    FtBalanceChecks,
+    UseHistograms,
+    ClaimsPerAccountForDrop { account_id_hash: CryptoHash },
+    PendingWrappedDeposits,
+    PasswordsPerUse,
+    DropsWithPasswordsPerUse,
+    FtClaimsInFlight,
+    ClaimRecordForGuard,
 }
 
 #[near_bindgen]
@@ -121,6 +246,116 @@ pub struct Keypom {
 
     // Keep track of the price of 1 GAS per 1 yocto
     pub yocto_per_gas: u128,
+
+    // Short-lived records of claims processed with an idempotency key so that relayer retries aren't executed twice
+    pub claim_records: UnorderedMap<CryptoHash, ClaimRecord>,
+    // How long a claim record is kept before it can be swept (in nanoseconds)
+    pub claim_record_ttl: u64,
+    // Index of the claim record the next sweep starts at
+    pub claim_record_sweep_cursor: u64,
+    // How long before their end timestamp keys can be tightened to a single claim attempt (in nanoseconds)
+    pub expiring_key_window: u64,
+
+    // Roots that are known to accept `create_account` calls from this contract. Only the owner can edit this.
+    pub known_roots: UnorderedSet<AccountId>,
+
+    // Minimum balance a new account must have when created. Only the owner can edit this.
+    pub new_account_base: Balance,
+
+    // Suffixes that FC receivers must end with for drops created by each restricted account
+    pub fc_receiver_restrictions: LookupMap<AccountId, Vec<String>>,
+
+    // FT metadata cached for each token contract the first time an FT drop is created for it
+    pub ft_metadata: LookupMap<AccountId, FTMetadataInfo>,
+
+    // Most recent claim promise failures for each drop, oldest first
+    pub recent_failures: LookupMap<DropId, Vec<PromiseFailure>>,
+
+    // Owner curated routes for swapping $NEAR into each alternative payout token
+    pub swap_routes: LookupMap<AccountId, SwapRoute>,
+
+    // Campaigns grouping drops from the same funder and the campaign each drop is part of
+    pub campaigns: LookupMap<CampaignId, Campaign>,
+    pub campaign_for_drop: LookupMap<DropId, CampaignId>,
+    // Keep track of a nonce used for the campaign IDs
+    pub next_campaign_id: CampaignId,
+
+    // Last time each FT drop's registration on its token contract was refreshed
+    pub last_ft_refresh: LookupMap<DropId, u64>,
+
+    // Keys of each drop whose access keys haven't been added yet because there wasn't enough GAS
+    pub pending_key_activations: LookupMap<DropId, Vec<PublicKey>>,
+
+    // Aggregate claim stats for each drop, emitted in the `drop_complete` event once the drop is done
+    pub drop_stats: LookupMap<DropId, DropStats>,
+
+    // Partners registered by the owner with their share of the fees in basis points, and the share locked in for each drop
+    pub partners: UnorderedMap<AccountId, u16>,
+    pub partner_for_drop: LookupMap<DropId, PartnerShare>,
+
+    // Balance kept on top of the storage staking requirement that outbound transfers can't dip into. Only the owner can edit this.
+    pub operational_buffer: Balance,
+
+    // Listed FT and NFT drops for each asset contract so that token teams can find the drops distributing their tokens
+    pub drop_ids_for_asset: UnorderedMap<AccountId, UnorderedSet<DropId>>,
+
+    // Contract that mints badges for drops with `mint_badge`. Only the owner can edit this.
+    pub badge_minter: Option<AccountId>,
+
+    // Most recent owner-gated calls along with how many have ever been recorded. Old entries are overwritten.
+    pub admin_changelog: Vector<AdminChange>,
+    pub admin_changes_recorded: u64,
+
+    // Salt for the storage prefixes of the next drop's collections. Never reused so drops can't share prefixes.
+    pub next_storage_salt: u64,
+
+    // Balance below which each account wants a `balance_low` event. Set by the accounts themselves.
+    pub balance_alerts: LookupMap<AccountId, Balance>,
+
+    // Running totals of every user balance and of the reserves held for keys. Used for the solvency report.
+    pub total_user_balances: Balance,
+    pub total_key_reserves: Balance,
+
+    // Allowed and denied FC receivers. Receivers that aren't listed are unknown. Only the owner can edit this.
+    pub fc_receiver_reputations: UnorderedMap<AccountId, FcReceiverReputation>,
+
+    // How each account wants large refunds released, the refunds currently vesting and the total still held for them
+    pub refund_vesting_configs: LookupMap<AccountId, RefundVestingConfig>,
+    pub refund_vestings: LookupMap<AccountId, VersionedRefundVesting>,
+    pub total_vesting_refunds: Balance,
+
+    // Uses of keys whose claims haven't resolved yet. A second claim for the same use is rejected.
+    pub claims_in_flight: LookupSet<ClaimGuard>,
+
+    // Force refunds the owner announced that the funder can still veto
+    pub pending_force_refunds: LookupMap<DropId, PendingForceRefund>,
+
+    // Tokens of each FT contract held for drops and the balance check in flight for transfers that verify what was received
+    pub ft_held: LookupMap<AccountId, Balance>,
+    pub ft_balance_checks: LookupMap<AccountId, FTBalanceCheck>,
+
+    // Successful claims of each drop by use number. Drops created before it was tracked have no entry.
+    pub use_histograms: LookupMap<DropId, Vec<u64>>,
+
+    // Wrap contract whose tokens can top up balances and the wNEAR each account sent that hasn't been unwrapped yet.
+    // Only the owner can edit the wrap contract.
+    pub wrap_contract: Option<AccountId>,
+    pub pending_wrapped_deposits: LookupMap<AccountId, Balance>,
+
+    // Access keys on the contract account as confirmed once their add or delete batches resolve and the soft cap
+    // on them the owner can set. New drops and keys are rejected once the cap is reached.
+    pub active_access_keys: u64,
+    pub access_key_cap: Option<u64>,
+
+    // sha256 hashes of the passwords for single uses of multi-use keys by drop, key ID and use, and the drops that have any
+    pub passwords_per_use: LookupMap<(DropId, u64, u64), Vec<u8>>,
+    pub drops_with_passwords_per_use: LookupSet<DropId>,
+
+    // Claim transfers of each FT contract that haven't resolved yet. Balance checks wait until there are none.
+    pub ft_claims_in_flight: LookupMap<AccountId, u64>,
+
+    // Claim record of each use that was claimed with an idempotency key and hasn't resolved yet
+    pub claim_record_for_guard: LookupMap<ClaimGuard, CryptoHash>,
 }
 
 #[near_bindgen]
@@ -128,6 +363,10 @@ impl Keypom {
     /// Initialize contract and pass in the desired deployed linkdrop contract (i.e testnet or near)
     #[init]
     pub fn new(root_account: AccountId, owner_id: AccountId) -> Self {
+        // The global root is always able to create accounts
+        let mut known_roots = UnorderedSet::new(StorageKey::KnownRoots);
+        known_roots.insert(&root_account);
+
         Self {
             owner_id,
             root_account,
@@ -144,6 +383,50 @@ impl Keypom {
             key_fee: KEY_ADDITION_FEE,
             fees_collected: 0,
             yocto_per_gas: 100_000_000,
+            claim_records: UnorderedMap::new(StorageKey::ClaimRecords),
+            claim_record_ttl: DEFAULT_CLAIM_RECORD_TTL,
+            claim_record_sweep_cursor: 0,
+            expiring_key_window: DEFAULT_EXPIRING_KEY_WINDOW,
+            known_roots,
+            new_account_base: NEW_ACCOUNT_BASE,
+            fc_receiver_restrictions: LookupMap::new(StorageKey::FcReceiverRestrictions),
+            ft_metadata: LookupMap::new(StorageKey::FtMetadata),
+            recent_failures: LookupMap::new(StorageKey::RecentFailures),
+            swap_routes: LookupMap::new(StorageKey::SwapRoutes),
+            campaigns: LookupMap::new(StorageKey::Campaigns),
+            campaign_for_drop: LookupMap::new(StorageKey::CampaignForDrop),
+            next_campaign_id: 0,
+            last_ft_refresh: LookupMap::new(StorageKey::LastFtRefresh),
+            pending_key_activations: LookupMap::new(StorageKey::PendingKeyActivations),
+            drop_stats: LookupMap::new(StorageKey::DropStats),
+            partners: UnorderedMap::new(StorageKey::Partners),
+            partner_for_drop: LookupMap::new(StorageKey::PartnerForDrop),
+            operational_buffer: 0,
+            drop_ids_for_asset: UnorderedMap::new(StorageKey::DropIdsForAsset),
+            badge_minter: None,
+            admin_changelog: Vector::new(StorageKey::AdminChangelog),
+            admin_changes_recorded: 0,
+            next_storage_salt: 0,
+            balance_alerts: LookupMap::new(StorageKey::BalanceAlerts),
+            total_user_balances: 0,
+            total_key_reserves: 0,
+            fc_receiver_reputations: UnorderedMap::new(StorageKey::FcReceiverReputations),
+            refund_vesting_configs: LookupMap::new(StorageKey::RefundVestingConfigs),
+            refund_vestings: LookupMap::new(StorageKey::RefundVestings),
+            total_vesting_refunds: 0,
+            claims_in_flight: LookupSet::new(StorageKey::ClaimsInFlight),
+            pending_force_refunds: LookupMap::new(StorageKey::PendingForceRefunds),
+            ft_held: LookupMap::new(StorageKey::FtHeld),
+            ft_balance_checks: LookupMap::new(StorageKey::FtBalanceChecks),
+            use_histograms: LookupMap::new(StorageKey::UseHistograms),
+            wrap_contract: None,
+            pending_wrapped_deposits: LookupMap::new(StorageKey::PendingWrappedDeposits),
+            active_access_keys: 0,
+            access_key_cap: None,
+            passwords_per_use: LookupMap::new(StorageKey::PasswordsPerUse),
+            drops_with_passwords_per_use: LookupSet::new(StorageKey::DropsWithPasswordsPerUse),
+            ft_claims_in_flight: LookupMap::new(StorageKey::FtClaimsInFlight),
+            claim_record_for_guard: LookupMap::new(StorageKey::ClaimRecordForGuard),
         }
     }
 }
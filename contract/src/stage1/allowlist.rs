@@ -0,0 +1,106 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+/// How identifiers in a drop's allowlist are consumed when claiming
+#[derive(BorshSerialize, BorshDeserialize, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum IdentifierAllowlist {
+    // Each identifier can only be used for a single claim
+    SingleUse,
+    // Identifiers can be used for any number of claims
+    Reusable,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Add sha256 hashes of identifiers (i.e emails or IDs known only to the funder) to a drop's allowlist.
+        Claimers must then pass in the identifier itself when claiming. The funder's balance is charged
+        for the storage used. Returns how many new hashes were added.
+    */
+    #[payable]
+    pub fn add_allowed_hashes(&mut self, drop_id: DropId, hashes: Vec<Base64VecU8>) -> u64 {
+        assert_one_yocto();
+
+        // Measure initial storage before doing any operations
+        let initial_storage = env::storage_usage();
+
+        let mut drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        let owner_id = drop.owner_id.clone();
+        require!(
+            owner_id == env::predecessor_account_id(),
+            "only drop funder can update the allowlist"
+        );
+        require!(
+            drop.config
+                .as_ref()
+                .and_then(|c| c.identifier_allowlist.as_ref())
+                .is_some(),
+            "drop doesn't have an identifier allowlist"
+        );
+
+        let mut num_added = 0;
+        for hash in hashes {
+            require!(hash.0.len() == 32, "hashes must be 32 byte sha256 hashes");
+            num_added += drop.allowed_hashes.insert(&hash.0) as u64;
+        }
+        self.drop_for_id.insert(&drop_id, &drop);
+
+        // Charge the funder for the storage used
+        let final_storage = env::storage_usage();
+        let storage_cost = Balance::from(final_storage - initial_storage) * drop.storage_byte_cost;
+        let mut cur_balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        require!(
+            cur_balance >= storage_cost,
+            "Not enough balance to cover the storage for the allowlist"
+        );
+        cur_balance -= storage_cost;
+        self.internal_set_user_balance(&owner_id, cur_balance);
+        self.internal_check_balance_alert(&owner_id, cur_balance);
+
+        near_sdk::log!(
+            "Added {} hashes to the allowlist for drop {}. Charged {} for storage",
+            num_added,
+            drop_id,
+            yocto_to_near(storage_cost)
+        );
+        num_added
+    }
+
+    /// Remove hashes from a drop's allowlist and refund the storage freed to the funder's balance. Returns how many hashes were removed.
+    #[payable]
+    pub fn remove_allowed_hashes(&mut self, drop_id: DropId, hashes: Vec<Base64VecU8>) -> u64 {
+        assert_one_yocto();
+
+        // Measure initial storage before doing any operations
+        let initial_storage = env::storage_usage();
+
+        let mut drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        let owner_id = drop.owner_id.clone();
+        require!(
+            owner_id == env::predecessor_account_id(),
+            "only drop funder can update the allowlist"
+        );
+
+        let mut num_removed = 0;
+        for hash in hashes {
+            num_removed += drop.allowed_hashes.remove(&hash.0) as u64;
+        }
+        self.drop_for_id.insert(&drop_id, &drop);
+
+        // Refund the funder for the storage freed
+        let final_storage = env::storage_usage();
+        let storage_freed = Balance::from(initial_storage - final_storage) * drop.storage_byte_cost;
+        let mut cur_balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        cur_balance += storage_freed;
+        self.internal_set_user_balance(&owner_id, cur_balance);
+
+        near_sdk::log!(
+            "Removed {} hashes from the allowlist for drop {}. Refunded {} for storage",
+            num_removed,
+            drop_id,
+            yocto_to_near(storage_freed)
+        );
+        num_removed
+    }
+}
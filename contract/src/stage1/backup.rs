@@ -0,0 +1,301 @@
+use crate::*;
+use near_sdk::collections::{LazyOption, Vector};
+
+/// Snapshot of a drop for off-chain backup or migrating between Keypom deployments.
+/// Keys and token IDs are paginated so large drops are exported across multiple bundles.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonDropExport {
+    // Drop ID on the contract the drop was exported from
+    pub drop_id: DropId,
+    // Owner of the drop
+    pub owner_id: AccountId,
+
+    // Balance for all keys of this drop
+    pub deposit_per_use: U128,
+
+    // Drop type along with its data. NFT token IDs are exported separately below.
+    pub drop_type: JsonDropType,
+
+    // Config and metadata for the drop
+    pub config: Option<DropConfig>,
+    pub metadata: Option<DropMetadata>,
+
+    // How many claims were registered when the drop was exported
+    pub registered_uses: u64,
+
+    // Ensure this drop can only be used when the function has the required gas to attach
+    pub required_gas: Gas,
+
+    // Keep track of the next nonce to give out to a key
+    pub next_key_id: u64,
+
+    // Claim counters for the early bonus and max total claims
+    pub early_claims: u64,
+    pub total_claims: u64,
+    // Compaction bounties already paid out so that they aren't reserved again
+    #[serde(default)]
+    pub compaction_bounties_paid: u64,
+
+    // Total number of keys and token IDs in the drop so that callers know when to stop paginating
+    pub key_supply: u64,
+    pub token_id_supply: u64,
+
+    // The page of keys and token IDs included in this bundle
+    pub keys: Vec<(PublicKey, JsonKeyUsage)>,
+    pub token_ids: Vec<String>,
+
+    // Per-use passwords left on the keys in this page by key ID. Empty for backups exported before they existed.
+    #[serde(default)]
+    pub passwords_per_use: Vec<(u64, Vec<PasswordPerUse>)>,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /// Export a drop's full state. Keys and token IDs are paginated using the same index and limit.
+    pub fn export_drop(
+        &self,
+        drop_id: DropId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> JsonDropExport {
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0))) as usize;
+        //take the first "limit" elements. If we didn't specify a limit, use 50
+        let limit = limit.unwrap_or(50) as usize;
+
+        let drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("No drop for given ID");
+
+        let page: Vec<(PublicKey, KeyInfo)> = drop.pks.iter().skip(start).take(limit).collect();
+        let passwords_per_use = page
+            .iter()
+            .map(|(_, key_info)| {
+                let passwords =
+                    self.internal_passwords_per_use_left(drop_id, &drop.config, key_info);
+                (key_info.key_id, passwords)
+            })
+            .filter(|(_, passwords)| !passwords.is_empty())
+            .collect();
+        let keys = page
+            .into_iter()
+            .map(|(pk, key_info)| (pk, key_info.into()))
+            .collect();
+
+        let mut token_ids = vec![];
+        let mut token_id_supply = 0;
+        let drop_type = match drop.drop_type {
+            DropType::NonFungibleToken(data) => {
+                token_id_supply = data.token_ids.len();
+                token_ids = data.token_ids.iter().skip(start).take(limit).collect();
+                JsonDropType::NonFungibleToken(JsonNFTData {
+                    sender_id: data.sender_id,
+                    contract_id: data.contract_id,
+                    longest_token_id: data.longest_token_id,
+                    storage_for_longest: U128(data.storage_for_longest),
+                    tokens_per_use: data.tokens_per_use,
+                })
+            }
+            DropType::FungibleToken(data) => JsonDropType::FungibleToken(data),
+            DropType::FunctionCall(data) => JsonDropType::FunctionCall(data),
+            DropType::Simple => JsonDropType::Simple,
+        };
+
+        JsonDropExport {
+            drop_id,
+            owner_id: drop.owner_id,
+            deposit_per_use: U128(drop.deposit_per_use),
+            drop_type,
+            config: drop.config,
+            metadata: drop.metadata.get(),
+            registered_uses: drop.registered_uses,
+            required_gas: drop.required_gas,
+            next_key_id: drop.next_key_id,
+            early_claims: drop.early_claims,
+            total_claims: drop.total_claims,
+            compaction_bounties_paid: drop.compaction_bounties_paid,
+            key_supply: drop.pks.len(),
+            token_id_supply,
+            keys,
+            token_ids,
+            passwords_per_use,
+        }
+    }
+
+    /*
+        Recreate a drop from a bundle returned by `export_drop`. The first bundle creates a new drop
+        with a new ID which is returned. The rest of the pages are imported by passing in that ID.
+
+        The drop owner's balance is charged for the storage used along with the allowance, access key
+        storage and deposits for each key's remaining uses. NFTs and FTs aren't moved between contracts
+        so FT and NFT drops start with no registered uses and the assets must be sent again.
+    */
+    #[payable]
+    pub fn import_drop(&mut self, bundle: JsonDropExport, drop_id: Option<DropId>) -> DropId {
+        self.assert_owner_and_record("import_drop");
+
+        // Pessimistically measure storage
+        let initial_storage = env::storage_usage();
+        let owner_id = bundle.owner_id.clone();
+
+        // Either continue a previous import or create a new drop from the bundle
+        let (drop_id, mut drop, mut required_deposit) = match drop_id {
+            Some(drop_id) => {
+                let drop = self
+                    .drop_for_id
+                    .get(&drop_id)
+                    .expect("No drop for given ID");
+                require!(
+                    drop.owner_id == owner_id,
+                    "bundle owner doesn't match the drop being imported into"
+                );
+                (drop_id, drop, 0)
+            }
+            None => {
+                let storage_keys = self.internal_new_drop_storage_keys();
+                let drop = self.internal_drop_from_export(&bundle, &storage_keys);
+                let drop_id = self.next_drop_id;
+                self.next_drop_id += 1;
+                self.internal_add_drop_to_funder(&owner_id, &drop_id);
+                if let Some(contract_id) = drop
+                    .drop_type
+                    .behavior()
+                    .asset_contract_id()
+                    .filter(|_| !is_unlisted(&drop.config))
+                {
+                    self.internal_add_drop_to_asset_index(contract_id, drop_id);
+                }
+                self.internal_start_drop_stats(drop_id, owner_id.clone(), &drop.config);
+
+                // Any early bonus and compaction bounties left have to be reserved again
+                let reserves = unspent_early_bonus(&drop) + unspent_compaction_reserve(&drop);
+                (drop_id, drop, reserves)
+            }
+        };
+
+        let uses_per_key = drop
+            .config
+            .clone()
+            .and_then(|c| c.uses_per_key)
+            .unwrap_or(1);
+        let access_key_method_names = access_key_method_names(&drop.claim_permission);
+        let mut keys = Vec::with_capacity(bundle.keys.len());
+
+        for (pk, key_usage) in bundle.keys {
+            let key_info: KeyInfo = key_usage.into();
+            require!(
+                self.drop_id_for_pk.insert(&pk, &drop_id).is_none(),
+                "Keys cannot belong to another drop"
+            );
+            drop.pk_for_key_id.insert(&key_info.key_id, &pk);
+
+            // Every remaining use needs to be backed again on this contract
+            let behavior = drop.drop_type.behavior();
+            let (deposit_value, num_none_fcs) =
+                behavior.deposits_left(uses_per_key, key_info.remaining_uses);
+            let num_paid_uses = key_info.remaining_uses - num_none_fcs;
+            required_deposit += deposit_value
+                + behavior.asset_storage_per_use(drop.storage_byte_cost)
+                    * key_info.remaining_uses as u128;
+            if behavior.registers_uses_with_keys() {
+                drop.registered_uses += key_info.remaining_uses;
+            }
+            required_deposit += key_info.allowance
+                + ACCESS_KEY_STORAGE
+                + (drop.deposit_per_use + extra_cost_per_use(&drop.config)) * num_paid_uses as u128;
+
+            self.internal_insert_key_info(&mut drop.pks, drop.deposit_per_use, &pk, &key_info);
+            keys.push((pk, key_info.allowance));
+        }
+        for (key_id, passwords) in bundle.passwords_per_use {
+            self.internal_insert_passwords_per_use(drop_id, key_id, passwords);
+        }
+
+        self.drop_for_id.insert(&drop_id, &drop);
+
+        // Charge the owner for the storage and everything needed to back the imported keys
+        let final_storage = env::storage_usage();
+        let total_required_storage =
+            Balance::from(final_storage - initial_storage) * env::storage_byte_cost();
+        required_deposit += total_required_storage;
+
+        self.internal_charge_balance(&owner_id, required_deposit, 0);
+
+        near_sdk::log!(
+            "Imported drop {} from drop {} for {}. Charged {}. Total keys: {}",
+            drop_id,
+            bundle.drop_id,
+            owner_id,
+            yocto_to_near(required_deposit),
+            drop.pks.len()
+        );
+
+        // Create the access keys in as many batches as the GAS allows
+        self.internal_add_access_keys(drop_id, keys, &access_key_method_names);
+        drop_id
+    }
+
+    /// Build a new drop from an exported bundle with empty key collections under the given prefixes
+    fn internal_drop_from_export(
+        &self,
+        bundle: &JsonDropExport,
+        storage_keys: &DropStorageKeys,
+    ) -> Drop {
+        let owner_id = &bundle.owner_id;
+        let drop_type = match bundle.drop_type.clone() {
+            JsonDropType::NonFungibleToken(data) => DropType::NonFungibleToken(NFTData {
+                sender_id: data.sender_id,
+                contract_id: data.contract_id,
+                longest_token_id: data.longest_token_id,
+                storage_for_longest: data.storage_for_longest.0,
+                token_ids: Vector::new(storage_keys.token_ids()),
+                tokens_per_use: data.tokens_per_use.max(1),
+            }),
+            JsonDropType::FungibleToken(data) => DropType::FungibleToken(data),
+            JsonDropType::FunctionCall(data) => {
+                // Keys that attach gas to the function call can't also send a balance
+                if data.config.as_ref().and_then(|c| c.attached_gas).is_some() {
+                    require!(
+                        bundle.deposit_per_use.0 == 0,
+                        "cannot specify gas to attach and have a balance in the linkdrop"
+                    );
+                }
+                self.internal_check_fc_receivers_not_denied(&data)
+                    .unwrap_or_else(|e| env::panic_str(&e));
+                DropType::FunctionCall(data)
+            }
+            JsonDropType::Simple => DropType::Simple,
+        };
+        let claim_permission = match &drop_type {
+            DropType::FunctionCall(data) => effective_claim_permission(&bundle.config, Some(data)),
+            _ => effective_claim_permission(&bundle.config, None),
+        };
+
+        Drop {
+            owner_id: owner_id.clone(),
+            deposit_per_use: bundle.deposit_per_use.0,
+            pks: UnorderedMap::new(storage_keys.pks()),
+            pk_for_key_id: LookupMap::new(storage_keys.pk_for_key_id()),
+            allowed_hashes: UnorderedSet::new(storage_keys.allowed_hashes()),
+            claims_per_account: UnorderedMap::new(storage_keys.claims_per_account()),
+            drop_type,
+            config: bundle.config.clone(),
+            // Uses are registered as keys are imported. Assets for FT and NFT drops must be sent again.
+            registered_uses: 0,
+            required_gas: bundle.required_gas,
+            early_claims: bundle.early_claims,
+            total_claims: bundle.total_claims,
+            pending_claims: 0,
+            claim_permission,
+            state: DropState::Active,
+            metadata: LazyOption::new(storage_keys.metadata(), bundle.metadata.as_ref()),
+            metadata_summary: metadata_summary(&bundle.config, bundle.metadata.as_ref()),
+            next_key_id: bundle.next_key_id,
+            compaction_bounties_paid: bundle.compaction_bounties_paid,
+            compaction_cursor: 0,
+            storage_byte_cost: env::storage_byte_cost(),
+        }
+    }
+}
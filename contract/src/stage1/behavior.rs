@@ -0,0 +1,114 @@
+use crate::*;
+
+/*
+    What differs between drop types when drops are created and keys are added, claimed, backed, refunded or
+    deleted. Each type's data implements this next to the rest of that type's logic (simple drops in simple.rs,
+    FTs and NFTs in stage2, FCs in function_call.rs) so that new drop type features don't have to touch the
+    others. Claims still resolve through one callback per type since each callback takes different arguments.
+*/
+pub(crate) trait DropBehavior {
+    /// Short name for the drop type used in events
+    fn type_name(&self) -> &'static str;
+
+    /// Whether a use is registered for every use of every key added. FT and NFT drops register a use for every asset sent instead.
+    fn registers_uses_with_keys(&self) -> bool {
+        true
+    }
+
+    /// Storage reserved for every use so that the asset can be delivered (i.e the FT registration). Storage on this
+    /// contract is priced at the byte cost recorded on the drop.
+    fn asset_storage_per_use(&self, _storage_byte_cost: Balance) -> Balance {
+        0
+    }
+
+    /// Attached deposits reserved for the remaining uses of a key along with how many of those uses have no method
+    /// to call (and therefore don't send the deposit per use)
+    fn deposits_left(&self, _uses_per_key: u64, _remaining_uses: u64) -> (Balance, u64) {
+        (0, 0)
+    }
+
+    /// Contract of the FT or NFT being distributed
+    fn asset_contract_id(&self) -> Option<&AccountId> {
+        None
+    }
+
+    /// Everything reserved for a key added with `uses_per_key` uses on top of its allowance and deposits, along with
+    /// how many of those uses don't send the deposit. It's what deleting the key with every use left refunds.
+    fn cost_per_key(&self, uses_per_key: u64, storage_byte_cost: Balance) -> (Balance, u64) {
+        self.on_refund(uses_per_key, uses_per_key, storage_byte_cost)
+    }
+
+    /// Set up the type's data once the drop has been stored for the first time. Returns the storage (in bytes)
+    /// measured for every use, if the type stores anything per use on this contract.
+    fn on_create(&mut self) -> u128 {
+        0
+    }
+
+    /// Check that keys can still be added to the drop under the contract's current restrictions
+    fn on_add_keys(&self, _contract: &Keypom, _funder_id: &AccountId) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Take what the next use of a key hands over out of the drop. `use_index` is which of the key's uses is claimed.
+    fn on_claim(&mut self, _use_index: u64, _storage_byte_cost: Balance) -> ClaimedAssets {
+        ClaimedAssets::default()
+    }
+
+    /// Everything reserved for a key with `remaining_uses` uses left on top of its allowance and deposits, along with
+    /// how many of those uses don't send the deposit. This is refunded when the key is deleted.
+    fn on_refund(
+        &self,
+        uses_per_key: u64,
+        remaining_uses: u64,
+        storage_byte_cost: Balance,
+    ) -> (Balance, u64) {
+        let (deposits, num_none_fcs) = self.deposits_left(uses_per_key, remaining_uses);
+        (
+            deposits + self.asset_storage_per_use(storage_byte_cost) * remaining_uses as u128,
+            num_none_fcs,
+        )
+    }
+}
+
+/// What a claim takes out of the drop to hand over
+pub(crate) struct ClaimedAssets {
+    // Token IDs of the NFT pack being sent
+    pub token_ids: Option<Vec<String>>,
+    // Storage prepaid for the token IDs, refunded to the funder once they're sent
+    pub storage_for_longest: Option<Balance>,
+    // Whether there's anything to send. FC uses with no method don't call anything.
+    pub should_continue: bool,
+}
+
+impl Default for ClaimedAssets {
+    fn default() -> Self {
+        Self {
+            token_ids: None,
+            storage_for_longest: None,
+            should_continue: true,
+        }
+    }
+}
+
+impl DropType {
+    /// Behavior specific to the drop's type
+    pub(crate) fn behavior(&self) -> &dyn DropBehavior {
+        match self {
+            DropType::Simple => &SimpleDrop,
+            DropType::NonFungibleToken(data) => data,
+            DropType::FungibleToken(data) => data,
+            DropType::FunctionCall(data) => data,
+        }
+    }
+
+    /// Same as `behavior` for the hooks that update the type's data
+    pub(crate) fn behavior_mut(&mut self) -> &mut dyn DropBehavior {
+        match self {
+            // Simple drops have no data. Leaking a zero sized type doesn't allocate anything.
+            DropType::Simple => Box::leak(Box::new(SimpleDrop)),
+            DropType::NonFungibleToken(data) => data,
+            DropType::FungibleToken(data) => data,
+            DropType::FunctionCall(data) => data,
+        }
+    }
+}
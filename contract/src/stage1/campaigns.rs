@@ -0,0 +1,137 @@
+use crate::*;
+
+pub type CampaignId = u64;
+
+/// Group of drops from the same funder (i.e different tiers of a single campaign)
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Campaign {
+    // Funder of the campaign. Only their drops can be added to it.
+    pub owner_id: AccountId,
+    // Name of the campaign
+    pub name: String,
+    // Metadata for the campaign which can be JSON or anything the users want
+    pub metadata: Option<String>,
+    // Drops that are part of the campaign in the order they were created
+    pub drop_ids: Vec<DropId>,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /// Create an empty campaign that drops can be linked to when they're created. The funder's balance is charged for the storage used.
+    pub fn create_campaign(&mut self, name: String, metadata: Option<String>) -> CampaignId {
+        // Measure initial storage before doing any operations
+        let initial_storage = env::storage_usage();
+
+        let owner_id = env::predecessor_account_id();
+        let campaign_id = self.next_campaign_id;
+        self.next_campaign_id += 1;
+        self.campaigns.insert(
+            &campaign_id,
+            &Campaign {
+                owner_id: owner_id.clone(),
+                name,
+                metadata,
+                drop_ids: vec![],
+            },
+        );
+
+        // Charge the funder for the storage used
+        let final_storage = env::storage_usage();
+        let storage_cost =
+            Balance::from(final_storage - initial_storage) * env::storage_byte_cost();
+        let mut cur_balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        require!(
+            cur_balance >= storage_cost,
+            "Not enough balance to cover the storage for the campaign"
+        );
+        cur_balance -= storage_cost;
+        self.internal_set_user_balance(&owner_id, cur_balance);
+        self.internal_check_balance_alert(&owner_id, cur_balance);
+
+        near_sdk::log!(
+            "Created campaign {}. Charged {} for storage",
+            campaign_id,
+            yocto_to_near(storage_cost)
+        );
+        campaign_id
+    }
+
+    /// Delete an empty campaign and refund the storage freed to the funder's balance. All of its drops must be deleted first.
+    #[payable]
+    pub fn delete_campaign(&mut self, campaign_id: CampaignId) {
+        assert_one_yocto();
+
+        // Measure initial storage before doing any operations
+        let initial_storage = env::storage_usage();
+
+        let campaign = self.campaigns.get(&campaign_id).expect("No campaign found");
+        let owner_id = campaign.owner_id;
+        require!(
+            owner_id == env::predecessor_account_id(),
+            "only campaign funder can delete the campaign"
+        );
+        require!(
+            campaign.drop_ids.is_empty(),
+            "all drops in the campaign must be deleted first"
+        );
+        self.campaigns.remove(&campaign_id);
+
+        // Refund the funder for the storage freed
+        let final_storage = env::storage_usage();
+        let storage_freed =
+            Balance::from(initial_storage - final_storage) * env::storage_byte_cost();
+        let mut cur_balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        cur_balance += storage_freed;
+        self.internal_set_user_balance(&owner_id, cur_balance);
+
+        near_sdk::log!(
+            "Deleted campaign {}. Refunded {} for storage",
+            campaign_id,
+            yocto_to_near(storage_freed)
+        );
+    }
+
+    /// Ensure a campaign exists and is owned by the funder of the drop being added to it
+    pub(crate) fn internal_check_campaign_owner(
+        &self,
+        owner_id: &AccountId,
+        campaign_id: CampaignId,
+    ) -> Result<Campaign, String> {
+        let campaign = self
+            .campaigns
+            .get(&campaign_id)
+            .ok_or_else(|| "No campaign found".to_string())?;
+        if &campaign.owner_id != owner_id {
+            return Err(
+                "drops can only be added to campaigns owned by the same funder".to_string(),
+            );
+        }
+        Ok(campaign)
+    }
+
+    /// Link a newly created drop to one of the funder's campaigns
+    pub(crate) fn internal_add_drop_to_campaign(
+        &mut self,
+        owner_id: &AccountId,
+        campaign_id: CampaignId,
+        drop_id: DropId,
+    ) {
+        let mut campaign = self
+            .internal_check_campaign_owner(owner_id, campaign_id)
+            .unwrap_or_else(|e| env::panic_str(&e));
+        campaign.drop_ids.push(drop_id);
+        self.campaigns.insert(&campaign_id, &campaign);
+        self.campaign_for_drop.insert(&drop_id, &campaign_id);
+    }
+
+    /// Unlink a drop from its campaign (if any) once the drop is removed
+    pub(crate) fn internal_remove_drop_from_campaign(&mut self, drop_id: &DropId) {
+        if let Some(campaign_id) = self.campaign_for_drop.remove(drop_id) {
+            if let Some(mut campaign) = self.campaigns.get(&campaign_id) {
+                campaign.drop_ids.retain(|id| id != drop_id);
+                self.campaigns.insert(&campaign_id, &campaign);
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::*;
+
+/// Most keys a single `compact_drop` call looks at so that its GAS stays bounded
+const MAX_COMPACTION_SCAN: u64 = 100;
+
+/// NEAR set aside at creation for the drop's compaction bounties
+pub(crate) fn compaction_reserve(config: &Option<DropConfig>) -> Balance {
+    match config.as_ref().and_then(|c| c.compaction_bounty) {
+        Some((num_bounties, amount)) => num_bounties as u128 * amount.0,
+        None => 0,
+    }
+}
+
+/// Compaction bounties that were reserved but haven't been paid out yet
+pub(crate) fn unspent_compaction_reserve(drop: &Drop) -> Balance {
+    match drop.config.as_ref().and_then(|c| c.compaction_bounty) {
+        Some((num_bounties, amount)) => {
+            num_bounties.saturating_sub(drop.compaction_bounties_paid) as u128 * amount.0
+        }
+        None => 0,
+    }
+}
+
+/// A compaction bounty must pay something for at least one key
+pub(crate) fn check_compaction_bounty(config: &Option<DropConfig>) -> Result<(), String> {
+    match config.as_ref().and_then(|c| c.compaction_bounty) {
+        Some((num_bounties, amount)) if num_bounties == 0 || amount.0 == 0 => {
+            Err("compaction_bounty must have at least 1 bounty greater than 0".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Remove keys that have no uses left but are still part of the drop (i.e imported with no uses remaining).
+        Anyone can call this. Each call looks at up to `limit` keys (at most 100) starting where the last call left
+        off, so a large drop is compacted over several calls. Each stale key's access key is deleted again and its
+        allowance, access key storage and the storage freed are refunded to the funder. If the funder reserved
+        compaction bounties, the caller is paid one for every key removed until the reserve runs out.
+        Calling it again on keys that were already compacted does nothing. Returns the number of keys removed.
+    */
+    pub fn compact_drop(&mut self, drop_id: DropId, limit: Option<u64>) -> u64 {
+        let mut drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        let initial_storage = env::storage_usage();
+
+        // Look at the next page of keys, wrapping around once the end is reached
+        let num_keys = drop.pks.len();
+        let start = if drop.compaction_cursor < num_keys {
+            drop.compaction_cursor
+        } else {
+            0
+        };
+        let end = start
+            .saturating_add(
+                limit
+                    .unwrap_or(MAX_COMPACTION_SCAN)
+                    .min(MAX_COMPACTION_SCAN),
+            )
+            .min(num_keys);
+        let stale_keys: Vec<PublicKey> = (start..end)
+            .filter_map(|index| drop.pks.keys_as_vector().get(index))
+            .filter(|pk| {
+                drop.pks
+                    .get(pk)
+                    .is_some_and(|key_info| key_info.remaining_uses == 0)
+            })
+            .collect();
+        drop.compaction_cursor = if end < num_keys { end } else { 0 };
+
+        let mut allowance_left = 0;
+        for pk in &stale_keys {
+            if let Some(key_info) =
+                self.internal_remove_key_info(&mut drop.pks, drop.deposit_per_use, pk)
+            {
+                self.drop_id_for_pk.remove(pk);
+                drop.pk_for_key_id.remove(&key_info.key_id);
+                allowance_left += key_info.allowance;
+            }
+        }
+        let num_removed = stale_keys.len() as u64;
+
+        // Pay the caller for each key removed while there are bounties left
+        let bounty = drop.config.as_ref().and_then(|c| c.compaction_bounty);
+        let mut bounty_total = 0;
+        if let Some((num_bounties, amount)) = bounty {
+            let num_paid =
+                num_removed.min(num_bounties.saturating_sub(drop.compaction_bounties_paid));
+            drop.compaction_bounties_paid += num_paid;
+            bounty_total = num_paid as u128 * amount.0;
+        }
+        self.drop_for_id.insert(&drop_id, &drop);
+
+        // Refund the funder for everything the removed keys were still holding
+        let final_storage = env::storage_usage();
+        let storage_freed =
+            Balance::from(initial_storage.saturating_sub(final_storage)) * drop.storage_byte_cost;
+        let refund = storage_freed + allowance_left + ACCESS_KEY_STORAGE * num_removed as u128;
+        if refund > 0 {
+            let balance = self.user_balances.get(&drop.owner_id).unwrap_or(0) + refund;
+            self.internal_set_user_balance(&drop.owner_id, balance);
+        }
+        near_sdk::log!(
+            "Compacted {} keys of drop {}. Refunded funder {}. Bounty paid: {}. Next scan starts at {}",
+            num_removed,
+            drop_id,
+            yocto_to_near(refund),
+            yocto_to_near(bounty_total),
+            drop.compaction_cursor
+        );
+
+        if bounty_total > 0 {
+            self.internal_transfer_or_defer(env::predecessor_account_id(), bounty_total);
+        }
+
+        // The access keys may still be on the contract if deleting them failed before
+        let mut batches = vec![];
+        for pk in &stale_keys {
+            let promise = env::promise_batch_create(&env::current_account_id());
+            env::promise_batch_action_delete_key(promise, pk);
+            batches.push((promise, 1));
+        }
+        self.internal_count_access_key_batches(&batches, false);
+
+        num_removed
+    }
+}
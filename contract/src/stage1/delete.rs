@@ -2,22 +2,36 @@ use near_sdk::GasWeight;
 
 use crate::*;
 
+/// What a drop left behind once it was removed by `internal_remove_drop`
+pub(crate) struct RemovedDrop {
+    pub funder_id: AccountId,
+    // Access keys that are still on the contract and need to be deleted
+    pub keys_to_delete: Vec<PublicKey>,
+    // Amount credited to the funder's balance
+    pub refund: Balance,
+    // Uses that still had NFTs or FTs registered. Those assets aren't sent back.
+    pub registered_uses_left: u64,
+}
+
 #[near_bindgen]
 impl Keypom {
     /*
         User can pass in a vector of public keys or a drop ID.
         If a drop ID is passed in, it will auto delete up to limit.
         If limit is not specified, auto assume 100 keys from the drop.
-        All keys must be from the same drop ID.
+        All keys must be from the same drop ID. Keys that aren't in the drop (i.e already deleted) are skipped.
 
         All keys must be unregistered (NFTs / FTs refunded) for the drop.
     */
+    #[payable]
     pub fn delete_keys(
         &mut self,
         drop_id: DropId,
         public_keys: Option<Vec<PublicKey>>,
-        limit: Option<u8>,
+        limit: Option<u64>,
     ) {
+        assert_one_yocto();
+
         // Measure initial storage before doing any operations
         let initial_storage = env::storage_usage();
 
@@ -29,6 +43,11 @@ impl Keypom {
             owner_id == env::predecessor_account_id(),
             "only drop funder can delete keys"
         );
+        // Pending FT drops don't have access keys yet. They're cancelled with cancel_pending_drop instead.
+        require!(
+            drop.state == DropState::Active,
+            "keys can only be deleted from active drops"
+        );
 
         // Get the max claims per key. Default to 1 if not specified in the drop config.
         let uses_per_key = drop
@@ -37,27 +56,19 @@ impl Keypom {
             .and_then(|c| c.uses_per_key)
             .unwrap_or(1);
 
-        // Get optional costs
-        let mut nft_optional_costs_per_key = 0;
-        let mut ft_optional_costs_per_claim = 0;
-
         // ensure that there are no FTs or NFTs left to be refunded
         match drop_type {
             DropType::NonFungibleToken(data) => {
                 require!(
-                    drop.registered_uses == 0,
+                    drop.registered_uses == 0 && data.token_ids.is_empty(),
                     "NFTs must be refunded before keys are deleted"
                 );
-
-                nft_optional_costs_per_key = data.storage_for_longest * env::storage_byte_cost();
             }
             DropType::FungibleToken(data) => {
                 require!(
-                    drop.registered_uses == 0,
+                    drop.registered_uses == 0 && data.dust.0 == 0,
                     "FTs must be refunded before keys are deleted"
                 );
-
-                ft_optional_costs_per_claim = data.ft_storage.0;
             }
             _ => {}
         };
@@ -65,86 +76,77 @@ impl Keypom {
         // Keep track of the total refund amount
         let total_refund_amount;
         // Default the keys to use to be the public keys or an empty vector. We'll populate it if no PKs are passed in.
-        let keys_to_delete;
+        let mut keys_to_delete;
         let mut total_allowance_left = 0;
 
         // Get the total number of claims and none FCs across all keys being deleted
         let mut total_num_claims_left = 0;
         let mut total_num_none_fcs = 0;
-        let mut total_deposit_value = 0;
+        let mut total_type_costs = 0;
         // If the user passed in public keys, loop through and remove them from the drop
         if let Some(keys) = public_keys {
-            // Set the keys to delete equal to the keys passed in
-            keys_to_delete = keys;
+            require!(
+                keys.len() as u128 <= limit.unwrap_or(100) as u128,
+                "cannot delete more keys than the limit (100 by default) at a time"
+            );
+            // Keys that were already deleted (or were never part of the drop) are skipped so deleting twice is a no-op
+            let num_passed_in = keys.len();
+            keys_to_delete = keys
+                .into_iter()
+                .filter(|key| drop.pks.get(key).is_some())
+                .collect::<Vec<PublicKey>>();
 
             let len = keys_to_delete.len() as u128;
-            require!(
-                len <= limit.unwrap_or(100) as u128,
-                "cannot delete more than 100 keys at a time"
+            near_sdk::log!(
+                "Removing {} keys from the drop. Skipped {} keys that aren't in it",
+                len,
+                num_passed_in - keys_to_delete.len()
             );
-            near_sdk::log!("Removing {} keys from the drop", len);
 
             // Loop through and remove keys
             for key in &keys_to_delete {
                 // Unlink key to drop ID
                 self.drop_id_for_pk.remove(key);
-                // Attempt to remove the public key. panic if it didn't exist
-                let key_info = drop.pks.remove(key).expect("public key must be in drop");
+                // The key was checked to be in the drop above
+                let key_info = self
+                    .internal_remove_key_info(&mut drop.pks, drop.deposit_per_use, key)
+                    .expect("public key must be in drop");
+                drop.pk_for_key_id.remove(&key_info.key_id);
+                self.internal_remove_passwords_per_use(drop_id, &drop.config, &key_info);
                 total_num_claims_left += key_info.remaining_uses;
 
-                // If the drop is FC, we need to loop through method_name data for the remaining number of
-                // Claims and get the deposits left along with the total number of None FCs
-                if let DropType::FunctionCall(data) = &drop.drop_type {
-                    let num_fcs = data.methods.len() as u64;
-
-                    // If there's one FC specified and more than 1 claim per key, that FC is to be used
-                    // For all the claims. In this case, we need to tally all the deposits for each claim.
-                    if uses_per_key > 1 && num_fcs == 1 {
-                        let attached_deposit = data
-                            .methods
-                            .iter()
-                            .next()
-                            .unwrap()
-                            .clone()
-                            .expect("cannot have a single none function call")
-                            // iterate through   all entries and sum the attached_deposit
-                            .iter()
-                            .fold(0, |acc, x| acc + x.attached_deposit.0);
-                        near_sdk::log!(format!(
-                            "Total attached_deposits for all method data: {}",
-                            attached_deposit
-                        )
-                        .as_str());
-                        total_deposit_value += key_info.remaining_uses as u128 * attached_deposit;
-
-                    // In the case where either there's 1 claim per key or the number of FCs is not 1,
-                    // We can simply loop through and manually get this data
-                    } else {
-                        // We need to loop through the remaining methods. This means we should skip and start at the
-                        // MAX - keys left
-                        let starting_index = (uses_per_key - key_info.remaining_uses) as usize;
-                        for method_name in data.methods.iter().skip(starting_index) {
-                            total_num_none_fcs += method_name.is_none() as u64;
-
-                            // If the method is not None, we need to get the attached_deposit by looping through the method datas
-                            if let Some(method_data) = method_name {
-                                let attached_deposit = method_data
-                                    .iter()
-                                    .fold(0, |acc, x| acc + x.attached_deposit.0);
-                                total_deposit_value += attached_deposit;
-                            }
-                        }
-                    }
-                }
+                // Get what the drop type reserved for the remaining number of claims (FC deposits and asset
+                // storage) along with the total number of None FCs
+                let (type_costs, num_none_fcs) = drop.drop_type.behavior().on_refund(
+                    uses_per_key,
+                    key_info.remaining_uses,
+                    drop.storage_byte_cost,
+                );
+                total_type_costs += type_costs;
+                total_num_none_fcs += num_none_fcs;
 
                 // Increment the allowance left by whatever is left on the key
                 total_allowance_left += key_info.allowance;
             }
 
+            // The deleted keys' uses are no longer registered for FC and Simple drops (FT and NFT drops are already at 0)
+            release_registered_uses(&mut drop, total_num_claims_left);
+
+            emit_key_deletion_event(&drop, drop_id, &keys_to_delete);
+            // Keys still pending activation never had access keys added
+            keys_to_delete = self.internal_remove_pending_keys(&drop_id, keys_to_delete);
+
             // If the drop has no keys, remove it from the funder. Otherwise, insert it back with the updated keys.
             if drop.pks.len() == 0 {
                 near_sdk::log!("Drop empty. Removing from funder");
+                drop.metadata.remove();
+                drop.allowed_hashes.clear();
+                drop.claims_per_account.clear();
+                self.drops_with_passwords_per_use.remove(&drop_id);
+                self.recent_failures.remove(&drop_id);
+                self.internal_remove_drop_stats(drop_id, drop.pending_claims);
                 self.internal_remove_drop_for_funder(&owner_id, &drop_id);
+                self.internal_remove_drop_from_asset_index(&drop.drop_type, drop_id);
             } else {
                 near_sdk::log!("Drop non empty. Adding back. Len: {}", drop.pks.len());
                 self.drop_for_id.insert(&drop_id, &drop);
@@ -153,7 +155,7 @@ impl Keypom {
             // Calculate the storage being freed. initial - final should be >= 0 since final should be smaller than initial.
             let final_storage = env::storage_usage();
             let total_storage_freed =
-                Balance::from(initial_storage - final_storage) * env::storage_byte_cost();
+                Balance::from(initial_storage - final_storage) * drop.storage_byte_cost;
             near_sdk::log!(
                 "Storage freed: {} bytes: {}",
                 yocto_to_near(total_storage_freed),
@@ -168,44 +170,43 @@ impl Keypom {
                 - Balance for each key * (number of claims - claims with None for FC Data)
 
                 Optional:
-                - total FC deposits
-                - storage for longest token ID for each key
-                - FT storage registration cost for each key * claims (calculated in resolve storage calculation function)
+                - drop type costs for each key (see `DropBehavior::on_refund`)
+                - wrap contract registration and deployed contract storage for each paid claim
             */
             total_refund_amount = total_storage_freed
-                + drop.deposit_per_use * (total_num_claims_left - total_num_none_fcs) as u128
-                + ft_optional_costs_per_claim * total_num_claims_left as u128
-                + total_deposit_value
+                + (drop.deposit_per_use + extra_cost_per_use(&drop.config))
+                    * (total_num_claims_left - total_num_none_fcs) as u128
+                + total_type_costs
                 + total_allowance_left
-                + (ACCESS_KEY_STORAGE + nft_optional_costs_per_key) * len;
+                + ACCESS_KEY_STORAGE * len;
 
             near_sdk::log!(
                 "Total refund: {}
                 storage freed: {}
                 drop balance: {}
-                FT costs per claim: {}
-                total attached_deposit value: {}
+                drop type costs: {}
                 total allowance left: {}
                 access key storage: {}
-                nft optional costs per key: {}
                 total num claims left: {}
                 total num none FCs {}
                 len: {}",
                 yocto_to_near(total_refund_amount),
                 yocto_to_near(total_storage_freed),
                 yocto_to_near(drop.deposit_per_use),
-                yocto_to_near(ft_optional_costs_per_claim),
-                yocto_to_near(total_deposit_value),
+                yocto_to_near(total_type_costs),
                 yocto_to_near(total_allowance_left),
                 yocto_to_near(ACCESS_KEY_STORAGE),
-                yocto_to_near(nft_optional_costs_per_key),
                 total_num_claims_left,
                 total_num_none_fcs,
                 len
             );
         } else {
             // If no PKs were passed in, attempt to remove limit or 100 keys at a time
-            keys_to_delete = drop.pks.keys().take(limit.unwrap_or(100).into()).collect();
+            keys_to_delete = drop
+                .pks
+                .keys()
+                .take(limit.unwrap_or(100) as usize)
+                .collect();
 
             let len = keys_to_delete.len() as u128;
             near_sdk::log!("Removing {} keys from the drop", len);
@@ -215,61 +216,45 @@ impl Keypom {
                 // Unlink key to drop ID
                 self.drop_id_for_pk.remove(key);
                 // Attempt to remove the public key. panic if it didn't exist
-                let key_info = drop.pks.remove(key).expect("public key must be in drop");
+                let key_info = self
+                    .internal_remove_key_info(&mut drop.pks, drop.deposit_per_use, key)
+                    .expect("public key must be in drop");
+                drop.pk_for_key_id.remove(&key_info.key_id);
+                self.internal_remove_passwords_per_use(drop_id, &drop.config, &key_info);
                 total_num_claims_left += key_info.remaining_uses;
 
-                // If the drop is FC, we need to loop through method_name data for the remaining number of
-                // Claims and get the deposits left along with the total number of None FCs
-                if let DropType::FunctionCall(data) = &drop.drop_type {
-                    let num_fcs = data.methods.len() as u64;
-
-                    // If there's one FC specified and more than 1 claim per key, that FC is to be used
-                    // For all the claims. In this case, we need to tally all the deposits for each claim.
-                    if uses_per_key > 1 && num_fcs == 1 {
-                        let attached_deposit = data
-                            .methods
-                            .iter()
-                            .next()
-                            .unwrap()
-                            .clone()
-                            .expect("cannot have a single none function call")
-                            // iterate through   all entries and sum the attached_deposit
-                            .iter()
-                            .fold(0, |acc, x| acc + x.attached_deposit.0);
-                        near_sdk::log!(format!(
-                            "Total attached_deposits for all method data: {}",
-                            attached_deposit
-                        )
-                        .as_str());
-                        total_deposit_value += key_info.remaining_uses as u128 * attached_deposit;
-
-                    // In the case where either there's 1 claim per key or the number of FCs is not 1,
-                    // We can simply loop through and manually get this data
-                    } else {
-                        // We need to loop through the remaining methods. This means we should skip and start at the
-                        // MAX - keys left
-                        let starting_index = (uses_per_key - key_info.remaining_uses) as usize;
-                        for method_name in data.methods.iter().skip(starting_index) {
-                            total_num_none_fcs += method_name.is_none() as u64;
-                            // If the method is not None, we need to get the attached_deposit by looping through the method datas
-                            if let Some(method_data) = method_name {
-                                let attached_deposit = method_data
-                                    .iter()
-                                    .fold(0, |acc, x| acc + x.attached_deposit.0);
-                                total_deposit_value += attached_deposit;
-                            }
-                        }
-                    }
-                }
+                // Get what the drop type reserved for the remaining number of claims (FC deposits and asset
+                // storage) along with the total number of None FCs
+                let (type_costs, num_none_fcs) = drop.drop_type.behavior().on_refund(
+                    uses_per_key,
+                    key_info.remaining_uses,
+                    drop.storage_byte_cost,
+                );
+                total_type_costs += type_costs;
+                total_num_none_fcs += num_none_fcs;
 
                 // Increment the allowance left by whatever is left on the key
                 total_allowance_left += key_info.allowance;
             }
 
+            // The deleted keys' uses are no longer registered for FC and Simple drops (FT and NFT drops are already at 0)
+            release_registered_uses(&mut drop, total_num_claims_left);
+
+            emit_key_deletion_event(&drop, drop_id, &keys_to_delete);
+            // Keys still pending activation never had access keys added
+            keys_to_delete = self.internal_remove_pending_keys(&drop_id, keys_to_delete);
+
             // If the drop has no keys, remove it from the funder. Otherwise, insert it back with the updated keys.
             if drop.pks.len() == 0 {
                 near_sdk::log!("Drop empty. Removing from funder");
+                drop.metadata.remove();
+                drop.allowed_hashes.clear();
+                drop.claims_per_account.clear();
+                self.drops_with_passwords_per_use.remove(&drop_id);
+                self.recent_failures.remove(&drop_id);
+                self.internal_remove_drop_stats(drop_id, drop.pending_claims);
                 self.internal_remove_drop_for_funder(&owner_id, &drop_id);
+                self.internal_remove_drop_from_asset_index(&drop.drop_type, drop_id);
             } else {
                 near_sdk::log!("Drop non empty. Adding back. Len: {}", drop.pks.len());
                 self.drop_for_id.insert(&drop_id, &drop);
@@ -278,7 +263,7 @@ impl Keypom {
             // Calculate the storage being freed. initial - final should be >= 0 since final should be smaller than initial.
             let final_storage = env::storage_usage();
             let total_storage_freed =
-                Balance::from(initial_storage - final_storage) * env::storage_byte_cost();
+                Balance::from(initial_storage - final_storage) * drop.storage_byte_cost;
             near_sdk::log!(
                 "Storage freed: {} bytes: {}",
                 yocto_to_near(total_storage_freed),
@@ -293,70 +278,102 @@ impl Keypom {
                 - Balance for each key * (number of claims - claims with None for FC Data)
 
                 Optional:
-                - total FC deposits
-                - storage for longest token ID for each key
-                - FT storage registration cost for each key * claims (calculated in resolve storage calculation function)
+                - drop type costs for each key (see `DropBehavior::on_refund`)
+                - wrap contract registration and deployed contract storage for each paid claim
             */
             total_refund_amount = total_storage_freed
-                + drop.deposit_per_use * (total_num_claims_left - total_num_none_fcs) as u128
-                + ft_optional_costs_per_claim * total_num_claims_left as u128
-                + total_deposit_value
+                + (drop.deposit_per_use + extra_cost_per_use(&drop.config))
+                    * (total_num_claims_left - total_num_none_fcs) as u128
+                + total_type_costs
                 + total_allowance_left
-                + (ACCESS_KEY_STORAGE + nft_optional_costs_per_key) * len;
+                + ACCESS_KEY_STORAGE * len;
 
             near_sdk::log!(
                 "Total refund: {}
                 storage freed: {}
                 drop balance: {}
-                FT costs per claim: {}
-                total attached_deposit value: {}
+                drop type costs: {}
                 total allowance left: {}
                 access key storage: {}
-                nft optional costs per key: {}
                 total num claims left: {}
                 total num none FCs {}
                 len: {}",
                 yocto_to_near(total_refund_amount),
                 yocto_to_near(total_storage_freed),
                 yocto_to_near(drop.deposit_per_use),
-                yocto_to_near(ft_optional_costs_per_claim),
-                yocto_to_near(total_deposit_value),
+                yocto_to_near(total_type_costs),
                 yocto_to_near(total_allowance_left),
                 yocto_to_near(ACCESS_KEY_STORAGE),
-                yocto_to_near(nft_optional_costs_per_key),
                 total_num_claims_left,
                 total_num_none_fcs,
                 len
             );
         }
 
-        // Refund the user
-        let mut cur_balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        // If the drop was removed, any early bonus or compaction bounties reserved but never paid out are refunded as well
+        let unspent_bonus = if drop.pks.is_empty() {
+            unspent_early_bonus(&drop) + unspent_compaction_reserve(&drop)
+        } else {
+            0
+        };
+
+        // Refund the user. Large refunds vest if the funder asked for it.
         near_sdk::log!(
-            "Refunding user {} old balance: {}. Total allowance left: {}",
+            "Refunding user {} old balance: {}. Total allowance left: {}. Unspent early bonus: {}",
             yocto_to_near(total_refund_amount),
-            yocto_to_near(cur_balance),
-            yocto_to_near(total_allowance_left)
+            yocto_to_near(self.user_balances.get(&owner_id).unwrap_or(0)),
+            yocto_to_near(total_allowance_left),
+            yocto_to_near(unspent_bonus)
         );
-        cur_balance += total_refund_amount;
-        self.user_balances.insert(&owner_id, &cur_balance);
+        self.internal_credit_refund(&owner_id, total_refund_amount + unspent_bonus);
 
         // Loop through and delete keys
+        let mut batches = vec![];
         for key in &keys_to_delete {
             // Create the batch promise
             let promise = env::promise_batch_create(&env::current_account_id());
 
             env::promise_batch_action_delete_key(promise, &key);
+            batches.push((promise, 1));
 
             env::promise_return(promise);
         }
+        self.internal_count_access_key_batches(&batches, false);
+    }
+
+    /*
+        Delete up to `limit` keys (100 by default) whose end timestamp has passed and credit the funder the same
+        refund as `delete_keys`. Keys whose own config ends later are left alone. As with `delete_keys`, any NFTs or
+        FTs must be refunded first.
+    */
+    #[payable]
+    pub fn refund_expired_keys(&mut self, drop_id: DropId, limit: Option<u64>) {
+        assert_one_yocto();
+
+        let drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        let current_timestamp = env::block_timestamp();
+        let expired_keys: Vec<PublicKey> = drop
+            .pks
+            .iter()
+            .filter(|(_, key_info)| key_has_ended(&drop, key_info, current_timestamp))
+            .map(|(pk, _)| pk)
+            .take(limit.unwrap_or(100) as usize)
+            .collect();
+        require!(!expired_keys.is_empty(), "no expired keys in drop");
+
+        near_sdk::log!("Refunding {} expired keys", expired_keys.len());
+        self.delete_keys(drop_id, Some(expired_keys), limit);
     }
 
     /*
         Refund NFTs or FTs for a drop. User can optionally pass in a number of assets to
-        refund. If not, it will try to refund all assets.
+        refund. If not, it will try to refund all assets. Any FT dust the drop accumulated
+        is always refunded along with them.
     */
+    #[payable]
     pub fn refund_assets(&mut self, drop_id: DropId, assets_to_refund: Option<u64>) {
+        assert_one_yocto();
+
         // get the drop object
         let mut drop = self.drop_for_id.get(&drop_id).expect("No drop found");
         let owner_id = drop.owner_id.clone();
@@ -365,9 +382,23 @@ impl Keypom {
             "only drop funder can delete keys"
         );
 
+        // Take the FT dust out of the drop. It's sent back with the refunded uses.
+        let dust = match &mut drop.drop_type {
+            DropType::FungibleToken(data) => std::mem::take(&mut data.dust.0),
+            _ => 0,
+        };
+        // Token IDs that don't add up to a full pack are sent back with the refunded uses as well
+        let leftover_tokens = match &drop.drop_type {
+            DropType::NonFungibleToken(data) => data.leftover_tokens(),
+            _ => 0,
+        };
+
         // Get the number of claims registered for the drop.
         let claims_registered = drop.registered_uses;
-        require!(claims_registered > 0, "no claims left to unregister");
+        require!(
+            claims_registered > 0 || dust > 0 || leftover_tokens > 0,
+            "no claims left to unregister"
+        );
 
         // Get the claims to refund. If not specified, this is the number of claims currently registered.
         let num_to_refund = assets_to_refund.unwrap_or(claims_registered);
@@ -392,7 +423,7 @@ impl Keypom {
                 let mut token_ids: Vec<String> = vec![];
 
                 // Loop through and pop / transfer all token IDs. If anything goes wrong, we send back all the token IDs, we popped and push them back in the callback.
-                for _ in 0..num_to_refund {
+                for _ in 0..num_to_refund * data.tokens_per_use + leftover_tokens {
                     let token_id = data.token_ids.pop().unwrap();
                     token_ids.push(token_id.clone());
                     // Send the NFTs back to the sender
@@ -433,18 +464,288 @@ impl Keypom {
                     .with_attached_deposit(1)
                     .ft_transfer(
                         data.sender_id.clone(),
-                        U128(data.balance_per_use.0 * num_to_refund as u128),
+                        U128(data.balance_per_use.0 * num_to_refund as u128 + dust),
                         None,
                     )
                     // We then resolve the promise and call nft_resolve_transfer on our own contract
                     .then(
                         // Call resolve refund with the min GAS and no attached_deposit. 1/2 unspent GAS will be added on top
-                        Self::ext(env::current_account_id())
-                            .ft_resolve_refund(drop_id, num_to_refund),
+                        Self::ext(env::current_account_id()).ft_resolve_refund(
+                            drop_id,
+                            num_to_refund,
+                            U128(dust),
+                        ),
                     )
                     .as_return();
             }
             _ => env::panic_str("can only refund assets for FT and NFT drops"),
         };
     }
+
+    /*
+        Delete a drop and everything stored for it. By default the drop can't have any keys left. Pass in `force`
+        to delete the remaining keys along with it. Every key is removed in this call so drops with many keys
+        should be emptied with `delete_keys` first. Any NFTs or FTs still registered (along with FT dust) are sent
+        back to their sender first and the drop is only removed once that succeeds. If sending them back fails,
+        the assets are put back and the drop is left as it was. The storage freed and everything reserved for the
+        remaining keys is credited to the funder's balance.
+    */
+    #[payable]
+    pub fn delete_drop(&mut self, drop_id: DropId, force: Option<bool>) {
+        assert_one_yocto();
+
+        let drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        require!(
+            drop.owner_id == env::predecessor_account_id(),
+            "only drop funder can delete the drop"
+        );
+        // Pending FT drops are cancelled with cancel_pending_drop instead
+        require!(
+            drop.state == DropState::Active,
+            "only active drops can be deleted"
+        );
+        require!(
+            force.unwrap_or(false) || drop.pks.is_empty(),
+            "drop still has keys. Delete them first or pass in force"
+        );
+
+        self.internal_delete_drop(drop_id, drop);
+    }
+
+    #[private]
+    /// Self callback for `delete_drop`. Removes the drop if its assets were sent back and puts them back otherwise.
+    pub fn resolve_delete_drop(
+        &mut self,
+        drop_id: DropId,
+        num_to_refund: u64,
+        token_ids: Vec<String>,
+        dust: U128,
+    ) -> bool {
+        // The refund resolvers put the assets back if sending them failed
+        let is_nft = matches!(
+            self.drop_for_id
+                .get(&drop_id)
+                .expect("no drop for ID")
+                .drop_type,
+            DropType::NonFungibleToken(_)
+        );
+        let refunded = if is_nft {
+            self.nft_resolve_refund(U128(drop_id), token_ids)
+        } else {
+            self.ft_resolve_refund(drop_id, num_to_refund, dust)
+        };
+
+        let mut drop = self.drop_for_id.get(&drop_id).expect("no drop for ID");
+        if !refunded {
+            near_sdk::log!(
+                "Assets for drop {} couldn't be sent back. The drop wasn't deleted",
+                drop_id
+            );
+            drop.state = DropState::Active;
+            self.drop_for_id.insert(&drop_id, &drop);
+            return false;
+        }
+
+        self.internal_finish_delete_drop(drop_id, drop);
+        true
+    }
+}
+
+impl Keypom {
+    /*
+        Remove a drop along with its keys and everything stored for it and credit the funder with the storage freed,
+        the allowance, access key storage and deposits left on the keys and any reserves that were never paid out.
+        Any NFTs or FTs still registered are dropped without being sent back. The access keys are returned so the
+        caller can delete them.
+    */
+    pub(crate) fn internal_remove_drop(&mut self, drop_id: DropId, mut drop: Drop) -> RemovedDrop {
+        // Measure initial storage before doing any operations
+        let initial_storage = env::storage_usage();
+
+        self.drop_for_id.remove(&drop_id);
+        let funder_id = drop.owner_id.clone();
+        let uses_per_key = drop
+            .config
+            .clone()
+            .and_then(|c| c.uses_per_key)
+            .unwrap_or(1);
+
+        // Tally up everything that was reserved for the keys that are left
+        let mut total_allowance_left = 0;
+        let mut total_num_claims_left = 0;
+        let mut total_num_none_fcs = 0;
+        let mut total_type_costs = 0;
+        let mut keys_to_delete: Vec<PublicKey> = drop.pks.keys().collect();
+        for (key, key_info) in drop.pks.iter() {
+            self.drop_id_for_pk.remove(&key);
+            drop.pk_for_key_id.remove(&key_info.key_id);
+            self.internal_remove_passwords_per_use(drop_id, &drop.config, &key_info);
+            total_allowance_left += key_info.allowance;
+            total_num_claims_left += key_info.remaining_uses;
+
+            // FC drops reserve the attached deposit for every method left and FT and NFT drops the asset storage
+            let (type_costs, num_none_fcs) = drop.drop_type.behavior().on_refund(
+                uses_per_key,
+                key_info.remaining_uses,
+                drop.storage_byte_cost,
+            );
+            total_type_costs += type_costs;
+            total_num_none_fcs += num_none_fcs;
+        }
+        self.internal_clear_key_infos(&mut drop.pks, drop.deposit_per_use);
+
+        // Clear any remaining asset data for the drop
+        if let DropType::NonFungibleToken(data) = &mut drop.drop_type {
+            data.token_ids.clear();
+        }
+        drop.metadata.remove();
+        drop.allowed_hashes.clear();
+        drop.claims_per_account.clear();
+        self.drops_with_passwords_per_use.remove(&drop_id);
+        self.recent_failures.remove(&drop_id);
+        self.pending_force_refunds.remove(&drop_id);
+        emit_key_deletion_event(&drop, drop_id, &keys_to_delete);
+        // Keys still pending activation never had access keys added
+        keys_to_delete = self.internal_remove_pending_keys(&drop_id, keys_to_delete);
+        self.internal_remove_drop_stats(drop_id, drop.pending_claims);
+        self.internal_remove_drop_for_funder(&funder_id, &drop_id);
+        self.internal_remove_drop_from_asset_index(&drop.drop_type, drop_id);
+
+        // Calculate the storage being freed. initial - final should be >= 0 since final should be smaller than initial.
+        let final_storage = env::storage_usage();
+        let total_storage_freed =
+            Balance::from(initial_storage - final_storage) * drop.storage_byte_cost;
+
+        let refund = total_storage_freed
+            + (drop.deposit_per_use + extra_cost_per_use(&drop.config))
+                * (total_num_claims_left - total_num_none_fcs) as u128
+            + total_type_costs
+            + total_allowance_left
+            + ACCESS_KEY_STORAGE * keys_to_delete.len() as u128
+            + unspent_early_bonus(&drop)
+            + unspent_compaction_reserve(&drop);
+
+        self.internal_credit_refund(&funder_id, refund);
+
+        // FTs that weren't sent back stay with the contract but are no longer held for the drop
+        if let DropType::FungibleToken(data) = &drop.drop_type {
+            let abandoned = data.balance_per_use.0 * drop.registered_uses as u128 + data.dust.0;
+            self.internal_release_ft(&data.contract_id, abandoned);
+        }
+
+        RemovedDrop {
+            funder_id,
+            keys_to_delete,
+            refund,
+            registered_uses_left: drop.registered_uses,
+        }
+    }
+
+    /*
+        Send any NFTs or FTs still registered to the drop (along with FT dust) back to their sender and remove the
+        drop once that succeeds. The drop is marked as deleting in the meantime. Drops with nothing to send back are
+        removed right away.
+    */
+    pub(crate) fn internal_delete_drop(&mut self, drop_id: DropId, mut drop: Drop) {
+        // Take the assets still registered out of the drop. They're put back in the callback if sending them fails.
+        let num_to_refund = drop.registered_uses;
+        match &mut drop.drop_type {
+            DropType::NonFungibleToken(data) if !data.token_ids.is_empty() => {
+                let token_ids = data.token_ids.to_vec();
+                data.token_ids.clear();
+                let contract_id = data.contract_id.clone();
+                let sender_id = data.sender_id.clone();
+                drop.registered_uses = 0;
+                drop.state = DropState::Deleting;
+                self.drop_for_id.insert(&drop_id, &drop);
+
+                let nft_batch_index = env::promise_batch_create(&contract_id);
+                for token_id in &token_ids {
+                    env::promise_batch_action_function_call_weight(
+                        nft_batch_index,
+                        "nft_transfer",
+                        json!({ "receiver_id": sender_id, "token_id": token_id, "memo": "Refund" })
+                            .to_string()
+                            .as_bytes(),
+                        1,
+                        MIN_GAS_FOR_SIMPLE_NFT_TRANSFER,
+                        GasWeight(1),
+                    );
+                }
+
+                // Only remove the drop once every NFT was sent back
+                let resolve_promise_id =
+                    env::promise_batch_then(nft_batch_index, &env::current_account_id());
+                env::promise_batch_action_function_call_weight(
+                    resolve_promise_id,
+                    "resolve_delete_drop",
+                    json!({ "drop_id": U128(drop_id), "num_to_refund": num_to_refund, "token_ids": token_ids, "dust": U128(0) })
+                        .to_string()
+                        .as_bytes(),
+                    NO_DEPOSIT,
+                    MIN_GAS_FOR_RESOLVE_BATCH,
+                    GasWeight(10),
+                );
+                env::promise_return(resolve_promise_id);
+            }
+            DropType::FungibleToken(data) if num_to_refund > 0 || data.dust.0 > 0 => {
+                let dust = std::mem::take(&mut data.dust.0);
+                let amount = data.balance_per_use.0 * num_to_refund as u128 + dust;
+                let contract_id = data.contract_id.clone();
+                let sender_id = data.sender_id.clone();
+                drop.registered_uses = 0;
+                drop.state = DropState::Deleting;
+                self.drop_for_id.insert(&drop_id, &drop);
+
+                ext_ft_contract::ext(contract_id)
+                    .with_attached_deposit(1)
+                    .ft_transfer(sender_id, U128(amount), None)
+                    // Only remove the drop once the FTs were sent back
+                    .then(Self::ext(env::current_account_id()).resolve_delete_drop(
+                        drop_id,
+                        num_to_refund,
+                        vec![],
+                        U128(dust),
+                    ))
+                    .as_return();
+            }
+            // Nothing to send back so the drop can be removed right away
+            _ => self.internal_finish_delete_drop(drop_id, drop),
+        }
+    }
+
+    /// Remove a drop being deleted once it has no assets left and delete its access keys
+    fn internal_finish_delete_drop(&mut self, drop_id: DropId, drop: Drop) {
+        let removed = self.internal_remove_drop(drop_id, drop);
+        near_sdk::log!(
+            "Drop {} deleted. {} keys deleted. Refunded {} to the funder's balance",
+            drop_id,
+            removed.keys_to_delete.len(),
+            yocto_to_near(removed.refund)
+        );
+
+        if !removed.keys_to_delete.is_empty() {
+            let promise = env::promise_batch_create(&env::current_account_id());
+            for key in &removed.keys_to_delete {
+                env::promise_batch_action_delete_key(promise, key);
+            }
+            self.internal_count_access_key_batches(
+                &[(promise, removed.keys_to_delete.len() as u64)],
+                false,
+            );
+        }
+    }
+}
+
+/// Emit a `key_deletion` event for keys removed from a drop. Nothing is emitted if no keys were removed.
+fn emit_key_deletion_event(drop: &Drop, drop_id: DropId, public_keys: &[PublicKey]) {
+    if public_keys.is_empty() {
+        return;
+    }
+    emit_event(EventLogVariant::KeyDeletion(vec![KeyDeletionLog {
+        owner_id: drop.owner_id.clone(),
+        drop_id,
+        public_keys: public_keys.to_vec(),
+        drop_removed: drop.pks.is_empty(),
+    }]));
 }
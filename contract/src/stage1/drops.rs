@@ -1,6 +1,6 @@
 use crate::*;
 use near_sdk::{
-    collections::{LazyOption, Vector},
+    collections::{LazyOption, LookupMap, Vector},
     require, Balance,
 };
 
@@ -14,11 +14,64 @@ pub enum DropType {
     FunctionCall(FCData),
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Deserialize, Serialize, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub enum ClaimPermissions {
     Claim,
     CreateAccountAndClaim,
+    Both,
+}
+
+impl ClaimPermissions {
+    /// Whether a key with this permission can call the claim method represented by `method`
+    pub fn allows(&self, method: &ClaimPermissions) -> bool {
+        *self == ClaimPermissions::Both || self == method
+    }
+}
+
+/// Stored state of a drop's setup. FT drops stay pending until the storage check on the FT contract resolves.
+#[derive(BorshSerialize, BorshDeserialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum DropState {
+    // Waiting on the storage check. Keys haven't been added yet and the funder can still cancel.
+    Pending {
+        // Everything charged to the funder when the drop was created
+        required_deposit: U128,
+        // Block at which the storage check was queued
+        since_block: u64,
+    },
+    // Keys have been added and can be claimed
+    Active,
+    // The funder cancelled the drop while it was pending. It's rolled back once the storage check resolves.
+    Cancelled,
+    // The storage check failed or never resolved. The drop is rolled back and the funder refunded.
+    Failed,
+    // The funder is deleting the drop and its NFTs or FTs are being sent back. It goes back to Active if that fails.
+    Deleting,
+}
+
+/// Where a drop is in its lifecycle. This is derived from the drop's state, config and claims in `drop_status`.
+#[derive(BorshSerialize, BorshDeserialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum DropStatus {
+    // FT drop waiting on the storage check. Keys haven't been added yet.
+    PendingStorage,
+    // The funder cancelled the drop while it was pending
+    Cancelled,
+    // The storage check failed or never resolved
+    Failed,
+    // FT or NFT drop with no assets registered for the next claim
+    Unfunded,
+    // The start timestamp hasn't been reached yet
+    NotStarted,
+    // The end timestamp has passed. Keys can only be deleted.
+    Expired,
+    // The max number of total claims has been reached
+    Exhausted,
+    // Keys can be claimed
+    Active,
+    // The funder is deleting the drop and its assets are being sent back
+    Deleting,
 }
 
 /// Keep track of different configuration options for each key in a drop
@@ -36,10 +89,30 @@ pub struct KeyInfo {
 
     // Nonce for the current key.
     pub key_id: u64,
+
+    // Running total of the allowance spent by the key's claims, based on the GAS actually attached to each one
+    pub allowance_used: u128,
+
+    // Short label set by the funder when adding the key (i.e where it was handed out). Only used for analytics.
+    pub context_tag: Option<String>,
+
+    // Overrides of the drop config given to the key when the drop was created
+    pub config: Option<KeyConfig>,
+
+    // sha256 hash of the password that must be passed in to claim the key. Keys without one don't need a password.
+    pub password_hash: Option<Vec<u8>>,
+}
+
+impl KeyInfo {
+    /// Take the GAS spent by a claim out of the key's allowance and add it to the running total
+    pub(crate) fn spend_allowance(&mut self, amount: Balance) {
+        self.allowance -= amount;
+        self.allowance_used += amount;
+    }
 }
 
 /// Keep track of different configuration options for each key in a drop
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
 #[serde(crate = "near_sdk::serde")]
 pub struct DropConfig {
     // How many claims can each key have. If None, default to 1.
@@ -47,20 +120,115 @@ pub struct DropConfig {
 
     // Minimum block timestamp that keys can be used. If None, keys can be used immediately
     // Measured in number of non-leap-nanoseconds since January 1, 1970 0:00:00 UTC.
+    // Can also be passed in as an ISO-8601 timestamp (i.e "2023-01-31T18:00:00Z") which is stored in nanoseconds.
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
     pub start_timestamp: Option<u64>,
 
     // How often can a key be used
-    // Measured in number of non-leap-nanoseconds since January 1, 1970 0:00:00 UTC.
+    // Measured in nanoseconds. Can also be passed in as an ISO-8601 duration (i.e "PT1H") which is stored in nanoseconds.
+    #[serde(default, deserialize_with = "deserialize_duration")]
     pub throttle_timestamp: Option<u64>,
 
+    // If a throttle is specified, should the first use of each key also wait for the throttle interval
+    // measured from when the key was added? If None, default to false and keys can be used right away.
+    pub throttle_from_addition: Option<bool>,
+
     // If claim is called, refund the deposit to the owner's balance. If None, default to false.
     pub on_claim_refund_deposit: Option<bool>,
 
-    // Can the access key only call the claim method_name? Default to both method_name callable
+    // Can the access key only call the claim method_name? Default to both method_name callable.
+    // This is overwritten with the effective permission when it's overridden (i.e FC drops with attached GAS).
     pub claim_permission: Option<ClaimPermissions>,
 
     // Root account that all sub-accounts will default to. If None, default to the global drop root.
     pub drop_root: Option<AccountId>,
+
+    // The first N claims across the drop get an extra amount on top of the deposit per use.
+    // The bonus for all N claims is reserved when the drop is created.
+    pub early_bonus: Option<(u64, U128)>,
+
+    // Maximum number of successful claims across all keys in the drop. Claims beyond this are rejected
+    // without consuming a use. If None, the drop is only limited by its keys.
+    pub max_total_claims: Option<u64>,
+
+    // Wrap contract (i.e wrap.near) to deposit the claim balance into so that the claimer receives
+    // the wrapped tokens instead of $NEAR. If None, the balance is sent as native $NEAR.
+    pub wrap_contract: Option<AccountId>,
+
+    // Contract to deploy (and optionally initialize) on accounts created with create_account_and_claim.
+    // The contract's storage is reserved for every claim when keys are added. If None, no contract is deployed.
+    pub deploy: Option<DeployConfig>,
+
+    // Claimers must pass in an identifier whose sha256 hash is in the drop's allowlist. Hashes are added by the
+    // funder with add_allowed_hashes. If None, the drop has no allowlist and anyone with a key can claim.
+    pub identifier_allowlist: Option<IdentifierAllowlist>,
+
+    // Tokens that claimers can choose to receive instead of $NEAR when claiming. Each token must have a swap route
+    // registered by the contract owner. Only supported for simple drops. If None, claimers always receive $NEAR.
+    pub alternative_payouts: Option<Vec<AccountId>>,
+
+    // Beneficiaries that receive a share of each claim's $NEAR (in basis points) with the rest going to the claimer.
+    // The funder still pays the full deposit per use. Only supported for simple drops. If None, the claimer receives it all.
+    pub deposit_split: Option<Vec<(AccountId, u16)>>,
+
+    // Keep FT and NFT drops out of `get_drops_for_asset_contract` (i.e for private campaigns). If None, default to false.
+    pub unlisted: Option<bool>,
+
+    // Redirect and / or message for wallets to show once a key is claimed. Surfaced in `get_claim_page_data` and the
+    // claim event. If None, wallets show their default success screen.
+    pub post_claim: Option<PostClaimInfo>,
+
+    // Allow a start timestamp or throttle more than 50 years out. These are rejected by default since they're
+    // usually in the wrong unit. If None, default to false.
+    pub allow_distant_timestamps: Option<bool>,
+
+    // $NEAR sent to accounts created with create_account_and_claim on top of the deposit so new users can pay for
+    // their first transactions. It's reserved for every paid claim and refunded when a key is used with claim instead.
+    // If None, no rebate is sent.
+    pub gas_rebate: Option<U128>,
+
+    // Method on the funder's contract that must return true for the receiving account before claim goes through
+    // (i.e checking that it holds a membership NFT). The GAS is added to required_gas. Requires keys that can only
+    // call claim. If None, anyone with a key can claim.
+    pub eligibility_check: Option<EligibilityCheck>,
+
+    // Mint a non-transferable badge on the owner registered badge minter for every successful claim. The deposit
+    // and GAS for the mint are reserved for every paid claim. If None, default to false.
+    pub mint_badge: Option<bool>,
+
+    // sha256 commitment to a batch of keys that are added later with reveal_keys instead of add_keys, proving that
+    // no key was swapped. Each reveal replaces it with the commitment for the keys still to come. If None, keys are
+    // added with add_keys.
+    pub key_commitment: Option<Base64VecU8>,
+
+    // Leading zero bits that sha256(public key || receiver || pow_nonce) must have for a claim to go through. Slows down
+    // bots farming public drops. Clients mine the nonce before claiming. If None or 0, no proof of work is needed.
+    pub pow_difficulty: Option<u8>,
+
+    // Let create_account_and_claim be called without a new_account_id. The contract then names the account
+    // `k<key_id>-<drop_id>.<root>` so that claimers don't need to pick one.
+    pub auto_account_names: Option<bool>,
+
+    // Number of bounties and the amount of each paid to whoever removes a key with no uses left using compact_drop.
+    // The bounties for all of them are reserved when the drop is created and whatever isn't paid out is refunded.
+    pub compaction_bounty: Option<(u64, U128)>,
+
+    // Short description of the drop (at most 256 bytes) that claim pages can show without reading the metadata.
+    // If None, the first 256 bytes of the metadata are used instead.
+    pub metadata_abstract: Option<String>,
+
+    // Block timestamp after which keys can no longer be used. If None, keys never expire.
+    // Must be after the start timestamp. Can also be passed in as an ISO-8601 timestamp like the start timestamp.
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub end_timestamp: Option<u64>,
+
+    // Label for each use of a key (i.e "Scan at booth B") so apps can show which step comes next.
+    // Must have one label per use and each can be at most 64 bytes.
+    pub use_labels: Option<Vec<String>>,
+
+    // Maximum number of times a single receiving account can claim across all keys in the drop. Claims beyond this
+    // are rejected without consuming a use. If None, accounts can claim as many keys as they hold.
+    pub max_claims_per_account: Option<u64>,
 }
 
 // Drop Metadata should be a string which can be JSON or anything the users want.
@@ -73,16 +241,39 @@ pub struct Drop {
     pub owner_id: AccountId,
     // Set of public keys associated with this drop mapped to their usages
     pub pks: UnorderedMap<PublicKey, KeyInfo>,
+    // Map each key ID to its public key so keys can be iterated in the order they were added
+    pub pk_for_key_id: LookupMap<u64, PublicKey>,
+
+    // sha256 hashes of the identifiers allowed to claim if the drop has an identifier allowlist
+    pub allowed_hashes: UnorderedSet<Vec<u8>>,
+
+    // How many times each receiving account has claimed if the drop has a max number of claims per account
+    pub claims_per_account: UnorderedMap<AccountId, u64>,
 
     // Balance for all keys of this drop. Can be 0 if specified.
     pub deposit_per_use: u128,
 
-    // How many uses are registered (for FTs and NFTs)
+    // How many uses are registered. For FTs and NFTs, it's the uses with assets sent. For FC and Simple drops,
+    // it's every remaining use across the drop's keys, including uses whose method is None.
+    // Every claim consumes one and claims are rejected once it hits 0.
     pub registered_uses: u64,
 
     // Ensure this drop can only be used when the function has the required gas to attach
     pub required_gas: Gas,
 
+    // How many claims have received the early bonus so far
+    pub early_claims: u64,
+
+    // How many claims have been confirmed successful and how many are still resolving
+    pub total_claims: u64,
+    pub pending_claims: u64,
+
+    // Which claim methods the keys can actually call. This is enforced in the claim methods and matches the access keys.
+    pub claim_permission: ClaimPermissions,
+
+    // Whether the drop's setup has resolved or was rolled back. The full lifecycle status is derived from this with `drop_status`.
+    pub state: DropState,
+
     // Every drop must have a type
     pub drop_type: DropType,
 
@@ -91,9 +282,19 @@ pub struct Drop {
 
     // Metadata for the drop
     pub metadata: LazyOption<DropMetadata>,
+    // Abstract from the config or the start of the metadata. Kept in sync whenever either changes.
+    pub metadata_summary: Option<String>,
 
     // Keep track of the next nonce to give out to a key
     pub next_key_id: u64,
+
+    // How many compaction bounties have been paid out and the index of the key the next compaction starts at
+    pub compaction_bounties_paid: u64,
+    pub compaction_cursor: u64,
+
+    // Cost per byte of storage when the drop was created. The drop's storage is charged and refunded at this rate
+    // so that refunds match what the funder paid even if the protocol's storage price changes.
+    pub storage_byte_cost: Balance,
 }
 
 #[near_bindgen]
@@ -108,297 +309,253 @@ impl Keypom {
     #[payable]
     pub fn create_drop(
         &mut self,
-        public_keys: Vec<PublicKey>,
+        public_keys: Vec<PublicKeyArg>,
         deposit_per_use: U128,
         config: Option<DropConfig>,
         metadata: Option<DropMetadata>,
         ft_data: Option<FTDataConfig>,
         nft_data: Option<NFTDataConfig>,
         fc_data: Option<FCData>,
+        campaign_id: Option<CampaignId>,
+        partner_id: Option<AccountId>,
+        key_configs: Option<Vec<Option<KeyConfig>>>,
+        password_hashes: Option<Vec<Option<Base64VecU8>>>,
+        passwords_per_use: Option<Vec<Option<Vec<PasswordPerUse>>>>,
     ) -> DropId {
-        // Ensure the user has only specified one type of callback data
-        let num_cbs_specified =
-            ft_data.is_some() as u8 + nft_data.is_some() as u8 + fc_data.is_some() as u8;
-        require!(
-            num_cbs_specified <= 1,
-            "You cannot specify more than one callback data"
-        );
+        // Funder is the predecessor
+        let owner_id = env::predecessor_account_id();
+        // Run every check that doesn't depend on the keys and derive what the drop needs
+        let plan = self
+            .internal_plan_drop(
+                &owner_id,
+                deposit_per_use.0,
+                config,
+                ft_data.as_ref(),
+                nft_data.as_ref(),
+                fc_data.as_ref(),
+            )
+            .unwrap_or_else(|errors| env::panic_str(&errors[0].message));
+        let public_keys = parse_public_keys(public_keys).unwrap_or_else(|e| env::panic_str(&e));
+        self.internal_check_new_keys(&public_keys)
+            .unwrap_or_else(|e| env::panic_str(&e));
+        check_key_commitment(&plan.config, public_keys.len())
+            .unwrap_or_else(|e| env::panic_str(&e));
+        if let Some(key_configs) = &key_configs {
+            let is_simple = ft_data.is_none() && nft_data.is_none() && fc_data.is_none();
+            check_key_configs(key_configs, public_keys.len(), is_simple, &plan.config)
+                .unwrap_or_else(|e| env::panic_str(&e));
+        }
+        if let Some(password_hashes) = &password_hashes {
+            check_password_hashes(password_hashes, public_keys.len())
+                .unwrap_or_else(|e| env::panic_str(&e));
+        }
+        if let Some(passwords_per_use) = &passwords_per_use {
+            let key_uses: Vec<u64> = (0..public_keys.len())
+                .map(|i| {
+                    key_configs
+                        .as_ref()
+                        .and_then(|c| c[i].as_ref())
+                        .and_then(|c| c.uses_per_key)
+                        .unwrap_or(plan.num_claims_per_key)
+                })
+                .collect();
+            check_passwords_per_use(passwords_per_use, &key_uses)
+                .unwrap_or_else(|e| env::panic_str(&e));
+        }
+        let mut passwords_per_use = passwords_per_use.unwrap_or_default().into_iter();
 
         // Warn if the balance for each drop is less than the minimum
-        if deposit_per_use.0 < NEW_ACCOUNT_BASE {
+        if deposit_per_use.0 < self.new_account_base {
             near_sdk::log!(
                 "Warning: Balance is less than absolute minimum for creating an account: {}",
-                NEW_ACCOUNT_BASE
+                self.new_account_base
             );
         }
 
-        // Funder is the predecessor
-        let owner_id = env::predecessor_account_id();
         let len = public_keys.len() as u128;
         let drop_id = self.next_drop_id;
         // Get the number of claims per key to dictate what key usage data we should put in the map
-        let num_claims_per_key = config.clone().and_then(|c| c.uses_per_key).unwrap_or(1);
+        let num_claims_per_key = plan.num_claims_per_key;
 
-        // Get the current balance of the funder.
-        let mut current_user_balance = self
-            .user_balances
-            .get(&owner_id)
-            .expect("No user balance found");
+        // Any deposit attached to the call goes into the funder's balance first so new accounts don't need to call add_to_balance
+        let attached_deposit = env::attached_deposit();
+        let current_user_balance =
+            self.internal_credit_attached_deposit(&owner_id, attached_deposit);
         near_sdk::log!("Cur User balance {}", yocto_to_near(current_user_balance));
 
         // Pessimistically measure storage
         let initial_storage = env::storage_usage();
-        let mut key_map: UnorderedMap<PublicKey, KeyInfo> =
-            UnorderedMap::new(StorageKey::PksForDrop {
-                // We get a new unique prefix for the collection
-                account_id_hash: hash_account_id(&format!("{}{}", self.next_drop_id, owner_id)),
-            });
-
-        // Decide what methods the access keys can call
-        let mut access_key_method_names = ACCESS_KEY_BOTH_METHOD_NAMES;
-        if let Some(perms) = config.clone().and_then(|c| c.claim_permission) {
-            match perms {
-                // If we have a config, use the config to determine what methods the access keys can call
-                ClaimPermissions::Claim => {
-                    access_key_method_names = ACCESS_KEY_CLAIM_METHOD_NAME;
-                }
-                ClaimPermissions::CreateAccountAndClaim => {
-                    access_key_method_names = ACCESS_KEY_CREATE_ACCOUNT_METHOD_NAME;
-                }
-            }
-        }
-
-        // Default the gas to attach to be the gas from the wallet. This will be used to calculate allowances.
-        let mut gas_to_attach = ATTACHED_GAS_FROM_WALLET;
-        // Depending on the FC Data, set the Gas to attach and the access key method_name names
-        if let Some(gas) = fc_data
-            .clone()
-            .and_then(|d| d.config.and_then(|c| c.attached_gas))
-        {
-            require!(
-                deposit_per_use.0 == 0,
-                "cannot specify gas to attach and have a balance in the linkdrop"
-            );
-            require!(
-                gas <= ATTACHED_GAS_FROM_WALLET - GAS_OFFSET_IF_FC_EXECUTE,
-                &format!(
-                    "cannot attach more than {:?} GAS.",
-                    ATTACHED_GAS_FROM_WALLET - GAS_OFFSET_IF_FC_EXECUTE
-                )
-            );
-            gas_to_attach = gas + GAS_OFFSET_IF_FC_EXECUTE;
-            access_key_method_names = ACCESS_KEY_CLAIM_METHOD_NAME;
-        }
-
-        // Calculate the base allowance to attach
-        let calculated_base_allowance = self.calculate_base_allowance(gas_to_attach);
-        // The actual allowance is the base * number of claims per key since each claim can potentially use the max pessimistic GAS.
-        let actual_allowance = calculated_base_allowance * num_claims_per_key as u128;
+        // We get new unique prefixes for the drop's collections
+        let storage_keys = self.internal_new_drop_storage_keys();
+        let mut key_map: UnorderedMap<PublicKey, KeyInfo> = UnorderedMap::new(storage_keys.pks());
+        let mut pk_for_key_id: LookupMap<u64, PublicKey> =
+            LookupMap::new(storage_keys.pk_for_key_id());
+
+        // The effective permission was written back into the config when planning
+        let claim_permission = plan.claim_permission.clone();
+        let config = plan.config.clone();
+        let access_key_method_names = access_key_method_names(&claim_permission);
+        let gas_to_attach = plan.gas_to_attach;
 
         // Loop through and add each drop ID to the public keys. Also populate the key set.
         let mut next_key_id = 0;
-        for pk in &public_keys {
-            key_map.insert(
+        // Keys can override the number of uses so the total and each key's allowance are tallied as they're added
+        let mut total_uses = 0;
+        let mut access_keys = Vec::with_capacity(public_keys.len());
+        for (i, pk) in public_keys.iter().enumerate() {
+            let key_config = key_configs.as_ref().and_then(|c| c[i].clone());
+            let num_uses = key_config
+                .as_ref()
+                .and_then(|c| c.uses_per_key)
+                .unwrap_or(num_claims_per_key);
+            total_uses += num_uses;
+            let allowance = plan.allowance_per_use * num_uses as u128;
+            access_keys.push((pk.clone(), allowance));
+            self.internal_insert_key_info(
+                &mut key_map,
+                deposit_per_use.0,
                 pk,
                 &KeyInfo {
-                    remaining_uses: num_claims_per_key,
-                    last_used: 0, // Set to 0 since this will make the key always claimable.
-                    allowance: actual_allowance,
+                    remaining_uses: num_uses,
+                    last_used: initial_last_used(&config),
+                    allowance,
                     key_id: next_key_id,
+                    allowance_used: 0,
+                    context_tag: None,
+                    config: key_config,
+                    password_hash: password_hashes
+                        .as_ref()
+                        .and_then(|hashes| hashes[i].clone())
+                        .map(|hash| hash.0),
                 },
             );
+            pk_for_key_id.insert(&next_key_id, pk);
             require!(
                 self.drop_id_for_pk.insert(pk, &drop_id).is_none(),
                 "Keys cannot belong to another drop"
             );
+            let passwords = passwords_per_use.next().flatten().unwrap_or_default();
+            self.internal_insert_passwords_per_use(drop_id, next_key_id, passwords);
             next_key_id += 1;
         }
 
         // Add this drop ID to the funder's set of drops
         self.internal_add_drop_to_funder(&env::predecessor_account_id(), &drop_id);
+        // List it under the FT or NFT contract it distributes unless the funder opted out
+        let asset_contract = ft_data
+            .as_ref()
+            .map(|data| data.contract_id.clone())
+            .or_else(|| nft_data.as_ref().map(|data| data.contract_id.clone()));
+        if let Some(contract_id) = asset_contract.filter(|_| !is_unlisted(&config)) {
+            self.internal_add_drop_to_asset_index(&contract_id, drop_id);
+        }
+        // And to the campaign it's part of
+        if let Some(campaign_id) = campaign_id {
+            self.internal_add_drop_to_campaign(&owner_id, campaign_id, drop_id);
+        }
+        // Lock in the partner's current share of the fees for the drop
+        if let Some(partner_id) = partner_id {
+            let share_bps = self
+                .internal_check_partner(&partner_id)
+                .unwrap_or_else(|e| env::panic_str(&e));
+            self.partner_for_drop.insert(
+                &drop_id,
+                &PartnerShare {
+                    partner_id,
+                    share_bps,
+                },
+            );
+        }
+        // Start keeping track of the stats for the drop's claims
+        self.internal_start_drop_stats(drop_id, owner_id.clone(), &config);
 
         // Create drop object
         let mut drop = Drop {
             owner_id: env::predecessor_account_id(),
             deposit_per_use: deposit_per_use.0,
             pks: key_map,
+            pk_for_key_id,
+            allowed_hashes: UnorderedSet::new(storage_keys.allowed_hashes()),
+            claims_per_account: UnorderedMap::new(storage_keys.claims_per_account()),
             drop_type: DropType::Simple, // Default to simple but will overwrite if not
             config: config.clone(),
-            registered_uses: num_claims_per_key * len as u64,
+            registered_uses: total_uses,
             required_gas: gas_to_attach,
-            metadata: LazyOption::new(
-                StorageKey::DropMetadata {
-                    // We get a new unique prefix for the collection
-                    account_id_hash: hash_account_id(&format!(
-                        "metadata-{}{}",
-                        self.next_drop_id, owner_id
-                    )),
-                },
-                metadata.as_ref(),
-            ),
+            early_claims: 0,
+            total_claims: 0,
+            pending_claims: 0,
+            claim_permission,
+            state: DropState::Active,
+            metadata_summary: metadata_summary(&config, metadata.as_ref()),
+            metadata: LazyOption::new(storage_keys.metadata(), metadata.as_ref()),
             next_key_id,
+            compaction_bounties_paid: 0,
+            compaction_cursor: 0,
+            storage_byte_cost: env::storage_byte_cost(),
         };
 
-        // For NFT drops, measure the storage for adding the longest token ID
-        let mut storage_per_longest = 0;
-        // Keep track of the total attached_deposit required for the FC data (depending on None and Some cases)
-        let mut deposit_required_for_fc_deposits = 0;
-        // Keep track of the number of none FCs so we don't charge the user
-        let mut num_none_fcs = 0;
-        // If NFT data was provided, we need to build the set of token IDs and cast the config to actual NFT data
+        // Cast the FT or NFT config to the data stored on the drop. Drops without any are simple drops.
         if let Some(data) = nft_data {
             let NFTDataConfig {
                 sender_id,
                 contract_id,
                 longest_token_id,
+                tokens_per_use,
             } = data;
 
-            // Create the token ID vector and insert the longest token ID
-            let token_ids = Vector::new(StorageKey::TokenIdsForDrop {
-                //we get a new unique prefix for the collection
-                account_id_hash: hash_account_id(&format!("nft-{}{}", self.next_drop_id, owner_id)),
-            });
-
-            // Create the NFT data
-            let actual_nft_data = NFTData {
+            // The storage for the longest token ID is measured once the drop type is set up
+            drop.drop_type = DropType::NonFungibleToken(NFTData {
                 sender_id,
                 contract_id,
-                longest_token_id: longest_token_id.clone(),
+                longest_token_id,
                 storage_for_longest: u128::MAX,
-                token_ids,
-            };
-
-            // The number of claims is 0 until NFTs are sent to the contract
-            drop.registered_uses = 0;
-            drop.drop_type = DropType::NonFungibleToken(actual_nft_data);
-
-            // Add the drop with the empty token IDs
-            self.drop_for_id.insert(&drop_id, &drop);
-
-            // Measure how much storage it costs to insert the 1 longest token ID
-            let initial_nft_storage_one = env::storage_usage();
-            // Now that the drop has been added, insert the longest token ID and measure storage
-            if let DropType::NonFungibleToken(data) = &mut drop.drop_type {
-                data.token_ids.push(&longest_token_id);
-            }
-
-            // Add drop with the longest possible token ID and max storage
-            self.drop_for_id.insert(&drop_id, &drop);
-            let final_nft_storage_one = env::storage_usage();
-            near_sdk::log!(
-                "i1: {} f1: {}",
-                initial_nft_storage_one,
-                final_nft_storage_one
-            );
-
-            // Measure the storage per single longest token ID
-            storage_per_longest = Balance::from(final_nft_storage_one - initial_nft_storage_one);
-            near_sdk::log!(
-                "TOKENS BEFORE {:?}",
-                self.get_nft_token_ids_for_drop(self.next_drop_id, None, None)
-            );
-
-            // Clear the token IDs so it's an empty vector and put the storage in the drop's nft data
-            if let DropType::NonFungibleToken(data) = &mut drop.drop_type {
-                data.token_ids.pop();
-                data.storage_for_longest = storage_per_longest;
-            }
-
-            self.drop_for_id.insert(&drop_id, &drop);
+                token_ids: Vector::new(storage_keys.token_ids()),
+                tokens_per_use: tokens_per_use.unwrap_or(1),
+            });
         } else if let Some(data) = ft_data.clone() {
-            // If FT Data was provided, we need to cast the FT Config to actual FT data and insert into the drop type
             let FTDataConfig {
                 sender_id,
                 contract_id,
                 balance_per_use,
+                balance_per_use_human: _,
+                accumulate_dust,
+                verify_received_balance,
             } = data;
 
-            // Create the NFT data
-            let actual_ft_data = FTData {
+            // If the token's metadata was cached, the balance has already been converted. Otherwise it's validated in the resolver.
+            let balance_per_use = plan.ft_balance_per_use.unwrap_or(balance_per_use);
+
+            drop.drop_type = DropType::FungibleToken(FTData {
                 contract_id,
                 sender_id,
                 balance_per_use,
                 ft_storage: U128(u128::MAX),
+                accumulate_dust,
+                dust: U128(0),
+                verify_received_balance,
+                pending: U128(0),
+            });
+            // The required deposit is filled in once it's known so that the size of the drop doesn't change
+            drop.state = DropState::Pending {
+                required_deposit: U128(0),
+                since_block: env::block_height(),
             };
+        } else if let Some(data) = fc_data.clone() {
+            drop.drop_type = DropType::FunctionCall(data);
+        }
 
-            // The number of claims is 0 until FTs are sent to the contract
+        // The number of claims is 0 until FTs or NFTs are sent to the contract
+        if !drop.drop_type.behavior().registers_uses_with_keys() {
             drop.registered_uses = 0;
-            drop.drop_type = DropType::FungibleToken(actual_ft_data);
-
-            // Add the drop with the empty token IDs
-            self.drop_for_id.insert(&drop_id, &drop);
-        } else if let Some(data) = fc_data.clone() {
-            drop.drop_type = DropType::FunctionCall(data.clone());
-
-            // Ensure proper method data is passed in
-            let num_method_data = data.clone().methods.len() as u64;
-            // If there's 1 claim, there should be 1 method data defined
-            if num_claims_per_key == 1 {
-                require!(
-                    num_method_data == 1,
-                    "Cannot have more Method Data than the number of claims per key"
-                );
-            // If there's more than 1 method data defined and the number of claims per key more than 1, the number of methods should equal the number of claims per key
-            } else if num_method_data > 1 {
-                require!(
-                    num_method_data == num_claims_per_key,
-                    "Number of FCs must match number of claims per key if more than 1 is specified"
-                );
-            }
-
-            // If there's one method data specified and more than 1 claim per key, that data is to be used
-            // For all the claims. In this case, we need to tally all the deposits for each method in all method data.
-            if num_claims_per_key > 1 && num_method_data == 1 {
-                let attached_deposit = data
-                    .methods
-                    .iter()
-                    .next()
-                    .unwrap()
-                    .clone()
-                    .expect("cannot have a single none function call")
-                    // iterate through   all entries and sum the attached_deposit
-                    .iter()
-                    .fold(0, |acc, x| acc + x.attached_deposit.0);
-
-                near_sdk::log!(format!(
-                    "Total attached_deposits for all method data: {}",
-                    attached_deposit
-                )
-                .as_str());
-                deposit_required_for_fc_deposits = num_claims_per_key as u128 * attached_deposit;
-            // In the case where either there's 1 claim per key or the number of FCs is not 1,
-            // We can simply loop through and manually get this data
-            } else {
-                for method_name in data.methods {
-                    num_none_fcs += method_name.is_none() as u64;
-                    // If the method is not None, we need to get the attached_deposit by looping through the method datas
-                    if let Some(method_data) = method_name {
-                        let attached_deposit = method_data
-                            .iter()
-                            .fold(0, |acc, x| acc + x.attached_deposit.0);
-                        near_sdk::log!(
-                            format!("Adding attached deposit: {}", attached_deposit).as_str()
-                        );
-                        deposit_required_for_fc_deposits += attached_deposit;
-                    }
-                }
-            }
-
-            // Add the drop with the empty token IDs
-            self.drop_for_id.insert(&drop_id, &drop);
-        } else {
-            require!(
-                deposit_per_use.0 > 0,
-                "Cannot have a simple drop with zero balance"
-            );
-            // In simple case, we just insert the drop with whatever it was initialized with.
-            self.drop_for_id.insert(&drop_id, &drop);
         }
+        // For NFT drops, this measures the storage for adding the longest token IDs of a use
+        let nft_storage_per_use = drop.drop_type.behavior_mut().on_create();
+        self.drop_for_id.insert(&drop_id, &drop);
 
         // Calculate the storage being used for the entire drop
         let final_storage = env::storage_usage();
         let total_required_storage = Balance::from(final_storage - initial_storage)
-            * env::storage_byte_cost();
+            * drop.storage_byte_cost;
         near_sdk::log!("Total required storage Yocto {}", total_required_storage);
 
         // Increment the drop ID nonce
@@ -416,20 +573,20 @@ impl Keypom {
             - FC attached_deposit for each key * num Some(data) claims
             - storage for longest token ID for each key
             - FT storage registration cost for each key * claims (calculated in resolve storage calculation function)
+            - Early bonus for the first N claims
+            - Compaction bounties
+            - Wrap contract registration and deployed contract storage for each key * (number of claims - claims with None for FC Data)
         */
-        let fees = self
-            .fees_per_user
-            .get(&owner_id)
-            .unwrap_or((self.drop_fee, self.key_fee));
-        let required_deposit = fees.0 // drop fee
-            + total_required_storage
-            + (fees.1 // key fee
-                + actual_allowance
-                + ACCESS_KEY_STORAGE
-                + deposit_per_use.0 * (num_claims_per_key - num_none_fcs) as u128
-                + storage_per_longest * env::storage_byte_cost() * (num_claims_per_key - num_none_fcs) as u128
-                + deposit_required_for_fc_deposits)
-                * len;
+        let fees = self.internal_fees_for_user(&owner_id);
+        let price = plan.price(
+            fees,
+            total_required_storage,
+            nft_storage_per_use,
+            deposit_per_use.0,
+            len,
+            total_uses as u128,
+        );
+        let required_deposit = price.total.0;
         near_sdk::log!(
             "Current balance: {}, 
             Required Deposit: {}, 
@@ -439,8 +596,10 @@ impl Keypom {
             ACCESS_KEY_ALLOWANCE: {}, 
             ACCESS_KEY_STORAGE: {},
             Linkdrop Balance: {}, 
-            Storage for longest token ID (if applicable): {},
+            Storage for longest token IDs per use (if applicable): {},
             total function call deposits (if applicable): {},
+            Early bonus reserve (if applicable): {},
+            Compaction reserve (if applicable): {},
             Num claims per key: {}
             Num none FCs: {},
             length: {}
@@ -450,32 +609,28 @@ impl Keypom {
             yocto_to_near(fees.0),
             yocto_to_near(total_required_storage),
             yocto_to_near(fees.1),
-            yocto_to_near(actual_allowance),
+            yocto_to_near(plan.allowance_per_use * num_claims_per_key as u128),
             yocto_to_near(ACCESS_KEY_STORAGE),
             yocto_to_near(deposit_per_use.0),
-            yocto_to_near(storage_per_longest * env::storage_byte_cost()),
-            yocto_to_near(deposit_required_for_fc_deposits),
+            yocto_to_near(nft_storage_per_use * env::storage_byte_cost()),
+            yocto_to_near(plan.fc_deposits_per_key),
+            yocto_to_near(plan.early_bonus_reserve),
+            yocto_to_near(plan.compaction_reserve),
             num_claims_per_key,
-            num_none_fcs,
+            plan.num_none_fcs,
             len,
             gas_to_attach.0
         );
 
-        /*
-            Ensure the attached attached_deposit can cover:
-        */
-        require!(
-            current_user_balance >= required_deposit,
-            "Not enough attached_deposit"
-        );
-        // Decrement the user's balance by the required attached_deposit and insert back into the map
-        current_user_balance -= required_deposit;
-        self.user_balances.insert(&owner_id, &current_user_balance);
+        // Decrement the user's balance by the required attached_deposit. Panics with the shortfall if it can't be covered.
+        let current_user_balance =
+            self.internal_charge_balance(&owner_id, required_deposit, attached_deposit);
         near_sdk::log!("New user balance {}", yocto_to_near(current_user_balance));
 
-        // Increment our fees earned
-        self.fees_collected += fees.0 + fees.1 * len;
-        near_sdk::log!("Fees collected {}", yocto_to_near(fees.0 + fees.1 * len));
+        // Increment our fees earned. Accounts with zero fees leave this unchanged.
+        let total_fees = fees.0 + fees.1 * len;
+        self.internal_collect_fees(drop_id, total_fees);
+        near_sdk::log!("Fees collected {}", yocto_to_near(total_fees));
 
         let current_account_id = env::current_account_id();
 
@@ -484,42 +639,67 @@ impl Keypom {
             keys will be added in the FT resolver
         */
         if ft_data.is_none() {
-            // Create a new promise batch to create all the access keys
-            let promise = env::promise_batch_create(&current_account_id);
-
-            // Loop through each public key and create the access keys
-            for pk in public_keys.clone() {
-                // Must assert in the loop so no access keys are made?
-                env::promise_batch_action_add_key_with_function_call(
-                    promise,
-                    &pk,
-                    0,
-                    actual_allowance,
-                    &current_account_id,
-                    access_key_method_names,
-                );
-            }
-
-            env::promise_return(promise);
+            // Create the access keys in as many batches as the GAS allows
+            self.internal_add_access_keys(drop_id, access_keys, &access_key_method_names);
+
+            // The batches aren't returned so that the drop ID is always the return value regardless of the drop type
         } else {
             /*
                 Get the storage required by the FT contract and ensure the user has attached enough
                 attached_deposit to cover the storage and perform refunds if they overpayed.
             */
+            // Keep track of what was charged so a cancelled or stuck drop can be fully refunded
+            drop.state = DropState::Pending {
+                required_deposit: U128(required_deposit),
+                since_block: env::block_height(),
+            };
+            self.drop_for_id.insert(&drop_id, &drop);
 
-            ext_ft_contract::ext(ft_data.unwrap().contract_id)
+            let ft_data = ft_data.unwrap();
+            let storage_check = ext_ft_contract::ext(ft_data.contract_id.clone())
                 // Call storage balance bounds with exactly this amount of GAS. No unspent GAS will be added on top.
                 .with_static_gas(GAS_FOR_STORAGE_BALANCE_BOUNDS)
                 .with_unused_gas_weight(0)
-                .storage_balance_bounds()
-                .then(
-                    Self::ext(current_account_id)
-                        // Resolve the promise with the min GAS. All unspent GAS will be added to this call.
-                        .with_static_gas(MIN_GAS_FOR_RESOLVE_STORAGE_CHECK)
-                        .resolve_storage_check(public_keys, drop_id, required_deposit),
-                );
+                .storage_balance_bounds();
+
+            // Query the token's metadata alongside the storage if it hasn't been cached yet
+            let (promise, balance_per_use_human) =
+                if self.ft_metadata.get(&ft_data.contract_id).is_none() {
+                    let metadata_query = ext_ft_contract::ext(ft_data.contract_id)
+                        // Call FT metadata with exactly this amount of GAS. No unspent GAS will be added on top.
+                        .with_static_gas(GAS_FOR_FT_METADATA)
+                        .with_unused_gas_weight(0)
+                        .ft_metadata();
+                    (
+                        storage_check.and(metadata_query),
+                        ft_data.balance_per_use_human,
+                    )
+                } else {
+                    (storage_check, None)
+                };
+
+            promise.then(
+                Self::ext(current_account_id)
+                    // Resolve the promise with the min GAS. All unspent GAS will be added to this call.
+                    .with_static_gas(MIN_GAS_FOR_RESOLVE_STORAGE_CHECK)
+                    .resolve_storage_check(
+                        public_keys,
+                        drop_id,
+                        required_deposit,
+                        balance_per_use_human,
+                    ),
+            );
         }
 
+        // Emit the drop ID for every drop type so there's one reliable way to learn it.
+        // For FT drops, the keys are only added once the storage check resolves.
+        emit_event(EventLogVariant::DropCreation(vec![DropCreationLog {
+            owner_id,
+            drop_id,
+            drop_type: drop.drop_type.behavior().type_name().to_string(),
+            num_keys: len as u64,
+        }]));
+
         drop_id
     }
 
@@ -528,7 +708,34 @@ impl Keypom {
         Only the funder can call this method_name
     */
     #[payable]
-    pub fn add_keys(&mut self, public_keys: Vec<PublicKey>, drop_id: DropId) -> DropId {
+    pub fn add_keys(
+        &mut self,
+        public_keys: Vec<PublicKeyArg>,
+        drop_id: DropId,
+        context_tags: Option<Vec<Option<String>>>,
+        // sha256 hash of the password for each key. Keys with None don't need a password.
+        password_hashes: Option<Vec<Option<Base64VecU8>>>,
+    ) -> DropId {
+        // Drops committed to a batch of keys can only add them by revealing them
+        require!(
+            !self
+                .drop_for_id
+                .get(&drop_id)
+                .is_some_and(|drop| has_key_commitment(&drop.config)),
+            "keys for this drop must be added with reveal_keys"
+        );
+        let public_keys = parse_public_keys(public_keys).unwrap_or_else(|e| env::panic_str(&e));
+        self.internal_add_keys(public_keys, drop_id, context_tags, password_hashes)
+    }
+
+    /// Add keys to a drop and charge the funder for them. Only the funder can add keys.
+    pub(crate) fn internal_add_keys(
+        &mut self,
+        public_keys: Vec<PublicKey>,
+        drop_id: DropId,
+        context_tags: Option<Vec<Option<String>>>,
+        password_hashes: Option<Vec<Option<Base64VecU8>>>,
+    ) -> DropId {
         let mut drop = self
             .drop_for_id
             .get(&drop_id)
@@ -540,6 +747,29 @@ impl Keypom {
             funder == &env::predecessor_account_id(),
             "only funder can add to drops"
         );
+        // Pending FT drops add their keys once the storage check resolves
+        require!(
+            drop.state == DropState::Active,
+            "keys can only be added to active drops"
+        );
+        // New keys are priced off the stored drop so it must still follow the rules it was created with
+        assert_drop_invariants(&drop);
+
+        // Restrictions added after the drop was created apply to new keys as well
+        drop.drop_type
+            .behavior()
+            .on_add_keys(self, funder)
+            .unwrap_or_else(|e| env::panic_str(&e));
+        self.internal_check_new_keys(&public_keys)
+            .unwrap_or_else(|e| env::panic_str(&e));
+        // Tags are stored on the keys so their storage is measured along with the rest
+        check_context_tags(&context_tags, public_keys.len()).unwrap_or_else(|e| env::panic_str(&e));
+        let mut context_tags = context_tags.unwrap_or_default().into_iter();
+        if let Some(password_hashes) = &password_hashes {
+            check_password_hashes(password_hashes, public_keys.len())
+                .unwrap_or_else(|e| env::panic_str(&e));
+        }
+        let mut password_hashes = password_hashes.unwrap_or_default().into_iter();
 
         let len = public_keys.len() as u128;
 
@@ -562,15 +792,22 @@ impl Keypom {
         // Loop through and add each drop ID to the public keys. Also populate the key set.
         let mut next_key_id = drop.next_key_id;
         for pk in public_keys.clone() {
-            exiting_key_map.insert(
+            self.internal_insert_key_info(
+                &mut exiting_key_map,
+                drop.deposit_per_use,
                 &pk,
                 &KeyInfo {
                     remaining_uses: num_claims_per_key,
-                    last_used: 0, // Set to 0 since this will make the key always claimable.
+                    last_used: initial_last_used(&drop.config),
                     allowance: actual_allowance,
                     key_id: next_key_id,
+                    allowance_used: 0,
+                    context_tag: context_tags.next().flatten(),
+                    config: None,
+                    password_hash: password_hashes.next().flatten().map(|hash| hash.0),
                 },
             );
+            drop.pk_for_key_id.insert(&next_key_id, &pk);
             require!(
                 self.drop_id_for_pk.insert(&pk, &drop_id).is_none(),
                 "Keys cannot belong to another drop"
@@ -584,108 +821,34 @@ impl Keypom {
         drop.next_key_id = next_key_id;
 
         // Decide what methods the access keys can call
-        // Decide what methods the access keys can call
-        let mut access_key_method_names = ACCESS_KEY_BOTH_METHOD_NAMES;
-        if let Some(perms) = config.clone().and_then(|c| c.claim_permission) {
-            match perms {
-                // If we have a config, use the config to determine what methods the access keys can call
-                ClaimPermissions::Claim => {
-                    access_key_method_names = ACCESS_KEY_CLAIM_METHOD_NAME;
-                }
-                ClaimPermissions::CreateAccountAndClaim => {
-                    access_key_method_names = ACCESS_KEY_CREATE_ACCOUNT_METHOD_NAME;
-                }
-            }
-        }
+        let access_key_method_names = access_key_method_names(&drop.claim_permission);
 
         // Increment the claims registered if drop is FC or Simple
-        match &drop.drop_type {
-            DropType::FunctionCall(data) => {
-                drop.registered_uses += num_claims_per_key * len as u64;
-
-                // If GAS is specified, set the GAS to attach for allowance calculations
-                if let Some(_) = data.config.clone().and_then(|c| c.attached_gas) {
-                    access_key_method_names = ACCESS_KEY_CLAIM_METHOD_NAME;
-                }
-            }
-            DropType::Simple => {
-                drop.registered_uses += num_claims_per_key * len as u64;
-            }
-            _ => {}
-        };
+        if drop.drop_type.behavior().registers_uses_with_keys() {
+            drop.registered_uses += num_claims_per_key * len as u64;
+        }
 
         // Add the drop back in for the drop ID
         self.drop_for_id.insert(&drop_id, &drop);
 
-        // Get the current balance of the funder.
-        let mut current_user_balance = self
-            .user_balances
-            .get(&funder)
-            .expect("No user balance found");
+        // Any deposit attached to the call goes into the funder's balance first
+        let attached_deposit = env::attached_deposit();
+        let current_user_balance = self.internal_credit_attached_deposit(funder, attached_deposit);
         near_sdk::log!("Cur user balance {}", yocto_to_near(current_user_balance));
 
-        // Get the required attached_deposit for all the FCs
-        let mut deposit_required_for_fc_deposits = 0;
-        // Get the number of none FCs in FCData (if there are any)
-        let mut num_none_fcs = 0;
-        if let DropType::FunctionCall(data) = &drop.drop_type {
-            // Ensure proper method data is passed in
-            let num_method_data = data.clone().methods.len() as u64;
-
-            // If there's one method data specified and more than 1 claim per key, that data is to be used
-            // For all the claims. In this case, we need to tally all the deposits for each method in all method data.
-            if num_claims_per_key > 1 && num_method_data == 1 {
-                let attached_deposit = data
-                    .methods
-                    .iter()
-                    .next()
-                    .unwrap()
-                    .clone()
-                    .expect("cannot have a single none function call")
-                    // iterate through   all entries and sum the attached_deposit
-                    .iter()
-                    .fold(0, |acc, x| acc + x.attached_deposit.0);
-
-                near_sdk::log!(format!(
-                    "Total attached_deposits for all method data: {}",
-                    attached_deposit
-                )
-                .as_str());
-                deposit_required_for_fc_deposits = num_claims_per_key as u128 * attached_deposit;
-            // In the case where either there's 1 claim per key or the number of FCs is not 1,
-            // We can simply loop through and manually get this data
-            } else {
-                for method_name in data.methods.clone() {
-                    num_none_fcs += method_name.is_none() as u64;
-                    // If the method is not None, we need to get the attached_deposit by looping through the method datas
-                    if let Some(method_data) = method_name {
-                        let attached_deposit = method_data
-                            .iter()
-                            .fold(0, |acc, x| acc + x.attached_deposit.0);
-                        near_sdk::log!(
-                            format!("Adding attached deposit: {}", attached_deposit).as_str()
-                        );
-                        deposit_required_for_fc_deposits += attached_deposit;
-                    }
-                }
-            }
-        }
+        // Keys added later are charged at the rate the drop was created with since that's what they're refunded at
+        let storage_byte_cost = drop.storage_byte_cost;
 
-        // Get optional costs
-        let mut nft_optional_costs_per_key = 0;
-        let mut ft_optional_costs_per_claim = 0;
-        match drop.drop_type {
-            DropType::NonFungibleToken(data) => {
-                nft_optional_costs_per_key = data.storage_for_longest * env::storage_byte_cost()
-            }
-            DropType::FungibleToken(data) => ft_optional_costs_per_claim = data.ft_storage.0,
-            _ => {}
-        };
+        // Get what the drop type reserves for each key (FC deposits and asset storage) along with the number of none FCs
+        let (type_costs_per_key, num_none_fcs) = drop
+            .drop_type
+            .behavior()
+            .cost_per_key(num_claims_per_key, storage_byte_cost);
 
         // Calculate the storage being used for the entire drop
         let final_storage = env::storage_usage();
         let total_required_storage =
-            Balance::from(final_storage - initial_storage) * env::storage_byte_cost();
+            Balance::from(final_storage - initial_storage) * storage_byte_cost;
         near_sdk::log!("Total required storage Yocto {}", total_required_storage);
 
         /*
@@ -697,22 +860,17 @@ impl Keypom {
             - Balance for each key * (number of claims - claims with None for FC Data)
 
             Optional:
-            - FC attached_deposit for each key * num Some(data) claims
-            - storage for longest token ID for each key
-            - FT storage registration cost for each key * claims (calculated in resolve storage calculation function)
+            - Drop type costs for each key (see `DropBehavior::cost_per_key`)
+            - Wrap contract registration and deployed contract storage for each key * (number of claims - claims with None for FC Data)
         */
-        let fees = self
-            .fees_per_user
-            .get(&funder)
-            .unwrap_or((self.drop_fee, self.key_fee));
+        let fees = self.internal_fees_for_user(funder);
         let required_deposit = total_required_storage
             + (fees.1 // key fee
                 + actual_allowance
                 + ACCESS_KEY_STORAGE
                 + drop.deposit_per_use * (num_claims_per_key - num_none_fcs) as u128
-                + nft_optional_costs_per_key
-                + deposit_required_for_fc_deposits
-                + ft_optional_costs_per_claim * num_claims_per_key as u128)
+                + extra_cost_per_use(&drop.config) * (num_claims_per_key - num_none_fcs) as u128
+                + type_costs_per_key)
                 * len;
 
         near_sdk::log!(
@@ -723,9 +881,7 @@ impl Keypom {
             ACCESS_KEY_ALLOWANCE: {}, 
             ACCESS_KEY_STORAGE: {},
             Linkdrop Balance: {}, 
-            NFT Optional costs per key: {},
-            total function call deposits per key: {},
-            FT Optional costs per claim: {},
+            Drop type costs per key: {},
             Num claims per key: {}
             Num none FCs: {},
             length: {}",
@@ -736,47 +892,27 @@ impl Keypom {
             yocto_to_near(actual_allowance),
             yocto_to_near(ACCESS_KEY_STORAGE),
             yocto_to_near(drop.deposit_per_use),
-            yocto_to_near(nft_optional_costs_per_key),
-            yocto_to_near(deposit_required_for_fc_deposits),
-            yocto_to_near(ft_optional_costs_per_claim),
+            yocto_to_near(type_costs_per_key),
             num_claims_per_key,
             num_none_fcs,
             len,
         );
-        /*
-            Ensure the attached attached_deposit can cover:
-        */
-        require!(
-            current_user_balance >= required_deposit,
-            "Not enough attached_deposit"
-        );
-        // Decrement the user's balance by the required attached_deposit and insert back into the map
-        current_user_balance -= required_deposit;
-        self.user_balances.insert(&funder, &current_user_balance);
+        // Decrement the user's balance by the required attached_deposit. Panics with the shortfall if it can't be covered.
+        let current_user_balance =
+            self.internal_charge_balance(funder, required_deposit, attached_deposit);
         near_sdk::log!("New user balance {}", yocto_to_near(current_user_balance));
 
-        // Increment our fees earned
-        self.fees_collected += fees.1 * len;
-        near_sdk::log!("Fees collected {}", yocto_to_near(fees.1 * len));
-
-        // Create a new promise batch to create all the access keys
-        let current_account_id = env::current_account_id();
-        let promise = env::promise_batch_create(&current_account_id);
-
-        // Loop through each public key and create the access keys
-        for pk in public_keys.clone() {
-            // Must assert in the loop so no access keys are made?
-            env::promise_batch_action_add_key_with_function_call(
-                promise,
-                &pk,
-                0,
-                actual_allowance,
-                &current_account_id,
-                access_key_method_names,
-            );
-        }
-
-        env::promise_return(promise);
+        // Increment our fees earned. Accounts with zero fees leave this unchanged.
+        let total_fees = fees.1 * len;
+        self.internal_collect_fees(drop_id, total_fees);
+        near_sdk::log!("Fees collected {}", yocto_to_near(total_fees));
+
+        // Create the access keys in as many batches as the GAS allows
+        let keys = public_keys
+            .iter()
+            .map(|pk| (pk.clone(), actual_allowance))
+            .collect();
+        self.internal_add_access_keys(drop_id, keys, &access_key_method_names);
 
         drop_id
     }
@@ -0,0 +1,139 @@
+use crate::*;
+
+/// Default window before a key's end timestamp in which its access key can be tightened
+pub(crate) const DEFAULT_EXPIRING_KEY_WINDOW: u64 = 3_600_000_000_000; // 1 hour
+/// GAS for the callback crediting the allowance reclaimed from tightened keys
+const GAS_FOR_RESOLVE_TIGHTENED_KEYS: Gas = Gas(20_000_000_000_000); // 20 TGas
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Re-add the access keys of up to `limit` keys (50 by default) that expire within the expiring key window with
+        an allowance that only covers a single claim attempt. Keys that were still claimable for longer than that
+        could otherwise burn through their allowance on attempts right up until the end timestamp. Each key is deleted
+        and re-added in its own batch and the allowance it no longer needs is credited to the funder once the batch
+        goes through. If a batch fails, the key keeps its allowance. Keys still pending activation are left alone.
+    */
+    #[payable]
+    pub fn tighten_expiring_keys(&mut self, drop_id: DropId, limit: Option<u64>) {
+        assert_one_yocto();
+
+        let mut drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        require!(
+            drop.owner_id == env::predecessor_account_id(),
+            "only drop funder can tighten keys"
+        );
+
+        let one_attempt = drop.required_gas.0 as u128 * self.yocto_per_gas;
+        let current_timestamp = env::block_timestamp();
+        let pending_keys = self
+            .pending_key_activations
+            .get(&drop_id)
+            .unwrap_or_default();
+        let expiring_keys: Vec<(PublicKey, KeyInfo)> = drop
+            .pks
+            .iter()
+            .filter(|(pk, key_info)| {
+                key_end_timestamp(&drop, key_info).is_some_and(|end| {
+                    current_timestamp <= end && end - current_timestamp <= self.expiring_key_window
+                }) && key_info.allowance > one_attempt
+                    && !pending_keys.contains(pk)
+            })
+            .take(limit.unwrap_or(50) as usize)
+            .collect();
+        require!(
+            !expiring_keys.is_empty(),
+            "no expiring keys to tighten in drop"
+        );
+
+        let current_account_id = env::current_account_id();
+        let method_names = access_key_method_names(&drop.claim_permission);
+        let mut batches = vec![];
+        let mut reclaimed = vec![];
+        for (pk, mut key_info) in expiring_keys {
+            let surplus = key_info.allowance - one_attempt;
+            key_info.allowance = one_attempt;
+            self.internal_insert_key_info(&mut drop.pks, drop.deposit_per_use, &pk, &key_info);
+
+            let promise = env::promise_batch_create(&current_account_id);
+            env::promise_batch_action_delete_key(promise, &pk);
+            env::promise_batch_action_add_key_with_function_call(
+                promise,
+                &pk,
+                0,
+                one_attempt,
+                &current_account_id,
+                &method_names,
+            );
+            batches.push(promise);
+            reclaimed.push((pk, U128(surplus)));
+        }
+        self.drop_for_id.insert(&drop_id, &drop);
+        near_sdk::log!(
+            "Tightening {} keys to an allowance of {}",
+            reclaimed.len(),
+            yocto_to_near(one_attempt)
+        );
+
+        let joined = if batches.len() == 1 {
+            batches[0]
+        } else {
+            env::promise_and(&batches)
+        };
+        env::promise_then(
+            joined,
+            current_account_id,
+            "resolve_tightened_keys",
+            json!({ "drop_id": U128(drop_id), "owner_id": drop.owner_id, "reclaimed": reclaimed })
+                .to_string()
+                .as_bytes(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_TIGHTENED_KEYS,
+        );
+    }
+
+    #[private]
+    /*
+        Self callback credits the funder with the allowance reclaimed from each key whose batch went through. Keys
+        whose batch failed still have their old access key so their allowance is put back. If the key was deleted
+        in the meantime there's no access key left to cover, so the funder is credited either way.
+    */
+    pub fn resolve_tightened_keys(
+        &mut self,
+        drop_id: U128,
+        owner_id: AccountId,
+        reclaimed: Vec<(PublicKey, U128)>,
+    ) {
+        let drop_id = drop_id.0;
+        let owner_id = self.internal_current_owner(drop_id, owner_id);
+        let mut drop = self.drop_for_id.get(&drop_id);
+        let mut credited = 0;
+        for (i, (pk, surplus)) in reclaimed.into_iter().enumerate() {
+            let succeeded = matches!(env::promise_result(i as u64), PromiseResult::Successful(_));
+            let key_info = drop.as_ref().and_then(|drop| drop.pks.get(&pk));
+            match (succeeded, drop.as_mut(), key_info) {
+                (false, Some(drop), Some(mut key_info)) => {
+                    near_sdk::log!("Couldn't tighten key. Restoring its allowance");
+                    key_info.allowance += surplus.0;
+                    self.internal_insert_key_info(
+                        &mut drop.pks,
+                        drop.deposit_per_use,
+                        &pk,
+                        &key_info,
+                    );
+                }
+                _ => credited += surplus.0,
+            }
+        }
+
+        if credited > 0 {
+            near_sdk::log!(
+                "Crediting {} reclaimed from expiring keys to {}",
+                yocto_to_near(credited),
+                owner_id
+            );
+            let balance = self.user_balances.get(&owner_id).unwrap_or(0) + credited;
+            self.internal_set_user_balance(&owner_id, balance);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::*;
+
+/// What the contract owner knows about a contract FC drops call. Receivers that were never reviewed are `Unknown`.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum FcReceiverReputation {
+    // Reviewed and safe to call
+    Allowed,
+    // Not reviewed. Drops calling it are created but flagged as unverified.
+    Unknown,
+    // Known to be malicious (i.e controlled by claimers or used to phish them). Drops can't call it.
+    Denied,
+}
+
+/// A receiver on the reputation list along with its reputation
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonFcReceiverReputation {
+    pub receiver_id: AccountId,
+    pub reputation: FcReceiverReputation,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Set the reputation of an FC receiver. Drops calling a denied receiver can't be created or have keys added and
+        drops calling anything that isn't allowed are flagged as unverified in views and claim events. Setting a
+        receiver back to `Unknown` removes it from the list. Returns the reputation it had before.
+    */
+    #[payable]
+    pub fn set_fc_receiver_reputation(
+        &mut self,
+        receiver_id: AccountId,
+        reputation: FcReceiverReputation,
+    ) -> FcReceiverReputation {
+        self.assert_owner_and_record("set_fc_receiver_reputation");
+
+        let old_reputation = match reputation {
+            FcReceiverReputation::Unknown => self.fc_receiver_reputations.remove(&receiver_id),
+            _ => self
+                .fc_receiver_reputations
+                .insert(&receiver_id, &reputation),
+        }
+        .unwrap_or(FcReceiverReputation::Unknown);
+
+        emit_event(EventLogVariant::FcReceiverReputationUpdate(vec![
+            FcReceiverReputationUpdateLog {
+                receiver_id,
+                old_reputation,
+                new_reputation: reputation,
+            },
+        ]));
+        old_reputation
+    }
+
+    /// Reputation of a single FC receiver
+    pub(crate) fn internal_fc_receiver_reputation(
+        &self,
+        receiver_id: &AccountId,
+    ) -> FcReceiverReputation {
+        self.fc_receiver_reputations
+            .get(receiver_id)
+            .unwrap_or(FcReceiverReputation::Unknown)
+    }
+
+    /// None of the methods in the FC data can call a denied receiver
+    pub(crate) fn internal_check_fc_receivers_not_denied(
+        &self,
+        data: &FCData,
+    ) -> Result<(), String> {
+        match data.methods.iter().flatten().flatten().find(|method| {
+            self.internal_fc_receiver_reputation(&method.receiver_id)
+                == FcReceiverReputation::Denied
+        }) {
+            Some(method) => Err(format!("{} is a denied FC receiver", method.receiver_id)),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the drop calls any receiver that the contract owner hasn't allowed. Always false for other drop types.
+    pub(crate) fn internal_is_drop_unverified(&self, drop_type: &DropType) -> bool {
+        match drop_type {
+            DropType::FunctionCall(data) => data.methods.iter().flatten().flatten().any(|method| {
+                self.internal_fc_receiver_reputation(&method.receiver_id)
+                    != FcReceiverReputation::Allowed
+            }),
+            _ => false,
+        }
+    }
+
+    /*
+        Only allow FC drops created by `account_id` to call receivers ending with one of its allowed suffixes
+        (i.e ".ourdao.near" for any sub-account of ourdao.near). Can be called by the contract owner or by the
+        account itself. Only the contract owner can lift a restriction. Returns whether the suffix was added.
+    */
+    #[payable]
+    pub fn restrict_fc_receivers(&mut self, account_id: AccountId, suffix: String) -> bool {
+        assert_one_yocto();
+
+        let predecessor = env::predecessor_account_id();
+        require!(
+            predecessor == self.owner_id || predecessor == account_id,
+            "only the contract owner or the account itself can restrict FC receivers"
+        );
+        require!(!suffix.is_empty(), "suffix cannot be empty");
+        if predecessor == self.owner_id {
+            self.internal_record_admin_change("restrict_fc_receivers");
+        }
+
+        let mut suffixes = self
+            .fc_receiver_restrictions
+            .get(&account_id)
+            .unwrap_or_default();
+        if suffixes.contains(&suffix) {
+            return false;
+        }
+        suffixes.push(suffix);
+        self.fc_receiver_restrictions.insert(&account_id, &suffixes);
+        true
+    }
+
+    /// Remove an allowed suffix for FC receivers. Once the last suffix is removed, the account is no longer restricted.
+    #[payable]
+    pub fn unrestrict_fc_receivers(&mut self, account_id: AccountId, suffix: String) -> bool {
+        self.assert_owner_and_record("unrestrict_fc_receivers");
+
+        let mut suffixes = self
+            .fc_receiver_restrictions
+            .get(&account_id)
+            .unwrap_or_default();
+        let len_before = suffixes.len();
+        suffixes.retain(|s| s != &suffix);
+        if suffixes.len() == len_before {
+            return false;
+        }
+
+        if suffixes.is_empty() {
+            self.fc_receiver_restrictions.remove(&account_id);
+        } else {
+            self.fc_receiver_restrictions.insert(&account_id, &suffixes);
+        }
+        true
+    }
+
+    /// Panic if the funder is restricted and any method in the FC data calls a receiver outside of its allowed suffixes
+    pub(crate) fn internal_assert_fc_receivers_allowed(&self, funder: &AccountId, data: &FCData) {
+        self.internal_check_fc_receivers_allowed(funder, data)
+            .unwrap_or_else(|e| env::panic_str(&e));
+    }
+
+    /// Same as `internal_assert_fc_receivers_allowed` but returns the error instead of panicking
+    pub(crate) fn internal_check_fc_receivers_allowed(
+        &self,
+        funder: &AccountId,
+        data: &FCData,
+    ) -> Result<(), String> {
+        let suffixes = match self.fc_receiver_restrictions.get(funder) {
+            Some(suffixes) => suffixes,
+            None => return Ok(()),
+        };
+
+        for method in data.methods.iter().flatten().flatten() {
+            if !suffixes
+                .iter()
+                .any(|suffix| method.receiver_id.as_str().ends_with(suffix.as_str()))
+            {
+                return Err(format!(
+                    "{} is not an allowed FC receiver for {}",
+                    method.receiver_id, funder
+                ));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use crate::*;
+
+/// Placeholders that can be used in FC args and are filled in when the key is claimed
+const FC_ARGS_PLACEHOLDERS: [&str; 2] = ["deposit", "use_number"];
+/// Placeholders for assets that FC drops never hold
+const FC_ARGS_ASSET_PLACEHOLDERS: [&str; 2] = ["ft_amount", "token_id"];
+
+/// Claim-time values that placeholders in FC args are replaced with
+pub(crate) struct FCArgsValues {
+    // $NEAR the claimer received. 0 if sending it failed.
+    pub deposit: Balance,
+    // Which use of the key was claimed, starting at 1
+    pub use_number: u64,
+}
+
+impl FCArgsValues {
+    fn value_for(&self, name: &str) -> Option<String> {
+        match name {
+            "deposit" => Some(self.deposit.to_string()),
+            "use_number" => Some(self.use_number.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Piece of an args template
+enum FCArgsSegment<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+/*
+    Split FC args into literal text and `{{name}}` placeholders. `{{{{` is a literal `{{` and a `}}` outside a
+    placeholder is kept as is. Returns None if a placeholder is never closed.
+*/
+fn fc_args_segments(args: &str) -> Option<Vec<FCArgsSegment<'_>>> {
+    let mut segments = vec![];
+    let mut rest = args;
+    while let Some(start) = rest.find("{{") {
+        segments.push(FCArgsSegment::Literal(&rest[..start]));
+        let after = &rest[start + 2..];
+        if let Some(escaped) = after.strip_prefix("{{") {
+            segments.push(FCArgsSegment::Literal("{{"));
+            rest = escaped;
+            continue;
+        }
+        let end = after.find("}}")?;
+        segments.push(FCArgsSegment::Placeholder(&after[..end]));
+        rest = &after[end + 2..];
+    }
+    segments.push(FCArgsSegment::Literal(rest));
+    Some(segments)
+}
+
+/*
+    Replace every placeholder in the args with its claim-time value. Args without `{{` are returned untouched. Args
+    stored before placeholders were validated may not parse, in which case they're also passed through as is and
+    unknown placeholders are kept as literal text.
+*/
+pub(crate) fn render_fc_args(args: &str, values: &FCArgsValues) -> String {
+    if !args.contains("{{") {
+        return args.to_string();
+    }
+    let segments = match fc_args_segments(args) {
+        Some(segments) => segments,
+        None => return args.to_string(),
+    };
+
+    let mut rendered = String::with_capacity(args.len());
+    for segment in segments {
+        match segment {
+            FCArgsSegment::Literal(text) => rendered.push_str(text),
+            FCArgsSegment::Placeholder(name) => match values.value_for(name) {
+                Some(value) => rendered.push_str(&value),
+                None => {
+                    rendered.push_str("{{");
+                    rendered.push_str(name);
+                    rendered.push_str("}}");
+                }
+            },
+        }
+    }
+    rendered
+}
+
+/// Every placeholder must be known and args with placeholders must be valid JSON once they're filled in
+fn check_fc_args_template(args: &str) -> Result<(), String> {
+    if !args.contains("{{") {
+        return Ok(());
+    }
+    let segments = fc_args_segments(args)
+        .ok_or_else(|| format!("FC args have a placeholder that isn't closed: {}", args))?;
+
+    for segment in segments {
+        let name = match segment {
+            FCArgsSegment::Placeholder(name) => name,
+            FCArgsSegment::Literal(_) => continue,
+        };
+        if FC_ARGS_ASSET_PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "{{{{{}}}}} can't be used since FC drops don't hold FTs or NFTs",
+                name
+            ));
+        }
+        if !FC_ARGS_PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "unknown placeholder {{{{{}}}}} in FC args. Expected one of {:?}. Write {{{{{{{{ for a literal {{{{",
+                name, FC_ARGS_PLACEHOLDERS
+            ));
+        }
+    }
+
+    let sample = render_fc_args(
+        args,
+        &FCArgsValues {
+            deposit: 0,
+            use_number: 1,
+        },
+    );
+    if near_sdk::serde_json::from_str::<near_sdk::serde_json::Value>(&sample).is_err() {
+        return Err(
+            "FC args with placeholders must be valid JSON once they're filled in".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Check the args of every method the drop can call
+pub(crate) fn check_fc_args_templates(data: &FCData) -> Result<(), String> {
+    data.methods
+        .iter()
+        .flatten()
+        .flatten()
+        .try_for_each(|method| check_fc_args_template(&method.args))
+}
@@ -10,7 +10,7 @@ pub struct MethodData {
     pub receiver_id: AccountId,
     // Method to call on receiver_id contract
     pub method_name: String,
-    // Arguments to pass in (stringified JSON)
+    // Arguments to pass in (stringified JSON). `{{deposit}}` and `{{use_number}}` are filled in when the key is claimed.
     pub args: String,
     // Amount of yoctoNEAR to attach along with the call
     pub attached_deposit: U128,
@@ -50,6 +50,59 @@ pub struct FCData {
     pub config: Option<FCConfig>,
 }
 
+impl DropBehavior for FCData {
+    fn type_name(&self) -> &'static str {
+        "fc"
+    }
+
+    /// A single method is used for every claim. Otherwise the methods left start at the key's current use.
+    fn deposits_left(&self, uses_per_key: u64, remaining_uses: u64) -> (Balance, u64) {
+        let starting_index = if self.methods.len() == 1 {
+            0
+        } else {
+            (uses_per_key - remaining_uses) as usize
+        };
+
+        let mut total_deposit_value = 0;
+        let mut num_none_fcs = 0;
+        for method_name in self
+            .methods
+            .iter()
+            .cycle()
+            .skip(starting_index)
+            .take(remaining_uses as usize)
+        {
+            num_none_fcs += method_name.is_none() as u64;
+            if let Some(method_data) = method_name {
+                total_deposit_value += method_data
+                    .iter()
+                    .fold(0, |acc, x| acc + x.attached_deposit.0);
+            }
+        }
+
+        (total_deposit_value, num_none_fcs)
+    }
+
+    /// Funders restricted to some receivers (or receivers denied since the drop was created) can't add more keys
+    fn on_add_keys(&self, contract: &Keypom, funder_id: &AccountId) -> Result<(), String> {
+        contract.internal_check_fc_receivers_allowed(funder_id, self)?;
+        contract.internal_check_fc_receivers_not_denied(self)
+    }
+
+    /// A single method is used for every claim. Uses whose method is None have nothing to call.
+    fn on_claim(&mut self, use_index: u64, _storage_byte_cost: Balance) -> ClaimedAssets {
+        let index = if self.methods.len() > 1 {
+            use_index as usize
+        } else {
+            0
+        };
+        ClaimedAssets {
+            should_continue: self.methods[index].is_some(),
+            ..Default::default()
+        }
+    }
+}
+
 #[near_bindgen]
 impl Keypom {
     // Internal method_name for transfer NFTs.
@@ -60,6 +113,7 @@ impl Keypom {
         key_id: u64,
         account_id: AccountId,
         drop_id: DropId,
+        args_values: FCArgsValues,
     ) {
         /*
             Function Calls
@@ -79,7 +133,8 @@ impl Keypom {
         let injected_fields = 2u8.pow(0) * account_field.is_some() as u8 + 2u8.pow(1) * drop_id_field.is_some() as u8 + 2u8.pow(2) * key_id_field.is_some() as u8;
 
         for method in methods {
-            let mut final_args = method.args.clone();
+            // Fill in the claim-time values before any fields are injected
+            let mut final_args = render_fc_args(&method.args, &args_values);
 
             if final_args.contains("\"injected_fields\"") {
                 near_sdk::log!("Injected fields detected in client args. Returning and decrementing keys");
@@ -0,0 +1,141 @@
+use crate::*;
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Add the access keys for a drop's keys that are still pending activation. Anyone can call this since the
+        keys were already paid for when they were added to the drop. Every call that adds some of the keys schedules
+        another one for whatever is left, so the list keeps shrinking until it's empty. A call that can't add a
+        single key fails and leaves the list as it was.
+    */
+    pub fn finish_key_creation(&mut self, drop_id: DropId) {
+        let drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        let pending_keys = self
+            .pending_key_activations
+            .remove(&drop_id)
+            .expect("No keys pending activation");
+
+        // Keys are removed from the list when they're deleted so they should all still be part of the drop
+        let keys: Vec<(PublicKey, Balance)> = pending_keys
+            .into_iter()
+            .filter_map(|pk| drop.pks.get(&pk).map(|key_info| (pk, key_info.allowance)))
+            .collect();
+        near_sdk::log!("Activating {} keys for drop {}", keys.len(), drop_id);
+
+        let num_keys = keys.len();
+        let num_added = self.internal_add_access_keys(
+            drop_id,
+            keys,
+            &access_key_method_names(&drop.claim_permission),
+        );
+        require!(
+            num_added > 0 || num_keys == 0,
+            "Not enough GAS attached to add any of the keys pending activation"
+        );
+    }
+
+    /*
+        Add function call access keys in batches of at most MAX_KEYS_PER_BATCH. The GAS used by each key is
+        measured as they're added and once the GAS left can't cover another one, the remaining keys are stored
+        as pending activation and `finish_key_creation` is scheduled for them with the GAS that was kept aside.
+        Returns how many access keys were added.
+    */
+    pub(crate) fn internal_add_access_keys(
+        &mut self,
+        drop_id: DropId,
+        keys: Vec<(PublicKey, Balance)>,
+        method_names: &str,
+    ) -> usize {
+        let current_account_id = env::current_account_id();
+        let reserved_gas = GAS_RESERVED_AFTER_KEY_CREATION.0
+            + GAS_FOR_RESOLVE_ACCESS_KEY_CHANGE.0
+            + MIN_GAS_FOR_FINISH_KEY_CREATION.0;
+
+        // Most GAS used to add a single key (including creating its batch if it was the first in one)
+        let mut gas_per_key = 0;
+        let mut batches: Vec<(PromiseIndex, u64)> = vec![];
+        let mut num_added = 0;
+        for (pk, allowance) in &keys {
+            let gas_left = env::prepaid_gas().0.saturating_sub(env::used_gas().0);
+            if gas_left < reserved_gas + gas_per_key {
+                break;
+            }
+
+            let used_gas = env::used_gas().0;
+            if num_added % MAX_KEYS_PER_BATCH == 0 {
+                batches.push((env::promise_batch_create(&current_account_id), 0));
+            }
+            let (promise, batch_size) = batches.last_mut().unwrap();
+            *batch_size += 1;
+            env::promise_batch_action_add_key_with_function_call(
+                *promise,
+                pk,
+                0,
+                *allowance,
+                &current_account_id,
+                method_names,
+            );
+            gas_per_key = gas_per_key.max(env::used_gas().0 - used_gas);
+            num_added += 1;
+        }
+        self.internal_count_access_key_batches(&batches, true);
+
+        if num_added == keys.len() {
+            return num_added;
+        }
+
+        let pending_keys: Vec<PublicKey> =
+            keys[num_added..].iter().map(|(pk, _)| pk.clone()).collect();
+        near_sdk::log!(
+            "Added {} access keys. {} keys are pending activation for drop {}",
+            num_added,
+            pending_keys.len(),
+            drop_id
+        );
+        self.internal_store_pending_keys(drop_id, pending_keys);
+        Self::ext(current_account_id)
+            .with_static_gas(MIN_GAS_FOR_FINISH_KEY_CREATION)
+            .finish_key_creation(drop_id);
+
+        num_added
+    }
+
+    /*
+        Append keys to the drop's pending activation list. The contract covers the list's storage since it only lives
+        until the keys are activated, and the funder already paid for everything the keys themselves need.
+    */
+    fn internal_store_pending_keys(&mut self, drop_id: DropId, keys: Vec<PublicKey>) {
+        let mut pending_keys = self
+            .pending_key_activations
+            .get(&drop_id)
+            .unwrap_or_default();
+        pending_keys.extend(keys);
+        self.pending_key_activations.insert(&drop_id, &pending_keys);
+    }
+
+    /*
+        Take keys that are about to be deleted off the drop's pending activation list. Returns the keys that
+        actually have access keys on the contract since deleting one that was never added would fail the batch.
+    */
+    pub(crate) fn internal_remove_pending_keys(
+        &mut self,
+        drop_id: &DropId,
+        keys: Vec<PublicKey>,
+    ) -> Vec<PublicKey> {
+        let mut pending_keys = match self.pending_key_activations.get(drop_id) {
+            Some(pending_keys) => pending_keys,
+            None => return keys,
+        };
+
+        let (still_pending, added): (Vec<PublicKey>, Vec<PublicKey>) =
+            keys.into_iter().partition(|pk| pending_keys.contains(pk));
+        pending_keys.retain(|pk| !still_pending.contains(pk));
+        if pending_keys.is_empty() {
+            self.pending_key_activations.remove(drop_id);
+        } else {
+            self.pending_key_activations.insert(drop_id, &pending_keys);
+        }
+
+        added
+    }
+}
@@ -0,0 +1,107 @@
+use std::convert::TryFrom;
+
+use crate::*;
+
+/// Commitment left once every committed key has been revealed
+const KEY_COMMITMENT_END: CryptoHash = [0; 32];
+
+/*
+    Fold keys into the commitment for them followed by `tail`: H(key_1 || H(key_2 || ... H(key_n || tail))) where
+    each key is its curve byte followed by its raw bytes. A batch committed with a tail of 32 zero bytes can then
+    be revealed a few keys at a time, in order, each reveal leaving the commitment for the keys still to come.
+*/
+pub(crate) fn fold_key_commitment(public_keys: &[PublicKey], tail: CryptoHash) -> CryptoHash {
+    public_keys.iter().rev().fold(tail, |acc, pk| {
+        env::sha256_array(&[pk.as_bytes(), &acc[..]].concat())
+    })
+}
+
+/// Read a 32 byte commitment passed in as base64
+fn commitment_from_bytes(bytes: &Base64VecU8) -> Result<CryptoHash, String> {
+    CryptoHash::try_from(bytes.0.as_slice())
+        .map_err(|_| format!("key commitments must be 32 bytes, got {}", bytes.0.len()))
+}
+
+/// Drops committed to a batch of keys start out without keys and must have keys left to reveal
+pub(crate) fn check_key_commitment(
+    config: &Option<DropConfig>,
+    num_keys: usize,
+) -> Result<(), String> {
+    let commitment = match config.as_ref().and_then(|c| c.key_commitment.as_ref()) {
+        Some(commitment) => commitment_from_bytes(commitment)?,
+        None => return Ok(()),
+    };
+    if commitment == KEY_COMMITMENT_END {
+        return Err("key_commitment cannot be all zeros".to_string());
+    }
+    if num_keys > 0 {
+        return Err(
+            "drops with a key_commitment are created without keys. Add them with reveal_keys"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Whether keys can only be added to the drop by revealing them against its commitment
+pub(crate) fn has_key_commitment(config: &Option<DropConfig>) -> bool {
+    config.as_ref().is_some_and(|c| c.key_commitment.is_some())
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Add the next keys of the batch the drop was committed to. The keys must be the next ones in the committed
+        order and `remaining_commitment` the commitment for the keys after them (null once the last key is revealed).
+        The keys are then added and paid for exactly like `add_keys`.
+    */
+    #[payable]
+    pub fn reveal_keys(
+        &mut self,
+        drop_id: DropId,
+        public_keys: Vec<PublicKey>,
+        remaining_commitment: Option<Base64VecU8>,
+        context_tags: Option<Vec<Option<String>>>,
+    ) -> DropId {
+        let mut drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("no drop found for ID");
+        require!(
+            drop.owner_id == env::predecessor_account_id(),
+            "only funder can reveal keys"
+        );
+        require!(!public_keys.is_empty(), "no keys to reveal");
+
+        let config = drop.config.as_mut();
+        let commitment = match config.and_then(|c| c.key_commitment.as_mut()) {
+            Some(commitment) => commitment,
+            None => env::panic_str("drop has no key_commitment. Add keys with add_keys"),
+        };
+        let expected = commitment_from_bytes(commitment).unwrap_or_else(|e| env::panic_str(&e));
+        require!(
+            expected != KEY_COMMITMENT_END,
+            "every committed key has been revealed"
+        );
+
+        let tail = remaining_commitment
+            .as_ref()
+            .map(|c| commitment_from_bytes(c).unwrap_or_else(|e| env::panic_str(&e)))
+            .unwrap_or(KEY_COMMITMENT_END);
+        require!(
+            fold_key_commitment(&public_keys, tail) == expected,
+            "revealed keys don't match the drop's key_commitment"
+        );
+
+        near_sdk::log!(
+            "Revealed {} keys for drop {}. Every committed key revealed: {}",
+            public_keys.len(),
+            drop_id,
+            tail == KEY_COMMITMENT_END
+        );
+        *commitment = Base64VecU8(tail.to_vec());
+        self.drop_for_id.insert(&drop_id, &drop);
+
+        self.internal_add_keys(public_keys, drop_id, context_tags, None)
+    }
+}
@@ -0,0 +1,63 @@
+use crate::*;
+
+/// Overrides of the drop config for a single key. Anything left out falls back to the drop's config.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct KeyConfig {
+    // How many uses the key has
+    pub uses_per_key: Option<u64>,
+
+    // When the key can start and stop being used. Accepts the same formats as the drop config.
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub start_timestamp: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub end_timestamp: Option<u64>,
+}
+
+/*
+    Per-key configs must line up with the public keys and are only supported for simple drops, since FC methods
+    as well as FT and NFT registrations are tied to the drop's uses per key. Each key's timestamps are checked
+    along with the drop's the same way the drop config's are.
+*/
+pub(crate) fn check_key_configs(
+    key_configs: &[Option<KeyConfig>],
+    num_keys: usize,
+    is_simple: bool,
+    config: &Option<DropConfig>,
+) -> Result<(), String> {
+    if key_configs.len() != num_keys {
+        return Err(format!(
+            "key_configs must have one entry for every key. Got {} for {} keys",
+            key_configs.len(),
+            num_keys
+        ));
+    }
+    if !is_simple && key_configs.iter().any(|c| c.is_some()) {
+        return Err("key_configs are only supported for simple drops".to_string());
+    }
+
+    for (i, key_config) in key_configs.iter().enumerate() {
+        let key_config = match key_config {
+            Some(key_config) => key_config,
+            None => continue,
+        };
+        if key_config.uses_per_key == Some(0) {
+            return Err(format!(
+                "key_configs[{}].uses_per_key must be more than 0",
+                i
+            ));
+        }
+
+        // Check the key's timestamps as if they were the drop's
+        let drop_config = config.clone().unwrap_or_default();
+        let resolved = Some(DropConfig {
+            start_timestamp: key_config.start_timestamp.or(drop_config.start_timestamp),
+            end_timestamp: key_config.end_timestamp.or(drop_config.end_timestamp),
+            ..drop_config
+        });
+        check_end_timestamp(&resolved)
+            .and_then(|_| check_config_timestamps(&resolved))
+            .map_err(|e| format!("key_configs[{}]: {}", i, e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use crate::*;
+
+/// Password hashes are sha256 digests of the password
+const PASSWORD_HASH_LEN: usize = 32;
+
+/// Password for a single use of a multi-use key
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PasswordPerUse {
+    // Use the password unlocks, starting at 1 for the key's first use
+    pub key_use: u64,
+    // sha256 hash of the password
+    pub password_hash: Base64VecU8,
+}
+
+/// Password hashes must line up with the public keys and each one must be a sha256 digest
+pub(crate) fn check_password_hashes(
+    password_hashes: &[Option<Base64VecU8>],
+    num_keys: usize,
+) -> Result<(), String> {
+    if password_hashes.len() != num_keys {
+        return Err(format!(
+            "password_hashes must have one entry for every key. Got {} for {} keys",
+            password_hashes.len(),
+            num_keys
+        ));
+    }
+    for (i, hash) in password_hashes.iter().enumerate() {
+        match hash {
+            Some(hash) if hash.0.len() != PASSWORD_HASH_LEN => {
+                return Err(format!(
+                    "password_hashes[{}] must be a {} byte sha256 hash, got {} bytes",
+                    i,
+                    PASSWORD_HASH_LEN,
+                    hash.0.len()
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Per-use passwords must line up with the public keys and each one must unlock a distinct use the key has
+pub(crate) fn check_passwords_per_use(
+    passwords_per_use: &[Option<Vec<PasswordPerUse>>],
+    key_uses: &[u64],
+) -> Result<(), String> {
+    if passwords_per_use.len() != key_uses.len() {
+        return Err(format!(
+            "passwords_per_use must have one entry for every key. Got {} for {} keys",
+            passwords_per_use.len(),
+            key_uses.len()
+        ));
+    }
+    for (i, passwords) in passwords_per_use.iter().enumerate() {
+        for (j, password) in passwords.iter().flatten().enumerate() {
+            if password.key_use == 0 || password.key_use > key_uses[i] {
+                return Err(format!(
+                    "passwords_per_use[{}][{}].key_use must be between 1 and {}",
+                    i, j, key_uses[i]
+                ));
+            }
+            if password.password_hash.0.len() != PASSWORD_HASH_LEN {
+                return Err(format!(
+                    "passwords_per_use[{}][{}] must be a {} byte sha256 hash, got {} bytes",
+                    i,
+                    j,
+                    PASSWORD_HASH_LEN,
+                    password.password_hash.0.len()
+                ));
+            }
+            if passwords
+                .iter()
+                .flatten()
+                .take(j)
+                .any(|other| other.key_use == password.key_use)
+            {
+                return Err(format!(
+                    "passwords_per_use[{}] has more than one password for use {}",
+                    i, password.key_use
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the password passed in when claiming matches the hash. Uses without a password hash are always unlocked.
+pub(crate) fn password_unlocks_key(
+    password_hash: &Option<Vec<u8>>,
+    password: &Option<String>,
+) -> bool {
+    match password_hash {
+        Some(hash) => password
+            .as_ref()
+            .is_some_and(|password| env::sha256(password.as_bytes()) == *hash),
+        None => true,
+    }
+}
+
+impl Keypom {
+    /*
+        Store the per-use passwords of a key being added to a drop. The drop is flagged as having per-use passwords
+        so that keys of other drops never look them up.
+    */
+    pub(crate) fn internal_insert_passwords_per_use(
+        &mut self,
+        drop_id: DropId,
+        key_id: u64,
+        passwords: Vec<PasswordPerUse>,
+    ) {
+        if passwords.is_empty() {
+            return;
+        }
+        self.drops_with_passwords_per_use.insert(&drop_id);
+        for password in passwords {
+            self.passwords_per_use.insert(
+                &(drop_id, key_id, password.key_use),
+                &password.password_hash.0,
+            );
+        }
+    }
+
+    /// Hash of the password a use of a key needs: the use's own password if it has one, otherwise the key's password
+    pub(crate) fn internal_password_hash_for_use(
+        &self,
+        drop_id: DropId,
+        key_info: &KeyInfo,
+        key_use: u64,
+    ) -> Option<Vec<u8>> {
+        if self.drops_with_passwords_per_use.contains(&drop_id) {
+            if let Some(hash) = self
+                .passwords_per_use
+                .get(&(drop_id, key_info.key_id, key_use))
+            {
+                return Some(hash);
+            }
+        }
+        key_info.password_hash.clone()
+    }
+
+    /// Remove the per-use password of a use once it's been claimed
+    pub(crate) fn internal_remove_password_for_use(
+        &mut self,
+        drop_id: DropId,
+        key_id: u64,
+        key_use: u64,
+    ) {
+        if self.drops_with_passwords_per_use.contains(&drop_id) {
+            self.passwords_per_use.remove(&(drop_id, key_id, key_use));
+        }
+    }
+
+    /// Remove the per-use passwords of every use a key has left when it's deleted
+    pub(crate) fn internal_remove_passwords_per_use(
+        &mut self,
+        drop_id: DropId,
+        config: &Option<DropConfig>,
+        key_info: &KeyInfo,
+    ) {
+        if !self.drops_with_passwords_per_use.contains(&drop_id) {
+            return;
+        }
+        for key_use in uses_left(config, key_info) {
+            self.passwords_per_use
+                .remove(&(drop_id, key_info.key_id, key_use));
+        }
+    }
+
+    /// Per-use passwords of the uses a key has left so that they can be exported along with it
+    pub(crate) fn internal_passwords_per_use_left(
+        &self,
+        drop_id: DropId,
+        config: &Option<DropConfig>,
+        key_info: &KeyInfo,
+    ) -> Vec<PasswordPerUse> {
+        if !self.drops_with_passwords_per_use.contains(&drop_id) {
+            return vec![];
+        }
+        uses_left(config, key_info)
+            .filter_map(|key_use| {
+                self.passwords_per_use
+                    .get(&(drop_id, key_info.key_id, key_use))
+                    .map(|hash| PasswordPerUse {
+                        key_use,
+                        password_hash: Base64VecU8(hash),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Use numbers a key hasn't claimed yet
+fn uses_left(config: &Option<DropConfig>, key_info: &KeyInfo) -> std::ops::RangeInclusive<u64> {
+    let uses_per_key = key_uses_per_key(config, key_info);
+    uses_per_key - key_info.remaining_uses + 1..=uses_per_key
+}
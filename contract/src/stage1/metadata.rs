@@ -0,0 +1,44 @@
+use crate::*;
+
+/// Most bytes a drop's metadata summary can take
+pub(crate) const MAX_METADATA_SUMMARY_LEN: usize = 256;
+
+/// Longest prefix of `s` that fits in `max_bytes` without splitting a character
+pub(crate) fn truncate_utf8(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// The funder's abstract must fit in a summary
+pub(crate) fn check_metadata_abstract(config: &Option<DropConfig>) -> Result<(), String> {
+    match config.as_ref().and_then(|c| c.metadata_abstract.as_ref()) {
+        Some(summary) if summary.is_empty() => Err("metadata_abstract cannot be empty".to_string()),
+        Some(summary) if summary.len() > MAX_METADATA_SUMMARY_LEN => Err(format!(
+            "metadata_abstract cannot be more than {} bytes",
+            MAX_METADATA_SUMMARY_LEN
+        )),
+        _ => Ok(()),
+    }
+}
+
+/*
+    Summary stored alongside the drop so claim pages can show something without reading the full metadata. It's
+    the funder's abstract if the config has one and the first 256 bytes of the metadata otherwise. It must be
+    recomputed every time the config or metadata changes.
+*/
+pub(crate) fn metadata_summary(
+    config: &Option<DropConfig>,
+    metadata: Option<&DropMetadata>,
+) -> Option<String> {
+    config
+        .as_ref()
+        .and_then(|c| c.metadata_abstract.clone())
+        .or_else(|| metadata.map(|m| truncate_utf8(m, MAX_METADATA_SUMMARY_LEN).to_string()))
+        .filter(|summary| !summary.is_empty())
+}
@@ -1,6 +1,40 @@
+mod allowlist;
+mod backup;
+mod behavior;
+mod campaigns;
+mod compaction;
 mod delete;
 mod drops;
+mod expiring_keys;
+mod fc_restrictions;
+mod fc_templates;
 pub mod function_call;
+mod key_activation;
+mod key_config;
+mod key_password;
+mod key_commitment;
+mod metadata;
+mod partners;
+mod simple;
+mod update;
+mod use_labels;
+mod validation;
 
+pub use allowlist::*;
+pub(crate) use behavior::*;
+pub use campaigns::*;
+pub(crate) use compaction::*;
+pub(crate) use delete::*;
 pub use drops::*;
+pub(crate) use expiring_keys::*;
+pub use fc_restrictions::*;
+pub(crate) use fc_templates::*;
 pub use function_call::*;
+pub use key_config::*;
+pub(crate) use key_password::*;
+pub(crate) use key_commitment::*;
+pub(crate) use metadata::*;
+pub use partners::*;
+pub(crate) use simple::*;
+pub use use_labels::*;
+pub use validation::*;
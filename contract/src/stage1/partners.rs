@@ -0,0 +1,70 @@
+use crate::*;
+
+/// Basis points that make up the whole of the fees
+pub(crate) const MAX_PARTNER_SHARE_BPS: u16 = 10_000;
+
+/// Partner that earns a share of the fees for the drops they bring in
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PartnerShare {
+    // Account whose balance is credited with the share
+    pub partner_id: AccountId,
+    // Share of the drop and key fees in basis points (i.e 2500 for 25%)
+    pub share_bps: u16,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /// Register a partner or change its share of the fees. Drops that were already created keep the share they were created with.
+    #[payable]
+    pub fn register_partner(&mut self, account_id: AccountId, share_bps: u16) -> bool {
+        self.assert_owner_and_record("register_partner");
+        require!(
+            share_bps <= MAX_PARTNER_SHARE_BPS,
+            "share cannot be more than 10000 basis points"
+        );
+        self.partners.insert(&account_id, &share_bps).is_some()
+    }
+
+    /// Remove a partner so that it can't be passed into new drops. Existing drops keep paying out its share.
+    #[payable]
+    pub fn unregister_partner(&mut self, account_id: AccountId) -> bool {
+        self.assert_owner_and_record("unregister_partner");
+        self.partners.remove(&account_id).is_some()
+    }
+
+    /// Get the current share of a registered partner
+    pub(crate) fn internal_check_partner(&self, partner_id: &AccountId) -> Result<u16, String> {
+        self.partners
+            .get(partner_id)
+            .ok_or_else(|| format!("{} is not a registered partner", partner_id))
+    }
+
+    /*
+        Add the fees paid for a drop to the fees collected. If the drop was created through a partner, the partner's
+        share (as it was when the drop was created) is credited to its balance instead.
+    */
+    pub(crate) fn internal_collect_fees(&mut self, drop_id: DropId, total_fees: Balance) {
+        let partner = match self.partner_for_drop.get(&drop_id) {
+            Some(partner) if total_fees > 0 => partner,
+            _ => {
+                self.fees_collected += total_fees;
+                return;
+            }
+        };
+
+        let partner_amount = total_fees * partner.share_bps as u128 / MAX_PARTNER_SHARE_BPS as u128;
+        self.fees_collected += total_fees - partner_amount;
+        let mut partner_balance = self.user_balances.get(&partner.partner_id).unwrap_or(0);
+        partner_balance += partner_amount;
+        self.internal_set_user_balance(&partner.partner_id, partner_balance);
+
+        emit_event(EventLogVariant::PartnerFeeShare(vec![PartnerFeeShareLog {
+            partner_id: partner.partner_id,
+            drop_id,
+            share_bps: partner.share_bps,
+            total_fees: U128(total_fees),
+            partner_amount: U128(partner_amount),
+        }]));
+    }
+}
@@ -0,0 +1,10 @@
+use crate::*;
+
+/// Simple drops only send $NEAR so every default applies
+pub(crate) struct SimpleDrop;
+
+impl DropBehavior for SimpleDrop {
+    fn type_name(&self) -> &'static str {
+        "simple"
+    }
+}
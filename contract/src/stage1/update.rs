@@ -0,0 +1,511 @@
+use crate::*;
+
+/// Maximum number of keys whose access keys can be re-added in a single update
+const MAX_KEYS_FOR_PERMISSION_UPDATE: u64 = 100;
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Allows the drop owner to update the config and / or metadata for a drop in a single call.
+        Passing in `Some(None)` or an empty string for the metadata will clear it.
+
+        Any change in storage is charged to or refunded to the owner's balance. If the change
+        in config alters which methods the keys can call, the access keys are re-added with the
+        same remaining allowance and the new method names.
+    */
+    #[payable]
+    pub fn update_drop(
+        &mut self,
+        drop_id: DropId,
+        new_config: Option<DropConfig>,
+        new_metadata: Option<Option<DropMetadata>>,
+    ) {
+        assert_one_yocto();
+
+        // Measure initial storage before doing any operations
+        let initial_storage = env::storage_usage();
+
+        let mut drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("no drop found for ID");
+        let owner_id = drop.owner_id.clone();
+        require!(
+            owner_id == env::predecessor_account_id(),
+            "only funder can update drops"
+        );
+
+        let fc_data = match &drop.drop_type {
+            DropType::FunctionCall(data) => Some(data.clone()),
+            _ => None,
+        };
+        let old_permission = drop.claim_permission.clone();
+
+        let config_updated = new_config.is_some();
+        if let Some(config) = new_config {
+            // The number of uses per key is baked into every key's remaining uses and allowance
+            let old_uses_per_key = drop
+                .config
+                .as_ref()
+                .and_then(|c| c.uses_per_key)
+                .unwrap_or(1);
+            require!(
+                config.uses_per_key.unwrap_or(1) == old_uses_per_key,
+                "uses_per_key cannot be changed once a drop is created"
+            );
+
+            // The early bonus is reserved up front so it can't be changed either
+            let old_early_bonus = drop.config.as_ref().and_then(|c| c.early_bonus);
+            require!(
+                config.early_bonus == old_early_bonus,
+                "early_bonus cannot be changed once a drop is created"
+            );
+
+            // As are the compaction bounties
+            require!(
+                config.compaction_bounty == drop.config.as_ref().and_then(|c| c.compaction_bounty),
+                "compaction_bounty cannot be changed once a drop is created"
+            );
+
+            // The wrap cost and GAS are reserved up front so wrapping can't be turned on or off. The contract can be swapped.
+            let old_wraps = drop
+                .config
+                .as_ref()
+                .and_then(|c| c.wrap_contract.as_ref())
+                .is_some();
+            require!(
+                config.wrap_contract.is_some() == old_wraps,
+                "wrap_contract cannot be added or removed once a drop is created"
+            );
+
+            // As are the swap cost and GAS. The payout tokens can be changed as long as they all have a swap route.
+            let new_config = Some(config.clone());
+            let old_swaps = drop
+                .config
+                .as_ref()
+                .and_then(|c| c.alternative_payouts.as_ref())
+                .is_some();
+            require!(
+                config.alternative_payouts.is_some() == old_swaps,
+                "alternative_payouts cannot be added or removed once a drop is created"
+            );
+            self.internal_assert_alternative_payouts(
+                &new_config,
+                matches!(drop.drop_type, DropType::Simple),
+            );
+
+            // Same goes for the contract deployed to new accounts. It can only be swapped for one of the same size.
+            require!(
+                new_config
+                    .as_ref()
+                    .and_then(|c| c.deploy.as_ref())
+                    .is_some()
+                    == drop
+                        .config
+                        .as_ref()
+                        .and_then(|c| c.deploy.as_ref())
+                        .is_some()
+                    && deploy_cost_per_use(&new_config) == deploy_cost_per_use(&drop.config),
+                "deploy can only be replaced with a contract of the same size"
+            );
+
+            // The eligibility check's GAS is part of the keys' allowance so it can't be added or removed. The predicate can be changed.
+            require!(
+                config.eligibility_check.is_some()
+                    == drop
+                        .config
+                        .as_ref()
+                        .and_then(|c| c.eligibility_check.as_ref())
+                        .is_some(),
+                "eligibility_check cannot be added or removed once a drop is created"
+            );
+
+            // As is the badge deposit and GAS
+            require!(
+                mints_badge(&new_config) == mints_badge(&drop.config),
+                "mint_badge cannot be changed once a drop is created"
+            );
+
+            // Revealed keys are checked against the commitment so only reveal_keys can move it forward
+            require!(
+                config.key_commitment
+                    == drop.config.as_ref().and_then(|c| c.key_commitment.clone()),
+                "key_commitment cannot be changed with update_drop"
+            );
+
+            // The gas rebate was reserved for every paid claim when keys were added so it can't change either
+            require!(
+                gas_rebate_per_use(&new_config) == gas_rebate_per_use(&drop.config),
+                "gas_rebate cannot be changed once a drop is created"
+            );
+
+            if let Err(e) = check_end_timestamp(&new_config) {
+                env::panic_str(&e);
+            }
+            if let Err(e) = check_config_timestamps(&new_config) {
+                env::panic_str(&e);
+            }
+            if let Err(e) = check_pow_difficulty(&new_config) {
+                env::panic_str(&e);
+            }
+            if let Err(e) = check_metadata_abstract(&new_config) {
+                env::panic_str(&e);
+            }
+            if let Err(e) = check_use_labels(&new_config) {
+                env::panic_str(&e);
+            }
+            if let Err(e) = check_max_claims_per_account(&new_config) {
+                env::panic_str(&e);
+            }
+
+            // Add the drop to or take it off its asset contract's list if the funder changed whether it's listed
+            if is_unlisted(&new_config) != is_unlisted(&drop.config) {
+                if is_unlisted(&new_config) {
+                    self.internal_remove_drop_from_asset_index(&drop.drop_type, drop_id);
+                } else if let Some(contract_id) = drop.drop_type.behavior().asset_contract_id() {
+                    self.internal_add_drop_to_asset_index(contract_id, drop_id);
+                }
+            }
+
+            // Store the effective permission so that it's enforced in the claim methods and reflected in views
+            drop.claim_permission =
+                effective_claim_permission(&Some(config.clone()), fc_data.as_ref());
+            drop.config = with_claim_permission(Some(config), &drop.claim_permission);
+            if let Err(e) = check_auto_account_names(
+                &drop.config,
+                &drop.claim_permission,
+                drop_id,
+                &self.root_account,
+            ) {
+                env::panic_str(&e);
+            }
+            assert_drop_invariants(&drop);
+        }
+
+        let metadata_updated = new_metadata.is_some();
+        match new_metadata {
+            Some(Some(metadata)) if !metadata.is_empty() => {
+                drop.metadata.set(&metadata);
+            }
+            Some(_) => {
+                drop.metadata.remove();
+            }
+            None => {}
+        };
+        if config_updated || metadata_updated {
+            drop.metadata_summary = metadata_summary(&drop.config, drop.metadata.get().as_ref());
+        }
+
+        self.drop_for_id.insert(&drop_id, &drop);
+
+        // Charge or refund the owner for the change in storage
+        let final_storage = env::storage_usage();
+        let mut cur_balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        if final_storage > initial_storage {
+            let storage_cost =
+                Balance::from(final_storage - initial_storage) * drop.storage_byte_cost;
+            require!(
+                cur_balance >= storage_cost,
+                "Not enough balance to cover the storage for the update"
+            );
+            near_sdk::log!("Charging for storage: {}", yocto_to_near(storage_cost));
+            cur_balance -= storage_cost;
+        } else {
+            let storage_freed =
+                Balance::from(initial_storage - final_storage) * drop.storage_byte_cost;
+            near_sdk::log!("Refunding freed storage: {}", yocto_to_near(storage_freed));
+            cur_balance += storage_freed;
+        }
+        self.internal_set_user_balance(&owner_id, cur_balance);
+        self.internal_check_balance_alert(&owner_id, cur_balance);
+
+        // If the methods the keys can call have changed, re-add each access key with the same allowance
+        if drop.claim_permission != old_permission && !drop.pks.is_empty() {
+            let old_method_names = access_key_method_names(&old_permission);
+            let new_method_names = access_key_method_names(&drop.claim_permission);
+            require!(
+                drop.pks.len() <= MAX_KEYS_FOR_PERMISSION_UPDATE,
+                &format!(
+                    "cannot change claim permissions for drops with more than {} keys",
+                    MAX_KEYS_FOR_PERMISSION_UPDATE
+                )
+            );
+            require!(
+                self.pending_key_activations.get(&drop_id).is_none(),
+                "cannot change claim permissions while keys are pending activation"
+            );
+            near_sdk::log!(
+                "Access key methods changed from {} to {}. Re-adding {} keys",
+                old_method_names,
+                new_method_names,
+                drop.pks.len()
+            );
+
+            let current_account_id = env::current_account_id();
+            let promise = env::promise_batch_create(&current_account_id);
+            for (pk, key_info) in drop.pks.iter() {
+                env::promise_batch_action_delete_key(promise, &pk);
+                env::promise_batch_action_add_key_with_function_call(
+                    promise,
+                    &pk,
+                    0,
+                    key_info.allowance,
+                    &current_account_id,
+                    &new_method_names,
+                );
+            }
+            env::promise_return(promise);
+        }
+
+        emit_event(EventLogVariant::DropUpdate(vec![DropUpdateLog {
+            owner_id,
+            drop_id,
+            config_updated,
+            metadata_updated,
+        }]));
+    }
+
+    /*
+        Allows the drop owner to top up the allowance of keys that ran low, i.e because the GAS price went up
+        between retries. Each key gets `extra_allowance` more and its access key is re-added with the new
+        allowance. Keys still pending activation only have their stored allowance raised since they're added
+        with it once activated. The total is charged to the owner's balance.
+    */
+    #[payable]
+    pub fn add_allowance_to_keys(
+        &mut self,
+        drop_id: DropId,
+        public_keys: Vec<PublicKey>,
+        extra_allowance: U128,
+    ) {
+        assert_one_yocto();
+
+        let mut drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("no drop found for ID");
+        let owner_id = drop.owner_id.clone();
+        require!(
+            owner_id == env::predecessor_account_id(),
+            "only funder can update drops"
+        );
+        require!(drop.state != DropState::Deleting, "drop is being deleted");
+        require!(extra_allowance.0 > 0, "extra_allowance must be more than 0");
+        require!(!public_keys.is_empty(), "no keys passed in");
+        require!(
+            public_keys.len() as u64 <= MAX_KEYS_FOR_PERMISSION_UPDATE,
+            &format!(
+                "cannot top up more than {} keys at once",
+                MAX_KEYS_FOR_PERMISSION_UPDATE
+            )
+        );
+
+        let total_allowance = extra_allowance.0 * public_keys.len() as u128;
+        let cur_balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        require!(
+            cur_balance >= total_allowance,
+            &format!(
+                "Not enough balance to top up the allowance. Required: {} Available: {}",
+                yocto_to_near(total_allowance),
+                yocto_to_near(cur_balance)
+            )
+        );
+
+        let pending_keys = self
+            .pending_key_activations
+            .get(&drop_id)
+            .unwrap_or_default();
+        let method_names = access_key_method_names(&drop.claim_permission);
+        let mut keys_to_readd = vec![];
+        for pk in &public_keys {
+            let mut key_info = drop.pks.get(pk).expect("key doesn't belong to the drop");
+            key_info.allowance += extra_allowance.0;
+            self.internal_insert_key_info(&mut drop.pks, drop.deposit_per_use, pk, &key_info);
+
+            if !pending_keys.contains(pk) {
+                keys_to_readd.push((pk, key_info.allowance));
+            }
+        }
+        self.drop_for_id.insert(&drop_id, &drop);
+
+        near_sdk::log!(
+            "Topping up the allowance of {} keys by {} each. Charging {}",
+            public_keys.len(),
+            yocto_to_near(extra_allowance.0),
+            yocto_to_near(total_allowance)
+        );
+        self.internal_set_user_balance(&owner_id, cur_balance - total_allowance);
+        self.internal_check_balance_alert(&owner_id, cur_balance - total_allowance);
+
+        if keys_to_readd.is_empty() {
+            return;
+        }
+        let current_account_id = env::current_account_id();
+        let promise = env::promise_batch_create(&current_account_id);
+        for (pk, allowance) in keys_to_readd {
+            env::promise_batch_action_delete_key(promise, pk);
+            env::promise_batch_action_add_key_with_function_call(
+                promise,
+                pk,
+                0,
+                allowance,
+                &current_account_id,
+                &method_names,
+            );
+        }
+        env::promise_return(promise);
+    }
+
+    /*
+        Allows the drop owner to change the account that NFTs or FTs must be sent from. This is only
+        possible before any assets are registered so that refunds always go back to the account that
+        actually sent them.
+    */
+    #[payable]
+    pub fn update_asset_sender(&mut self, drop_id: DropId, new_sender: AccountId) {
+        assert_one_yocto();
+
+        // Measure initial storage before doing any operations
+        let initial_storage = env::storage_usage();
+
+        let mut drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("no drop found for ID");
+        let owner_id = drop.owner_id.clone();
+        require!(
+            owner_id == env::predecessor_account_id(),
+            "only funder can update drops"
+        );
+        require!(
+            drop.registered_uses == 0,
+            "cannot change the sender once assets are registered"
+        );
+
+        let old_sender = match &mut drop.drop_type {
+            DropType::NonFungibleToken(data) => {
+                require!(
+                    data.token_ids.is_empty(),
+                    "NFTs must be refunded before changing the sender"
+                );
+                std::mem::replace(&mut data.sender_id, new_sender.clone())
+            }
+            DropType::FungibleToken(data) => {
+                require!(
+                    data.dust.0 == 0,
+                    "FT dust must be refunded before changing the sender"
+                );
+                std::mem::replace(&mut data.sender_id, new_sender.clone())
+            }
+            _ => env::panic_str("can only update the sender for FT and NFT drops"),
+        };
+        near_sdk::log!(
+            "Updating asset sender from {} to {}",
+            old_sender,
+            new_sender
+        );
+
+        self.drop_for_id.insert(&drop_id, &drop);
+
+        // Charge or refund the owner for the change in storage
+        let final_storage = env::storage_usage();
+        let mut cur_balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        if final_storage > initial_storage {
+            let storage_cost =
+                Balance::from(final_storage - initial_storage) * drop.storage_byte_cost;
+            require!(
+                cur_balance >= storage_cost,
+                "Not enough balance to cover the storage for the update"
+            );
+            cur_balance -= storage_cost;
+        } else {
+            cur_balance += Balance::from(initial_storage - final_storage) * drop.storage_byte_cost;
+        }
+        self.internal_set_user_balance(&owner_id, cur_balance);
+        self.internal_check_balance_alert(&owner_id, cur_balance);
+
+        emit_event(EventLogVariant::AssetSenderUpdate(vec![
+            AssetSenderUpdateLog {
+                owner_id,
+                drop_id,
+                old_sender_id: old_sender,
+                new_sender_id: new_sender,
+            },
+        ]));
+    }
+
+    /*
+        Allows the drop owner to hand the drop over to another account. From then on, only the new owner can
+        manage the drop and every refund from it (deleted keys, refunded deposits, freed storage) goes to the new
+        owner's balance. The drop is unlinked from the old owner's campaign. The old owner pays for any storage
+        the transfer takes, including the new owner's balance entry if they didn't have one yet.
+    */
+    #[payable]
+    pub fn transfer_drop_ownership(&mut self, drop_id: DropId, new_owner_id: AccountId) {
+        assert_one_yocto();
+
+        // Measure initial storage before doing any operations
+        let initial_storage = env::storage_usage();
+
+        let mut drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("no drop found for ID");
+        let old_owner_id = drop.owner_id.clone();
+        require!(
+            old_owner_id == env::predecessor_account_id(),
+            "only funder can transfer drops"
+        );
+        require!(
+            new_owner_id != old_owner_id,
+            "drop is already owned by the account"
+        );
+        // The new owner couldn't have created the drop if it calls receivers they're restricted from
+        if let DropType::FunctionCall(data) = &drop.drop_type {
+            require!(
+                self.internal_check_fc_receivers_allowed(&new_owner_id, data)
+                    .is_ok(),
+                "drop calls FC receivers that the new owner is restricted from"
+            );
+        }
+
+        drop.owner_id = new_owner_id.clone();
+        self.drop_for_id.insert(&drop_id, &drop);
+        self.internal_remove_drop_id_for_owner(&old_owner_id, &drop_id);
+        self.internal_add_drop_to_funder(&new_owner_id, &drop_id);
+        self.internal_remove_drop_from_campaign(&drop_id);
+        if let Some(mut stats) = self.drop_stats.get(&drop_id) {
+            stats.owner_id = new_owner_id.clone();
+            self.drop_stats.insert(&drop_id, &stats);
+        }
+        // Refunds are credited to the new owner's balance so make sure it has one
+        if self.user_balances.get(&new_owner_id).is_none() {
+            self.internal_set_user_balance(&new_owner_id, 0);
+        }
+
+        // Charge or refund the old owner for the change in storage
+        let final_storage = env::storage_usage();
+        let mut cur_balance = self.user_balances.get(&old_owner_id).unwrap_or(0);
+        if final_storage > initial_storage {
+            let storage_cost =
+                Balance::from(final_storage - initial_storage) * drop.storage_byte_cost;
+            require!(
+                cur_balance >= storage_cost,
+                "Not enough balance to cover the storage for the transfer"
+            );
+            cur_balance -= storage_cost;
+        } else {
+            cur_balance += Balance::from(initial_storage - final_storage) * drop.storage_byte_cost;
+        }
+        self.internal_set_user_balance(&old_owner_id, cur_balance);
+        self.internal_check_balance_alert(&old_owner_id, cur_balance);
+
+        emit_event(EventLogVariant::DropOwnershipTransfer(vec![
+            DropOwnershipTransferLog {
+                drop_id,
+                old_owner_id,
+                new_owner_id,
+            },
+        ]));
+    }
+}
@@ -0,0 +1,80 @@
+use crate::*;
+
+/// Most bytes a single use label can take
+pub(crate) const MAX_USE_LABEL_LEN: usize = 64;
+
+/// One use of a key as shown in the key's schedule
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonUseStep {
+    // Which use of the key this is, starting at 1
+    pub use_number: u64,
+    // Label the funder gave this use, if any
+    pub label: Option<String>,
+    // Methods the use calls as `receiver_id.method_name`. Always None for drops that aren't FC drops.
+    pub methods: Option<Vec<String>>,
+}
+
+/// There must be one label for every use of a key and each label must be short enough to display
+pub(crate) fn check_use_labels(config: &Option<DropConfig>) -> Result<(), String> {
+    let config = match config {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+    let labels = match &config.use_labels {
+        Some(labels) => labels,
+        None => return Ok(()),
+    };
+
+    let uses_per_key = config.uses_per_key.unwrap_or(1);
+    if labels.len() as u64 != uses_per_key {
+        return Err(format!(
+            "use_labels must have one label for every use. Got {} for {} uses per key",
+            labels.len(),
+            uses_per_key
+        ));
+    }
+    for (i, label) in labels.iter().enumerate() {
+        if label.is_empty() || label.len() > MAX_USE_LABEL_LEN {
+            return Err(format!(
+                "use_labels[{}] must be between 1 and {} bytes",
+                i, MAX_USE_LABEL_LEN
+            ));
+        }
+    }
+    Ok(())
+}
+
+/*
+    Every use of a key along with its label and the methods it calls, in the order they're claimed. The next use
+    is `uses - remaining_uses + 1` where `uses` is the length of the schedule. FC drops with a single set of methods
+    call it for every use.
+*/
+pub(crate) fn use_schedule(drop: &Drop, key_info: &KeyInfo) -> Vec<JsonUseStep> {
+    let labels = drop.config.as_ref().and_then(|c| c.use_labels.as_ref());
+    let methods = match &drop.drop_type {
+        DropType::FunctionCall(data) => Some(&data.methods),
+        _ => None,
+    };
+
+    (0..key_uses_per_key(&drop.config, key_info) as usize)
+        .map(|i| JsonUseStep {
+            use_number: i as u64 + 1,
+            label: labels.and_then(|labels| labels.get(i).cloned()),
+            methods: methods
+                .and_then(|methods| {
+                    if methods.len() == 1 {
+                        methods[0].as_ref()
+                    } else {
+                        methods.get(i).and_then(|m| m.as_ref())
+                    }
+                })
+                .map(|methods| {
+                    methods
+                        .iter()
+                        .map(|m| format!("{}.{}", m.receiver_id, m.method_name))
+                        .collect()
+                }),
+        })
+        .collect()
+}
@@ -0,0 +1,693 @@
+use crate::*;
+use near_sdk::CurveType;
+use std::convert::TryFrom;
+
+/// Conservative estimate of the storage used by a drop (excluding its config, metadata and FC data) for views that can't measure it
+const DROP_STORAGE_ESTIMATE: u64 = 1_000; // bytes
+/// Conservative estimate of the storage used by each key in a drop
+const KEY_STORAGE_ESTIMATE: u64 = 400; // bytes
+/// Conservative estimate of the storage used by each token ID on top of its length
+const TOKEN_ID_STORAGE_ESTIMATE: u64 = 100; // bytes
+/// Maximum length of a key's context tag
+const MAX_CONTEXT_TAG_LEN: usize = 32; // bytes
+
+/// A single problem with the arguments passed into `create_drop`
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DropValidationError {
+    // Argument the problem is with (i.e `fc_data` or `config.alternative_payouts`)
+    pub field: String,
+    // Same message `create_drop` would panic with
+    pub message: String,
+}
+
+impl DropValidationError {
+    fn new(field: &str, message: String) -> Self {
+        Self {
+            field: field.to_string(),
+            message,
+        }
+    }
+}
+
+/// Itemized cost of creating a drop. Everything except the drop fee, storage and early bonus reserve is for all keys combined.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DropPrice {
+    pub drop_fee: U128,
+    pub key_fees: U128,
+    pub storage: U128,
+    pub early_bonus_reserve: U128,
+    pub compaction_reserve: U128,
+    pub allowance: U128,
+    pub access_key_storage: U128,
+    pub deposits: U128,
+    // Part of the deposits that goes to the beneficiaries of a deposit split. It's already included in `deposits`.
+    pub split_deposits: U128,
+    pub nft_storage: U128,
+    pub fc_deposits: U128,
+    pub extra_costs: U128,
+    // Part of the extra costs reserved for gas rebates to newly created accounts. It's already included in `extra_costs`.
+    pub gas_rebates: U128,
+    pub total: U128,
+}
+
+/// Public key passed into `create_drop`, `add_keys` or `validate_drop`. Keys are accepted as `ed25519:<base58>`, bare
+/// `<base58>` or the raw bytes of the key (with or without the leading curve byte) and parsed one by one so that a
+/// malformed key can be reported with its index.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(untagged)]
+pub enum PublicKeyArg {
+    Encoded(String),
+    Bytes(Vec<u8>),
+}
+
+impl From<PublicKey> for PublicKeyArg {
+    fn from(public_key: PublicKey) -> Self {
+        PublicKeyArg::Encoded(String::from(&public_key))
+    }
+}
+
+impl PublicKeyArg {
+    fn parse(self) -> Result<PublicKey, String> {
+        let parsed = match self {
+            PublicKeyArg::Encoded(encoded) => encoded.parse::<PublicKey>(),
+            // Bytes without the curve byte are assumed to be for the curve whose keys are that long
+            PublicKeyArg::Bytes(mut bytes) => {
+                match bytes.len() {
+                    32 => bytes.insert(0, CurveType::ED25519 as u8),
+                    64 => bytes.insert(0, CurveType::SECP256K1 as u8),
+                    _ => {}
+                }
+                PublicKey::try_from(bytes)
+            }
+        };
+        parsed.map_err(|e| format!("invalid public key: {}", e))
+    }
+}
+
+/// Parse the keys passed into `create_drop`, `add_keys` or `validate_drop`, failing on the first malformed key
+pub(crate) fn parse_public_keys(public_keys: Vec<PublicKeyArg>) -> Result<Vec<PublicKey>, String> {
+    public_keys
+        .into_iter()
+        .enumerate()
+        .map(|(i, pk)| pk.parse().map_err(|e| format!("index {}: {}", i, e)))
+        .collect()
+}
+
+/// Arguments of `create_drop` so that the same JSON can be passed into `validate_drop`
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CreateDropArgs {
+    pub public_keys: Vec<PublicKeyArg>,
+    pub deposit_per_use: U128,
+    pub config: Option<DropConfig>,
+    pub metadata: Option<DropMetadata>,
+    pub ft_data: Option<FTDataConfig>,
+    pub nft_data: Option<NFTDataConfig>,
+    pub fc_data: Option<FCData>,
+    pub campaign_id: Option<CampaignId>,
+    pub partner_id: Option<AccountId>,
+}
+
+/// Outcome of `validate_drop`
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ValidationResult {
+    Ok { price: DropPrice },
+    Invalid { errors: Vec<DropValidationError> },
+}
+
+/// Everything derived from the `create_drop` arguments before any state is written
+pub(crate) struct DropPlan {
+    // Methods the access keys can call
+    pub claim_permission: ClaimPermissions,
+    // Config with the effective claim permission written back into it
+    pub config: Option<DropConfig>,
+    // GAS attached to each claim, used to calculate the allowance
+    pub gas_to_attach: Gas,
+    // Allowance given to each key for every one of its uses
+    pub allowance_per_use: Balance,
+    pub num_claims_per_key: u64,
+    // Claims that have no FC data and don't need a balance
+    pub num_none_fcs: u64,
+    // Attached deposits reserved for each key's function calls
+    pub fc_deposits_per_key: Balance,
+    // FT balance per use converted with the cached metadata. None if the token's metadata isn't cached yet.
+    pub ft_balance_per_use: Option<U128>,
+    pub early_bonus_reserve: Balance,
+    // Compaction bounties reserved for the whole drop
+    pub compaction_reserve: Balance,
+}
+
+impl DropPlan {
+    /*
+        Cost of the drop given the storage it uses. NFT storage is the storage needed for the longest token IDs of a
+        use (in bytes).
+        `num_uses` is the total across every key, which differs from `num_claims_per_key * num_keys` when keys
+        override their uses.
+    */
+    pub(crate) fn price(
+        &self,
+        fees: (Balance, Balance),
+        storage_cost: Balance,
+        nft_storage_per_use: u128,
+        deposit_per_use: Balance,
+        num_keys: u128,
+        num_uses: u128,
+    ) -> DropPrice {
+        let num_paid_uses = num_uses - self.num_none_fcs as u128 * num_keys;
+        let key_fees = fees.1 * num_keys;
+        let allowance = self.allowance_per_use * num_uses;
+        let access_key_storage = ACCESS_KEY_STORAGE * num_keys;
+        let deposits = deposit_per_use * num_paid_uses;
+        let split_deposits =
+            split_total(&split_deposit(&self.config, deposit_per_use)) * num_paid_uses;
+        let nft_storage = nft_storage_per_use * env::storage_byte_cost() * num_paid_uses;
+        let fc_deposits = self.fc_deposits_per_key * num_keys;
+        let extra_costs = extra_cost_per_use(&self.config) * num_paid_uses;
+        let gas_rebates = gas_rebate_per_use(&self.config) * num_paid_uses;
+        let total = fees.0
+            + storage_cost
+            + self.early_bonus_reserve
+            + self.compaction_reserve
+            + key_fees
+            + allowance
+            + access_key_storage
+            + deposits
+            + nft_storage
+            + fc_deposits
+            + extra_costs;
+
+        DropPrice {
+            drop_fee: U128(fees.0),
+            key_fees: U128(key_fees),
+            storage: U128(storage_cost),
+            early_bonus_reserve: U128(self.early_bonus_reserve),
+            compaction_reserve: U128(self.compaction_reserve),
+            allowance: U128(allowance),
+            access_key_storage: U128(access_key_storage),
+            deposits: U128(deposits),
+            split_deposits: U128(split_deposits),
+            nft_storage: U128(nft_storage),
+            fc_deposits: U128(fc_deposits),
+            extra_costs: U128(extra_costs),
+            gas_rebates: U128(gas_rebates),
+            total: U128(total),
+        }
+    }
+}
+
+/// Gas rebates are only sent to accounts created with create_account_and_claim so the keys must be able to call it
+pub(crate) fn check_gas_rebate(
+    config: &Option<DropConfig>,
+    claim_permission: &ClaimPermissions,
+) -> Result<(), String> {
+    match config.as_ref().and_then(|c| c.gas_rebate) {
+        Some(rebate) if rebate.0 == 0 => Err("gas_rebate must be greater than 0".to_string()),
+        Some(_) if !claim_permission.allows(&ClaimPermissions::CreateAccountAndClaim) => {
+            Err("gas_rebate requires keys that can call create_account_and_claim".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Context tags are optional but if passed in, there must be one (possibly null) for every key being added
+pub(crate) fn check_context_tags(
+    context_tags: &Option<Vec<Option<String>>>,
+    num_keys: usize,
+) -> Result<(), String> {
+    let tags = match context_tags {
+        Some(tags) => tags,
+        None => return Ok(()),
+    };
+    if tags.len() != num_keys {
+        return Err(format!(
+            "{} context tags were passed in for {} keys",
+            tags.len(),
+            num_keys
+        ));
+    }
+    for (i, tag) in tags.iter().enumerate() {
+        if let Some(tag) = tag {
+            if tag.is_empty() || tag.len() > MAX_CONTEXT_TAG_LEN {
+                return Err(format!(
+                    "context_tags[{}] must be between 1 and {} bytes",
+                    i, MAX_CONTEXT_TAG_LEN
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Only one of FT, NFT or FC data can be passed in
+pub(crate) fn check_single_drop_type(num_cbs_specified: u8) -> Result<(), String> {
+    if num_cbs_specified > 1 {
+        return Err("You cannot specify more than one callback data".to_string());
+    }
+    Ok(())
+}
+
+/// GAS attached to each claim depending on the FC data and the extra work done by the config
+pub(crate) fn drop_gas_to_attach(
+    deposit_per_use: Balance,
+    config: &Option<DropConfig>,
+    fc_data: Option<&FCData>,
+) -> Result<Gas, String> {
+    // Default the gas to attach to be the gas from the wallet. This will be used to calculate allowances.
+    let mut gas_to_attach = ATTACHED_GAS_FROM_WALLET;
+    if let Some(gas) = fc_data.and_then(|d| d.config.as_ref().and_then(|c| c.attached_gas)) {
+        if deposit_per_use != 0 {
+            return Err(
+                "cannot specify gas to attach and have a balance in the linkdrop".to_string(),
+            );
+        }
+        if gas > ATTACHED_GAS_FROM_WALLET - GAS_OFFSET_IF_FC_EXECUTE {
+            return Err(format!(
+                "cannot attach more than {:?} GAS.",
+                ATTACHED_GAS_FROM_WALLET - GAS_OFFSET_IF_FC_EXECUTE
+            ));
+        }
+        gas_to_attach = gas + GAS_OFFSET_IF_FC_EXECUTE;
+    }
+    // Wrapping the balance requires extra cross contract calls in claim
+    if config
+        .as_ref()
+        .and_then(|c| c.wrap_contract.as_ref())
+        .is_some()
+    {
+        gas_to_attach += GAS_FOR_WRAPPING;
+    }
+    // Swapping it into an alternative payout token requires even more
+    if config
+        .as_ref()
+        .and_then(|c| c.alternative_payouts.as_ref())
+        .is_some()
+    {
+        gas_to_attach += GAS_FOR_SWAPPING;
+    }
+    // As does deploying a contract to the new account in create_account_and_claim
+    if config.as_ref().and_then(|c| c.deploy.as_ref()).is_some() {
+        gas_to_attach += GAS_FOR_DEPLOYING;
+    }
+    // As does minting a badge once the claim resolves
+    if mints_badge(config) {
+        gas_to_attach += GAS_FOR_BADGES;
+    }
+    // And checking the claimer's eligibility before claim
+    if config
+        .as_ref()
+        .and_then(|c| c.eligibility_check.as_ref())
+        .is_some()
+    {
+        gas_to_attach += GAS_FOR_ELIGIBILITY_CHECK;
+    }
+    Ok(gas_to_attach)
+}
+
+/*
+    Ensure the FC data has the right number of methods for the claims per key and tally the attached deposits
+    each key needs. Returns the deposits along with how many claims have no method data.
+*/
+pub(crate) fn fc_deposits_per_key(
+    data: &FCData,
+    num_claims_per_key: u64,
+) -> Result<(Balance, u64), String> {
+    let num_method_data = data.methods.len() as u64;
+    // If there's 1 claim, there should be 1 method data defined
+    if num_claims_per_key == 1 {
+        if num_method_data != 1 {
+            return Err(
+                "Cannot have more Method Data than the number of claims per key".to_string(),
+            );
+        }
+    // If there's more than 1 method data defined and the number of claims per key more than 1, the number of methods should equal the number of claims per key
+    } else if num_method_data > 1 && num_method_data != num_claims_per_key {
+        return Err(
+            "Number of FCs must match number of claims per key if more than 1 is specified"
+                .to_string(),
+        );
+    }
+
+    // If there's one method data specified and more than 1 claim per key, that data is to be used
+    // For all the claims. In this case, we need to tally all the deposits for each method in all method data.
+    if num_claims_per_key > 1 && num_method_data == 1 {
+        let attached_deposit = data
+            .methods
+            .first()
+            .and_then(|m| m.as_ref())
+            .ok_or_else(|| "cannot have a single none function call".to_string())?
+            .iter()
+            .fold(0, |acc, x| acc + x.attached_deposit.0);
+        return Ok((num_claims_per_key as u128 * attached_deposit, 0));
+    }
+
+    // In the case where either there's 1 claim per key or the number of FCs is not 1,
+    // We can simply loop through and manually get this data
+    let mut deposits = 0;
+    let mut num_none_fcs = 0;
+    for method in &data.methods {
+        match method {
+            Some(method_data) => {
+                deposits += method_data
+                    .iter()
+                    .fold(0, |acc, x| acc + x.attached_deposit.0)
+            }
+            None => num_none_fcs += 1,
+        }
+    }
+    Ok((deposits, num_none_fcs))
+}
+
+/*
+    Re-check the rules between a stored drop's fields that `create_drop` enforces. Drops created before a rule
+    existed or changed by later updates could break them, so methods that build on the stored drop check these first.
+*/
+pub(crate) fn check_drop_invariants(drop: &Drop) -> Result<(), String> {
+    let uses_per_key = drop
+        .config
+        .as_ref()
+        .and_then(|c| c.uses_per_key)
+        .unwrap_or(1);
+    match &drop.drop_type {
+        DropType::FunctionCall(data) => {
+            drop_gas_to_attach(drop.deposit_per_use, &drop.config, Some(data))?;
+            fc_deposits_per_key(data, uses_per_key)?;
+            // Keys that attach GAS can only call claim
+            if data.config.as_ref().and_then(|c| c.attached_gas).is_some()
+                && drop.claim_permission != ClaimPermissions::Claim
+            {
+                return Err("FC drops with attached GAS can only be claimed with claim".to_string());
+            }
+        }
+        DropType::Simple if drop.deposit_per_use == 0 => {
+            return Err("Cannot have a simple drop with zero balance".to_string());
+        }
+        _ => {}
+    }
+
+    if let Some(config) = drop.config.as_ref() {
+        if config.alternative_payouts.is_some() {
+            if !matches!(drop.drop_type, DropType::Simple) {
+                return Err("alternative payouts are only supported for simple drops".to_string());
+            }
+            if config.wrap_contract.is_some() {
+                return Err(
+                    "cannot specify both alternative payouts and a wrap contract".to_string(),
+                );
+            }
+        }
+    }
+    check_deposit_split(&drop.config, matches!(drop.drop_type, DropType::Simple))?;
+    check_eligibility_check(&drop.config, &drop.claim_permission)?;
+    check_post_claim(&drop.config)
+}
+
+/// Panic naming the violated invariant if the drop breaks any of the rules `create_drop` enforces
+pub(crate) fn assert_drop_invariants(drop: &Drop) {
+    let violation = check_drop_invariants(drop).err();
+    require!(
+        violation.is_none(),
+        format!(
+            "drop invariant violated: {}",
+            violation.as_deref().unwrap_or_default()
+        )
+    );
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Dry run of `create_drop` for the funder. Runs the same checks without writing any state and returns either
+        the itemized price or every problem found. Storage is estimated conservatively so the real price should be
+        lower. If no balance is passed in, the funder's current balance is used. FT registration costs are only
+        known once the FT contract is queried and aren't included.
+    */
+    pub fn validate_drop(
+        &self,
+        funder_id: AccountId,
+        balance: Option<U128>,
+        args: CreateDropArgs,
+    ) -> ValidationResult {
+        let CreateDropArgs {
+            public_keys,
+            deposit_per_use,
+            config,
+            metadata,
+            ft_data,
+            nft_data,
+            fc_data,
+            campaign_id,
+            partner_id,
+        } = args;
+
+        let (plan, mut errors) = match self.internal_plan_drop(
+            &funder_id,
+            deposit_per_use.0,
+            config,
+            ft_data.as_ref(),
+            nft_data.as_ref(),
+            fc_data.as_ref(),
+        ) {
+            Ok(plan) => (Some(plan), vec![]),
+            Err(errors) => (None, errors),
+        };
+        if let Some(campaign_id) = campaign_id {
+            if let Err(e) = self.internal_check_campaign_owner(&funder_id, campaign_id) {
+                errors.push(DropValidationError::new("campaign_id", e));
+            }
+        }
+        if let Some(partner_id) = partner_id {
+            if let Err(e) = self.internal_check_partner(&partner_id) {
+                errors.push(DropValidationError::new("partner_id", e));
+            }
+        }
+
+        // Keys must be well formed, unique and can't belong to another drop
+        let num_keys = public_keys.len() as u64;
+        if let Err(e) = parse_public_keys(public_keys)
+            .and_then(|public_keys| self.internal_check_new_keys(&public_keys))
+        {
+            errors.push(DropValidationError::new("public_keys", e));
+        }
+        if let Err(e) = check_key_commitment(
+            &plan.as_ref().and_then(|p| p.config.clone()),
+            num_keys as usize,
+        ) {
+            errors.push(DropValidationError::new("config.key_commitment", e));
+        }
+
+        let plan = match plan {
+            Some(plan) if errors.is_empty() => plan,
+            _ => return ValidationResult::Invalid { errors },
+        };
+
+        // Estimate the storage since it can't be measured in a view
+        let storage_bytes = DROP_STORAGE_ESTIMATE
+            + KEY_STORAGE_ESTIMATE * num_keys
+            + plan.config.try_to_vec().map_or(0, |v| v.len() as u64)
+            + metadata.try_to_vec().map_or(0, |v| v.len() as u64)
+            + fc_data.try_to_vec().map_or(0, |v| v.len() as u64);
+        let nft_storage_per_use = nft_data.map_or(0, |data| {
+            (TOKEN_ID_STORAGE_ESTIMATE + data.longest_token_id.len() as u64) as u128
+                * data.tokens_per_use.unwrap_or(1) as u128
+        });
+
+        let price = plan.price(
+            self.internal_fees_for_user(&funder_id),
+            Balance::from(storage_bytes) * env::storage_byte_cost(),
+            nft_storage_per_use,
+            deposit_per_use.0,
+            num_keys as u128,
+            (plan.num_claims_per_key * num_keys) as u128,
+        );
+        let balance = balance
+            .map(|b| b.0)
+            .unwrap_or_else(|| self.user_balances.get(&funder_id).unwrap_or(0));
+        if balance < price.total.0 {
+            return ValidationResult::Invalid {
+                errors: vec![DropValidationError::new(
+                    "balance",
+                    insufficient_balance_message(balance, price.total.0, 0),
+                )],
+            };
+        }
+
+        ValidationResult::Ok { price }
+    }
+
+    /// Check that keys being added are unique, don't belong to another drop and fit under the access key cap. Keys are
+    /// parsed into their curve and raw bytes when deserialized so `ed25519:<base58>` and bare `<base58>` are caught
+    /// as the same key.
+    pub(crate) fn internal_check_new_keys(&self, public_keys: &[PublicKey]) -> Result<(), String> {
+        self.internal_check_access_key_capacity(public_keys.len())?;
+        for (i, pk) in public_keys.iter().enumerate() {
+            if let Some(first) = public_keys[..i].iter().position(|other| other == pk) {
+                return Err(format!(
+                    "public_keys[{}] is the same key as public_keys[{}]",
+                    i, first
+                ));
+            }
+            if let Some(drop_id) = self.drop_id_for_pk.get(pk) {
+                return Err(format!(
+                    "public_keys[{}] already belongs to drop {}",
+                    i, drop_id
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /*
+        Run every check on the `create_drop` arguments that doesn't depend on the keys or campaign and derive what
+        the drop needs. All problems are collected so that `validate_drop` can report them at once.
+    */
+    pub(crate) fn internal_plan_drop(
+        &self,
+        owner_id: &AccountId,
+        deposit_per_use: Balance,
+        config: Option<DropConfig>,
+        ft_data: Option<&FTDataConfig>,
+        nft_data: Option<&NFTDataConfig>,
+        fc_data: Option<&FCData>,
+    ) -> Result<DropPlan, Vec<DropValidationError>> {
+        let mut errors = vec![];
+        let is_nft = nft_data.is_some();
+        let num_cbs_specified = ft_data.is_some() as u8 + is_nft as u8 + fc_data.is_some() as u8;
+        if let Err(e) = check_single_drop_type(num_cbs_specified) {
+            errors.push(DropValidationError::new("drop_type", e));
+        }
+        if let Err(e) = self.internal_check_alternative_payouts(&config, num_cbs_specified == 0) {
+            errors.push(DropValidationError::new("config.alternative_payouts", e));
+        }
+        if let Err(e) = check_deposit_split(&config, num_cbs_specified == 0) {
+            errors.push(DropValidationError::new("config.deposit_split", e));
+        }
+        if let Err(e) = check_post_claim(&config) {
+            errors.push(DropValidationError::new("config.post_claim", e));
+        }
+        if let Err(e) = check_end_timestamp(&config) {
+            errors.push(DropValidationError::new("config.end_timestamp", e));
+        }
+        if let Err(e) = check_config_timestamps(&config) {
+            errors.push(DropValidationError::new("config", e));
+        }
+
+        // Decide what methods the access keys can call and write the effective permission back into the config
+        let claim_permission = effective_claim_permission(&config, fc_data);
+        let config = with_claim_permission(config, &claim_permission);
+        let num_claims_per_key = config.as_ref().and_then(|c| c.uses_per_key).unwrap_or(1);
+        if let Err(e) = check_gas_rebate(&config, &claim_permission) {
+            errors.push(DropValidationError::new("config.gas_rebate", e));
+        }
+        if let Err(e) = check_eligibility_check(&config, &claim_permission) {
+            errors.push(DropValidationError::new("config.eligibility_check", e));
+        }
+        if let Err(e) = self.internal_check_mint_badge(&config) {
+            errors.push(DropValidationError::new("config.mint_badge", e));
+        }
+        if let Err(e) = check_pow_difficulty(&config) {
+            errors.push(DropValidationError::new("config.pow_difficulty", e));
+        }
+        if let Err(e) = check_compaction_bounty(&config) {
+            errors.push(DropValidationError::new("config.compaction_bounty", e));
+        }
+        if let Err(e) = check_metadata_abstract(&config) {
+            errors.push(DropValidationError::new("config.metadata_abstract", e));
+        }
+        if let Err(e) = check_use_labels(&config) {
+            errors.push(DropValidationError::new("config.use_labels", e));
+        }
+        if let Err(e) = check_max_claims_per_account(&config) {
+            errors.push(DropValidationError::new("config.max_claims_per_account", e));
+        }
+        if let Err(e) = check_auto_account_names(
+            &config,
+            &claim_permission,
+            self.next_drop_id,
+            &self.root_account,
+        ) {
+            errors.push(DropValidationError::new("config.auto_account_names", e));
+        }
+
+        let mut gas_to_attach = drop_gas_to_attach(deposit_per_use, &config, fc_data)
+            .unwrap_or_else(|e| {
+                errors.push(DropValidationError::new("fc_data.config.attached_gas", e));
+                ATTACHED_GAS_FROM_WALLET
+            });
+        if let Some(data) = nft_data {
+            match nft_gas_to_attach(data, gas_to_attach) {
+                Ok(gas) => gas_to_attach = gas,
+                Err(e) => errors.push(DropValidationError::new("nft_data.tokens_per_use", e)),
+            }
+        }
+
+        let mut fc_deposits = 0;
+        let mut num_none_fcs = 0;
+        if let Some(data) = fc_data {
+            // Restricted funders can only call receivers under their allowed suffixes
+            if let Err(e) = self.internal_check_fc_receivers_allowed(owner_id, data) {
+                errors.push(DropValidationError::new("fc_data", e));
+            }
+            if let Err(e) = self.internal_check_fc_receivers_not_denied(data) {
+                errors.push(DropValidationError::new("fc_data", e));
+            }
+            if let Err(e) = check_fc_args_templates(data) {
+                errors.push(DropValidationError::new("fc_data.methods.args", e));
+            }
+            match fc_deposits_per_key(data, num_claims_per_key) {
+                Ok((deposits, none_fcs)) => {
+                    fc_deposits = deposits;
+                    num_none_fcs = none_fcs;
+                }
+                Err(e) => errors.push(DropValidationError::new("fc_data.methods", e)),
+            }
+        } else if ft_data.is_none() && !is_nft && deposit_per_use == 0 {
+            errors.push(DropValidationError::new(
+                "deposit_per_use",
+                "Cannot have a simple drop with zero balance".to_string(),
+            ));
+        }
+
+        // If the token's metadata is already cached, validate the balance right away. Otherwise it's validated in the resolver.
+        let mut ft_balance = None;
+        if let Some(data) = ft_data {
+            if let Some(metadata) = self.ft_metadata.get(&data.contract_id) {
+                match ft_balance_per_use(
+                    data.balance_per_use,
+                    &data.balance_per_use_human,
+                    Some(metadata.decimals),
+                ) {
+                    Ok(balance) => ft_balance = Some(balance),
+                    Err(e) => errors.push(DropValidationError::new("ft_data", e)),
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let early_bonus_reserve = match config.as_ref().and_then(|c| c.early_bonus) {
+            Some((num_bonus_claims, amount)) => num_bonus_claims as u128 * amount.0,
+            None => 0,
+        };
+        let compaction_reserve = compaction_reserve(&config);
+        Ok(DropPlan {
+            claim_permission,
+            config,
+            gas_to_attach,
+            // Each key gets the base allowance for every use since each claim can potentially use the max pessimistic GAS.
+            allowance_per_use: self.calculate_base_allowance(gas_to_attach),
+            num_claims_per_key,
+            num_none_fcs,
+            fc_deposits_per_key: fc_deposits,
+            ft_balance_per_use: ft_balance,
+            early_bonus_reserve,
+            compaction_reserve,
+        })
+    }
+}
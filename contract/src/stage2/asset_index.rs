@@ -0,0 +1,49 @@
+use crate::*;
+
+/// Whether the funder opted the drop out of the asset contract index
+pub(crate) fn is_unlisted(config: &Option<DropConfig>) -> bool {
+    config.as_ref().and_then(|c| c.unlisted).unwrap_or(false)
+}
+
+impl Keypom {
+    /// List a drop under the FT or NFT contract it distributes. The storage is part of whatever the caller measures.
+    pub(crate) fn internal_add_drop_to_asset_index(
+        &mut self,
+        contract_id: &AccountId,
+        drop_id: DropId,
+    ) {
+        let mut drop_set = self.drop_ids_for_asset.get(contract_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::DropIdsForAssetInner {
+                //we get a new unique prefix for the collection
+                account_id_hash: hash_account_id(&format!("asset-{}", contract_id)),
+            })
+        });
+        drop_set.insert(&drop_id);
+        self.drop_ids_for_asset.insert(contract_id, &drop_set);
+    }
+
+    /// Take a drop off its asset contract's list if it's on it. The contract is removed once it has no drops left.
+    pub(crate) fn internal_remove_drop_from_asset_index(
+        &mut self,
+        drop_type: &DropType,
+        drop_id: DropId,
+    ) {
+        let contract_id = match drop_type.behavior().asset_contract_id() {
+            Some(contract_id) => contract_id,
+            None => return,
+        };
+        let mut drop_set = match self.drop_ids_for_asset.get(contract_id) {
+            Some(drop_set) => drop_set,
+            None => return,
+        };
+
+        if !drop_set.remove(&drop_id) {
+            return;
+        }
+        if drop_set.is_empty() {
+            self.drop_ids_for_asset.remove(contract_id);
+        } else {
+            self.drop_ids_for_asset.insert(contract_id, &drop_set);
+        }
+    }
+}
@@ -9,6 +9,23 @@ pub struct FTData {
     pub sender_id: AccountId,
     pub balance_per_use: U128,
     pub ft_storage: U128,
+    // Whether tokens left over after registering uses are kept towards a future use instead of being returned
+    #[serde(default)]
+    pub accumulate_dust: bool,
+    // Tokens sent to the drop that haven't added up to a full use yet
+    #[serde(default = "no_dust")]
+    pub dust: U128,
+    // Whether transfers are checked against the contract's balance before uses are registered
+    #[serde(default)]
+    pub verify_received_balance: bool,
+    // Tokens sent to the drop that are waiting on the balance check. They don't count towards any uses yet.
+    #[serde(default = "no_dust")]
+    pub pending: U128,
+}
+
+/// Drops exported before dust was tracked don't have any
+fn no_dust() -> U128 {
+    U128(0)
 }
 
 /// FT Data to be passed in by the user
@@ -18,6 +35,95 @@ pub struct FTDataConfig {
     pub contract_id: AccountId,
     pub sender_id: AccountId,
     pub balance_per_use: U128,
+    // Balance per use in whole tokens (i.e "1.5") which is converted using the token's decimals.
+    // If specified, balance_per_use must be 0.
+    #[serde(default)]
+    pub balance_per_use_human: Option<String>,
+    // Keep tokens that don't divide evenly into uses and register an extra use once they add up to balance_per_use.
+    // By default, the remainder of each transfer is returned to the sender.
+    #[serde(default)]
+    pub accumulate_dust: bool,
+    // Only register uses for what actually reached this contract (i.e for tokens that take a fee on transfer).
+    // Each transfer queries this contract's balance on the token contract and uses are registered once it resolves.
+    #[serde(default)]
+    pub verify_received_balance: bool,
+}
+
+/// Subset of the FT metadata cached for each token contract so that amounts can be validated and rendered
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FTMetadataInfo {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Convert an amount in whole tokens (i.e "1.5") into the token's smallest units. Returns None if it isn't a valid amount.
+pub(crate) fn parse_ft_amount(amount: &str, decimals: u8) -> Option<u128> {
+    let (whole, fraction) = match amount.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (amount, ""),
+    };
+    if whole.is_empty() && fraction.is_empty()
+        || fraction.len() > decimals as usize
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    // Pad the fraction out to the number of decimals
+    let fraction_scale = 10u128.checked_pow((decimals as usize - fraction.len()) as u32)?;
+    let whole: u128 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let fraction: u128 = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse().ok()?
+    };
+    whole
+        .checked_mul(10u128.checked_pow(decimals as u32)?)?
+        .checked_add(fraction.checked_mul(fraction_scale)?)
+}
+
+/*
+    Work out the actual balance per use for an FT drop. Amounts in whole tokens are converted using the
+    token's decimals. Raw amounts that are tiny for the token's decimals (less than 10^(decimals / 2)) are
+    rejected since they're almost always whole token amounts that were passed in by mistake.
+*/
+pub(crate) fn ft_balance_per_use(
+    balance_per_use: U128,
+    balance_per_use_human: &Option<String>,
+    decimals: Option<u8>,
+) -> Result<U128, String> {
+    let balance = match (balance_per_use_human, decimals) {
+        (Some(_), _) if balance_per_use.0 != 0 => {
+            return Err("cannot specify both balance_per_use and balance_per_use_human".to_string())
+        }
+        (Some(_), None) => {
+            return Err("token metadata is required to use balance_per_use_human".to_string())
+        }
+        (Some(human), Some(decimals)) => parse_ft_amount(human, decimals)
+            .ok_or_else(|| format!("invalid balance_per_use_human: {}", human))?,
+        (None, Some(decimals)) => {
+            let min_raw = 10u128.checked_pow(decimals as u32 / 2).unwrap_or(u128::MAX);
+            if balance_per_use.0 < min_raw {
+                return Err(format!("balance_per_use {} is suspiciously small for a token with {} decimals. Use balance_per_use_human to specify whole tokens", balance_per_use.0, decimals));
+            }
+            balance_per_use.0
+        }
+        (None, None) => balance_per_use.0,
+    };
+    if balance == 0 {
+        return Err("balance_per_use must be greater than 0".to_string());
+    }
+
+    Ok(U128(balance))
 }
 
 // Returned from the storage balance bounds cross contract call on the FT contract
@@ -28,38 +134,115 @@ pub struct StorageBalanceBounds {
     pub max: Option<U128>,
 }
 
+impl DropBehavior for FTData {
+    fn type_name(&self) -> &'static str {
+        "ft"
+    }
+
+    fn registers_uses_with_keys(&self) -> bool {
+        false
+    }
+
+    /// Every claimer is registered on the token contract before the FTs are sent
+    fn asset_storage_per_use(&self, _storage_byte_cost: Balance) -> Balance {
+        self.ft_storage.0
+    }
+
+    fn asset_contract_id(&self) -> Option<&AccountId> {
+        Some(&self.contract_id)
+    }
+}
+
 #[near_bindgen]
 impl Keypom {
-    /// Allows users to attach fungible tokens to the Linkdrops. Must have storage recorded by this point. You can only attach one set of FTs or NFT at a time.
+    /*
+        Allows users to attach fungible tokens to the Linkdrops. Must have storage recorded by this point. You can only attach one set of FTs or NFT at a time.
+        Tokens that don't add up to a full use are returned to the sender unless the drop accumulates dust, in which case
+        they're tracked on the drop and count towards the next transfer. Drops that verify the received balance hold
+        the transfer as pending and only register uses once the balance check resolves. wNEAR sent with the
+        `add_to_balance` msg tops up the sender's balance instead.
+    */
     pub fn ft_on_transfer(
         &mut self,
         sender_id: AccountId,
         amount: U128,
-        msg: U128,
+        msg: String,
     ) -> PromiseOrValue<U128> {
         let contract_id = env::predecessor_account_id();
-
-        let mut drop = self.drop_for_id.get(&msg.0).expect("No drop found for ID");
-        if let DropType::FungibleToken(ft_data) = &drop.drop_type {
+        if msg == ADD_TO_BALANCE_MSG {
+            return self.internal_add_wrapped_to_balance(contract_id, sender_id, amount);
+        }
+        let drop_id: DropId = msg
+            .parse()
+            .unwrap_or_else(|_| env::panic_str("msg must be a drop ID or add_to_balance"));
+
+        let mut drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("No drop found for ID");
+        require!(
+            drop.state != DropState::Cancelled,
+            "drop has been cancelled"
+        );
+        require!(
+            drop.state != DropState::Deleting,
+            "drop is being deleted"
+        );
+        // Pending and failed drops are rolled back without sending any tokens back so they can't take any
+        require!(
+            drop.state == DropState::Active,
+            "drop's storage check hasn't resolved yet"
+        );
+        if let DropType::FungibleToken(ft_data) = &mut drop.drop_type {
             require!(
-                amount.0 % ft_data.balance_per_use.0 == 0,
-                "amount must be a multiple of the drop balance"
+                ft_data.balance_per_use.0 != 0,
+                "drop's balance per use hasn't been resolved yet"
             );
             require!(
                 ft_data.contract_id == contract_id && ft_data.sender_id == sender_id,
                 "FT data must match what was sent"
             );
 
+            if ft_data.verify_received_balance {
+                ft_data.pending = amount;
+                self.drop_for_id.insert(&drop_id, &drop);
+                return PromiseOrValue::Promise(self.internal_begin_ft_balance_check(
+                    contract_id,
+                    drop_id,
+                    amount,
+                ));
+            }
+
+            // Dust from earlier transfers counts towards this one if the drop accumulates it
+            let available = if ft_data.accumulate_dust {
+                amount.0 + ft_data.dust.0
+            } else {
+                amount.0
+            };
+
             // Get the number of claims to register with the amount that is sent.
-            let claims_to_register = (amount.0 / ft_data.balance_per_use.0) as u64;
+            let claims_to_register = (available / ft_data.balance_per_use.0) as u64;
+            let remainder = available % ft_data.balance_per_use.0;
+            let to_return = if ft_data.accumulate_dust {
+                ft_data.dust = U128(remainder);
+                0
+            } else {
+                remainder
+            };
             drop.registered_uses += claims_to_register;
-            near_sdk::log!("New claims registered {}", claims_to_register);
+            near_sdk::log!(
+                "New claims registered {}. Dust returned {}. Dust kept {}",
+                claims_to_register,
+                to_return,
+                remainder - to_return
+            );
 
             // Insert the drop with the updated data
-            self.drop_for_id.insert(&msg.0, &drop);
+            self.drop_for_id.insert(&drop_id, &drop);
+            self.internal_hold_ft(&contract_id, amount.0 - to_return);
 
-            // Everything went well and we don't need to return any tokens (if they over-sent, we keep it)
-            PromiseOrValue::Value(U128(0))
+            // Return whatever didn't add up to a full use. The token contract refunds it to the sender.
+            PromiseOrValue::Value(U128(to_return))
         } else {
             env::panic_str("drop type isn't FT");
         }
@@ -82,6 +265,9 @@ impl Keypom {
             prepaid_gas.0
         );
         let transfer_succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        // The tokens either went to the claimer or are refunded to the sender below
+        self.internal_release_ft(&token_contract, amount.0);
+        self.internal_end_ft_claim(&token_contract);
 
         used_gas = env::used_gas();
         prepaid_gas = env::prepaid_gas();
@@ -127,11 +313,17 @@ impl Keypom {
 
     #[private]
     /// Self callback checks if fungible tokens were successfully refunded. If yes, set keys registered to 0.
-    pub fn ft_resolve_refund(&mut self, drop_id: DropId, num_to_refund: u64) -> bool {
+    pub fn ft_resolve_refund(&mut self, drop_id: DropId, num_to_refund: u64, dust: U128) -> bool {
         let transfer_succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
 
         // Everything went well so we return true since the keys registered have already been decremented
         if transfer_succeeded {
+            if let Some(DropType::FungibleToken(data)) =
+                self.drop_for_id.get(&drop_id).map(|d| d.drop_type)
+            {
+                let refunded = data.balance_per_use.0 * num_to_refund as u128 + dust.0;
+                self.internal_release_ft(&data.contract_id, refunded);
+            }
             near_sdk::log!(
                 "Successfully refunded FTs for drop ID {}. {} keys unregistered. Returning true.",
                 drop_id,
@@ -140,12 +332,15 @@ impl Keypom {
             return true;
         }
 
-        // Transfer failed so we need to increment the claims registered and return false
+        // Transfer failed so we need to increment the claims registered, put the dust back and return false
         let mut drop = self.drop_for_id.get(&drop_id).expect("no drop for ID");
         drop.registered_uses += num_to_refund;
+        if let DropType::FungibleToken(data) = &mut drop.drop_type {
+            data.dust.0 += dust.0;
+        }
         self.drop_for_id.insert(&drop_id, &drop);
 
-        near_sdk::log!("Unsuccessful refund for drop ID {}. {} keys added back as registered. Returning false.", drop_id, num_to_refund);
+        near_sdk::log!("Unsuccessful refund for drop ID {}. {} keys and {} dust added back as registered. Returning false.", drop_id, num_to_refund, dust.0);
         false
     }
 
@@ -157,11 +352,40 @@ impl Keypom {
         public_keys: Vec<PublicKey>,
         drop_id: DropId,
         required_deposit: u128,
+        balance_per_use_human: Option<String>,
     ) -> bool {
-        // Check promise result.
-        let result = promise_result_as_success();
+        // Check promise result. The FT metadata is queried alongside the storage if it wasn't already cached.
+        let result = match env::promise_result(0) {
+            PromiseResult::Successful(result) => Some(result),
+            _ => None,
+        };
+        let metadata = if env::promise_results_count() > 1 {
+            match env::promise_result(1) {
+                PromiseResult::Successful(result) => {
+                    near_sdk::serde_json::from_slice::<FTMetadataInfo>(&result).ok()
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
         let pub_keys_len = public_keys.len() as u128;
 
+        // The funder cancelled the drop while the storage check was in flight so roll everything back
+        let state = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("drop not found")
+            .state;
+        if state == DropState::Cancelled {
+            near_sdk::log!(
+                "Drop was cancelled. Refunding funder's balance: {}",
+                yocto_to_near(required_deposit)
+            );
+            self.internal_rollback_pending_drop(drop_id, public_keys, required_deposit);
+            return false;
+        }
+
         // If things went wrong, we need to delete the data and refund the user.
         if result.is_none() {
             // Refund the funder any excess $NEAR
@@ -169,25 +393,7 @@ impl Keypom {
                 "Unsuccessful query to get storage. Refunding funder's balance: {}",
                 yocto_to_near(required_deposit)
             );
-            // Remove the drop
-            let mut drop = self.drop_for_id.remove(&drop_id).expect("drop not found");
-            // Clear the map
-            drop.pks.clear();
-            let owner_id = drop.owner_id.clone();
-
-            // Remove the drop ID from the funder's list
-            self.internal_remove_drop_for_funder(&drop.owner_id, &drop_id);
-
-            // Loop through the keys and remove the public keys' mapping
-            for pk in public_keys {
-                self.drop_id_for_pk.remove(&pk.clone());
-            }
-
-            // Refund the user's balance for the required attached_deposit
-            let mut user_balance = self.user_balances.get(&owner_id).unwrap();
-            user_balance += required_deposit;
-            self.user_balances.insert(&owner_id, &user_balance);
-
+            self.internal_rollback_pending_drop(drop_id, public_keys, required_deposit);
             return false;
         }
 
@@ -197,6 +403,12 @@ impl Keypom {
         {
             let mut drop = self.drop_for_id.get(&drop_id).unwrap();
             let owner_id = drop.owner_id.clone();
+            let (contract_id, balance_per_use) = match &drop.drop_type {
+                DropType::FungibleToken(ft_data) => {
+                    (ft_data.contract_id.clone(), ft_data.balance_per_use)
+                }
+                _ => env::panic_str("drop type isn't FT"),
+            };
 
             // Get the max claims per key. Default to 1 if not specified in the drop config.
             let uses_per_key = drop
@@ -205,60 +417,59 @@ impl Keypom {
                 .and_then(|c| c.uses_per_key)
                 .unwrap_or(1);
 
+            // Cache the token's metadata and charge the funder for the storage used
+            let mut metadata_storage_cost = 0;
+            if let Some(metadata) = metadata {
+                let initial_storage = env::storage_usage();
+                self.ft_metadata.insert(&contract_id, &metadata);
+                metadata_storage_cost = Balance::from(env::storage_usage() - initial_storage)
+                    * env::storage_byte_cost();
+            }
+
+            // Validate the balance per use (or convert it from whole tokens) now that the decimals are known
+            let decimals = self.ft_metadata.get(&contract_id).map(|m| m.decimals);
+            let balance_per_use =
+                match ft_balance_per_use(balance_per_use, &balance_per_use_human, decimals) {
+                    Ok(balance_per_use) => balance_per_use,
+                    Err(e) => {
+                        near_sdk::log!(
+                            "{}. Refunding funder's balance: {}",
+                            e,
+                            yocto_to_near(required_deposit)
+                        );
+                        self.internal_rollback_pending_drop(drop_id, public_keys, required_deposit);
+                        return false;
+                    }
+                };
+
             // Get the current user balance ad ensure that they have the extra $NEAR for covering the FT storage
             let mut cur_user_balance = self.user_balances.get(&owner_id).unwrap();
-            let extra_storage_required = min.0 * uses_per_key as u128 * pub_keys_len;
+            let extra_storage_required =
+                min.0 * uses_per_key as u128 * pub_keys_len + metadata_storage_cost;
 
             // Ensure the user's current balance can cover the extra storage required
             if cur_user_balance < extra_storage_required {
                 near_sdk::log!("Not enough balance to cover FT storage for each key and their claims. Refunding funder's balance: {}", yocto_to_near(required_deposit));
-                // Remove the drop
-                self.drop_for_id.remove(&drop_id).unwrap();
-                // Clear the map
-                drop.pks.clear();
-
-                // Remove the drop ID from the funder's list
-                self.internal_remove_drop_for_funder(&drop.owner_id, &drop_id);
-
-                // Loop through the keys and remove the keys from the drop and remove the drop ID for the key
-                for pk in public_keys {
-                    self.drop_id_for_pk.remove(&pk.clone());
-                }
-
-                // Refund the user's balance for the required attached_deposit
-                cur_user_balance += required_deposit;
-                self.user_balances.insert(&owner_id, &cur_user_balance);
-
+                self.internal_rollback_pending_drop(drop_id, public_keys, required_deposit);
                 return false;
             }
 
             // Update the FT data to include the storage and insert the drop back with the updated FT data
             if let DropType::FungibleToken(mut ft_data) = drop.drop_type {
                 ft_data.ft_storage = min;
+                ft_data.balance_per_use = balance_per_use;
                 drop.drop_type = DropType::FungibleToken(ft_data);
+                drop.state = DropState::Active;
 
                 self.drop_for_id.insert(&drop_id, &drop);
 
                 // Decrement the user's balance by the extra required and insert back into the map
                 cur_user_balance -= extra_storage_required;
-                self.user_balances.insert(&owner_id, &cur_user_balance);
-
-                // Create the keys for the contract
-                let promise = env::promise_batch_create(&env::current_account_id());
+                self.internal_set_user_balance(&owner_id, cur_user_balance);
+                self.internal_check_balance_alert(&owner_id, cur_user_balance);
 
                 // Decide what methods the access keys can call
-                let mut access_key_method_names = ACCESS_KEY_BOTH_METHOD_NAMES;
-                if let Some(perms) = drop.config.clone().and_then(|c| c.claim_permission) {
-                    match perms {
-                        // If we have a config, use the config to determine what methods the access keys can call
-                        ClaimPermissions::Claim => {
-                            access_key_method_names = ACCESS_KEY_CLAIM_METHOD_NAME;
-                        }
-                        ClaimPermissions::CreateAccountAndClaim => {
-                            access_key_method_names = ACCESS_KEY_CREATE_ACCOUNT_METHOD_NAME;
-                        }
-                    }
-                }
+                let access_key_method_names = access_key_method_names(&drop.claim_permission);
 
                 // Get the number of claims per key
                 let num_claims_per_key = drop.config.and_then(|c| c.uses_per_key).unwrap_or(1);
@@ -267,19 +478,12 @@ impl Keypom {
                 // The actual allowance is the base * number of claims per key since each claim can potentially use the max pessimistic GAS.
                 let actual_allowance = calculated_base_allowance * num_claims_per_key as u128;
 
-                // Loop through each public key and create the access keys
-                for pk in public_keys.clone() {
-                    env::promise_batch_action_add_key_with_function_call(
-                        promise,
-                        &pk,
-                        0,
-                        actual_allowance,
-                        &env::current_account_id(),
-                        access_key_method_names,
-                    );
-                }
-
-                env::promise_return(promise);
+                // Create the access keys in as many batches as the GAS allows
+                let keys = public_keys
+                    .iter()
+                    .map(|pk| (pk.clone(), actual_allowance))
+                    .collect();
+                self.internal_add_access_keys(drop_id, keys, &access_key_method_names);
 
                 // Everything went well and we return true
                 return true;
@@ -292,27 +496,99 @@ impl Keypom {
                 "Unsuccessful query to get storage. Refunding funder's balance: {}",
                 yocto_to_near(required_deposit)
             );
-            // Remove the drop
-            let mut drop = self.drop_for_id.remove(&drop_id).expect("drop not found");
-            // Clear the map
-            drop.pks.clear();
-            let owner_id = drop.owner_id.clone();
+            self.internal_rollback_pending_drop(drop_id, public_keys, required_deposit);
+            return false;
+        }
+    }
 
-            // Remove the drop ID from the funder's list
-            self.internal_remove_drop_for_funder(&drop.owner_id, &drop_id);
+    /*
+        Cancel an FT drop whose storage check hasn't resolved yet (i.e the FT contract ID was wrong).
+        If the storage check is still in flight, the drop is marked as cancelled and is rolled back
+        when it resolves. If it never resolved within PENDING_DROP_TIMEOUT_BLOCKS, it's assumed to have
+        failed and the drop is rolled back right away. Either way, the funder's balance is fully refunded.
+    */
+    #[payable]
+    pub fn cancel_pending_drop(&mut self, drop_id: DropId) -> DropState {
+        assert_one_yocto();
 
-            // Loop through the keys and remove the public keys' mapping
-            for pk in public_keys {
-                self.drop_id_for_pk.remove(&pk.clone());
-            }
+        let mut drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        require!(
+            drop.owner_id == env::predecessor_account_id(),
+            "only drop funder can cancel the drop"
+        );
+        require!(
+            drop.registered_uses == 0
+                && !matches!(&drop.drop_type, DropType::FungibleToken(data) if data.dust.0 != 0),
+            "FTs must be refunded before the drop is cancelled"
+        );
 
-            // Refund the user's balance for the required attached_deposit
-            let mut user_balance = self.user_balances.get(&owner_id).unwrap();
-            user_balance += required_deposit;
-            self.user_balances.insert(&owner_id, &user_balance);
+        let (required_deposit, since_block) = match drop.state {
+            DropState::Pending {
+                required_deposit,
+                since_block,
+            } => (required_deposit.0, since_block),
+            _ => env::panic_str("only pending drops can be cancelled"),
+        };
 
-            return false;
+        // The storage check should have long resolved so it must have failed before it could roll back
+        if env::block_height() >= since_block + PENDING_DROP_TIMEOUT_BLOCKS {
+            near_sdk::log!(
+                "Storage check for drop {} never resolved. Refunding funder's balance: {}",
+                drop_id,
+                yocto_to_near(required_deposit)
+            );
+            let public_keys = drop.pks.keys().collect();
+            self.internal_rollback_pending_drop(drop_id, public_keys, required_deposit);
+            return DropState::Failed;
         }
+
+        near_sdk::log!(
+            "Drop {} cancelled. It will be rolled back once the storage check resolves",
+            drop_id
+        );
+        drop.state = DropState::Cancelled;
+        self.drop_for_id.insert(&drop_id, &drop);
+        DropState::Cancelled
+    }
+
+    /// Remove a drop that never became active along with its keys and refund everything that was charged to the funder
+    pub(crate) fn internal_rollback_pending_drop(
+        &mut self,
+        drop_id: DropId,
+        public_keys: Vec<PublicKey>,
+        required_deposit: Balance,
+    ) {
+        // Remove the drop
+        let mut drop = self.drop_for_id.remove(&drop_id).expect("drop not found");
+        for key_info in drop.pks.values() {
+            self.internal_remove_passwords_per_use(drop_id, &drop.config, &key_info);
+        }
+        // Clear the map
+        self.internal_clear_key_infos(&mut drop.pks, drop.deposit_per_use);
+        // Clear the key ID index
+        for key_id in 0..drop.next_key_id {
+            drop.pk_for_key_id.remove(&key_id);
+        }
+        drop.allowed_hashes.clear();
+        drop.claims_per_account.clear();
+        self.drops_with_passwords_per_use.remove(&drop_id);
+        let owner_id = drop.owner_id.clone();
+
+        // Remove the drop ID from the funder's list. It was never claimed so there are no stats to emit.
+        self.internal_remove_drop_for_funder(&drop.owner_id, &drop_id);
+        self.internal_remove_drop_from_asset_index(&drop.drop_type, drop_id);
+        self.drop_stats.remove(&drop_id);
+        self.use_histograms.remove(&drop_id);
+
+        // Loop through the keys and remove the public keys' mapping
+        for pk in public_keys {
+            self.drop_id_for_pk.remove(&pk);
+        }
+
+        // Refund the user's balance for the required attached_deposit
+        let mut user_balance = self.user_balances.get(&owner_id).unwrap();
+        user_balance += required_deposit;
+        self.internal_set_user_balance(&owner_id, user_balance);
     }
 
     // Internal method_name for transfer FTs. Whether the claim was successful or not is passed in
@@ -328,6 +604,9 @@ impl Keypom {
         */
         // Only send the fungible tokens to the new account if the claim was successful. We return the FTs if it wasn't successful in the else case.
         if claim_succeeded {
+            // The tokens stay held until the transfer resolves. Balance checks wait for it in the meantime.
+            self.internal_begin_ft_claim(&ft_data.contract_id);
+
             // Create a new batch promise to pay storage and transfer FTs to the new account ID
             let batch_ft_promise_id = env::promise_batch_create(&ft_data.contract_id);
 
@@ -370,6 +649,8 @@ impl Keypom {
                 GasWeight(3)
             );
         } else {
+            self.internal_release_ft(&ft_data.contract_id, ft_data.balance_per_use.0);
+
             // Create a new batch promise to pay storage and refund the FTs to the original sender
             let batch_ft_promise_id = env::promise_batch_create(&ft_data.contract_id);
 
@@ -0,0 +1,206 @@
+use crate::*;
+
+/// A transfer to a drop that verifies what it received, waiting on the token contract to report this contract's balance
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FTBalanceCheck {
+    pub drop_id: DropId,
+    // Amount the token contract said was sent
+    pub amount: U128,
+}
+
+impl Keypom {
+    /*
+        Start verifying a transfer to a drop with `verify_received_balance` set. The amount is held as pending on
+        the drop and no uses are registered until the token contract reports this contract's balance. Only one
+        check can be in flight per token contract since the balance is shared by every drop using it. Checks are
+        also rejected while claim transfers of the token haven't resolved since their tokens are still held but
+        may already have left the balance. The entry's storage is held by the contract until the check resolves.
+    */
+    pub(crate) fn internal_begin_ft_balance_check(
+        &mut self,
+        contract_id: AccountId,
+        drop_id: DropId,
+        amount: U128,
+    ) -> Promise {
+        require!(
+            self.ft_balance_checks.get(&contract_id).is_none(),
+            "a balance check for this token is already in flight. Try again once it resolves"
+        );
+        require!(
+            self.ft_claims_in_flight.get(&contract_id).is_none(),
+            "claim transfers for this token are in flight. Try again once they resolve"
+        );
+        self.ft_balance_checks
+            .insert(&contract_id, &FTBalanceCheck { drop_id, amount });
+        near_sdk::log!(
+            "Verifying how many of the {} tokens sent to drop {} were received before registering uses",
+            amount.0,
+            drop_id
+        );
+
+        ext_ft_contract::ext(contract_id.clone())
+            // Call ft balance of with exactly this amount of GAS. No unspent GAS will be added on top.
+            .with_static_gas(GAS_FOR_FT_BALANCE_OF)
+            .with_unused_gas_weight(0)
+            .ft_balance_of(env::current_account_id())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MIN_GAS_FOR_RESOLVE_RECEIVED_BALANCE)
+                    .resolve_received_balance(contract_id),
+            )
+    }
+
+    /*
+        Record tokens of an FT contract that are now held for drops. Every token that comes in for a drop must be
+        recorded here and every token that leaves released below so that verified transfers can tell what they
+        actually added to the balance.
+    */
+    pub(crate) fn internal_hold_ft(&mut self, contract_id: &AccountId, amount: Balance) {
+        if amount == 0 {
+            return;
+        }
+        let held = self.ft_held.get(contract_id).unwrap_or(0) + amount;
+        self.ft_held.insert(contract_id, &held);
+    }
+
+    /// Release tokens that were sent out or are no longer held for a drop. The entry is removed once nothing is held.
+    pub(crate) fn internal_release_ft(&mut self, contract_id: &AccountId, amount: Balance) {
+        if amount == 0 {
+            return;
+        }
+        let held = self
+            .ft_held
+            .get(contract_id)
+            .unwrap_or(0)
+            .saturating_sub(amount);
+        if held == 0 {
+            self.ft_held.remove(contract_id);
+        } else {
+            self.ft_held.insert(contract_id, &held);
+        }
+    }
+
+    /// Count a claim transfer of an FT contract that was scheduled and hasn't resolved yet
+    pub(crate) fn internal_begin_ft_claim(&mut self, contract_id: &AccountId) {
+        let in_flight = self.ft_claims_in_flight.get(contract_id).unwrap_or(0) + 1;
+        self.ft_claims_in_flight.insert(contract_id, &in_flight);
+    }
+
+    /// Stop counting a claim transfer once it resolved. The entry is removed once none are in flight.
+    pub(crate) fn internal_end_ft_claim(&mut self, contract_id: &AccountId) {
+        let in_flight = self
+            .ft_claims_in_flight
+            .get(contract_id)
+            .unwrap_or(0)
+            .saturating_sub(1);
+        if in_flight == 0 {
+            self.ft_claims_in_flight.remove(contract_id);
+        } else {
+            self.ft_claims_in_flight.insert(contract_id, &in_flight);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Keypom {
+    #[private]
+    /*
+        Self callback registers uses for a verified transfer based on what it actually added to the balance: the
+        balance reported minus the tokens already held for drops, capped at the amount sent. Whatever doesn't add
+        up to a full use is handled like any other transfer. Returns the amount the token contract should refund
+        to the sender. If the balance couldn't be read, nothing is registered and the full amount is refunded. If
+        the drop was deleted in the meantime, the verified amount is refunded.
+    */
+    pub fn resolve_received_balance(&mut self, contract_id: AccountId) -> U128 {
+        let FTBalanceCheck { drop_id, amount } = self
+            .ft_balance_checks
+            .remove(&contract_id)
+            .expect("no balance check in flight for token");
+
+        let mut drop = self.drop_for_id.get(&drop_id);
+        if let Some(DropType::FungibleToken(ft_data)) = drop.as_mut().map(|d| &mut d.drop_type) {
+            ft_data.pending = U128(0);
+        }
+
+        let balance = match promise_result_as_success()
+            .and_then(|result| near_sdk::serde_json::from_slice::<U128>(&result).ok())
+        {
+            Some(balance) => balance.0,
+            None => {
+                near_sdk::log!(
+                    "Unsuccessful query to get the balance. Refunding the {} tokens sent",
+                    amount.0
+                );
+                if let Some(drop) = drop {
+                    self.drop_for_id.insert(&drop_id, &drop);
+                }
+                return amount;
+            }
+        };
+        let held = self.ft_held.get(&contract_id).unwrap_or(0);
+        let received = balance.saturating_sub(held).min(amount.0);
+        if received < amount.0 {
+            near_sdk::log!(
+                "Only {} of the {} tokens sent were received",
+                received,
+                amount.0
+            );
+        }
+
+        let mut drop = match drop {
+            Some(drop) if drop.state == DropState::Active => drop,
+            _ => {
+                near_sdk::log!(
+                    "Drop {} is no longer active. Refunding the {} tokens received",
+                    drop_id,
+                    received
+                );
+                if let Some(drop) = drop {
+                    self.drop_for_id.insert(&drop_id, &drop);
+                }
+                return U128(received);
+            }
+        };
+        let ft_data = match &mut drop.drop_type {
+            DropType::FungibleToken(ft_data) => ft_data,
+            _ => env::panic_str("drop type isn't FT"),
+        };
+
+        // Dust from earlier transfers counts towards this one if the drop accumulates it
+        let available = if ft_data.accumulate_dust {
+            received + ft_data.dust.0
+        } else {
+            received
+        };
+        let claims_to_register = (available / ft_data.balance_per_use.0) as u64;
+        let remainder = available % ft_data.balance_per_use.0;
+        let to_return = if ft_data.accumulate_dust {
+            ft_data.dust = U128(remainder);
+            0
+        } else {
+            remainder
+        };
+        drop.registered_uses += claims_to_register;
+        near_sdk::log!(
+            "New claims registered {}. Dust returned {}. Dust kept {}",
+            claims_to_register,
+            to_return,
+            remainder - to_return
+        );
+
+        self.drop_for_id.insert(&drop_id, &drop);
+        self.internal_hold_ft(&contract_id, received - to_return);
+        U128(to_return)
+    }
+
+    /// Returns the balance check in flight for an FT contract, if there is one
+    pub fn get_ft_balance_check(&self, contract_id: AccountId) -> Option<FTBalanceCheck> {
+        self.ft_balance_checks.get(&contract_id)
+    }
+
+    /// Returns how many of an FT contract's tokens are held for drops
+    pub fn get_ft_held(&self, contract_id: AccountId) -> U128 {
+        U128(self.ft_held.get(&contract_id).unwrap_or(0))
+    }
+}
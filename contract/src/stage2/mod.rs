@@ -1,5 +1,14 @@
+pub mod asset_index;
 pub mod ft;
+pub mod ft_verification;
 pub mod nft;
+pub mod registration;
+pub mod swap;
+pub mod wrap;
 
+pub(crate) use asset_index::*;
 pub use ft::*;
+pub use ft_verification::*;
 pub use nft::*;
+pub use registration::*;
+pub use swap::*;
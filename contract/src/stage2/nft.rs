@@ -10,6 +10,108 @@ pub struct NFTData {
     pub longest_token_id: String,
     pub storage_for_longest: Balance,
     pub token_ids: Vector<String>,
+    // Number of token IDs handed over by each claim
+    pub tokens_per_use: u64,
+}
+
+/*
+    GAS attached to each claim of an NFT drop. Every token of a pack is transferred separately and may have to be
+    sent back to the sender, so the GAS grows with the pack. Packs too big for a single claim are rejected.
+*/
+pub(crate) fn nft_gas_to_attach(
+    nft_data: &NFTDataConfig,
+    gas_to_attach: Gas,
+) -> Result<Gas, String> {
+    let tokens_per_use = nft_data.tokens_per_use.unwrap_or(1);
+    if tokens_per_use == 0 {
+        return Err("tokens_per_use must be more than 0".to_string());
+    }
+    let gas = GAS_PER_EXTRA_PACK_TOKEN
+        .0
+        .saturating_mul(tokens_per_use - 1)
+        .saturating_add(gas_to_attach.0);
+    if gas > MAX_GAS_FOR_CLAIM.0 {
+        return Err(format!(
+            "a pack of {} tokens needs {} TGas per claim but a claim can attach at most {} TGas",
+            tokens_per_use,
+            gas / 1_000_000_000_000,
+            MAX_GAS_FOR_CLAIM.0 / 1_000_000_000_000
+        ));
+    }
+    Ok(Gas(gas))
+}
+
+impl NFTData {
+    /// Bytes prepaid for each use to store its token IDs if they were all as long as the longest token ID
+    pub(crate) fn storage_per_use(&self) -> u128 {
+        self.storage_for_longest * self.tokens_per_use as u128
+    }
+
+    /// Token IDs that don't add up to a full use. They're sent back along with the refunded uses.
+    pub(crate) fn leftover_tokens(&self) -> u64 {
+        self.token_ids.len() % self.tokens_per_use
+    }
+
+    /// Whether there are enough token IDs left for a claim to hand over a full pack
+    pub(crate) fn has_full_pack(&self) -> bool {
+        self.token_ids.len() >= self.tokens_per_use
+    }
+
+    /// Add token IDs to the drop and return how many new uses they completed
+    pub(crate) fn push_tokens(&mut self, token_ids: &[String]) -> u64 {
+        let uses_before = self.token_ids.len() / self.tokens_per_use;
+        for token_id in token_ids {
+            self.token_ids.push(token_id);
+        }
+        self.token_ids.len() / self.tokens_per_use - uses_before
+    }
+}
+
+impl Keypom {
+    /// Push a token ID sent by the NFT contract to the drop in `msg` once it's checked against the drop's NFT data
+    fn internal_register_nft(
+        &mut self,
+        contract_id: &AccountId,
+        sender_id: &AccountId,
+        token_id: String,
+        msg: &str,
+    ) -> Result<(), String> {
+        let drop_id: DropId = msg
+            .parse()
+            .map_err(|_| "msg must be a drop ID".to_string())?;
+        let mut drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .ok_or_else(|| "No drop found for ID".to_string())?;
+        if drop.state == DropState::Cancelled {
+            return Err("drop has been cancelled".to_string());
+        }
+        if drop.state == DropState::Deleting {
+            return Err("drop is being deleted".to_string());
+        }
+
+        let nft_data = match &mut drop.drop_type {
+            DropType::NonFungibleToken(nft_data) => nft_data,
+            _ => return Err("drop type isn't NFT".to_string()),
+        };
+        if nft_data.contract_id != *contract_id {
+            return Err("NFT contract doesn't match the drop's contract".to_string());
+        }
+        if nft_data.sender_id != *sender_id {
+            return Err("sender doesn't match the drop's sender".to_string());
+        }
+        if token_id.len() > nft_data.longest_token_id.len() {
+            return Err("token ID must be less than largest token specified".to_string());
+        }
+
+        // Push the token ID to the back of the vector. A use is registered once it completes a full pack.
+        drop.registered_uses += nft_data.push_tokens(&[token_id]);
+        near_sdk::log!("drop.registered_uses {}", drop.registered_uses);
+
+        // Insert the drop with the updated data
+        self.drop_for_id.insert(&drop_id, &drop);
+        Ok(())
+    }
 }
 
 /// Keep track of nft data. This is passed in by the user
@@ -19,52 +121,89 @@ pub struct NFTDataConfig {
     pub sender_id: AccountId,
     pub contract_id: AccountId,
     pub longest_token_id: String,
+    // Number of token IDs handed over by each claim (i.e a pack of cards). Defaults to 1.
+    pub tokens_per_use: Option<u64>,
+}
+
+impl DropBehavior for NFTData {
+    fn type_name(&self) -> &'static str {
+        "nft"
+    }
+
+    fn registers_uses_with_keys(&self) -> bool {
+        false
+    }
+
+    /// Every token ID sent to the drop is stored until it's claimed
+    fn asset_storage_per_use(&self, storage_byte_cost: Balance) -> Balance {
+        self.storage_per_use() * storage_byte_cost
+    }
+
+    fn asset_contract_id(&self) -> Option<&AccountId> {
+        Some(&self.contract_id)
+    }
+
+    /// Measure the storage for a single longest token ID by adding one and taking it back out
+    fn on_create(&mut self) -> u128 {
+        let initial_storage = env::storage_usage();
+        self.token_ids.push(&self.longest_token_id);
+        self.storage_for_longest = Balance::from(env::storage_usage() - initial_storage);
+        self.token_ids.pop();
+        self.storage_per_use()
+    }
+
+    /// There's always a full pack left since claims without one are rejected before the assets are taken
+    fn on_claim(&mut self, _use_index: u64, storage_byte_cost: Balance) -> ClaimedAssets {
+        ClaimedAssets {
+            token_ids: Some(
+                (0..self.tokens_per_use)
+                    .filter_map(|_| self.token_ids.pop())
+                    .collect(),
+            ),
+            storage_for_longest: Some(self.asset_storage_per_use(storage_byte_cost)),
+            should_continue: true,
+        }
+    }
+
+    /// Keys only reserve the storage for a single pack regardless of how many uses they have left
+    fn on_refund(
+        &self,
+        _uses_per_key: u64,
+        _remaining_uses: u64,
+        storage_byte_cost: Balance,
+    ) -> (Balance, u64) {
+        (self.asset_storage_per_use(storage_byte_cost), 0)
+    }
 }
 
 #[near_bindgen]
 impl Keypom {
+    /*
+        Register an NFT sent with `nft_transfer_call` to the drop whose ID is passed in the `msg`. The token is
+        returned to its owner (by returning true) if the drop can't take it rather than panicking so that the
+        NFT contract always has a clear answer.
+    */
     pub fn nft_on_transfer(
         &mut self,
-        token_id: String,
         sender_id: AccountId,
-        msg: U128,
+        previous_owner_id: AccountId,
+        token_id: String,
+        msg: String,
     ) -> PromiseOrValue<bool> {
         let contract_id = env::predecessor_account_id();
 
-        let mut drop = self.drop_for_id.get(&msg.0).expect("No drop found for ID");
-        if let DropType::NonFungibleToken(mut nft_data) = drop.drop_type {
-            let mut token_ids = nft_data.token_ids;
-
-            require!(
-                nft_data.sender_id == sender_id && nft_data.contract_id == contract_id,
-                "NFT data must match what was sent"
-            );
-            require!(
-                token_id.len() <= nft_data.longest_token_id.len(),
-                "token ID must be less than largest token specified"
-            );
-
-            // Push the token ID to the back of the vector
-            token_ids.push(&token_id);
-
-            // Re-insert the token IDs into the NFT Data struct
-            nft_data.token_ids = token_ids;
-
-            // Increment the claims registered
-            drop.registered_uses += 1;
-            near_sdk::log!("drop.registered_uses {}", drop.registered_uses);
-
-            // Add the nft data back with the updated set
-            drop.drop_type = DropType::NonFungibleToken(nft_data);
-
-            // Insert the drop with the updated data
-            self.drop_for_id.insert(&msg.0, &drop);
-        } else {
-            env::panic_str("drop type isn't NFT");
+        match self.internal_register_nft(&contract_id, &sender_id, token_id, &msg) {
+            Ok(()) => PromiseOrValue::Value(false),
+            Err(e) => {
+                near_sdk::log!(
+                    "Returning NFT from {} to {}: {}",
+                    contract_id,
+                    previous_owner_id,
+                    e
+                );
+                PromiseOrValue::Value(true)
+            }
         }
-
-        // Everything went well and we don't need to return the token.
-        PromiseOrValue::Value(false)
     }
 
     #[private]
@@ -80,16 +219,12 @@ impl Keypom {
         );
         let transfer_succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
 
-        // If not successful, the length of the token IDs needs to be added back to the drop.
+        // If not successful, the token IDs and the uses they make up need to be added back to the drop.
         if !transfer_succeeded {
             let mut drop = self.drop_for_id.get(&drop_id.0).unwrap();
-            drop.registered_uses += token_ids.len() as u64;
 
             if let DropType::NonFungibleToken(nft_data) = &mut drop.drop_type {
-                // Loop through and add token IDs back into the vector
-                for token in &token_ids {
-                    nft_data.token_ids.push(token);
-                }
+                drop.registered_uses += nft_data.push_tokens(&token_ids);
             };
             self.drop_for_id.insert(&drop_id.0, &drop);
 
@@ -148,15 +283,101 @@ impl Keypom {
         transfer_succeeded
     }
 
-    // Internal method_name for transfer NFTs. Whether the claim was successful or not is passed in
+    #[private]
+    /*
+        Self callback checks which tokens of a pack were transferred to the new account. Tokens whose transfer failed
+        are put back into the drop so that they can be claimed again and the funder pays for their storage again. If
+        the drop is gone or the funder can't cover the storage, they're sent back to the original sender instead.
+    */
+    pub fn nft_resolve_pack_transfer(
+        &mut self,
+        drop_id: U128,
+        token_ids: Vec<String>,
+        token_sender: AccountId,
+        token_contract: AccountId,
+    ) -> bool {
+        let failed: Vec<String> = token_ids
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                !matches!(env::promise_result(*i as u64), PromiseResult::Successful(_))
+            })
+            .map(|(_, token_id)| token_id)
+            .collect();
+        if failed.is_empty() {
+            return true;
+        }
+
+        let drop = self
+            .drop_for_id
+            .get(&drop_id.0)
+            .filter(|drop| drop.state == DropState::Active);
+        if let Some(mut drop) = drop {
+            if let DropType::NonFungibleToken(data) = &mut drop.drop_type {
+                let storage_cost =
+                    data.storage_for_longest * drop.storage_byte_cost * failed.len() as u128;
+                let balance = self.user_balances.get(&drop.owner_id).unwrap_or(0);
+                if balance >= storage_cost {
+                    drop.registered_uses += data.push_tokens(&failed);
+                    self.internal_set_user_balance(&drop.owner_id, balance - storage_cost);
+                    self.internal_check_balance_alert(&drop.owner_id, balance - storage_cost);
+                    near_sdk::log!(
+                        "{} transfers of the pack failed. Token IDs put back into the drop. Charged {} for their storage",
+                        failed.len(),
+                        yocto_to_near(storage_cost)
+                    );
+                    self.drop_for_id.insert(&drop_id.0, &drop);
+                    return false;
+                }
+            }
+        }
+
+        near_sdk::log!(
+            "{} transfers of the pack failed. Sending the NFTs to the original sender.",
+            failed.len()
+        );
+        for token_id in failed {
+            ext_nft_contract::ext(token_contract.clone())
+                // Call nft transfer with the min GAS and 1 yoctoNEAR. all unspent GAS will be added on top
+                .with_static_gas(MIN_GAS_FOR_SIMPLE_NFT_TRANSFER)
+                .with_attached_deposit(1)
+                .nft_transfer(
+                    token_sender.clone(),
+                    token_id,
+                    None,
+                    Some("Linkdropped NFT Refund".to_string()),
+                );
+        }
+        false
+    }
+
+    /*
+        Internal method_name for transfer NFTs. Whether the claim was successful or not is passed in. A single token
+        is sent back to the sender if its transfer fails. The tokens of a pack are transferred in parallel and the
+        ones that fail are handled by `nft_resolve_pack_transfer`.
+    */
     pub(crate) fn internal_nft_transfer(
         &mut self,
         claim_succeeded: bool,
+        drop_id: DropId,
         contract_id: AccountId,
-        token_id: String,
+        mut token_ids: Vec<String>,
         sender_id: AccountId,
         account_id: AccountId,
     ) {
+        if token_ids.len() > 1 {
+            self.internal_nft_pack_transfer(
+                claim_succeeded,
+                drop_id,
+                contract_id,
+                token_ids,
+                sender_id,
+                account_id,
+            );
+            return;
+        }
+        let token_id = token_ids.pop().expect("no token ID found");
+
         /*
             Non Fungible Tokens
         */
@@ -195,3 +416,50 @@ impl Keypom {
         }
     }
 }
+
+impl Keypom {
+    /// Transfer every token of a pack. If the claim failed, they're all sent back to the original sender.
+    fn internal_nft_pack_transfer(
+        &mut self,
+        claim_succeeded: bool,
+        drop_id: DropId,
+        contract_id: AccountId,
+        token_ids: Vec<String>,
+        sender_id: AccountId,
+        account_id: AccountId,
+    ) {
+        let receiver_id = if claim_succeeded {
+            account_id
+        } else {
+            sender_id.clone()
+        };
+        let transfers = token_ids
+            .iter()
+            .map(|token_id| {
+                ext_nft_contract::ext(contract_id.clone())
+                    // Call nft transfer with the min GAS and 1 yoctoNEAR. unspent GAS is split between the transfers
+                    .with_static_gas(MIN_GAS_FOR_SIMPLE_NFT_TRANSFER)
+                    .with_attached_deposit(1)
+                    .nft_transfer(
+                        receiver_id.clone(),
+                        token_id.clone(),
+                        None,
+                        Some("Linkdropped NFT".to_string()),
+                    )
+            })
+            .reduce(|transfers, transfer| transfers.and(transfer))
+            .expect("no token IDs found");
+        if !claim_succeeded {
+            return;
+        }
+
+        // Enough GAS is kept to send every token back to the sender if their transfers fail
+        let resolve_gas = MIN_GAS_FOR_RESOLVE_TRANSFER.0
+            + MIN_GAS_FOR_SIMPLE_NFT_TRANSFER.0 * (token_ids.len() as u64 - 1);
+        transfers.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(Gas(resolve_gas))
+                .nft_resolve_pack_transfer(U128(drop_id), token_ids, sender_id, contract_id),
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::*;
+
+/// Minimum amount of time between refreshes of a drop's FT registration
+pub(crate) const FT_REFRESH_INTERVAL: u64 = 86_400_000_000_000; // 1 day
+
+// Returned from the storage balance of cross contract call on the FT contract. Only the total is needed.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    pub total: U128,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Check that this contract is still registered on an FT drop's token contract. Some contracts unregister
+        accounts with no balance so the tokens for future claims couldn't be received. If the registration is
+        gone, it's paid for again from the funder's balance. Can be called by anyone at most once per interval.
+    */
+    pub fn refresh_ft_registration(&mut self, drop_id: DropId) -> Promise {
+        let drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        let ft_data = match drop.drop_type {
+            DropType::FungibleToken(ft_data) => ft_data,
+            _ => env::panic_str("drop type isn't FT"),
+        };
+        require!(
+            drop.state == DropState::Active,
+            "only active drops can be refreshed"
+        );
+
+        let current_timestamp = env::block_timestamp();
+        if let Some(last_refresh) = self.last_ft_refresh.get(&drop_id) {
+            require!(
+                current_timestamp >= last_refresh + FT_REFRESH_INTERVAL,
+                "drop was refreshed too recently"
+            );
+        }
+        self.last_ft_refresh.insert(&drop_id, &current_timestamp);
+
+        ext_ft_contract::ext(ft_data.contract_id)
+            // Call storage balance of with exactly this amount of GAS. No unspent GAS will be added on top.
+            .with_static_gas(GAS_FOR_STORAGE_BALANCE_OF)
+            .with_unused_gas_weight(0)
+            .storage_balance_of(env::current_account_id())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MIN_GAS_FOR_RESOLVE_FT_REGISTRATION)
+                    .resolve_ft_registration(drop_id),
+            )
+    }
+
+    #[private]
+    /// Self callback registers this contract on the FT contract again if the registration is gone. Returns whether it was topped up.
+    pub fn resolve_ft_registration(&mut self, drop_id: DropId) -> bool {
+        // If the query failed, there's no way of knowing whether the registration is still there
+        let storage_balance = promise_result_as_success().and_then(|result| {
+            near_sdk::serde_json::from_slice::<Option<StorageBalance>>(&result).ok()
+        });
+        match storage_balance {
+            Some(Some(StorageBalance { total })) => {
+                near_sdk::log!(
+                    "Registration for drop {} is still active with {} deposited",
+                    drop_id,
+                    yocto_to_near(total.0)
+                );
+                return false;
+            }
+            Some(None) => {}
+            None => {
+                near_sdk::log!("Unsuccessful query to get the storage balance");
+                return false;
+            }
+        }
+
+        // The drop could have been deleted while the query was in flight
+        let drop = match self.drop_for_id.get(&drop_id) {
+            Some(drop) => drop,
+            None => return false,
+        };
+        let ft_data = match drop.drop_type {
+            DropType::FungibleToken(ft_data) => ft_data,
+            _ => return false,
+        };
+
+        // The funder pays for the registration again
+        let mut cur_balance = self.user_balances.get(&drop.owner_id).unwrap_or(0);
+        if cur_balance < ft_data.ft_storage.0 {
+            near_sdk::log!(
+                "Funder doesn't have {} to register on {} again",
+                yocto_to_near(ft_data.ft_storage.0),
+                ft_data.contract_id
+            );
+            return false;
+        }
+        cur_balance -= ft_data.ft_storage.0;
+        self.internal_set_user_balance(&drop.owner_id, cur_balance);
+        self.internal_check_balance_alert(&drop.owner_id, cur_balance);
+
+        near_sdk::log!(
+            "Registering on {} again for drop {}. Charged funder {}",
+            ft_data.contract_id,
+            drop_id,
+            yocto_to_near(ft_data.ft_storage.0)
+        );
+        Promise::new(ft_data.contract_id).function_call(
+            "storage_deposit".to_string(),
+            json!({ "account_id": env::current_account_id(), "registration_only": true })
+                .to_string()
+                .into_bytes(),
+            ft_data.ft_storage.0,
+            MIN_GAS_FOR_STORAGE_DEPOSIT,
+        );
+        true
+    }
+}
@@ -0,0 +1,191 @@
+use crate::*;
+
+/// Owner curated route for swapping $NEAR into a payout token that claimers can choose
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SwapRoute {
+    // Exchange that the swap goes through (i.e v2.ref-finance.near)
+    pub exchange: AccountId,
+    // Wrap contract whose tokens are the input of the pool (i.e wrap.near)
+    pub wrap_contract: AccountId,
+    // Pool on the exchange between the wrapped $NEAR and the payout token
+    pub pool_id: u64,
+    // Minimum amount of the payout token (in its smallest units) to receive for each 1 $NEAR swapped
+    pub min_amount_out_per_near: U128,
+}
+
+/// Minimum amount of the payout token to accept when swapping the balance. Never rounds in the claimer's disfavor.
+pub(crate) fn min_amount_out(balance: Balance, min_amount_out_per_near: Balance) -> Balance {
+    match balance.checked_mul(min_amount_out_per_near) {
+        Some(product) => product / ONE_NEAR,
+        // Divide first for very large amounts. The remainder is small enough to be rounded up.
+        None => (balance / ONE_NEAR + 1).saturating_mul(min_amount_out_per_near),
+    }
+}
+
+#[near_bindgen]
+impl Keypom {
+    /// Register the route used to swap $NEAR into a payout token. Returns whether an existing route was replaced.
+    #[payable]
+    pub fn register_swap_route(&mut self, token_id: AccountId, route: SwapRoute) -> bool {
+        self.assert_owner_and_record("register_swap_route");
+        self.swap_routes.insert(&token_id, &route).is_some()
+    }
+
+    /// Remove the route for a payout token. Claims that choose it afterwards receive native $NEAR instead.
+    #[payable]
+    pub fn unregister_swap_route(&mut self, token_id: AccountId) -> bool {
+        self.assert_owner_and_record("unregister_swap_route");
+        self.swap_routes.remove(&token_id).is_some()
+    }
+
+    /// Panic if a drop's alternative payouts are invalid. They're only supported for simple drops sending native $NEAR.
+    pub(crate) fn internal_assert_alternative_payouts(
+        &self,
+        config: &Option<DropConfig>,
+        is_simple: bool,
+    ) {
+        self.internal_check_alternative_payouts(config, is_simple)
+            .unwrap_or_else(|e| env::panic_str(&e));
+    }
+
+    /// Same as `internal_assert_alternative_payouts` but returns the error instead of panicking
+    pub(crate) fn internal_check_alternative_payouts(
+        &self,
+        config: &Option<DropConfig>,
+        is_simple: bool,
+    ) -> Result<(), String> {
+        let payouts = match config.as_ref().and_then(|c| c.alternative_payouts.as_ref()) {
+            Some(payouts) => payouts,
+            None => return Ok(()),
+        };
+
+        if !is_simple {
+            return Err("alternative payouts are only supported for simple drops".to_string());
+        }
+        if payouts.is_empty() {
+            return Err("alternative payouts cannot be empty".to_string());
+        }
+        if config
+            .as_ref()
+            .and_then(|c| c.wrap_contract.as_ref())
+            .is_some()
+        {
+            return Err("cannot specify both alternative payouts and a wrap contract".to_string());
+        }
+        match payouts
+            .iter()
+            .find(|token_id| self.swap_routes.get(token_id).is_none())
+        {
+            Some(token_id) => Err(format!("no swap route registered for {}", token_id)),
+            None => Ok(()),
+        }
+    }
+
+    /*
+        Register the claimer on the payout token, then wrap the balance and send it to the exchange to be swapped.
+        The exchange sends the swapped tokens straight to the claimer. If the swap doesn't return at least the
+        route's minimum amount, the exchange refunds the wrapped tokens and the callback falls back to native $NEAR.
+    */
+    pub(crate) fn internal_swap_and_transfer(
+        &self,
+        token_id: AccountId,
+        route: SwapRoute,
+        account_id: AccountId,
+        balance: Balance,
+    ) -> Promise {
+        let swap_msg = json!({
+            "actions": [{
+                "pool_id": route.pool_id,
+                "token_in": route.wrap_contract,
+                "token_out": token_id,
+                "min_amount_out": U128(min_amount_out(balance, route.min_amount_out_per_near.0)),
+            }],
+            "swap_out_recipient": account_id,
+        });
+
+        // If the claimer is already registered, the deposit is refunded to this contract.
+        Promise::new(token_id)
+            .function_call(
+                "storage_deposit".to_string(),
+                json!({ "account_id": account_id, "registration_only": true })
+                    .to_string()
+                    .into_bytes(),
+                SWAP_STORAGE_COST - 1,
+                MIN_GAS_FOR_STORAGE_DEPOSIT,
+            )
+            .then(
+                Promise::new(route.wrap_contract.clone())
+                    .function_call(
+                        "near_deposit".to_string(),
+                        json!({}).to_string().into_bytes(),
+                        balance,
+                        MIN_GAS_FOR_NEAR_DEPOSIT,
+                    )
+                    .function_call(
+                        "ft_transfer_call".to_string(),
+                        json!({ "receiver_id": route.exchange, "amount": U128(balance), "msg": swap_msg.to_string() })
+                            .to_string()
+                            .into_bytes(),
+                        1,
+                        GAS_FOR_SWAP_TRANSFER_CALL,
+                    ),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MIN_GAS_FOR_ON_SWAP_PAYOUT)
+                    .on_swap_payout(account_id, route.wrap_contract, U128(balance)),
+            )
+    }
+
+    #[private]
+    /// Self callback checks if the whole balance was swapped. If not, send whatever wasn't swapped as native $NEAR instead
+    pub fn on_swap_payout(
+        &mut self,
+        account_id: AccountId,
+        wrap_contract: AccountId,
+        balance: U128,
+    ) -> PromiseOrValue<bool> {
+        // ft_transfer_call returns how many of the wrapped tokens the exchange used
+        let amount_swapped = match env::promise_result(0) {
+            PromiseResult::Successful(result) => {
+                near_sdk::serde_json::from_slice::<U128>(&result).map_or(0, |used| used.0)
+            }
+            // The whole batch was rolled back so the balance was refunded as native $NEAR
+            _ => {
+                near_sdk::log!(
+                    "Swap failed. Sending {} $NEAR to {} instead.",
+                    yocto_to_near(balance.0),
+                    account_id
+                );
+                return match self.internal_transfer_or_defer(account_id, balance.0) {
+                    Some(promise) => PromiseOrValue::Promise(promise),
+                    None => PromiseOrValue::Value(true),
+                };
+            }
+        };
+        if amount_swapped >= balance.0 {
+            return PromiseOrValue::Value(true);
+        }
+
+        // The exchange refunded the wrapped tokens that weren't swapped. Unwrap them and send the $NEAR to the claimer.
+        let amount_refunded = balance.0 - amount_swapped;
+        near_sdk::log!(
+            "Swap failed. Unwrapping and sending {} $NEAR to {} instead.",
+            yocto_to_near(amount_refunded),
+            account_id
+        );
+        PromiseOrValue::Promise(
+            Promise::new(wrap_contract)
+                .function_call(
+                    "near_withdraw".to_string(),
+                    json!({ "amount": U128(amount_refunded) })
+                        .to_string()
+                        .into_bytes(),
+                    1,
+                    MIN_GAS_FOR_NEAR_WITHDRAW,
+                )
+                .then(Promise::new(account_id).transfer(amount_refunded)),
+        )
+    }
+}
@@ -0,0 +1,79 @@
+use crate::*;
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Deposit the claim balance into the wrap contract and send the wrapped tokens to the claimer.
+        Everything happens in a single batch so if any step fails, the whole batch is rolled back and
+        the balance is refunded to this contract. The callback then falls back to sending native $NEAR.
+    */
+    pub(crate) fn internal_wrap_and_transfer(
+        &self,
+        wrap_contract: AccountId,
+        account_id: AccountId,
+        funder_id: AccountId,
+        drop_id: DropId,
+        balance: Balance,
+    ) -> Promise {
+        Promise::new(wrap_contract)
+            .function_call(
+                "near_deposit".to_string(),
+                json!({}).to_string().into_bytes(),
+                balance,
+                MIN_GAS_FOR_NEAR_DEPOSIT,
+            )
+            // Register the claimer on the wrap contract. If they're already registered, the deposit is refunded to this contract.
+            .function_call(
+                "storage_deposit".to_string(),
+                json!({ "account_id": account_id, "registration_only": true })
+                    .to_string()
+                    .into_bytes(),
+                WRAP_STORAGE_COST - 1,
+                MIN_GAS_FOR_STORAGE_DEPOSIT,
+            )
+            .function_call(
+                "ft_transfer".to_string(),
+                json!({ "receiver_id": account_id, "amount": U128(balance), "memo": "Keypom Linkdrop" })
+                    .to_string()
+                    .into_bytes(),
+                1,
+                MIN_GAS_FOR_FT_TRANSFER,
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MIN_GAS_FOR_ON_WRAP_DEPOSIT)
+                    .on_wrap_deposit(account_id, funder_id, drop_id, U128(balance)),
+            )
+    }
+
+    #[private]
+    /// Self callback checks if the wrapped tokens were sent to the claimer. If not, send the balance as native $NEAR instead
+    pub fn on_wrap_deposit(
+        &mut self,
+        account_id: AccountId,
+        funder_id: AccountId,
+        drop_id: DropId,
+        balance: U128,
+    ) -> PromiseOrValue<bool> {
+        let wrap_succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        if wrap_succeeded {
+            return PromiseOrValue::Value(true);
+        }
+
+        // The storage registration was never used so the funder gets it back
+        let funder_id = self.internal_current_owner(drop_id, funder_id);
+        let mut user_balance = self.user_balances.get(&funder_id).unwrap_or(0);
+        user_balance += WRAP_STORAGE_COST;
+        self.internal_set_user_balance(&funder_id, user_balance);
+
+        near_sdk::log!(
+            "Wrapping failed. Sending {} $NEAR to {} instead.",
+            yocto_to_near(balance.0),
+            account_id
+        );
+        match self.internal_transfer_or_defer(account_id, balance.0) {
+            Some(promise) => PromiseOrValue::Promise(promise),
+            None => PromiseOrValue::Value(true),
+        }
+    }
+}
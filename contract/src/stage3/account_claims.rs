@@ -0,0 +1,61 @@
+use crate::*;
+
+/// Every account must be able to claim at least once
+pub(crate) fn check_max_claims_per_account(config: &Option<DropConfig>) -> Result<(), String> {
+    if config.as_ref().and_then(|c| c.max_claims_per_account) == Some(0) {
+        return Err("max_claims_per_account must be more than 0".to_string());
+    }
+    Ok(())
+}
+
+#[near_bindgen]
+impl Keypom {
+    /// Returns how many times an account has claimed from a drop. Only counted for drops with `max_claims_per_account`.
+    pub fn get_claims_for_account(&self, drop_id: DropId, account_id: AccountId) -> u64 {
+        let drop = self.drop_for_id.get(&drop_id).expect("no drop found");
+        drop.claims_per_account.get(&account_id).unwrap_or(0)
+    }
+}
+
+impl Keypom {
+    /*
+        Count a claim towards the receiving account's limit if the drop has `max_claims_per_account`. The claim is
+        rejected if the account already hit the limit or if the funder can't cover the storage for the account's
+        first claim. That storage is charged to the funder right away and refunded once the drop is deleted or
+        fully claimed. Returns how many bytes the count took.
+    */
+    pub(crate) fn internal_count_account_claim(
+        &mut self,
+        drop: &mut Drop,
+        account_id: &AccountId,
+    ) -> Result<u64, String> {
+        let max_claims = match drop.config.as_ref().and_then(|c| c.max_claims_per_account) {
+            Some(max_claims) => max_claims,
+            None => return Ok(0),
+        };
+        let claims = drop.claims_per_account.get(account_id).unwrap_or(0);
+        if claims >= max_claims {
+            return Err(format!(
+                "{} already claimed the max of {} times from the drop",
+                account_id, max_claims
+            ));
+        }
+
+        let initial_storage = env::storage_usage();
+        drop.claims_per_account.insert(account_id, &(claims + 1));
+        let storage_used = env::storage_usage() - initial_storage;
+        if storage_used > 0 {
+            let storage_cost = Balance::from(storage_used) * drop.storage_byte_cost;
+            let balance = self.user_balances.get(&drop.owner_id).unwrap_or(0);
+            if balance < storage_cost {
+                drop.claims_per_account.remove(account_id);
+                return Err(
+                    "Funder can't cover the storage to count the account's claims".to_string(),
+                );
+            }
+            self.internal_set_user_balance(&drop.owner_id, balance - storage_cost);
+            self.internal_check_balance_alert(&drop.owner_id, balance - storage_cost);
+        }
+        Ok(storage_used)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::*;
+
+/// Longest account ID the protocol accepts
+const MAX_ACCOUNT_ID_LEN: usize = 64;
+
+/// Whether new accounts can be named by the contract when `create_account_and_claim` is called without one
+pub(crate) fn auto_account_names(config: &Option<DropConfig>) -> bool {
+    config
+        .as_ref()
+        .and_then(|c| c.auto_account_names)
+        .unwrap_or(false)
+}
+
+/// Root that new accounts for the drop are created under
+pub(crate) fn drop_root(config: &Option<DropConfig>, default_root: &AccountId) -> AccountId {
+    config
+        .as_ref()
+        .and_then(|c| c.drop_root.clone())
+        .unwrap_or_else(|| default_root.clone())
+}
+
+/*
+    Name the contract gives the account created for a key: `k<key_id>-<drop_id>.<root>`. Key IDs are never reused
+    within a drop and drop IDs are never reused so no two keys are given the same name. Names that wouldn't be a
+    valid account ID (i.e because they're longer than 64 characters) are rejected.
+*/
+pub(crate) fn generated_account_id(
+    key_id: u64,
+    drop_id: DropId,
+    root: &AccountId,
+) -> Result<AccountId, String> {
+    let name = format!("k{}-{}.{}", key_id, drop_id, root);
+    if name.len() > MAX_ACCOUNT_ID_LEN {
+        return Err(format!(
+            "generated account ID {} is longer than {} characters",
+            name, MAX_ACCOUNT_ID_LEN
+        ));
+    }
+    name.parse()
+        .map_err(|_| format!("generated account ID {} isn't valid", name))
+}
+
+/*
+    Drops with auto_account_names must let keys call create_account_and_claim and every key's generated name
+    must fit. Key IDs are u64s so the name for the largest possible key ID is checked.
+*/
+pub(crate) fn check_auto_account_names(
+    config: &Option<DropConfig>,
+    claim_permission: &ClaimPermissions,
+    drop_id: DropId,
+    default_root: &AccountId,
+) -> Result<(), String> {
+    if !auto_account_names(config) {
+        return Ok(());
+    }
+    if !claim_permission.allows(&ClaimPermissions::CreateAccountAndClaim) {
+        return Err(
+            "auto_account_names requires keys that can call create_account_and_claim".to_string(),
+        );
+    }
+    generated_account_id(u64::MAX, drop_id, &drop_root(config, default_root)).map(|_| ())
+}
+
+impl Keypom {
+    /// Account to create for the signing key. If none was passed in, the drop must let the contract name it.
+    pub(crate) fn internal_new_account_id(&self, new_account_id: Option<AccountId>) -> AccountId {
+        if let Some(account_id) = new_account_id {
+            return account_id;
+        }
+
+        let signer_pk = env::signer_account_pk();
+        let drop_id = self
+            .drop_id_for_pk
+            .get(&signer_pk)
+            .expect("No drop ID found for PK");
+        let drop = self.drop_for_id.get(&drop_id).expect("drop not found");
+        require!(
+            auto_account_names(&drop.config),
+            "new_account_id is required unless the drop has auto_account_names"
+        );
+        let key_id = drop.pks.get(&signer_pk).expect("key not found").key_id;
+
+        let account_id = generated_account_id(
+            key_id,
+            drop_id,
+            &drop_root(&drop.config, &self.root_account),
+        )
+        .unwrap_or_else(|e| env::panic_str(&e));
+        near_sdk::log!("Generated account ID {}", account_id);
+        account_id
+    }
+}
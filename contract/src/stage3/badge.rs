@@ -0,0 +1,158 @@
+use crate::*;
+
+/// Badge minted for a claim on the owner registered badge minter
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BadgeMint {
+    // Contract minting the badges
+    pub minter_id: AccountId,
+    // Funder that paid for the mint. The deposit is refunded to them if the badge isn't minted.
+    pub owner_id: AccountId,
+    // Account receiving the badge
+    pub receiver_id: AccountId,
+    pub drop_id: DropId,
+    // Which use of the key the badge is for, starting at 1
+    pub use_number: u64,
+}
+
+/// Whether the drop mints a badge for every successful claim
+pub(crate) fn mints_badge(config: &Option<DropConfig>) -> bool {
+    config.as_ref().and_then(|c| c.mint_badge).unwrap_or(false)
+}
+
+/// Deposit reserved for each paid claim when the drop mints badges
+pub(crate) fn badge_cost_per_use(config: &Option<DropConfig>) -> Balance {
+    if mints_badge(config) {
+        BADGE_MINT_DEPOSIT
+    } else {
+        0
+    }
+}
+
+#[near_bindgen]
+impl Keypom {
+    /// Set the contract that mints badges for drops with `mint_badge`. Passing in None stops new drops from minting badges.
+    #[payable]
+    pub fn set_badge_minter(&mut self, minter_id: Option<AccountId>) {
+        self.assert_owner_and_record("set_badge_minter");
+        near_sdk::log!("Badge minter set to {:?}", minter_id);
+        self.badge_minter = minter_id;
+    }
+
+    /// Drops can only mint badges once the owner has registered a badge minter
+    pub(crate) fn internal_check_mint_badge(
+        &self,
+        config: &Option<DropConfig>,
+    ) -> Result<(), String> {
+        if mints_badge(config) && self.badge_minter.is_none() {
+            return Err("no badge minter has been registered".to_string());
+        }
+        Ok(())
+    }
+
+    /// Badge to mint for a claim if the drop mints them. If the minter was removed since the drop was created, the deposit is refunded instead.
+    pub(crate) fn internal_badge_for_claim(
+        &mut self,
+        drop: &Drop,
+        drop_id: DropId,
+        account_id: &AccountId,
+        cur_key_info: &KeyInfo,
+    ) -> Option<BadgeMint> {
+        if !mints_badge(&drop.config) {
+            return None;
+        }
+        let minter_id = match self.badge_minter.clone() {
+            Some(minter_id) => minter_id,
+            None => {
+                near_sdk::log!("No badge minter registered. Refunding the badge deposit.");
+                self.internal_refund_unused_claim_cost(&drop.owner_id, BADGE_MINT_DEPOSIT);
+                return None;
+            }
+        };
+
+        Some(BadgeMint {
+            minter_id,
+            owner_id: drop.owner_id.clone(),
+            receiver_id: account_id.clone(),
+            drop_id,
+            use_number: key_uses_per_key(&drop.config, cur_key_info) - cur_key_info.remaining_uses
+                + 1,
+        })
+    }
+
+    /// Mint the badge once the claim's callback has resolved
+    pub(crate) fn internal_mint_badge_after(&self, claim: Promise, badge: Option<BadgeMint>) {
+        if let Some(badge) = badge {
+            claim.then(
+                // No unspent GAS is attached so that it all goes to the claim's callback
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MIN_GAS_FOR_ON_CLAIM_RESOLVED_FOR_BADGE)
+                    .with_unused_gas_weight(0)
+                    .on_claim_resolved_for_badge(badge),
+            );
+        }
+    }
+
+    /// Mint the badge if the claim succeeded. Otherwise refund the deposit to the funder.
+    pub(crate) fn internal_resolve_badge(&mut self, claim_succeeded: bool, badge: BadgeMint) {
+        if !claim_succeeded {
+            near_sdk::log!("Claim unsuccessful. Refunding the badge deposit.");
+            let owner_id = self.internal_current_owner(badge.drop_id, badge.owner_id);
+            self.internal_refund_unused_claim_cost(&owner_id, BADGE_MINT_DEPOSIT);
+            return;
+        }
+
+        near_sdk::log!(
+            "Minting badge {} of drop {} for {}",
+            badge.use_number,
+            badge.drop_id,
+            badge.receiver_id
+        );
+        Promise::new(badge.minter_id.clone())
+            .function_call(
+                "mint".to_string(),
+                json!({
+                    "receiver": badge.receiver_id,
+                    "drop_id": badge.drop_id,
+                    "use_number": badge.use_number,
+                })
+                .to_string()
+                .into_bytes(),
+                BADGE_MINT_DEPOSIT,
+                GAS_FOR_BADGE_MINT,
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MIN_GAS_FOR_ON_BADGE_MINTED)
+                    .with_unused_gas_weight(0)
+                    .on_badge_minted(badge),
+            );
+    }
+
+    #[private]
+    /// Self callback mints the badge if the claim's callback reported the claim as successful
+    pub fn on_claim_resolved_for_badge(&mut self, badge: BadgeMint) {
+        let claim_succeeded = promise_result_as_success()
+            .and_then(|result| near_sdk::serde_json::from_slice::<bool>(&result).ok())
+            .unwrap_or(false);
+        self.internal_resolve_badge(claim_succeeded, badge);
+    }
+
+    #[private]
+    /// Self callback logs failed mints and refunds the deposit. The claim has already gone through either way.
+    pub fn on_badge_minted(&mut self, badge: BadgeMint) -> bool {
+        let minted = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        if !minted {
+            near_sdk::log!(
+                "Minting badge {} of drop {} for {} on {} failed. Refunding the badge deposit.",
+                badge.use_number,
+                badge.drop_id,
+                badge.receiver_id,
+                badge.minter_id
+            );
+            let owner_id = self.internal_current_owner(badge.drop_id, badge.owner_id);
+            self.internal_refund_unused_claim_cost(&owner_id, BADGE_MINT_DEPOSIT);
+        }
+        minted
+    }
+}
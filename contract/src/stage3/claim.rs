@@ -1,25 +1,132 @@
 use crate::*;
 
+/// What `process_claim` hands back to the claim methods. Rejected claims have no drop.
+pub(crate) struct ProcessedClaim {
+    // Drop containing all data
+    pub drop: Option<Drop>,
+    // Drop ID for the drop
+    pub drop_id: Option<DropId>,
+    // How much storage was freed
+    pub storage_freed: Option<Balance>,
+    // Next token IDs to claim
+    pub token_ids: Option<Vec<String>>,
+    // Storage for the longest token IDs
+    pub storage_for_longest: Option<Balance>,
+    // Should we return and not do anything once the drop is claimed (if FC data is none)
+    pub should_continue: bool,
+    // Current key info before decrementing
+    pub key_info: KeyInfo,
+    // Early bonus to send on top of the drop's balance
+    pub early_bonus: Balance,
+}
+
+impl ProcessedClaim {
+    fn rejected(key_info: KeyInfo) -> Self {
+        ProcessedClaim {
+            drop: None,
+            drop_id: None,
+            storage_freed: None,
+            token_ids: None,
+            storage_for_longest: None,
+            should_continue: false,
+            key_info,
+            early_bonus: 0,
+        }
+    }
+}
+
 #[near_bindgen]
 impl Keypom {
     /// Claim tokens for specific account that are attached to the public key this tx is signed with.
-    pub fn claim(&mut self, account_id: AccountId) {
+    /// Relayers can pass in an idempotency key so that retried transactions aren't executed twice.
+    /// If the drop allows alternative payouts, the claimer can choose to receive one of those tokens instead of $NEAR.
+    /// Every argument after `account_id` is optional so legacy wallets can keep calling `claim({"account_id": ...})`.
+    /// Password protected keys must be passed the password the funder set for them.
+    pub fn claim(
+        &mut self,
+        account_id: AccountId,
+        idempotency_key: Option<String>,
+        identifier: Option<String>,
+        payout_token: Option<AccountId>,
+        pow_nonce: Option<u64>,
+        password: Option<String>,
+    ) {
+        // Only the drop key can claim itself. Drops that require proof of work are checked before anything else.
+        self.internal_assert_claim_origin();
+        self.internal_assert_proof_of_work(&account_id, pow_nonce);
+
+        // If this claim was already processed with the same idempotency key, don't execute it again
+        if self.internal_is_duplicate_claim(&idempotency_key) {
+            return;
+        }
+
+        // If the drop checks eligibility, the claim only continues once the funder's predicate returns true
+        if let Some(check) = self.internal_eligibility_check_for_signer() {
+            self.internal_check_eligibility(
+                check,
+                account_id,
+                idempotency_key,
+                identifier,
+                password,
+                payout_token,
+            );
+            return;
+        }
+
+        self.internal_claim(
+            account_id,
+            idempotency_key,
+            identifier,
+            password,
+            payout_token,
+            env::prepaid_gas(),
+        );
+    }
+
+    /// Claim to an account with the GAS attached to the claim transaction. The eligibility check (if any) has already passed.
+    pub(crate) fn internal_claim(
+        &mut self,
+        account_id: AccountId,
+        idempotency_key: Option<String>,
+        identifier: Option<String>,
+        password: Option<String>,
+        payout_token: Option<AccountId>,
+        prepaid_gas: Gas,
+    ) {
         // Delete the access key and remove / return drop data and optional token ID for nft drops. Also return the storage freed.
-        let (
-            drop_data_option,
+        let ProcessedClaim {
+            drop: drop_data_option,
             drop_id,
-            storage_freed_option,
-            token_id,
+            storage_freed: storage_freed_option,
+            token_ids,
             storage_for_longest,
             should_continue,
-            cur_key_info,
-        ) = self.process_claim();
+            key_info: cur_key_info,
+            early_bonus,
+        } = self.process_claim(
+            ClaimPermissions::Claim,
+            &account_id,
+            idempotency_key,
+            identifier,
+            password,
+            prepaid_gas,
+        );
 
         if drop_data_option.is_none() {
             near_sdk::log!("Invalid claim. Returning.");
             return;
         }
 
+        // Let wallets know the key was used and what to show the claimer
+        emit_claim_event(
+            drop_id.unwrap(),
+            &account_id,
+            drop_data_option.as_ref().unwrap(),
+            &cur_key_info,
+            false,
+            self.internal_is_drop_unverified(&drop_data_option.as_ref().unwrap().drop_type),
+        );
+
         if should_continue == false {
             near_sdk::log!("Empty function call. Returning.");
             return;
@@ -29,23 +136,102 @@ impl Keypom {
         let storage_freed = storage_freed_option.unwrap();
 
         // Should we refund send back the $NEAR since an account isn't being created and just send the assets to the claiming account?
-        let account_to_transfer = if drop_data
+        let refund_deposit = drop_data
             .config
             .clone()
             .and_then(|c| c.on_claim_refund_deposit)
-            .unwrap_or(false)
-            == true
-        {
+            .unwrap_or(false);
+        let account_to_transfer = if refund_deposit {
             drop_data.owner_id.clone()
         } else {
             account_id.clone()
         };
 
+        // Early claimers get the bonus on top of the drop's balance
+        let balance = drop_data.deposit_per_use + early_bonus;
+
+        // Only wrap the balance if it's actually going to the claimer
+        let wrap_contract = drop_data
+            .config
+            .clone()
+            .and_then(|c| c.wrap_contract)
+            .filter(|_| !refund_deposit && balance > 0);
+        // Only swap the balance if the claimer chose one of the drop's alternative payouts
+        if let Some(token_id) = payout_token.as_ref() {
+            require!(
+                drop_data
+                    .config
+                    .as_ref()
+                    .and_then(|c| c.alternative_payouts.as_ref())
+                    .is_some_and(|payouts| payouts.contains(token_id)),
+                "payout token isn't allowed for this drop"
+            );
+        }
+        let swap = payout_token
+            .filter(|_| !refund_deposit && balance > 0)
+            .and_then(|token_id| match self.swap_routes.get(&token_id) {
+                Some(route) => Some((token_id, route)),
+                None => {
+                    near_sdk::log!("No swap route for {}. Sending $NEAR instead.", token_id);
+                    None
+                }
+            });
+        // Contracts and gas rebates only go to new accounts so those costs are never needed here
+        let mut unused_claim_cost =
+            deploy_cost_per_use(&drop_data.config) + gas_rebate_per_use(&drop_data.config);
+        if wrap_contract.is_none() {
+            unused_claim_cost += wrap_cost_per_use(&drop_data.config);
+        }
+        if swap.is_none() {
+            unused_claim_cost += swap_cost_per_use(&drop_data.config);
+        }
+        self.internal_refund_unused_claim_cost(&drop_data.owner_id, unused_claim_cost);
+
         let mut promise = None;
+        let mut split = vec![];
         // Only create a promise to transfer $NEAR if the drop's balance is > 0.
-        if drop_data.deposit_per_use > 0 {
-            // Send the account ID the desired balance.
-            promise = Some(Promise::new(account_to_transfer).transfer(drop_data.deposit_per_use));
+        if let Some((token_id, route)) = swap {
+            // Send the account ID the balance swapped into the payout token they chose
+            let target = PromiseTarget {
+                method_name: "ft_transfer_call".to_string(),
+                receiver_id: route.wrap_contract.clone(),
+            };
+            promise = Some((
+                self.internal_swap_and_transfer(token_id, route, account_id.clone(), balance),
+                target,
+            ));
+        } else if let Some(wrap_contract) = wrap_contract {
+            // Send the account ID the balance as wrapped tokens
+            let target = PromiseTarget {
+                method_name: "near_deposit".to_string(),
+                receiver_id: wrap_contract.clone(),
+            };
+            promise = Some((
+                self.internal_wrap_and_transfer(
+                    wrap_contract,
+                    account_id.clone(),
+                    drop_data.owner_id.clone(),
+                    drop_id.unwrap(),
+                    balance,
+                ),
+                target,
+            ));
+        } else if balance > 0 {
+            // Send the account ID the desired balance less the beneficiaries' shares if the drop splits it.
+            let target = PromiseTarget {
+                method_name: "transfer".to_string(),
+                receiver_id: account_to_transfer.clone(),
+            };
+            if !refund_deposit {
+                split = split_deposit(&drop_data.config, balance);
+            }
+            promise = Some((
+                with_split_transfers(
+                    Promise::new(account_to_transfer).transfer(balance - split_total(&split)),
+                    &split,
+                ),
+                target,
+            ));
         }
 
         // Execute the callback depending on the drop type. If the drop balance is 0, the promise will be none and the callback function will just straight up be executed instead of resolving the promise.
@@ -54,10 +240,12 @@ impl Keypom {
             drop_id.unwrap(),
             cur_key_info,
             account_id,
+            balance,
             storage_freed,
-            token_id,
+            token_ids,
             storage_for_longest,
             promise,
+            split,
         );
 
         let used_gas = env::used_gas();
@@ -71,26 +259,61 @@ impl Keypom {
     }
 
     /// Create new account and and claim tokens to it.
+    /// Relayers can pass in an idempotency key so that retried transactions aren't executed twice.
+    /// Every argument after `new_public_key` is optional so legacy wallets can keep calling it with just those two.
+    /// Drops with `auto_account_names` can leave out `new_account_id` and the contract names the account instead.
     pub fn create_account_and_claim(
         &mut self,
-        new_account_id: AccountId,
+        new_account_id: Option<AccountId>,
         new_public_key: PublicKey,
+        idempotency_key: Option<String>,
+        identifier: Option<String>,
+        pow_nonce: Option<u64>,
+        password: Option<String>,
     ) {
-        let (
-            drop_data_option,
+        // Only the drop key can claim itself. Drops that require proof of work are checked before anything else.
+        self.internal_assert_claim_origin();
+        let new_account_id = self.internal_new_account_id(new_account_id);
+        self.internal_assert_proof_of_work(&new_account_id, pow_nonce);
+
+        // If this claim was already processed with the same idempotency key, don't execute it again
+        if self.internal_is_duplicate_claim(&idempotency_key) {
+            return;
+        }
+
+        let ProcessedClaim {
+            drop: drop_data_option,
             drop_id,
-            storage_freed_option,
-            token_id,
+            storage_freed: storage_freed_option,
+            token_ids,
             storage_for_longest,
             should_continue,
-            cur_key_info,
-        ) = self.process_claim();
+            key_info: cur_key_info,
+            early_bonus,
+        } = self.process_claim(
+            ClaimPermissions::CreateAccountAndClaim,
+            &new_account_id,
+            idempotency_key,
+            identifier,
+            password,
+            env::prepaid_gas(),
+        );
 
         if drop_data_option.is_none() {
             near_sdk::log!("Invalid claim. Returning.");
             return;
         }
 
+        // Let wallets know the key was used and what to show the claimer
+        emit_claim_event(
+            drop_id.unwrap(),
+            &new_account_id,
+            drop_data_option.as_ref().unwrap(),
+            &cur_key_info,
+            true,
+            self.internal_is_drop_unverified(&drop_data_option.as_ref().unwrap().drop_type),
+        );
+
         if should_continue == false {
             near_sdk::log!("Empty function call. Returning.");
             return;
@@ -98,19 +321,59 @@ impl Keypom {
 
         let drop_data = drop_data_option.unwrap();
         let storage_freed = storage_freed_option.unwrap();
-        let root_account = drop_data
-            .config
-            .clone()
-            .and_then(|c| c.drop_root)
-            .unwrap_or(self.root_account.clone());
+        let root_account = drop_root(&drop_data.config, &self.root_account);
+        // Early claimers get the bonus on top of the drop's balance
+        let mut balance = drop_data.deposit_per_use + early_bonus;
+        // The beneficiaries' shares are taken out of what the new account is funded with
+        let split = split_deposit(&drop_data.config, balance);
+        // The new account also receives the gas rebate. This is refunded along with the balance if the claim fails.
+        balance += gas_rebate_per_use(&drop_data.config);
+        let account_balance = balance - split_total(&split);
+        // New accounts are funded with native $NEAR so the wrap and swap costs for this claim aren't needed
+        self.internal_refund_unused_claim_cost(
+            &drop_data.owner_id,
+            wrap_cost_per_use(&drop_data.config) + swap_cost_per_use(&drop_data.config),
+        );
 
-        // CCC to the linkdrop contract to create the account with the desired balance as the linkdrop amount
-        let promise = ext_linkdrop::ext(root_account)
-            // Attach the balance of the linkdrop along with the exact gas for create account. No unspent GAS is attached.
-            .with_attached_deposit(drop_data.deposit_per_use)
-            .with_static_gas(GAS_FOR_CREATE_ACCOUNT)
-            .with_unused_gas_weight(0)
-            .create_account(new_account_id.clone(), new_public_key);
+        // The account is funded with the balance so if it can't cover the account's storage, creating it will fail
+        if account_balance < self.new_account_base {
+            near_sdk::log!(
+                "Warning: Balance {} is less than the minimum for creating an account: {}",
+                yocto_to_near(account_balance),
+                yocto_to_near(self.new_account_base)
+            );
+        }
+
+        let (promise, method_name) = match drop_data.config.clone().and_then(|c| c.deploy) {
+            Some(deploy) => {
+                // The new account also receives the storage for its contract. This is refunded along with the balance if the claim fails.
+                balance += deploy_cost_per_use(&drop_data.config);
+                (
+                    self.internal_create_account_and_deploy(
+                        root_account.clone(),
+                        new_account_id.clone(),
+                        new_public_key,
+                        account_balance + deploy_cost_per_use(&drop_data.config),
+                        deploy,
+                    ),
+                    "create_account_advanced",
+                )
+            }
+            // CCC to the linkdrop contract to create the account with the desired balance as the linkdrop amount
+            None => (
+                ext_linkdrop::ext(root_account.clone())
+                    // Attach the balance of the linkdrop along with the exact gas for create account. No unspent GAS is attached.
+                    .with_attached_deposit(account_balance)
+                    .with_static_gas(GAS_FOR_CREATE_ACCOUNT)
+                    .with_unused_gas_weight(0)
+                    .create_account(new_account_id.clone(), new_public_key),
+                "create_account",
+            ),
+        };
+        let target = PromiseTarget {
+            method_name: method_name.to_string(),
+            receiver_id: root_account,
+        };
 
         // Execute the callback depending on the drop type. We'll pass in the promise to resolve
         self.internal_execute(
@@ -118,10 +381,12 @@ impl Keypom {
             drop_id.unwrap(),
             cur_key_info,
             new_account_id,
+            balance,
             storage_freed,
-            token_id,
+            token_ids,
             storage_for_longest,
-            Some(promise),
+            Some((with_split_transfers(promise, &split), target)),
+            split,
         );
 
         let used_gas = env::used_gas();
@@ -144,9 +409,39 @@ impl Keypom {
         balance: U128,
         // How much storage was freed when the key was claimed
         storage_used: Balance,
+        // Drop ID for the specific drop
+        drop_id: DropId,
+        // Use of the key being claimed
+        claim_guard: ClaimGuard,
+        // What the promise being resolved called
+        promise_target: PromiseTarget,
+        // Beneficiaries whose transfers were joined onto the claim's promise
+        split: Vec<(AccountId, U128)>,
     ) -> bool {
-        // Get the status of the cross contract call
+        // The drop may have changed hands since the claim was scheduled
+        let owner_id = self.internal_current_owner(drop_id, owner_id);
+        // Get the status of the cross contract call. The beneficiaries' transfers resolve independently after it.
         let claim_succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        let mut amount_not_sent = 0;
+        if !claim_succeeded {
+            amount_not_sent += balance.0 - split_total(&split);
+        }
+        for (i, (account_id, amount)) in split.iter().enumerate() {
+            if !matches!(env::promise_result(i as u64 + 1), PromiseResult::Successful(_)) {
+                near_sdk::log!(
+                    "Sending {} to beneficiary {} failed",
+                    yocto_to_near(amount.0),
+                    account_id
+                );
+                amount_not_sent += amount.0;
+            }
+        }
+        let delivery = ClaimDelivery::new(balance.0 - amount_not_sent, Some(&promise_target));
+        self.internal_resolve_total_claims(&claim_guard, claim_succeeded, delivery);
+        self.internal_end_claim_guard(&claim_guard);
+        if !claim_succeeded {
+            self.internal_record_promise_failure(drop_id, promise_target);
+        }
 
         let used_gas = env::used_gas();
         let prepaid_gas = env::prepaid_gas();
@@ -169,13 +464,13 @@ impl Keypom {
             yocto_to_near(storage_used)
         );
 
-        // If not successful, the balance is added to the amount to refund since it was never transferred.
-        if !claim_succeeded {
+        // Anything that wasn't transferred is added to the amount to refund.
+        if amount_not_sent > 0 {
             near_sdk::log!(
                 "Claim unsuccessful. Refunding linkdrop balance as well: {}",
-                balance.0
+                amount_not_sent
             );
-            amount_to_refund += balance.0
+            amount_to_refund += amount_not_sent
         }
 
         near_sdk::log!(
@@ -190,7 +485,7 @@ impl Keypom {
             .get(&owner_id)
             .expect("No funder balance found");
         cur_funder_balance += amount_to_refund;
-        self.user_balances.insert(&owner_id, &cur_funder_balance);
+        self.internal_set_user_balance(&owner_id, cur_funder_balance);
 
         claim_succeeded
     }
@@ -207,11 +502,19 @@ impl Keypom {
         balance: U128,
         // How much storage was freed when the key was claimed
         storage_used: Balance,
+        // Drop ID for the specific drop
+        drop_id: DropId,
         // FT Data for the drop
         ft_data: FTData,
+        // Use of the key being claimed
+        claim_guard: ClaimGuard,
+        // What the promise being resolved called. None if this function is invoked via an execute
+        promise_target: Option<PromiseTarget>,
         // Was this function invoked via an execute (no callback)
         execute: bool,
     ) -> bool {
+        // The drop may have changed hands since the claim was scheduled
+        let owner_id = self.internal_current_owner(drop_id, owner_id);
         let used_gas = env::used_gas();
         let prepaid_gas = env::prepaid_gas();
         near_sdk::log!(
@@ -226,6 +529,15 @@ impl Keypom {
             claim_succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
         }
         near_sdk::log!("Has function been executed via CCC: {}", !execute);
+        let delivery = ClaimDelivery {
+            ft: ft_data.balance_per_use.0,
+            ..ClaimDelivery::new(balance.0, promise_target.as_ref())
+        };
+        self.internal_resolve_total_claims(&claim_guard, claim_succeeded, delivery);
+        self.internal_end_claim_guard(&claim_guard);
+        if let Some(target) = promise_target.filter(|_| !claim_succeeded) {
+            self.internal_record_promise_failure(drop_id, target);
+        }
 
         // Default amount to refund to be everything except balance and burnt GAS since balance was sent to new account.
         let mut amount_to_refund = ACCESS_KEY_STORAGE + storage_used;
@@ -259,7 +571,7 @@ impl Keypom {
             .get(&owner_id)
             .expect("No funder balance found");
         cur_funder_balance += amount_to_refund;
-        self.user_balances.insert(&owner_id, &cur_funder_balance);
+        self.internal_set_user_balance(&owner_id, cur_funder_balance);
 
         // Perform the FT transfer functionality
         self.internal_ft_transfer(claim_succeeded, ft_data, account_id);
@@ -279,17 +591,25 @@ impl Keypom {
         balance: U128,
         // How much storage was freed when the key was claimed
         storage_used: Balance,
-        // How much storage was prepaid to cover the longest token ID being inserted.
+        // Drop ID for the specific drop
+        drop_id: DropId,
+        // Cost of the storage prepaid to cover the longest token IDs being inserted, at the drop's byte cost
         storage_for_longest: Balance,
         // Sender of the NFT
         sender_id: AccountId,
         // Contract where the NFT is stored
         contract_id: AccountId,
-        // Token ID for the NFT
-        token_id: String,
+        // Token IDs handed over by the claim
+        token_ids: Vec<String>,
+        // Use of the key being claimed
+        claim_guard: ClaimGuard,
+        // What the promise being resolved called. None if this function is invoked via an execute
+        promise_target: Option<PromiseTarget>,
         // Was this function invoked via an execute (no callback)
         execute: bool,
     ) -> bool {
+        // The drop may have changed hands since the claim was scheduled
+        let owner_id = self.internal_current_owner(drop_id, owner_id);
         let used_gas = env::used_gas();
         let prepaid_gas = env::prepaid_gas();
 
@@ -305,11 +625,20 @@ impl Keypom {
             claim_succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
         }
         near_sdk::log!("Has function been executed via CCC: {}", !execute);
+        let delivery = ClaimDelivery {
+            nft: token_ids.len() as u64,
+            ..ClaimDelivery::new(balance.0, promise_target.as_ref())
+        };
+        self.internal_resolve_total_claims(&claim_guard, claim_succeeded, delivery);
+        self.internal_end_claim_guard(&claim_guard);
+        if let Some(target) = promise_target.filter(|_| !claim_succeeded) {
+            self.internal_record_promise_failure(drop_id, target);
+        }
 
         // Default amount to refund to be everything except balance and burnt GAS since balance was sent to new account.
         // In addition, we refund them for the cost of storing the longest token ID now that a key has been claimed
         let mut amount_to_refund =
-            ACCESS_KEY_STORAGE + storage_used + storage_for_longest * env::storage_byte_cost();
+            ACCESS_KEY_STORAGE + storage_used + storage_for_longest;
 
         near_sdk::log!(
             "Refund Amount: {}, 
@@ -319,7 +648,7 @@ impl Keypom {
             yocto_to_near(amount_to_refund),
             yocto_to_near(ACCESS_KEY_STORAGE),
             yocto_to_near(storage_used),
-            yocto_to_near(storage_for_longest * env::storage_byte_cost())
+            yocto_to_near(storage_for_longest)
         );
 
         // If not successful, the balance is added to the amount to refund since it was never transferred.
@@ -342,13 +671,14 @@ impl Keypom {
             .get(&owner_id)
             .expect("No funder balance found");
         cur_funder_balance += amount_to_refund;
-        self.user_balances.insert(&owner_id, &cur_funder_balance);
+        self.internal_set_user_balance(&owner_id, cur_funder_balance);
 
-        // Transfer the NFT
+        // Transfer the NFTs
         self.internal_nft_transfer(
             claim_succeeded,
+            drop_id,
             contract_id,
-            token_id,
+            token_ids,
             sender_id,
             account_id,
         );
@@ -375,9 +705,15 @@ impl Keypom {
         cur_key_info: KeyInfo,
         // How many uses the key had left before it was decremented
         uses_per_key: u64,
+        // Use of the key being claimed
+        claim_guard: ClaimGuard,
+        // What the promise being resolved called. None if this function is invoked via an execute
+        promise_target: Option<PromiseTarget>,
         // Was this function invoked via an execute (no callback)
         execute: bool,
     ) -> bool {
+        // The drop may have changed hands since the claim was scheduled
+        let owner_id = self.internal_current_owner(drop_id, owner_id);
         let used_gas = env::used_gas();
         let prepaid_gas = env::prepaid_gas();
 
@@ -393,6 +729,12 @@ impl Keypom {
             claim_succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
         }
         near_sdk::log!("Has function been executed via CCC: {}", !execute);
+        let delivery = ClaimDelivery::new(balance.0, promise_target.as_ref());
+        self.internal_resolve_total_claims(&claim_guard, claim_succeeded, delivery);
+        self.internal_end_claim_guard(&claim_guard);
+        if let Some(target) = promise_target.filter(|_| !claim_succeeded) {
+            self.internal_record_promise_failure(drop_id, target);
+        }
 
         // Default amount to refund to be everything except balance and burnt GAS since balance was sent to new account.
         let mut amount_to_refund = ACCESS_KEY_STORAGE + storage_used;
@@ -443,7 +785,7 @@ impl Keypom {
             .get(&owner_id)
             .expect("No funder balance found");
         cur_funder_balance += amount_to_refund;
-        self.user_balances.insert(&owner_id, &cur_funder_balance);
+        self.internal_set_user_balance(&owner_id, cur_funder_balance);
 
         self.internal_fc_execute(
             &cur_method_data,
@@ -451,6 +793,10 @@ impl Keypom {
             cur_key_info.key_id,
             account_id,
             drop_id,
+            FCArgsValues {
+                deposit: if claim_succeeded { balance.0 } else { 0 },
+                use_number: uses_per_key - cur_key_info.remaining_uses + 1,
+            },
         );
         claim_succeeded
     }
@@ -459,24 +805,17 @@ impl Keypom {
     /// If drop is none, simulate a panic.
     fn process_claim(
         &mut self,
-    ) -> (
-        // Drop containing all data
-        Option<Drop>,
-        // Drop ID for the drop
-        Option<DropId>,
-        // How much storage was freed
-        Option<Balance>,
-        // Next token ID to claim
-        Option<String>,
-        // Storage for the longest token ID
-        Option<Balance>,
-        // Should we return and not do anything once the drop is claimed (if FC data is none)
-        bool,
-        // Current key info before decrementing
-        KeyInfo,
-    ) {
-        let mut used_gas = env::used_gas();
-        let prepaid_gas = env::prepaid_gas();
+        claim_method: ClaimPermissions,
+        // Account receiving the claim. Counted towards the drop's max claims per account.
+        receiver_id: &AccountId,
+        idempotency_key: Option<String>,
+        identifier: Option<String>,
+        // Password for password protected keys
+        password: Option<String>,
+        // GAS attached to the claim transaction. This is passed in since eligibility checks continue the claim in a callback.
+        prepaid_gas: Gas,
+    ) -> ProcessedClaim {
+        let used_gas = env::used_gas();
 
         near_sdk::log!(
             "Beginning of process claim used gas: {:?} prepaid gas: {:?}",
@@ -486,16 +825,9 @@ impl Keypom {
 
         // Pessimistically measure storage
         let initial_storage = env::storage_usage();
-        // Ensure only the current contract is calling the method_name using the access key
+        // Ensure the transaction was signed by the drop key being claimed (the contract's function call access key)
         // Panic doesn't affect allowance
-        assert_eq!(
-            env::predecessor_account_id(),
-            env::current_account_id(),
-            "predecessor != current"
-        );
-
-        // Get the PK of the signer which should be the contract's function call access key
-        let signer_pk = env::signer_account_pk();
+        let signer_pk = self.internal_assert_claim_origin();
 
         // By default, every key should have a drop ID. If we need to remove the key, remove later.
         // Panic doesn't affect allowance
@@ -503,94 +835,132 @@ impl Keypom {
             .drop_id_for_pk
             .get(&signer_pk)
             .expect("No drop ID found for PK");
+        // Keys can't be used once their end timestamp has passed. Unlike the other checks, the claim fails outright
+        // so the key keeps its uses and its allowance and the funder can still refund it with `refund_expired_keys`.
+        // Panic doesn't affect allowance
+        let current_timestamp = env::block_timestamp();
+        let unclaimed_drop = self.drop_for_id.get(&drop_id).expect("drop not found");
+        let unclaimed_key = unclaimed_drop.pks.get(&signer_pk).expect("key not found");
+        require!(
+            !key_has_ended(&unclaimed_drop, &unclaimed_key, current_timestamp),
+            format!(
+                "Drop has expired. Current timestamp is {}",
+                current_timestamp
+            )
+        );
         // Remove the drop. If the drop shouldn't be removed, we re-insert later.
         // Panic doesn't affect allowance
         let mut drop = self.drop_for_id.remove(&drop_id).expect("drop not found");
         // Remove the pk from the drop's set and check for key usage.
         // Panic doesn't affect allowance
-        let mut key_info = drop.pks.remove(&signer_pk).unwrap();
+        let mut key_info = self
+            .internal_remove_key_info(&mut drop.pks, drop.deposit_per_use, &signer_pk)
+            .unwrap();
         // Keep track of the current number of uses so that it can be used to index into FCData Method Data
         let current_key_info = key_info.clone();
         // Ensure there's enough claims left for the key to be used. (this *should* only happen in NFT or FT cases)
-        if drop.registered_uses < 1 || prepaid_gas != drop.required_gas {
-            used_gas = env::used_gas();
-
-            let amount_to_decrement =
-                (used_gas.0 + GAS_FOR_PANIC_OFFSET.0) as u128 * self.yocto_per_gas;
-            if drop.registered_uses < 1 {
-                near_sdk::log!("Not enough claims left for the drop. Decrementing allowance by {}. Used GAS: {}", amount_to_decrement, used_gas.0);
+        // Claims can attach more GAS than the drop requires as long as the key's allowance covers it.
+        if drop.registered_uses < 1
+            || prepaid_gas < drop.required_gas
+            || key_info.allowance < prepaid_gas.0 as u128 * self.yocto_per_gas
+        {
+            let message = if let Some(message) = out_of_assets_message(&drop) {
+                message
+            } else if drop.registered_uses < 1 {
+                "No uses registered for the drop".to_string()
+            } else if prepaid_gas < drop.required_gas {
+                format!(
+                    "Prepaid GAS less than what is specified in the drop: {}",
+                    drop.required_gas.0
+                )
             } else {
-                near_sdk::log!("Prepaid GAS different than what is specified in the drop: {}. Decrementing allowance by {}. Used GAS: {}", drop.required_gas.0, amount_to_decrement, used_gas.0);
-            }
-
-            key_info.allowance -= amount_to_decrement;
-            near_sdk::log!("Allowance is now {}", key_info.allowance);
-            drop.pks.insert(&signer_pk, &key_info);
-            self.drop_for_id.insert(&drop_id, &drop);
-            return (None, None, None, None, None, false, current_key_info);
+                format!(
+                    "Not enough allowance on the key {} for the prepaid GAS",
+                    key_info.allowance
+                )
+            };
+            return self.reject_claim(drop_id, &mut drop, &signer_pk, key_info, &message);
         }
 
-        // Ensure enough time has passed if a start timestamp was specified in the config.
-        let current_timestamp = env::block_timestamp();
-        let desired_timestamp = drop
-            .config
-            .clone()
-            .and_then(|c| c.start_timestamp)
-            .unwrap_or(current_timestamp);
+        // NFT drops hand over a full pack with every claim. Token IDs that don't make up a pack can't be claimed.
+        let missing_pack = match &drop.drop_type {
+            DropType::NonFungibleToken(data) if !data.has_full_pack() => {
+                Some((data.token_ids.len(), data.tokens_per_use))
+            }
+            _ => None,
+        };
+        if let Some((num_tokens, tokens_per_use)) = missing_pack {
+            let message = format!(
+                "Drop has {} token IDs but a claim hands over {}",
+                num_tokens, tokens_per_use
+            );
+            return self.reject_claim(drop_id, &mut drop, &signer_pk, key_info, &message);
+        }
 
-        if current_timestamp < desired_timestamp {
-            used_gas = env::used_gas();
+        // Ensure enough time has passed if a start timestamp was specified in the config or for the key.
+        if !key_has_started(&drop, &key_info, current_timestamp) {
+            let desired_timestamp =
+                key_start_timestamp(&drop, &key_info).unwrap_or(current_timestamp);
+            let message = format!(
+                "Drop isn't claimable until {}. Current timestamp is {}",
+                desired_timestamp, current_timestamp
+            );
+            return self.reject_claim(drop_id, &mut drop, &signer_pk, key_info, &message);
+        }
 
-            let amount_to_decrement =
-                (used_gas.0 + GAS_FOR_PANIC_OFFSET.0) as u128 * self.yocto_per_gas;
-            near_sdk::log!("Drop isn't claimable until {}. Current timestamp is {}. Decrementing allowance by {}. Used GAS: {}", desired_timestamp, current_timestamp, amount_to_decrement, used_gas.0);
+        // Ensure the drop's claim permission allows the method that was called
+        if !drop.claim_permission.allows(&claim_method) {
+            let message = format!(
+                "Drop can only be claimed with {}",
+                access_key_method_names(&drop.claim_permission)
+            );
+            return self.reject_claim(drop_id, &mut drop, &signer_pk, key_info, &message);
+        }
 
-            key_info.allowance -= amount_to_decrement;
-            near_sdk::log!("Allowance is now {}", key_info.allowance);
-            drop.pks.insert(&signer_pk, &key_info);
-            self.drop_for_id.insert(&drop_id, &drop);
-            return (None, None, None, None, None, false, current_key_info);
+        // Ensure the drop hasn't reached its max number of claims. Claims still resolving count towards the cap.
+        if drop_reached_max_claims(&drop) {
+            let message = format!(
+                "Drop has reached its max of {} claims ({} confirmed, {} pending)",
+                drop.config
+                    .as_ref()
+                    .and_then(|c| c.max_total_claims)
+                    .unwrap_or(0),
+                drop.total_claims,
+                drop.pending_claims
+            );
+            return self.reject_claim(drop_id, &mut drop, &signer_pk, key_info, &message);
         }
 
-        /*
-            If it's an NFT drop get the token ID and remove it from the set. Also set the storage for longest
-            If it's an FC drop, get the next method_name data and check if it's none (to skip transfer of funds)
-        */
-        // Default the token ID to none and return / remove the next token ID if it's an NFT drop
-        let mut token_id = None;
-        // Default the storage for longest to be none and return the actual value if it's an NFT drop
-        let mut storage_for_longest = None;
-        // Default the should continue variable to true. If the next FC method_name is None, we set it to false
-        let mut should_continue = true;
-        match &mut drop.drop_type {
-            DropType::NonFungibleToken(data) => {
-                token_id = data.token_ids.pop();
-                storage_for_longest = Some(data.storage_for_longest);
-            }
-            DropType::FunctionCall(data) => {
-                // The starting index is the max claims per key - the number of uses left. If the method_name data is of size 1, use that instead
-                let cur_len = data.methods.len() as u16;
-                let starting_index = if cur_len > 1 {
-                    (drop
-                        .config
-                        .clone()
-                        .and_then(|c| c.uses_per_key)
-                        .unwrap_or(1)
-                        - key_info.remaining_uses) as usize
-                } else {
-                    0 as usize
-                };
+        // Ensure the claimer passed in an identifier whose hash is in the drop's allowlist
+        let identifier_allowlist = drop.config.clone().and_then(|c| c.identifier_allowlist);
+        let identifier_hash = identifier.map(|id| env::sha256(id.as_bytes()));
+        if identifier_allowlist.is_some()
+            && !identifier_hash
+                .as_ref()
+                .map(|hash| drop.allowed_hashes.contains(hash))
+                .unwrap_or(false)
+        {
+            return self.reject_claim(
+                drop_id,
+                &mut drop,
+                &signer_pk,
+                key_info,
+                "Identifier isn't in the drop's allowlist",
+            );
+        }
 
-                should_continue = data
-                    .methods
-                    .iter()
-                    .skip(starting_index)
-                    .next()
-                    .unwrap()
-                    .is_some();
-            }
-            _ => {}
-        };
+        // Password protected keys can only be claimed with the password the funder set for the key or for this use
+        let key_use = key_uses_per_key(&drop.config, &key_info) - key_info.remaining_uses + 1;
+        let password_hash = self.internal_password_hash_for_use(drop_id, &key_info, key_use);
+        if !password_unlocks_key(&password_hash, &password) {
+            return self.reject_claim(
+                drop_id,
+                &mut drop,
+                &signer_pk,
+                key_info,
+                "Incorrect password for key",
+            );
+        }
 
         // Default the should delete variable to true. If there's a case where it shouldn't, change the bool.
         let mut should_delete = true;
@@ -613,46 +983,113 @@ impl Keypom {
             if (current_timestamp - key_info.last_used) < interval
                 || key_info.allowance < prepaid_gas.0 as u128 * self.yocto_per_gas
             {
-                used_gas = env::used_gas();
-
-                let amount_to_decrement =
-                    (used_gas.0 + GAS_FOR_PANIC_OFFSET.0) as u128 * self.yocto_per_gas;
-                if (current_timestamp - key_info.last_used) < interval {
-                    near_sdk::log!("Not enough time has passed since the key was last used. Decrementing allowance by {}. Used GAS: {}", amount_to_decrement, used_gas.0);
+                let message = if (current_timestamp - key_info.last_used) < interval {
+                    "Not enough time has passed since the key was last used".to_string()
                 } else {
-                    near_sdk::log!("Not enough allowance on the key {}. Decrementing allowance by {} Used GAS: {}", key_info.allowance, amount_to_decrement, used_gas.0);
-                }
-
-                key_info.allowance -= amount_to_decrement;
-                near_sdk::log!("Allowance is now {}", key_info.allowance);
-                drop.pks.insert(&signer_pk, &key_info);
-                self.drop_for_id.insert(&drop_id, &drop);
-                return (None, None, None, None, None, false, current_key_info);
+                    format!("Not enough allowance on the key {}", key_info.allowance)
+                };
+                return self.reject_claim(drop_id, &mut drop, &signer_pk, key_info, &message);
             }
 
             near_sdk::log!("Enough time has passed for key to be used. Setting last used to current timestamp {}", current_timestamp);
             key_info.last_used = current_timestamp;
         }
 
+        // Ensure the receiving account hasn't hit the drop's max claims per account. The count's storage is charged to the funder.
+        let account_claims_storage = match self.internal_count_account_claim(&mut drop, receiver_id)
+        {
+            Ok(storage_used) => storage_used,
+            Err(e) => {
+                return self.reject_claim(drop_id, &mut drop, &signer_pk, key_info, &e);
+            }
+        };
+
+        /*
+            Every check that can reject the claim has passed so nothing taken from the drop below needs to be put back.
+            If it's an NFT drop get the token ID and remove it from the set. Also set the storage for longest
+            If it's an FC drop, get the next method_name data and check if it's none (to skip transfer of funds)
+        */
+        let use_index = drop
+            .config
+            .as_ref()
+            .and_then(|c| c.uses_per_key)
+            .unwrap_or(1)
+            - key_info.remaining_uses;
+        let ClaimedAssets {
+            token_ids,
+            storage_for_longest,
+            should_continue,
+        } = drop
+            .drop_type
+            .behavior_mut()
+            .on_claim(use_index, drop.storage_byte_cost);
+
+        // Single use identifiers are consumed by the claim. The storage freed is refunded to the funder.
+        if identifier_allowlist == Some(IdentifierAllowlist::SingleUse) {
+            if let Some(hash) = &identifier_hash {
+                drop.allowed_hashes.remove(hash);
+            }
+        }
+
+        // Keep track of the claim until it resolves. Empty function calls have nothing to resolve so they're confirmed right away.
+        if should_continue {
+            drop.pending_claims += 1;
+        } else {
+            drop.total_claims += 1;
+            let use_number = ClaimGuard::new(drop_id, &current_key_info, &drop.config).use_number;
+            self.internal_resolve_claim_stats(
+                drop_id,
+                true,
+                use_number,
+                Some(ClaimDelivery::default()),
+            );
+        }
+
+        // Pay out the early bonus if the drop has one and there are still bonus claims left
+        let mut early_bonus = 0;
+        if let Some((num_bonus_claims, amount)) = drop.config.clone().and_then(|c| c.early_bonus) {
+            if should_continue && drop.early_claims < num_bonus_claims {
+                drop.early_claims += 1;
+                early_bonus = amount.0;
+                near_sdk::log!(
+                    "Early claim {} of {}. Adding bonus: {}",
+                    drop.early_claims,
+                    num_bonus_claims,
+                    yocto_to_near(early_bonus)
+                );
+            }
+        }
+
+        // The allowance spent by this claim is based on the GAS it actually attached rather than the pessimistic
+        // amount reserved when the key was added. Any surplus stays on the key and is refunded once it's deleted.
+        let claim_allowance = prepaid_gas.0 as u128 * self.yocto_per_gas;
+        key_info.spend_allowance(claim_allowance);
+        self.internal_remove_password_for_use(drop_id, key_info.key_id, key_use);
+
         // No uses left! The key should be deleted
         if key_info.remaining_uses == 1 {
             near_sdk::log!("Key has no uses left. It will be deleted");
             self.drop_id_for_pk.remove(&signer_pk);
+            drop.pk_for_key_id.remove(&key_info.key_id);
         } else {
             key_info.remaining_uses -= 1;
-            key_info.allowance -= drop.required_gas.0 as u128 * self.yocto_per_gas;
             near_sdk::log!(
                 "Key has {} uses left. Decrementing allowance by {}. Allowance left: {}",
                 key_info.remaining_uses,
-                drop.required_gas.0 as u128 * self.yocto_per_gas,
+                claim_allowance,
                 key_info.allowance
             );
 
-            drop.pks.insert(&signer_pk, &key_info);
+            self.internal_insert_key_info(
+                &mut drop.pks,
+                drop.deposit_per_use,
+                &signer_pk,
+                &key_info,
+            );
             should_delete = false;
         }
 
-        drop.registered_uses -= 1;
+        release_registered_uses(&mut drop, 1);
 
         // If there are keys still left in the drop, add the drop back in with updated data
         if !drop.pks.is_empty() {
@@ -661,20 +1098,71 @@ impl Keypom {
         } else {
             // Remove the drop ID from the funder's list if the drop is now empty
             self.internal_remove_drop_for_funder(&drop.owner_id, &drop_id);
+            self.internal_remove_drop_from_asset_index(&drop.drop_type, drop_id);
+            drop.allowed_hashes.clear();
+            drop.claims_per_account.clear();
+            self.drops_with_passwords_per_use.remove(&drop_id);
+            self.recent_failures.remove(&drop_id);
+            self.internal_complete_drop_stats(drop_id, drop.pending_claims);
+
+            // Refund any early bonus and compaction bounties that were reserved but never paid out
+            let unspent_bonus = unspent_early_bonus(&drop) + unspent_compaction_reserve(&drop);
+            if unspent_bonus > 0 {
+                near_sdk::log!(
+                    "Drop empty. Refunding unspent early bonus and compaction bounties: {}",
+                    yocto_to_near(unspent_bonus)
+                );
+                let mut cur_funder_balance = self
+                    .user_balances
+                    .get(&drop.owner_id)
+                    .expect("No funder balance found");
+                cur_funder_balance += unspent_bonus;
+                self.internal_set_user_balance(&drop.owner_id, cur_funder_balance);
+            }
         }
 
-        // Calculate the storage being freed. initial - final should be >= 0 since final should be smaller than initial.
+        // Record the claim before any promises are scheduled so that retries are caught even while the claim is resolving.
+        // This happens before storage is measured so that the funder pays for the record.
+        let claim_record = idempotency_key.map(|key| {
+            self.internal_record_claim(
+                &signer_pk,
+                &key,
+                drop_id,
+                &current_key_info,
+                receiver_id,
+                should_continue,
+            )
+        });
+
+        // Calculate the storage being freed. The claim count's storage was already charged to the funder so it's left out.
         let final_storage = env::storage_usage();
+        let storage_released = initial_storage + account_claims_storage;
         let total_storage_freed =
-            Balance::from(initial_storage - final_storage) * env::storage_byte_cost();
+            Balance::from(storage_released.saturating_sub(final_storage)) * drop.storage_byte_cost;
+        // Claims that free less than their record takes up (i.e uses of multi-use keys) are charged the difference
+        if final_storage > storage_released {
+            let storage_cost =
+                Balance::from(final_storage - storage_released) * drop.storage_byte_cost;
+            let balance = self
+                .user_balances
+                .get(&drop.owner_id)
+                .unwrap_or(0)
+                .saturating_sub(storage_cost);
+            self.internal_set_user_balance(&drop.owner_id, balance);
+            self.internal_check_balance_alert(&drop.owner_id, balance);
+        }
+
+        if let Some(hash) = claim_record.filter(|_| should_continue) {
+            let guard = ClaimGuard::new(drop_id, &current_key_info, &drop.config);
+            self.internal_track_claim_record(&guard, &hash);
+        }
 
         if should_delete {
-            // Amount to refund is the current allowance less the current execution's max GAS
-            let amount_to_refund =
-                key_info.allowance - drop.required_gas.0 as u128 * self.yocto_per_gas;
+            // The current execution's GAS was already taken out so the allowance left is exactly what's unspent
+            let amount_to_refund = key_info.allowance;
             near_sdk::log!(
-                "Key being deleted. Allowance Currently: {}. Will refund: {}",
-                key_info.allowance,
+                "Key being deleted. Allowance used: {}. Will refund: {}",
+                key_info.allowance_used,
                 amount_to_refund
             );
             // Get the funder's balance and increment it by the amount to refund
@@ -683,22 +1171,59 @@ impl Keypom {
                 .get(&drop.owner_id)
                 .expect("No funder balance found");
             cur_funder_balance += amount_to_refund;
-            self.user_balances
-                .insert(&drop.owner_id, &cur_funder_balance);
+            self.internal_set_user_balance(&drop.owner_id, cur_funder_balance);
 
             // Delete the key
-            Promise::new(env::current_account_id()).delete_key(signer_pk);
+            let promise = env::promise_batch_create(&env::current_account_id());
+            env::promise_batch_action_delete_key(promise, &signer_pk);
+            self.internal_count_access_key_batches(&[(promise, 1)], false);
         }
 
-        // Return the drop and optional token ID with how much storage was freed
-        (
-            Some(drop),
-            Some(drop_id),
-            Some(total_storage_freed),
-            token_id,
+        // Return the drop and optional token IDs with how much storage was freed
+        ProcessedClaim {
+            drop: Some(drop),
+            drop_id: Some(drop_id),
+            storage_freed: Some(total_storage_freed),
+            token_ids,
             storage_for_longest,
             should_continue,
-            current_key_info,
-        )
+            key_info: current_key_info,
+            early_bonus,
+        }
+    }
+
+    /*
+        Reject a claim that failed one of the checks in `process_claim`. The GAS used so far (plus an offset for
+        the rest of the call) is taken out of the key's allowance and the key and drop are put back as they were.
+    */
+    fn reject_claim(
+        &mut self,
+        drop_id: DropId,
+        drop: &mut Drop,
+        signer_pk: &PublicKey,
+        key_info: KeyInfo,
+        message: &str,
+    ) -> ProcessedClaim {
+        let used_gas = env::used_gas();
+        let amount_to_decrement =
+            (used_gas.0 + GAS_FOR_PANIC_OFFSET.0) as u128 * self.yocto_per_gas;
+        near_sdk::log!(
+            "{}. Decrementing allowance by {}. Used GAS: {}",
+            message,
+            amount_to_decrement,
+            used_gas.0
+        );
+
+        let mut spent_key_info = key_info.clone();
+        spent_key_info.spend_allowance(amount_to_decrement);
+        near_sdk::log!("Allowance is now {}", spent_key_info.allowance);
+        self.internal_insert_key_info(
+            &mut drop.pks,
+            drop.deposit_per_use,
+            signer_pk,
+            &spent_key_info,
+        );
+        self.drop_for_id.insert(&drop_id, drop);
+        ProcessedClaim::rejected(key_info)
     }
 }
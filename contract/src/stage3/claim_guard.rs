@@ -0,0 +1,50 @@
+use crate::*;
+
+/// A specific use of a key that's being claimed and hasn't resolved yet
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimGuard {
+    pub drop_id: DropId,
+    pub key_id: u64,
+    // Which use of the key is being claimed, starting at 1
+    pub use_number: u64,
+}
+
+impl ClaimGuard {
+    /// Guard for the use being claimed. `cur_key_info` is the key as it was before its uses were decremented.
+    pub(crate) fn new(
+        drop_id: DropId,
+        cur_key_info: &KeyInfo,
+        config: &Option<DropConfig>,
+    ) -> Self {
+        ClaimGuard {
+            drop_id,
+            key_id: cur_key_info.key_id,
+            use_number: key_uses_per_key(config, cur_key_info) - cur_key_info.remaining_uses + 1,
+        }
+    }
+}
+
+impl Keypom {
+    /*
+        Record that a use of a key is being claimed before any of the claim's promises are scheduled. A second claim
+        for the same use while the first hasn't resolved is rejected outright. This doesn't rely on the key's uses
+        having been decremented first, so it still holds if the order of the claim path ever changes. The entry's
+        storage is held by the contract until the claim resolves.
+    */
+    pub(crate) fn internal_begin_claim_guard(&mut self, guard: &ClaimGuard) {
+        let newly_claimed = self.claims_in_flight.insert(guard);
+        require!(
+            newly_claimed,
+            format!(
+                "use {} of key {} in drop {} is already being claimed",
+                guard.use_number, guard.key_id, guard.drop_id
+            )
+        );
+    }
+
+    /// Clear the guard once the claim's callback has run
+    pub(crate) fn internal_end_claim_guard(&mut self, guard: &ClaimGuard) {
+        self.claims_in_flight.remove(guard);
+    }
+}
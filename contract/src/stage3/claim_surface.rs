@@ -0,0 +1,189 @@
+use crate::*;
+
+/// A method around the claim flow along with whether drop keys can call it
+pub(crate) struct ClaimSurfaceMethod {
+    // Name of the method on the contract
+    pub name: &'static str,
+    // Claim permission a drop key needs to call the method. None if drop keys can never call it.
+    pub key_permission: Option<ClaimPermissions>,
+    // Whether the method is #[payable]
+    pub payable: bool,
+    // Whether the method can change balances other than the ones reserved for the key's own claim
+    pub mutates_balances: bool,
+}
+
+/*
+    Single source of truth for the methods drop keys can call. The access keys' method names are generated from
+    this list so any new claim method (i.e a signed or continued claim) must be added here to be callable by keys.
+    Methods that touch balances are listed so that making them key callable has to be done explicitly, which
+    the check below rejects at compile time.
+
+    Keys keep the method names they were created with for their whole life so new claim parameters are added as
+    optional arguments to these methods rather than as new methods. Wallets that only pass in `account_id`
+    (or `new_account_id` and `new_public_key`) keep working.
+*/
+pub(crate) const CLAIM_SURFACE: &[ClaimSurfaceMethod] = &[
+    ClaimSurfaceMethod {
+        name: "claim",
+        key_permission: Some(ClaimPermissions::Claim),
+        payable: false,
+        mutates_balances: false,
+    },
+    ClaimSurfaceMethod {
+        name: "create_account_and_claim",
+        key_permission: Some(ClaimPermissions::CreateAccountAndClaim),
+        payable: false,
+        mutates_balances: false,
+    },
+    ClaimSurfaceMethod {
+        name: "finish_key_creation",
+        key_permission: None,
+        payable: false,
+        mutates_balances: false,
+    },
+    ClaimSurfaceMethod {
+        name: "add_to_balance",
+        key_permission: None,
+        payable: true,
+        mutates_balances: true,
+    },
+    ClaimSurfaceMethod {
+        name: "withdraw_from_balance",
+        key_permission: None,
+        payable: true,
+        mutates_balances: true,
+    },
+];
+
+/// Fail the build if a drop key could ever call a payable or balance mutating method
+pub(crate) const fn assert_key_callable_methods_are_safe(methods: &[ClaimSurfaceMethod]) {
+    let mut i = 0;
+    while i < methods.len() {
+        let method = &methods[i];
+        if method.key_permission.is_some() && (method.payable || method.mutates_balances) {
+            panic!("drop keys cannot be allowed to call payable or balance mutating methods");
+        }
+        i += 1;
+    }
+}
+
+const _: () = assert_key_callable_methods_are_safe(CLAIM_SURFACE);
+
+/*
+    Owner and funder gated methods that change state. They require exactly 1 yoctoNEAR to be attached so that
+    wallets ask for a full access key confirmation and function call access keys, which can never attach a deposit,
+    can't call them. Every owner-gated method asserts this through `assert_owner_and_record`.
+*/
+pub(crate) const ONE_YOCTO_METHODS: &[&str] = &[
+    "delete_keys",
+    "refund_expired_keys",
+    "tighten_expiring_keys",
+    "refund_assets",
+    "delete_drop",
+    "update_drop",
+    "update_asset_sender",
+    "add_allowance_to_keys",
+    "transfer_drop_ownership",
+    "cancel_pending_drop",
+    "add_allowed_hashes",
+    "remove_allowed_hashes",
+    "delete_campaign",
+    "withdraw_from_balance",
+    "veto_admin_action",
+    "restrict_fc_receivers",
+    "unrestrict_fc_receivers",
+    "set_fc_receiver_reputation",
+    "set_owner",
+    "set_root_account",
+    "register_known_root",
+    "unregister_known_root",
+    "set_fees_per_user",
+    "set_gas_price",
+    "set_account_creation_cost",
+    "set_claim_record_ttl",
+    "set_expiring_key_window",
+    "set_operational_buffer",
+    "admin_refund_drop",
+    "admin_force_refund",
+    "withdraw_fees",
+    "set_badge_minter",
+    "set_wrap_contract",
+    "set_access_key_cap",
+    "import_drop",
+    "register_partner",
+    "unregister_partner",
+    "register_swap_route",
+    "unregister_swap_route",
+];
+
+const fn same_name(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Fail the build if a method that requires 1 yoctoNEAR is ever made callable by drop keys
+pub(crate) const fn assert_one_yocto_methods_not_key_callable(
+    methods: &[ClaimSurfaceMethod],
+    one_yocto: &[&str],
+) {
+    let mut i = 0;
+    while i < methods.len() {
+        let mut j = 0;
+        while j < one_yocto.len() {
+            if methods[i].key_permission.is_some() && same_name(methods[i].name, one_yocto[j]) {
+                panic!("methods that require 1 yoctoNEAR cannot be called by drop keys");
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+const _: () = assert_one_yocto_methods_not_key_callable(CLAIM_SURFACE, ONE_YOCTO_METHODS);
+
+/// Method names that the access keys can call for a given claim permission
+pub(crate) fn access_key_method_names(permission: &ClaimPermissions) -> String {
+    CLAIM_SURFACE
+        .iter()
+        .filter(|method| {
+            method
+                .key_permission
+                .as_ref()
+                .is_some_and(|required| permission.allows(required))
+        })
+        .map(|method| method.name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Keypom {
+    /*
+        Every claim entry point starts here. A claim is only honored if the transaction was signed by the drop key
+        being claimed: the key must be an access key on this contract (so the contract is the predecessor) and it
+        must belong to a drop. The key being claimed is always the signing key, never an argument, and there are no
+        operators that can claim on a key's behalf. Relayers submit transactions signed by the drop key itself.
+        Returns the signing key.
+    */
+    pub(crate) fn internal_assert_claim_origin(&self) -> PublicKey {
+        require!(
+            env::predecessor_account_id() == env::current_account_id(),
+            "claims must be signed by a drop key on this contract"
+        );
+        let signer_pk = env::signer_account_pk();
+        require!(
+            self.drop_id_for_pk.get(&signer_pk).is_some(),
+            "claims must be signed by the drop key being claimed"
+        );
+        signer_pk
+    }
+}
@@ -0,0 +1,87 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+/// Contract to deploy to accounts created through `create_account_and_claim`
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeployConfig {
+    // Wasm bytecode deployed to the new account
+    pub contract_bytes: Base64VecU8,
+
+    // Method to call on the new account once the contract is deployed (i.e "new"). If None, the contract isn't initialized.
+    pub init_method: Option<String>,
+
+    // JSON args to pass into the init method. If None, default to an empty object.
+    pub init_args: Option<String>,
+}
+
+/// Options passed to the linkdrop contract when creating an account with a contract
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CreateAccountOptions {
+    pub full_access_keys: Option<Vec<PublicKey>>,
+    pub contract_bytes: Option<Vec<u8>>,
+}
+
+#[near_bindgen]
+impl Keypom {
+    /// Create the new account through the root with the contract deployed and the public key added. The balance must cover the contract's storage.
+    pub(crate) fn internal_create_account_and_deploy(
+        &self,
+        root_account: AccountId,
+        new_account_id: AccountId,
+        new_public_key: PublicKey,
+        balance: Balance,
+        deploy: DeployConfig,
+    ) -> Promise {
+        ext_linkdrop::ext(root_account)
+            // Attach the balance along with the exact gas for creating the account and deploying. No unspent GAS is attached.
+            .with_attached_deposit(balance)
+            .with_static_gas(GAS_FOR_CREATE_ACCOUNT + GAS_FOR_DEPLOY_CONTRACT)
+            .with_unused_gas_weight(0)
+            .create_account_advanced(
+                new_account_id.clone(),
+                CreateAccountOptions {
+                    full_access_keys: Some(vec![new_public_key]),
+                    contract_bytes: Some(deploy.contract_bytes.0),
+                },
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MIN_GAS_FOR_ON_ACCOUNT_DEPLOYED)
+                    .with_unused_gas_weight(0)
+                    .on_account_deployed(new_account_id, deploy.init_method, deploy.init_args),
+            )
+    }
+
+    #[private]
+    /// Self callback checks if the account was created with its contract. If yes, call the init method. If no, fail so that the claim is refunded.
+    pub fn on_account_deployed(
+        &mut self,
+        new_account_id: AccountId,
+        init_method: Option<String>,
+        init_args: Option<String>,
+    ) -> bool {
+        // The linkdrop contract returns false rather than failing if the account couldn't be created
+        let account_created = promise_result_as_success()
+            .and_then(|result| near_sdk::serde_json::from_slice::<bool>(&result).ok())
+            .unwrap_or(false);
+        require!(
+            account_created,
+            &format!("creating {} with a contract failed", new_account_id)
+        );
+
+        // The account has already been funded so the claim succeeds even if the init call fails
+        if let Some(method_name) = init_method {
+            near_sdk::log!("Initializing {} with {}", new_account_id, method_name);
+            Promise::new(new_account_id).function_call(
+                method_name,
+                init_args.unwrap_or_else(|| "{}".to_string()).into_bytes(),
+                NO_DEPOSIT,
+                GAS_FOR_INIT_METHOD,
+            );
+        }
+
+        true
+    }
+}
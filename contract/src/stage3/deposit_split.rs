@@ -0,0 +1,92 @@
+use crate::*;
+
+/// Maximum number of beneficiaries a deposit can be split with. Each one adds a transfer to every claim.
+pub(crate) const MAX_DEPOSIT_SPLIT_RECIPIENTS: usize = 5;
+/// Basis points that make up the whole deposit
+const MAX_DEPOSIT_SPLIT_BPS: u32 = 10_000;
+
+/// Check that a drop's deposit split has a bounded number of distinct beneficiaries whose shares fit in the deposit
+pub(crate) fn check_deposit_split(
+    config: &Option<DropConfig>,
+    is_simple: bool,
+) -> Result<(), String> {
+    let (config, split) = match config
+        .as_ref()
+        .and_then(|c| c.deposit_split.as_ref().map(|split| (c, split)))
+    {
+        Some(split) => split,
+        None => return Ok(()),
+    };
+
+    if !is_simple {
+        return Err("deposit splits are only supported for simple drops".to_string());
+    }
+    if config.wrap_contract.is_some() || config.alternative_payouts.is_some() {
+        return Err(
+            "deposit splits can't be combined with a wrap contract or alternative payouts"
+                .to_string(),
+        );
+    }
+    if split.is_empty() || split.len() > MAX_DEPOSIT_SPLIT_RECIPIENTS {
+        return Err(format!(
+            "deposit splits must have between 1 and {} beneficiaries",
+            MAX_DEPOSIT_SPLIT_RECIPIENTS
+        ));
+    }
+
+    let mut total_bps = 0;
+    for (i, (account_id, bps)) in split.iter().enumerate() {
+        if *bps == 0 {
+            return Err(format!("{} has a share of 0 basis points", account_id));
+        }
+        if split[..i].iter().any(|(other, _)| other == account_id) {
+            return Err(format!(
+                "{} is in the deposit split more than once",
+                account_id
+            ));
+        }
+        total_bps += *bps as u32;
+    }
+    if total_bps > MAX_DEPOSIT_SPLIT_BPS {
+        return Err(
+            "deposit split shares cannot add up to more than 10000 basis points".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Amount of a claim's balance that goes to each beneficiary. The rest is left for the claimer.
+pub(crate) fn split_deposit(
+    config: &Option<DropConfig>,
+    balance: Balance,
+) -> Vec<(AccountId, U128)> {
+    config
+        .as_ref()
+        .and_then(|c| c.deposit_split.as_ref())
+        .map(|split| {
+            split
+                .iter()
+                .map(|(account_id, bps)| {
+                    (
+                        account_id.clone(),
+                        U128(balance * *bps as u128 / MAX_DEPOSIT_SPLIT_BPS as u128),
+                    )
+                })
+                // Shares that round down to nothing aren't worth a transfer
+                .filter(|(_, amount)| amount.0 > 0)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Total amount sent to the beneficiaries of a split
+pub(crate) fn split_total(split: &[(AccountId, U128)]) -> Balance {
+    split.iter().map(|(_, amount)| amount.0).sum()
+}
+
+/// Join the transfers to each beneficiary onto the claim's promise. Their results follow the claim's in the callback.
+pub(crate) fn with_split_transfers(promise: Promise, split: &[(AccountId, U128)]) -> Promise {
+    split.iter().fold(promise, |promise, (account_id, amount)| {
+        promise.and(Promise::new(account_id.clone()).transfer(amount.0))
+    })
+}
@@ -0,0 +1,184 @@
+use crate::*;
+
+/// Placeholder in the args template that's replaced with the account receiving the claim
+const RECEIVER_PLACEHOLDER: &str = "{account_id}";
+/// Maximum length of the args template passed to the eligibility predicate
+const MAX_ELIGIBILITY_ARGS_LEN: usize = 1024;
+
+/// View or call on the funder's contract that must return `true` for the receiving account before a claim goes through
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EligibilityCheck {
+    // Contract the predicate lives on (i.e the membership NFT contract)
+    pub contract_id: AccountId,
+
+    // Method to call. It must return a JSON boolean.
+    pub method: String,
+
+    // JSON args for the method. Every `{account_id}` is replaced with the account receiving the claim.
+    pub args_template: String,
+}
+
+impl EligibilityCheck {
+    /// The predicate's args for a specific receiver
+    fn args_for(&self, account_id: &AccountId) -> Vec<u8> {
+        self.args_template
+            .replace(RECEIVER_PLACEHOLDER, account_id.as_str())
+            .into_bytes()
+    }
+}
+
+/// Check that the drop's eligibility check is well formed and that its keys can only call `claim`.
+/// Accounts created with `create_account_and_claim` don't exist yet so there's nothing to check.
+pub(crate) fn check_eligibility_check(
+    config: &Option<DropConfig>,
+    claim_permission: &ClaimPermissions,
+) -> Result<(), String> {
+    let check = match config.as_ref().and_then(|c| c.eligibility_check.as_ref()) {
+        Some(check) => check,
+        None => return Ok(()),
+    };
+
+    if *claim_permission != ClaimPermissions::Claim {
+        return Err(
+            "eligibility checks require keys that can only call claim (claim_permission: Claim)"
+                .to_string(),
+        );
+    }
+    if check.method.is_empty() {
+        return Err("eligibility check method cannot be empty".to_string());
+    }
+    if check.args_template.len() > MAX_ELIGIBILITY_ARGS_LEN {
+        return Err(format!(
+            "eligibility check args cannot be longer than {} characters",
+            MAX_ELIGIBILITY_ARGS_LEN
+        ));
+    }
+    // Account IDs never need escaping so any valid receiver keeps the args valid JSON
+    let sample_args = check.args_template.replace(RECEIVER_PLACEHOLDER, "a");
+    if near_sdk::serde_json::from_str::<near_sdk::serde_json::Value>(&sample_args).is_err() {
+        return Err("eligibility check args must be valid JSON".to_string());
+    }
+    Ok(())
+}
+
+#[near_bindgen]
+impl Keypom {
+    /// Eligibility check of the drop the signing key belongs to, if it has one
+    pub(crate) fn internal_eligibility_check_for_signer(&self) -> Option<EligibilityCheck> {
+        let drop_id = self.drop_id_for_pk.get(&env::signer_account_pk())?;
+        self.drop_for_id
+            .get(&drop_id)?
+            .config
+            .and_then(|c| c.eligibility_check)
+    }
+
+    /// Call the funder's predicate and only continue the claim in the callback if it returned true
+    pub(crate) fn internal_check_eligibility(
+        &self,
+        check: EligibilityCheck,
+        account_id: AccountId,
+        idempotency_key: Option<String>,
+        identifier: Option<String>,
+        password: Option<String>,
+        payout_token: Option<AccountId>,
+    ) {
+        // Ensure only the current contract is calling the method_name using the access key
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "predecessor != current"
+        );
+
+        near_sdk::log!(
+            "Checking if {} is eligible with {} on {}",
+            account_id,
+            check.method,
+            check.contract_id
+        );
+        Promise::new(check.contract_id.clone())
+            .function_call(
+                check.method.clone(),
+                check.args_for(&account_id),
+                NO_DEPOSIT,
+                GAS_FOR_ELIGIBILITY_PREDICATE,
+            )
+            .then(
+                // The rest of the claim runs in the callback so all unspent GAS is attached on top of the minimum
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MIN_GAS_FOR_ON_ELIGIBILITY_CHECKED)
+                    .with_unused_gas_weight(1)
+                    .on_eligibility_checked(
+                        account_id,
+                        idempotency_key,
+                        identifier,
+                        password,
+                        payout_token,
+                        env::prepaid_gas(),
+                    ),
+            );
+    }
+
+    #[private]
+    /// Self callback continues the claim if the predicate returned true. Otherwise the key's use isn't consumed.
+    pub fn on_eligibility_checked(
+        &mut self,
+        account_id: AccountId,
+        idempotency_key: Option<String>,
+        identifier: Option<String>,
+        password: Option<String>,
+        payout_token: Option<AccountId>,
+        // GAS attached to the original claim transaction
+        prepaid_gas: Gas,
+    ) {
+        let eligible = promise_result_as_success()
+            .and_then(|result| near_sdk::serde_json::from_slice::<bool>(&result).ok())
+            .unwrap_or(false);
+        if !eligible {
+            near_sdk::log!(
+                "{} isn't eligible to claim. The key's use wasn't consumed.",
+                account_id
+            );
+            self.internal_spend_ineligible_claim_allowance(prepaid_gas);
+            return;
+        }
+
+        // The key may have been deleted or the same claim may have gone through while the predicate was being checked
+        self.internal_assert_claim_origin();
+        if self.internal_is_duplicate_claim(&idempotency_key) {
+            return;
+        }
+        self.internal_claim(
+            account_id,
+            idempotency_key,
+            identifier,
+            password,
+            payout_token,
+            prepaid_gas,
+        );
+    }
+
+    /// The GAS burnt by a claim that was turned down still came out of the access key's allowance
+    fn internal_spend_ineligible_claim_allowance(&mut self, prepaid_gas: Gas) {
+        let signer_pk = env::signer_account_pk();
+        let mut drop = match self
+            .drop_id_for_pk
+            .get(&signer_pk)
+            .and_then(|drop_id| self.drop_for_id.get(&drop_id))
+        {
+            Some(drop) => drop,
+            None => return,
+        };
+        if let Some(mut key_info) = drop.pks.get(&signer_pk) {
+            let amount = (prepaid_gas.0 as u128 * self.yocto_per_gas).min(key_info.allowance);
+            key_info.spend_allowance(amount);
+            near_sdk::log!("Allowance is now {}", key_info.allowance);
+            self.internal_insert_key_info(
+                &mut drop.pks,
+                drop.deposit_per_use,
+                &signer_pk,
+                &key_info,
+            );
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::*;
+
+/// Maximum number of failures kept for each drop. Older failures are rotated out once this is reached.
+pub(crate) const MAX_RECENT_FAILURES: usize = 10;
+/// Maximum number of bytes kept from a failure's error description
+pub(crate) const MAX_FAILURE_ERROR_BYTES: usize = 200;
+
+/// The cross contract call a claim's callback is resolving
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PromiseTarget {
+    // Method that was called (i.e "create_account"). Plain $NEAR transfers are recorded as "transfer".
+    pub method_name: String,
+    // Account the promise was sent to
+    pub receiver_id: AccountId,
+}
+
+/// A failed claim promise captured by its callback so that it can be inspected without the receipt outcome
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PromiseFailure {
+    // Method that was called
+    pub method_name: String,
+    // Account the promise was sent to
+    pub receiver_id: AccountId,
+    // Truncated description of the failure
+    pub error: String,
+    // Block at which the failure was resolved
+    pub block_height: u64,
+}
+
+/// Truncate a string to at most `max_bytes` without splitting a character
+pub(crate) fn truncate_to_bytes(s: &str, max_bytes: usize) -> String {
+    let mut end = s.len().min(max_bytes);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    s[..end].to_string()
+}
+
+impl Keypom {
+    /*
+        Record the failed promise being resolved in the drop's recent failures. The funder's balance pays for
+        the storage and the oldest failure is rotated out once there are MAX_RECENT_FAILURES. If the drop no
+        longer exists or the funder can't cover the storage, the failure is only logged.
+    */
+    pub(crate) fn internal_record_promise_failure(
+        &mut self,
+        drop_id: DropId,
+        target: PromiseTarget,
+    ) {
+        // The runtime doesn't expose the failed receipt's error message to callbacks
+        let error = match env::promise_result(0) {
+            PromiseResult::Failed => "promise failed".to_string(),
+            PromiseResult::NotReady => "promise was not ready".to_string(),
+            PromiseResult::Successful(_) => return,
+        };
+        near_sdk::log!("Recording failure for drop {}: {}", drop_id, error);
+
+        let (owner_id, storage_byte_cost) = match self.drop_for_id.get(&drop_id) {
+            Some(drop) => (drop.owner_id, drop.storage_byte_cost),
+            None => return,
+        };
+
+        // Measure initial storage before doing any operations
+        let initial_storage = env::storage_usage();
+
+        let mut failures = self.recent_failures.get(&drop_id).unwrap_or_default();
+        if failures.len() >= MAX_RECENT_FAILURES {
+            failures.remove(0);
+        }
+        failures.push(PromiseFailure {
+            method_name: target.method_name,
+            receiver_id: target.receiver_id,
+            error: truncate_to_bytes(&error, MAX_FAILURE_ERROR_BYTES),
+            block_height: env::block_height(),
+        });
+        let old_failures = self.recent_failures.insert(&drop_id, &failures);
+
+        // Charge the funder for any extra storage used or refund them if an entry that was rotated out was larger
+        let final_storage = env::storage_usage();
+        let mut cur_balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        if final_storage >= initial_storage {
+            let storage_cost = Balance::from(final_storage - initial_storage) * storage_byte_cost;
+            if cur_balance < storage_cost {
+                near_sdk::log!("Funder can't cover the storage for the failure. Not recording it.");
+                match old_failures {
+                    Some(old_failures) => self.recent_failures.insert(&drop_id, &old_failures),
+                    None => self.recent_failures.remove(&drop_id),
+                };
+                return;
+            }
+            cur_balance -= storage_cost;
+        } else {
+            cur_balance += Balance::from(initial_storage - final_storage) * storage_byte_cost;
+        }
+        self.internal_set_user_balance(&owner_id, cur_balance);
+        self.internal_check_balance_alert(&owner_id, cur_balance);
+    }
+}
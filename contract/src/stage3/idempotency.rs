@@ -0,0 +1,171 @@
+use crate::*;
+
+/// Default amount of time a claim record is kept around before it can be swept
+pub(crate) const DEFAULT_CLAIM_RECORD_TTL: u64 = 600_000_000_000; // 10 minutes
+
+/// Where a claim recorded with an idempotency key is at. Retries are told the outcome of the original claim.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimOutcome {
+    // The claim's promises haven't resolved yet
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// Keep track of a claim that was processed with an idempotency key so retries can be caught
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimRecord {
+    // Drop that the claim was processed for
+    pub drop_id: DropId,
+    // Nonce of the key that was used
+    pub key_id: u64,
+    // How many uses the key had left before the claim was processed
+    pub remaining_uses: u64,
+    // Account the claim was sent to
+    pub account_id: AccountId,
+    // Whether the claim went through, updated once it resolves
+    pub outcome: ClaimOutcome,
+    // Block timestamp at which the claim was processed
+    pub claimed_at: u64,
+    // Block timestamp after which the record can be swept
+    pub expires_at: u64,
+}
+
+/// Hash the public key and idempotency key together so records are scoped to the key being used
+pub(crate) fn hash_claim_record(public_key: &PublicKey, idempotency_key: &str) -> CryptoHash {
+    let mut bytes = public_key.as_bytes().to_vec();
+    bytes.extend_from_slice(idempotency_key.as_bytes());
+    env::sha256_array(&bytes)
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Look at up to `limit` claim records and remove the ones that have expired to reclaim their storage. Each
+        sweep picks up where the last one left off and starts over from the first record once it reaches the end,
+        so no single call has to scan every record. Can be called by anyone.
+    */
+    pub fn sweep_claim_records(&mut self, limit: Option<u64>) -> u64 {
+        let current_timestamp = env::block_timestamp();
+        let limit = limit.unwrap_or(50);
+
+        let mut index = self.claim_record_sweep_cursor;
+        if index >= self.claim_records.len() {
+            index = 0;
+        }
+        let mut num_scanned = 0;
+        let mut num_removed = 0;
+        while num_scanned < limit && index < self.claim_records.len() {
+            num_scanned += 1;
+            let record = self.claim_records.values_as_vector().get(index).unwrap();
+            if record.expires_at > current_timestamp {
+                index += 1;
+                continue;
+            }
+
+            // The last record is swapped into the removed one's place so the same index is looked at next
+            let hash = self.claim_records.keys_as_vector().get(index).unwrap();
+            self.claim_records.remove(&hash);
+            num_removed += 1;
+        }
+        self.claim_record_sweep_cursor = index;
+
+        near_sdk::log!(
+            "Swept {} expired claim records out of {} scanned",
+            num_removed,
+            num_scanned
+        );
+        num_removed
+    }
+
+    /// Returns true if the current claim is a retry of a claim that was already processed. The original claim's
+    /// outcome is emitted so that the retry can report it.
+    pub(crate) fn internal_is_duplicate_claim(&self, idempotency_key: &Option<String>) -> bool {
+        if let Some(key) = idempotency_key {
+            let public_key = env::signer_account_pk();
+            let hash = hash_claim_record(&public_key, key);
+            if let Some(record) = self.claim_records.get(&hash) {
+                if record.expires_at > env::block_timestamp() {
+                    near_sdk::log!("Duplicate claim for idempotency key");
+                    emit_event(EventLogVariant::DuplicateClaim(vec![DuplicateClaimLog {
+                        public_key,
+                        drop_id: record.drop_id,
+                        key_id: record.key_id,
+                        account_id: record.account_id,
+                        outcome: record.outcome,
+                        claimed_at: record.claimed_at,
+                    }]));
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Store a record of the claim being processed. This must be called before any promises are scheduled.
+    /// Claims with nothing to resolve are recorded as succeeded straight away.
+    pub(crate) fn internal_record_claim(
+        &mut self,
+        public_key: &PublicKey,
+        idempotency_key: &str,
+        drop_id: DropId,
+        key_info: &KeyInfo,
+        account_id: &AccountId,
+        will_resolve: bool,
+    ) -> CryptoHash {
+        let claimed_at = env::block_timestamp();
+        let hash = hash_claim_record(public_key, idempotency_key);
+        self.claim_records.insert(
+            &hash,
+            &ClaimRecord {
+                drop_id,
+                key_id: key_info.key_id,
+                remaining_uses: key_info.remaining_uses,
+                account_id: account_id.clone(),
+                outcome: if will_resolve {
+                    ClaimOutcome::Pending
+                } else {
+                    ClaimOutcome::Succeeded
+                },
+                claimed_at,
+                expires_at: claimed_at + self.claim_record_ttl,
+            },
+        );
+        hash
+    }
+
+    /*
+        Point the use being claimed at its claim record so that the record's outcome can be set once the claim
+        resolves. The contract covers the entry's storage since it only lives until the claim resolves.
+    */
+    pub(crate) fn internal_track_claim_record(&mut self, guard: &ClaimGuard, hash: &CryptoHash) {
+        self.claim_record_for_guard.insert(guard, hash);
+    }
+
+    /// Set the outcome of the claim record (if any) for a use that just resolved
+    pub(crate) fn internal_resolve_claim_record(
+        &mut self,
+        guard: &ClaimGuard,
+        claim_succeeded: bool,
+    ) {
+        let hash = match self.claim_record_for_guard.remove(guard) {
+            Some(hash) => hash,
+            None => return,
+        };
+        // The record may have been swept if the claim took longer than its TTL to resolve
+        if let Some(mut record) = self.claim_records.get(&hash) {
+            record.outcome = if claim_succeeded {
+                ClaimOutcome::Succeeded
+            } else {
+                ClaimOutcome::Failed
+            };
+            self.claim_records.insert(&hash, &record);
+        }
+    }
+}
@@ -1,3 +1,29 @@
+mod account_claims;
+mod account_names;
+pub mod badge;
 pub mod claim;
+pub mod claim_guard;
+mod claim_surface;
+pub mod deploy;
+pub mod deposit_split;
+pub mod eligibility;
+pub mod failures;
+pub mod idempotency;
+pub mod post_claim;
+mod proof_of_work;
+pub mod stats;
 
+pub(crate) use account_claims::*;
+pub(crate) use account_names::*;
+pub use badge::*;
 pub use claim::*;
+pub use claim_guard::*;
+pub(crate) use claim_surface::*;
+pub use deploy::*;
+pub(crate) use deposit_split::*;
+pub use eligibility::*;
+pub use failures::*;
+pub use idempotency::*;
+pub use post_claim::*;
+pub(crate) use proof_of_work::*;
+pub use stats::*;
@@ -0,0 +1,92 @@
+use crate::*;
+
+/// Maximum length of the URL claimers are sent to after claiming
+const MAX_REDIRECT_URL_LEN: usize = 512;
+/// Maximum length of the message shown to claimers after claiming
+const MAX_POST_CLAIM_MESSAGE_LEN: usize = 280;
+/// Maximum length of the label for the button that opens the redirect URL
+const MAX_CTA_LABEL_LEN: usize = 32;
+
+/// What wallets should show once a key is claimed. It's only surfaced in views and the claim event and isn't enforced on-chain.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostClaimInfo {
+    // Page to send the claimer to (i.e the funder's app). Must use https.
+    pub redirect_url: Option<String>,
+
+    // Message to show on the success screen
+    pub message: Option<String>,
+
+    // Label for the button that opens the redirect URL. Requires a redirect URL.
+    pub cta_label: Option<String>,
+}
+
+/// Check the lengths and format of the drop's post claim info if it has any
+pub(crate) fn check_post_claim(config: &Option<DropConfig>) -> Result<(), String> {
+    let info = match config.as_ref().and_then(|c| c.post_claim.as_ref()) {
+        Some(info) => info,
+        None => return Ok(()),
+    };
+
+    if info.redirect_url.is_none() && info.message.is_none() {
+        return Err("post claim info needs a redirect URL or a message".to_string());
+    }
+    if let Some(url) = info.redirect_url.as_ref() {
+        if url.len() > MAX_REDIRECT_URL_LEN {
+            return Err(format!(
+                "redirect URL cannot be longer than {} characters",
+                MAX_REDIRECT_URL_LEN
+            ));
+        }
+        if !url.starts_with("https://") || url.chars().any(char::is_whitespace) {
+            return Err("redirect URL must be an https URL".to_string());
+        }
+    }
+    if let Some(message) = info.message.as_ref() {
+        if message.is_empty() || message.chars().count() > MAX_POST_CLAIM_MESSAGE_LEN {
+            return Err(format!(
+                "post claim message must be between 1 and {} characters",
+                MAX_POST_CLAIM_MESSAGE_LEN
+            ));
+        }
+    }
+    if let Some(label) = info.cta_label.as_ref() {
+        if info.redirect_url.is_none() {
+            return Err("cta_label requires a redirect URL".to_string());
+        }
+        if label.is_empty() || label.chars().count() > MAX_CTA_LABEL_LEN {
+            return Err(format!(
+                "cta_label must be between 1 and {} characters",
+                MAX_CTA_LABEL_LEN
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Emit the `claim` event for a key that was just used so wallets can render the drop's success screen.
+/// The gas rebate is only included when the claim is creating the account. `unverified` flags drops calling unreviewed FC receivers.
+pub(crate) fn emit_claim_event(
+    drop_id: DropId,
+    account_id: &AccountId,
+    drop: &Drop,
+    key_info: &KeyInfo,
+    creating_account: bool,
+    unverified: bool,
+) {
+    emit_event(EventLogVariant::Claim(vec![ClaimLog {
+        drop_id,
+        public_key: env::signer_account_pk(),
+        key_id: key_info.key_id,
+        account_id: account_id.clone(),
+        post_claim: drop.config.as_ref().and_then(|c| c.post_claim.clone()),
+        deposit_split: drop.config.as_ref().and_then(|c| c.deposit_split.clone()),
+        gas_rebate: drop
+            .config
+            .as_ref()
+            .and_then(|c| c.gas_rebate)
+            .filter(|_| creating_account),
+        context_tag: key_info.context_tag.clone(),
+        unverified,
+    }]));
+}
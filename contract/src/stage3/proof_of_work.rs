@@ -0,0 +1,73 @@
+use crate::*;
+
+/// Highest difficulty a drop can require. Each extra bit doubles the hashes a claimer needs on average.
+const MAX_POW_DIFFICULTY: u8 = 32;
+
+/// Difficulty the drop's claims must meet. None and 0 both mean no proof of work is needed.
+pub(crate) fn pow_difficulty(config: &Option<DropConfig>) -> u8 {
+    config.as_ref().and_then(|c| c.pow_difficulty).unwrap_or(0)
+}
+
+/// The difficulty must be something a claim page can mine in a reasonable time
+pub(crate) fn check_pow_difficulty(config: &Option<DropConfig>) -> Result<(), String> {
+    if pow_difficulty(config) > MAX_POW_DIFFICULTY {
+        return Err(format!(
+            "pow_difficulty cannot be more than {} bits",
+            MAX_POW_DIFFICULTY
+        ));
+    }
+    Ok(())
+}
+
+/// Number of leading zero bits in a hash
+fn leading_zero_bits(hash: &CryptoHash) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+impl Keypom {
+    /*
+        Panic unless `pow_nonce` makes sha256(public key || receiver || nonce) start with the drop's required number
+        of zero bits. The public key is the signing key's curve byte followed by its raw bytes, the receiver is the
+        account ID's UTF-8 bytes and the nonce is 8 little endian bytes. This runs before any state is changed.
+    */
+    pub(crate) fn internal_assert_proof_of_work(
+        &self,
+        receiver_id: &AccountId,
+        pow_nonce: Option<u64>,
+    ) {
+        let signer_pk = env::signer_account_pk();
+        let difficulty = self
+            .drop_id_for_pk
+            .get(&signer_pk)
+            .and_then(|drop_id| self.drop_for_id.get(&drop_id))
+            .map(|drop| pow_difficulty(&drop.config))
+            .unwrap_or(0);
+        if difficulty == 0 {
+            return;
+        }
+
+        let nonce = pow_nonce.unwrap_or_else(|| env::panic_str("this drop requires a pow_nonce"));
+        let hash = env::sha256_array(
+            &[
+                signer_pk.as_bytes(),
+                receiver_id.as_bytes(),
+                &nonce.to_le_bytes()[..],
+            ]
+            .concat(),
+        );
+        require!(
+            leading_zero_bits(&hash) >= difficulty as u32,
+            &format!(
+                "pow_nonce doesn't meet the drop's difficulty of {} bits",
+                difficulty
+            )
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use crate::*;
+
+/// Aggregate stats for the claims of a drop. They're kept until the drop completes or is deleted.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DropStats {
+    // Funder of the drop so the storage can be refunded once the stats are removed
+    pub owner_id: AccountId,
+    // Number of claims that succeeded
+    pub total_claims: u64,
+    // Total $NEAR sent to claimers
+    pub near_distributed: Balance,
+    // Number of accounts created by claims
+    pub accounts_created: u64,
+    // Total amount of FTs sent to claimers (in the token's smallest units)
+    pub ft_delivered: Balance,
+    // Number of NFTs sent to claimers
+    pub nft_delivered: u64,
+    // When the first and last successful claims were resolved. 0 if there haven't been any.
+    pub first_claim_timestamp: u64,
+    pub last_claim_timestamp: u64,
+    // Claims that were still resolving when the last use of the last key was consumed
+    pub unresolved_claims: u64,
+}
+
+impl DropStats {
+    pub(crate) fn new(owner_id: AccountId) -> Self {
+        Self {
+            owner_id,
+            total_claims: 0,
+            near_distributed: 0,
+            accounts_created: 0,
+            ft_delivered: 0,
+            nft_delivered: 0,
+            first_claim_timestamp: 0,
+            last_claim_timestamp: 0,
+            unresolved_claims: 0,
+        }
+    }
+}
+
+/// What a single successful claim delivered to the claimer
+#[derive(Default)]
+pub(crate) struct ClaimDelivery {
+    pub near: Balance,
+    pub account_created: bool,
+    pub ft: Balance,
+    pub nft: u64,
+}
+
+impl ClaimDelivery {
+    /// $NEAR sent by a claim whose promise called `promise_target`. Accounts are created by the `create_account` methods.
+    pub(crate) fn new(near: Balance, promise_target: Option<&PromiseTarget>) -> Self {
+        Self {
+            near,
+            account_created: promise_target
+                .is_some_and(|target| target.method_name.starts_with("create_account")),
+            ..Default::default()
+        }
+    }
+}
+
+#[near_bindgen]
+impl Keypom {
+    /*
+        Returns how many successful claims there were for each use of the drop's keys: the first entry counts
+        first uses, the second counts second uses and so on. There's one entry for each of the drop's
+        `uses_per_key`. Drops created before this was tracked return all zeros.
+    */
+    pub fn get_use_histogram(&self, drop_id: DropId) -> Vec<u64> {
+        if let Some(histogram) = self.use_histograms.get(&drop_id) {
+            return histogram;
+        }
+        let drop = self.drop_for_id.get(&drop_id).expect("No drop found");
+        vec![0; drop_uses_per_key(&drop.config) as usize]
+    }
+}
+
+impl Keypom {
+    /// Start keeping track of the stats for a new drop's claims. The storage is part of the drop's creation cost.
+    pub(crate) fn internal_start_drop_stats(
+        &mut self,
+        drop_id: DropId,
+        owner_id: AccountId,
+        config: &Option<DropConfig>,
+    ) {
+        self.drop_stats.insert(&drop_id, &DropStats::new(owner_id));
+        self.use_histograms
+            .insert(&drop_id, &vec![0; drop_uses_per_key(config) as usize]);
+    }
+
+    /*
+        Add a resolved claim to the drop's stats. If the drop was already removed because the last use of its
+        last key was consumed, the claim was one of the unresolved ones and the drop is complete once they've
+        all resolved. The storage freed at that point is refunded to the funder.
+    */
+    pub(crate) fn internal_resolve_claim_stats(
+        &mut self,
+        drop_id: DropId,
+        drop_exists: bool,
+        use_number: u64,
+        delivery: Option<ClaimDelivery>,
+    ) {
+        if delivery.is_some() {
+            self.internal_record_use(drop_id, use_number);
+        }
+
+        let mut stats = match self.drop_stats.get(&drop_id) {
+            Some(stats) => stats,
+            None => return,
+        };
+
+        if let Some(delivery) = delivery {
+            let current_timestamp = env::block_timestamp();
+            if stats.total_claims == 0 {
+                stats.first_claim_timestamp = current_timestamp;
+            }
+            stats.last_claim_timestamp = current_timestamp;
+            stats.total_claims += 1;
+            stats.near_distributed += delivery.near;
+            stats.ft_delivered += delivery.ft;
+            stats.nft_delivered += delivery.nft;
+            if delivery.account_created {
+                stats.accounts_created += 1;
+            }
+        }
+
+        if drop_exists {
+            self.drop_stats.insert(&drop_id, &stats);
+            return;
+        }
+
+        stats.unresolved_claims = stats.unresolved_claims.saturating_sub(1);
+        if stats.unresolved_claims > 0 {
+            self.drop_stats.insert(&drop_id, &stats);
+            return;
+        }
+
+        // Measure initial storage before doing any operations
+        let initial_storage = env::storage_usage();
+        let owner_id = stats.owner_id.clone();
+        self.internal_close_drop_stats(drop_id, stats, true);
+
+        let final_storage = env::storage_usage();
+        let storage_freed =
+            Balance::from(initial_storage - final_storage) * env::storage_byte_cost();
+        let mut cur_balance = self.user_balances.get(&owner_id).unwrap_or(0);
+        cur_balance += storage_freed;
+        self.internal_set_user_balance(&owner_id, cur_balance);
+    }
+
+    /*
+        Called when the last use of a drop's last key was consumed. The `drop_complete` event is emitted right
+        away unless some claims are still resolving, in which case the last one to resolve emits it.
+    */
+    pub(crate) fn internal_complete_drop_stats(&mut self, drop_id: DropId, pending_claims: u64) {
+        let mut stats = match self.drop_stats.get(&drop_id) {
+            Some(stats) => stats,
+            None => return,
+        };
+
+        if pending_claims > 0 {
+            stats.unresolved_claims = pending_claims;
+            self.drop_stats.insert(&drop_id, &stats);
+        } else {
+            self.internal_close_drop_stats(drop_id, stats, true);
+        }
+    }
+
+    /// Remove a drop's stats when it's deleted. The `drop_complete` event is only emitted if it was claimed at all.
+    pub(crate) fn internal_remove_drop_stats(&mut self, drop_id: DropId, pending_claims: u64) {
+        if let Some(stats) = self.drop_stats.get(&drop_id) {
+            if stats.total_claims + pending_claims > 0 {
+                self.internal_close_drop_stats(drop_id, stats, false);
+            } else {
+                self.drop_stats.remove(&drop_id);
+                self.use_histograms.remove(&drop_id);
+            }
+        }
+    }
+
+    /*
+        Count a successful claim of a use in the drop's histogram. Keys that override the drop's `uses_per_key`
+        with more uses have those extra uses counted in the last entry. Drops without a histogram aren't tracked.
+    */
+    fn internal_record_use(&mut self, drop_id: DropId, use_number: u64) {
+        let mut histogram = match self.use_histograms.get(&drop_id) {
+            Some(histogram) if !histogram.is_empty() => histogram,
+            _ => return,
+        };
+        let index = (use_number.max(1) as usize - 1).min(histogram.len() - 1);
+        histogram[index] += 1;
+        self.use_histograms.insert(&drop_id, &histogram);
+    }
+
+    /// Remove the stats and emit them in a `drop_complete` event
+    fn internal_close_drop_stats(&mut self, drop_id: DropId, stats: DropStats, completed: bool) {
+        self.drop_stats.remove(&drop_id);
+        let use_histogram = self.use_histograms.remove(&drop_id).unwrap_or_default();
+
+        let first_claim_timestamp =
+            Some(stats.first_claim_timestamp).filter(|_| stats.total_claims > 0);
+        let last_claim_timestamp =
+            Some(stats.last_claim_timestamp).filter(|_| stats.total_claims > 0);
+        emit_event(EventLogVariant::DropComplete(vec![DropCompleteLog {
+            owner_id: stats.owner_id,
+            drop_id,
+            completed,
+            total_claims: stats.total_claims,
+            near_distributed: U128(stats.near_distributed),
+            accounts_created: stats.accounts_created,
+            ft_delivered: U128(stats.ft_delivered),
+            nft_delivered: stats.nft_delivered,
+            first_claim_timestamp: first_claim_timestamp.map(U64),
+            last_claim_timestamp: last_claim_timestamp.map(U64),
+            duration: U64(stats.last_claim_timestamp - stats.first_claim_timestamp),
+            use_histogram,
+        }]));
+    }
+}
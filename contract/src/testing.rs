@@ -0,0 +1,238 @@
+use crate::*;
+use std::convert::TryFrom;
+
+/*
+    Helpers for writing sandbox tests against the contract. Everything in this module is
+    behind the `testing` feature and is compiled out of the production wasm.
+*/
+
+/// Deterministically derive `count` ed25519 public keys from a seed. The keys are only
+/// meant to be used as drop keys in tests where no transactions need to be signed with them.
+pub fn deterministic_public_keys(seed: &str, count: u64) -> Vec<PublicKey> {
+    (0..count)
+        .map(|index| {
+            // Prefix with the ed25519 curve type followed by 32 bytes of key data
+            let mut bytes = vec![0u8];
+            bytes.extend_from_slice(&env::sha256_array(format!("{}-{}", seed, index).as_bytes()));
+            PublicKey::try_from(bytes).unwrap()
+        })
+        .collect()
+}
+
+/// Builder for creating drop configs in tests without spelling out every field
+#[derive(Default)]
+pub struct DropConfigBuilder {
+    config: DropConfig,
+}
+
+impl DropConfig {
+    pub fn builder() -> DropConfigBuilder {
+        DropConfigBuilder::default()
+    }
+}
+
+impl DropConfigBuilder {
+    pub fn uses_per_key(mut self, uses_per_key: u64) -> Self {
+        self.config.uses_per_key = Some(uses_per_key);
+        self
+    }
+
+    pub fn start_timestamp(mut self, start_timestamp: u64) -> Self {
+        self.config.start_timestamp = Some(start_timestamp);
+        self
+    }
+
+    pub fn throttle_timestamp(mut self, throttle_timestamp: u64) -> Self {
+        self.config.throttle_timestamp = Some(throttle_timestamp);
+        self
+    }
+
+    pub fn throttle_from_addition(mut self, throttle_from_addition: bool) -> Self {
+        self.config.throttle_from_addition = Some(throttle_from_addition);
+        self
+    }
+
+    pub fn on_claim_refund_deposit(mut self, on_claim_refund_deposit: bool) -> Self {
+        self.config.on_claim_refund_deposit = Some(on_claim_refund_deposit);
+        self
+    }
+
+    pub fn claim_permission(mut self, claim_permission: ClaimPermissions) -> Self {
+        self.config.claim_permission = Some(claim_permission);
+        self
+    }
+
+    pub fn drop_root(mut self, drop_root: AccountId) -> Self {
+        self.config.drop_root = Some(drop_root);
+        self
+    }
+
+    pub fn early_bonus(mut self, num_bonus_claims: u64, amount: U128) -> Self {
+        self.config.early_bonus = Some((num_bonus_claims, amount));
+        self
+    }
+
+    pub fn max_total_claims(mut self, max_total_claims: u64) -> Self {
+        self.config.max_total_claims = Some(max_total_claims);
+        self
+    }
+
+    pub fn wrap_contract(mut self, wrap_contract: AccountId) -> Self {
+        self.config.wrap_contract = Some(wrap_contract);
+        self
+    }
+
+    pub fn deploy(mut self, deploy: DeployConfig) -> Self {
+        self.config.deploy = Some(deploy);
+        self
+    }
+
+    pub fn alternative_payouts(mut self, alternative_payouts: Vec<AccountId>) -> Self {
+        self.config.alternative_payouts = Some(alternative_payouts);
+        self
+    }
+
+    pub fn deposit_split(mut self, deposit_split: Vec<(AccountId, u16)>) -> Self {
+        self.config.deposit_split = Some(deposit_split);
+        self
+    }
+
+    pub fn unlisted(mut self, unlisted: bool) -> Self {
+        self.config.unlisted = Some(unlisted);
+        self
+    }
+
+    pub fn post_claim(mut self, post_claim: PostClaimInfo) -> Self {
+        self.config.post_claim = Some(post_claim);
+        self
+    }
+
+    pub fn allow_distant_timestamps(mut self, allow_distant_timestamps: bool) -> Self {
+        self.config.allow_distant_timestamps = Some(allow_distant_timestamps);
+        self
+    }
+
+    pub fn gas_rebate(mut self, gas_rebate: U128) -> Self {
+        self.config.gas_rebate = Some(gas_rebate);
+        self
+    }
+
+    pub fn eligibility_check(mut self, eligibility_check: EligibilityCheck) -> Self {
+        self.config.eligibility_check = Some(eligibility_check);
+        self
+    }
+
+    pub fn mint_badge(mut self, mint_badge: bool) -> Self {
+        self.config.mint_badge = Some(mint_badge);
+        self
+    }
+
+    pub fn key_commitment(mut self, key_commitment: Base64VecU8) -> Self {
+        self.config.key_commitment = Some(key_commitment);
+        self
+    }
+
+    pub fn pow_difficulty(mut self, pow_difficulty: u8) -> Self {
+        self.config.pow_difficulty = Some(pow_difficulty);
+        self
+    }
+
+    pub fn auto_account_names(mut self) -> Self {
+        self.config.auto_account_names = Some(true);
+        self
+    }
+
+    pub fn compaction_bounty(mut self, num_bounties: u64, amount: U128) -> Self {
+        self.config.compaction_bounty = Some((num_bounties, amount));
+        self
+    }
+
+    pub fn end_timestamp(mut self, end_timestamp: u64) -> Self {
+        self.config.end_timestamp = Some(end_timestamp);
+        self
+    }
+
+    pub fn metadata_abstract(mut self, metadata_abstract: &str) -> Self {
+        self.config.metadata_abstract = Some(metadata_abstract.to_string());
+        self
+    }
+
+    pub fn use_labels(mut self, use_labels: &[&str]) -> Self {
+        self.config.use_labels = Some(use_labels.iter().map(|l| l.to_string()).collect());
+        self
+    }
+
+    pub fn max_claims_per_account(mut self, max_claims_per_account: u64) -> Self {
+        self.config.max_claims_per_account = Some(max_claims_per_account);
+        self
+    }
+
+    pub fn build(self) -> DropConfig {
+        self.config
+    }
+}
+
+impl FCData {
+    /// Single method with no deposit that is called for every use of the key
+    pub fn fixture(receiver_id: AccountId, method_name: &str) -> Self {
+        FCData {
+            methods: vec![Some(vec![MethodData {
+                receiver_id,
+                method_name: method_name.to_string(),
+                args: "{}".to_string(),
+                attached_deposit: U128(0),
+            }])],
+            config: None,
+        }
+    }
+}
+
+impl FTDataConfig {
+    /// Send 1 whole token (assuming 24 decimals) per use
+    pub fn fixture(contract_id: AccountId, sender_id: AccountId) -> Self {
+        FTDataConfig {
+            contract_id,
+            sender_id,
+            balance_per_use: U128(1_000_000_000_000_000_000_000_000),
+            balance_per_use_human: None,
+            accumulate_dust: false,
+            verify_received_balance: false,
+        }
+    }
+}
+
+impl NFTDataConfig {
+    /// Token IDs of up to 64 characters
+    pub fn fixture(contract_id: AccountId, sender_id: AccountId) -> Self {
+        NFTDataConfig {
+            sender_id,
+            contract_id,
+            longest_token_id: "a".repeat(64),
+            tokens_per_use: None,
+        }
+    }
+
+    /// Hand over a pack of token IDs with every claim
+    pub fn with_tokens_per_use(mut self, tokens_per_use: u64) -> Self {
+        self.tokens_per_use = Some(tokens_per_use);
+        self
+    }
+}
+
+#[near_bindgen]
+impl Keypom {
+    /// Initialize the contract with user balances already funded so tests don't need to call `add_to_balance`.
+    /// The balances aren't backed by any deposit so this must never be used outside of the sandbox.
+    #[init]
+    pub fn test_new_with_balances(
+        root_account: AccountId,
+        owner_id: AccountId,
+        balances: Vec<(AccountId, U128)>,
+    ) -> Self {
+        let mut contract = Self::new(root_account, owner_id);
+        for (account_id, balance) in balances {
+            contract.internal_set_user_balance(&account_id, balance.0);
+        }
+        contract
+    }
+}
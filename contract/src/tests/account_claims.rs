@@ -0,0 +1,62 @@
+use super::*;
+
+fn nft_drop_with_limit(contract: &mut Keypom, pks: Vec<PublicKey>, tokens: u64) -> DropId {
+    let nft_contract = account("nft");
+    let args = DropArgs {
+        config: Some(DropConfig::builder().max_claims_per_account(1).build()),
+        nft_data: Some(NFTDataConfig::fixture(nft_contract.clone(), funder_id())),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(contract, pks, args);
+    set_caller(&nft_contract, 0);
+    for i in 0..tokens {
+        contract.nft_on_transfer(
+            funder_id(),
+            funder_id(),
+            format!("token-{}", i),
+            drop_id.to_string(),
+        );
+    }
+    drop_id
+}
+
+#[test]
+fn account_can_only_claim_up_to_the_limit() {
+    let mut contract = setup();
+    let pks = keys("limit", 3);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().max_claims_per_account(1).build()),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+
+    claim(&mut contract, &pks[0], account("alice"));
+    claim(&mut contract, &pks[1], account("alice"));
+    claim(&mut contract, &pks[2], account("bob"));
+
+    assert_eq!(
+        contract.get_claims_for_account(drop_id, account("alice")),
+        1
+    );
+    assert_eq!(contract.get_claims_for_account(drop_id, account("bob")), 1);
+    // The rejected claim didn't consume the key's use
+    assert_eq!(key_info(&contract, &pks[1]).remaining_uses, 1);
+    assert_eq!(registered_uses(&contract, drop_id), 1);
+}
+
+#[test]
+fn rejected_claim_keeps_the_drops_nfts() {
+    let mut contract = setup();
+    let pks = keys("limit-nft", 2);
+    let drop_id = nft_drop_with_limit(&mut contract, pks.clone(), 2);
+
+    claim(&mut contract, &pks[0], account("alice"));
+    claim(&mut contract, &pks[1], account("alice"));
+
+    assert_eq!(registered_uses(&contract, drop_id), 1);
+    assert_eq!(contract.get_nft_supply_for_drop(drop_id), 1);
+
+    // The token is still there for the next account to claim
+    claim(&mut contract, &pks[1], account("bob"));
+    assert!(contract.get_key_information(pks[1].clone()).is_none());
+}
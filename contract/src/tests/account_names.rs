@@ -0,0 +1,100 @@
+/*
+    Drops with auto_account_names let keys call `create_account_and_claim` without a new account ID. The contract
+    names the account `k<key_id>-<drop_id>.<root>`, which has to fit in an account ID for every possible key.
+*/
+use super::*;
+use near_sdk::test_utils::get_logs;
+
+fn auto_names_args(config: DropConfigBuilder) -> DropArgs {
+    DropArgs {
+        config: Some(config.auto_account_names().build()),
+        ..DropArgs::simple()
+    }
+}
+
+fn create_account_without_name(contract: &mut Keypom, public_key: &PublicKey) {
+    set_create_account_signer(contract, public_key);
+    contract.create_account_and_claim(
+        None,
+        keys("new-account", 1).remove(0),
+        None,
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn names_for_very_large_ids_must_fit() {
+    let max_key_id = u64::MAX;
+    // k18446744073709551615-<drop ID>.near is 64 characters with a 37 digit drop ID
+    let longest_drop_id = 10u128.pow(36);
+
+    let name = generated_account_id(max_key_id, longest_drop_id, &root_id()).unwrap();
+    assert_eq!(name.as_str().len(), 64);
+    assert_eq!(
+        generated_account_id(max_key_id, longest_drop_id * 10, &root_id()),
+        Err(format!(
+            "generated account ID k{}-{}.near is longer than 64 characters",
+            max_key_id,
+            longest_drop_id * 10
+        ))
+    );
+    assert_eq!(
+        generated_account_id(3, 7, &account("app")).unwrap(),
+        "k3-7.app.near".parse::<AccountId>().unwrap()
+    );
+}
+
+#[test]
+fn drops_whose_names_could_be_too_long_are_rejected() {
+    let contract = setup();
+    let long_root: AccountId = format!("{}.near", "a".repeat(40)).parse().unwrap();
+
+    let errors = validation_errors(
+        &contract,
+        keys("long-root", 1),
+        auto_names_args(DropConfig::builder().drop_root(long_root)),
+    );
+    assert_eq!(errors[0].field, "config.auto_account_names");
+
+    let errors = validation_errors(
+        &contract,
+        keys("claim-only", 1),
+        auto_names_args(DropConfig::builder().claim_permission(ClaimPermissions::Claim)),
+    );
+    assert_eq!(
+        errors[0].message,
+        "auto_account_names requires keys that can call create_account_and_claim"
+    );
+}
+
+#[test]
+fn account_is_named_by_the_contract() {
+    let mut contract = setup();
+    let pks = keys("auto-names", 2);
+    let drop_id = create_drop(
+        &mut contract,
+        pks.clone(),
+        auto_names_args(DropConfig::builder()),
+    );
+    let key_id = key_info(&contract, &pks[1]).key_id;
+
+    create_account_without_name(&mut contract, &pks[1]);
+
+    let expected = format!("k{}-{}.near", key_id, drop_id);
+    let logs = get_logs();
+    assert!(logs.contains(&format!("Generated account ID {}", expected)));
+    assert!(logs.iter().any(|log| log.starts_with("EVENT_JSON:")
+        && log.contains(&format!(r#""account_id":"{}""#, expected))));
+}
+
+#[test]
+#[should_panic(expected = "new_account_id is required unless the drop has auto_account_names")]
+fn account_name_is_required_without_auto_names() {
+    let mut contract = setup();
+    let pks = keys("named", 1);
+    create_drop(&mut contract, pks.clone(), DropArgs::simple());
+
+    create_account_without_name(&mut contract, &pks[0]);
+}
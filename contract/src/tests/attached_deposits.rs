@@ -0,0 +1,101 @@
+/*
+    Deposits attached to `create_drop` and `add_keys` are credited to the caller's balance before the drop is paid
+    for, so accounts without a balance can fund drops in a single transaction. Anything left over stays in the
+    balance.
+*/
+use super::*;
+
+/// Same length as `funder.near` so that both accounts' drops take up the same storage
+fn newcomer_id() -> AccountId {
+    account("newbie")
+}
+
+/// Create a simple drop as `caller` and return what it cost them, paid from their balance and `attached_deposit`
+fn create_drop_as(
+    contract: &mut Keypom,
+    caller: &AccountId,
+    seed: &str,
+    attached_deposit: Balance,
+) -> (DropId, Balance) {
+    let balance_before = user_balance(contract, caller) + attached_deposit;
+    set_caller(caller, attached_deposit);
+    let drop_id = contract.create_drop(
+        key_args(keys(seed, 1)),
+        U128(ONE_NEAR),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+    (drop_id, balance_before - user_balance(contract, caller))
+}
+
+fn add_key_as(
+    contract: &mut Keypom,
+    caller: &AccountId,
+    drop_id: DropId,
+    seed: &str,
+    attached_deposit: Balance,
+) {
+    set_caller(caller, attached_deposit);
+    contract.add_keys(key_args(keys(seed, 1)), drop_id, None, None);
+}
+
+#[test]
+fn add_to_balance_creates_the_balance() {
+    let mut contract = setup();
+    assert!(contract.user_balances.get(&newcomer_id()).is_none());
+
+    set_caller(&newcomer_id(), 5 * ONE_NEAR);
+    contract.add_to_balance();
+    set_caller(&newcomer_id(), ONE_NEAR);
+    contract.add_to_balance();
+
+    assert_eq!(user_balance(&contract, &newcomer_id()), 6 * ONE_NEAR);
+}
+
+#[test]
+fn new_account_funds_a_drop_with_exactly_what_it_costs() {
+    let mut contract = setup();
+    let (_, cost) = create_drop_as(&mut contract, &funder_id(), "funder", 0);
+
+    let (drop_id, spent) = create_drop_as(&mut contract, &newcomer_id(), "newcomer", cost);
+
+    assert_eq!(spent, cost);
+    assert_eq!(user_balance(&contract, &newcomer_id()), 0);
+    assert_eq!(
+        contract.get_drop_information(Some(drop_id), None).owner_id,
+        newcomer_id()
+    );
+}
+
+#[test]
+fn attached_deposit_pays_for_added_keys_and_the_surplus_is_kept() {
+    let mut contract = setup();
+    let (funder_drop, _) = create_drop_as(&mut contract, &funder_id(), "funder", 0);
+    let balance_before = user_balance(&contract, &funder_id());
+    add_key_as(&mut contract, &funder_id(), funder_drop, "funder-added", 0);
+    let key_cost = balance_before - user_balance(&contract, &funder_id());
+
+    let (drop_id, _) = create_drop_as(&mut contract, &newcomer_id(), "newcomer", 10 * ONE_NEAR);
+    let balance_before = user_balance(&contract, &newcomer_id());
+    add_key_as(
+        &mut contract,
+        &newcomer_id(),
+        drop_id,
+        "newcomer-added",
+        3 * ONE_NEAR,
+    );
+
+    assert_eq!(
+        user_balance(&contract, &newcomer_id()),
+        balance_before + 3 * ONE_NEAR - key_cost
+    );
+    assert_eq!(contract.get_key_supply_for_drop(drop_id), 2);
+}
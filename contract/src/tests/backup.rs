@@ -0,0 +1,54 @@
+/*
+    Drops exported with `export_drop` can be recreated with `import_drop`, i.e after moving to another deployment
+*/
+use super::*;
+
+fn export(contract: &Keypom, drop_id: DropId) -> String {
+    let mut bundle = contract.export_drop(drop_id, None, None);
+    // Only the ID changes when a drop is imported
+    bundle.drop_id = 0;
+    near_sdk::serde_json::to_string(&bundle).unwrap()
+}
+
+#[test]
+fn exported_drops_are_imported_as_they_were() {
+    let mut contract = setup();
+    let pks = keys("backup", 3);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().uses_per_key(2).build()),
+        metadata: Some("{\"title\":\"Backed up\"}".to_string()),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+    claim(&mut contract, &pks[0], account("alice"));
+    let exported = export(&contract, drop_id);
+
+    // Recreate the drop from the bundle once the original is gone
+    set_caller(&funder_id(), 1);
+    contract.delete_drop(drop_id, Some(true));
+    set_caller(&owner_id(), 1);
+    let imported_id =
+        contract.import_drop(near_sdk::serde_json::from_str(&exported).unwrap(), None);
+
+    assert_ne!(imported_id, drop_id);
+    assert_eq!(export(&contract, imported_id), exported);
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 1);
+    assert_eq!(contract.drop_id_for_pk.get(&pks[2]), Some(imported_id));
+}
+
+#[test]
+fn imports_charge_the_drop_owner() {
+    let mut contract = setup();
+    let pks = keys("backup", 2);
+    let drop_id = create_drop(&mut contract, pks, DropArgs::simple());
+    let bundle = contract.export_drop(drop_id, None, None);
+    set_caller(&funder_id(), 1);
+    contract.delete_drop(drop_id, Some(true));
+    let balance = user_balance(&contract, &funder_id());
+
+    set_caller(&owner_id(), 1);
+    contract.import_drop(bundle, None);
+
+    // At least the deposits for both keys are backed again
+    assert!(balance - user_balance(&contract, &funder_id()) > 2 * ONE_NEAR);
+}
@@ -0,0 +1,74 @@
+/*
+    Claims are charged the allowance for the GAS they attach rather than the pessimistic amount reserved per key
+*/
+use super::*;
+
+const EXTRA_GAS: Gas = Gas(50_000_000_000_000);
+
+fn two_use_keys(contract: &mut Keypom, count: u64) -> Vec<PublicKey> {
+    let pks = keys("gas", count);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().uses_per_key(2).build()),
+        ..DropArgs::simple()
+    };
+    create_drop(contract, pks.clone(), args);
+    pks
+}
+
+#[test]
+fn claims_with_extra_gas_only_spend_what_they_attached() {
+    let mut contract = setup();
+    let pks = two_use_keys(&mut contract, 2);
+    let required_gas = required_gas_for_key(&contract, &pks[0]);
+    let allowance = key_info(&contract, &pks[0]).allowance;
+
+    claim_with_gas(&mut contract, &pks[0], account("alice"), required_gas);
+    claim_with_gas(
+        &mut contract,
+        &pks[1],
+        account("bob"),
+        required_gas + EXTRA_GAS,
+    );
+
+    let low = key_info(&contract, &pks[0]);
+    let high = key_info(&contract, &pks[1]);
+    assert_eq!(low.remaining_uses, 1);
+    assert_eq!(high.remaining_uses, 1);
+    // The surplus reserved for the key stays on it
+    assert_eq!(
+        low.allowance,
+        allowance - required_gas.0 as u128 * contract.yocto_per_gas
+    );
+    assert_eq!(
+        low.allowance - high.allowance,
+        EXTRA_GAS.0 as u128 * contract.yocto_per_gas
+    );
+}
+
+#[test]
+fn claims_with_too_little_gas_are_rejected() {
+    let mut contract = setup();
+    let pks = two_use_keys(&mut contract, 1);
+    let required_gas = required_gas_for_key(&contract, &pks[0]);
+
+    claim_with_gas(
+        &mut contract,
+        &pks[0],
+        account("alice"),
+        Gas(required_gas.0 - 1),
+    );
+
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 2);
+}
+
+#[test]
+fn claims_with_more_gas_than_the_allowance_are_rejected() {
+    let mut contract = setup();
+    let pks = two_use_keys(&mut contract, 1);
+    let allowance = key_info(&contract, &pks[0]).allowance;
+    let gas = Gas((allowance / contract.yocto_per_gas) as u64 + 1);
+
+    claim_with_gas(&mut contract, &pks[0], account("alice"), gas);
+
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 2);
+}
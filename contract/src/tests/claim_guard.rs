@@ -0,0 +1,90 @@
+/*
+    Each use of a key can only be claimed once while its promises are in flight. The guard is written before any
+    promise is scheduled and cleared by the claim's callback.
+*/
+use super::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+fn rejected_claim(contract: &mut Keypom) -> String {
+    let panic = catch_unwind(AssertUnwindSafe(|| {
+        contract.claim(account("alice"), None, None, None, None, None)
+    }))
+    .expect_err("second claim went through");
+    panic.downcast_ref::<String>().cloned().unwrap_or_default()
+}
+
+#[test]
+fn claiming_a_single_use_key_twice_in_one_block_pays_out_once() {
+    let mut contract = setup();
+    let pks = keys("guard", 1);
+    create_drop(&mut contract, pks.clone(), DropArgs::simple());
+
+    // Both claims are made in the same block, before the first one resolves
+    set_key_signer(&pks[0], required_gas_for_key(&contract, &pks[0]));
+    contract.claim(account("alice"), None, None, None, None, None);
+    assert_eq!(
+        rejected_claim(&mut contract),
+        "claims must be signed by the drop key being claimed"
+    );
+
+    assert_eq!(near_sent_to(&account("alice")), ONE_NEAR);
+}
+
+#[test]
+fn use_being_claimed_is_rejected_even_if_its_key_wasnt_decremented() {
+    let mut contract = setup();
+    let pks = keys("guard", 1);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().uses_per_key(2).build()),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+    set_key_signer(&pks[0], required_gas_for_key(&contract, &pks[0]));
+    contract.claim(account("alice"), None, None, None, None, None);
+
+    // Put the key back as it was before the claim, as if its uses were decremented after scheduling the payout
+    let mut drop = contract.drop_for_id.get(&drop_id).unwrap();
+    let mut key = drop.pks.get(&pks[0]).unwrap();
+    key.remaining_uses = 2;
+    drop.pks.insert(&pks[0], &key);
+    contract.drop_for_id.insert(&drop_id, &drop);
+
+    let message = rejected_claim(&mut contract);
+    assert_eq!(
+        message,
+        format!(
+            "use 1 of key {} in drop {} is already being claimed",
+            key.key_id, drop_id
+        )
+    );
+}
+
+#[test]
+fn guard_is_cleared_once_the_claim_resolves() {
+    let mut contract = setup();
+    let pks = keys("guard", 1);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+    claim(&mut contract, &pks[0], account("alice"));
+    let guard = ClaimGuard {
+        drop_id,
+        key_id: 0,
+        use_number: 1,
+    };
+    assert!(contract.claims_in_flight.contains(&guard));
+
+    set_callback(vec![PromiseResult::Successful(vec![])]);
+    contract.on_claim_simple(
+        funder_id(),
+        U128(ONE_NEAR),
+        0,
+        drop_id,
+        guard.clone(),
+        PromiseTarget {
+            method_name: "transfer".to_string(),
+            receiver_id: account("alice"),
+        },
+        vec![],
+    );
+
+    assert!(!contract.claims_in_flight.contains(&guard));
+}
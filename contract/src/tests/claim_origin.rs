@@ -0,0 +1,66 @@
+/*
+    Claims are only honored when the transaction is signed by the drop key being claimed. Anything else is
+    rejected before the key, the drop or any balance is touched.
+*/
+use super::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Run a claim that must be rejected and return the message it was rejected with
+fn rejected_claim(contract: &mut Keypom, call: impl FnOnce(&mut Keypom)) -> String {
+    let panic = catch_unwind(AssertUnwindSafe(|| call(contract))).expect_err("claim went through");
+    panic.downcast_ref::<String>().cloned().unwrap_or_default()
+}
+
+#[test]
+fn claim_from_unrelated_account_is_rejected() {
+    let mut contract = setup();
+    let pks = keys("origin", 1);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+
+    // Alice signs with her own key and passes in the drop key's account
+    testing_env!(context(&account("alice"))
+        .signer_account_pk(pks[0].clone())
+        .prepaid_gas(required_gas_for_key(&contract, &pks[0]))
+        .build());
+    let message = rejected_claim(&mut contract, |contract| {
+        contract.claim(account("alice"), None, None, None, None, None)
+    });
+
+    assert_eq!(
+        message,
+        "claims must be signed by a drop key on this contract"
+    );
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 1);
+    assert_eq!(registered_uses(&contract, drop_id), 1);
+    assert_eq!(user_balance(&contract, &account("alice")), 0);
+}
+
+#[test]
+fn claim_signed_by_another_key_is_rejected() {
+    let mut contract = setup();
+    let pks = keys("origin", 1);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+
+    // Signed on the contract account but not with a drop key
+    set_key_signer(
+        &keys("stranger", 1)[0],
+        required_gas_for_key(&contract, &pks[0]),
+    );
+    let message = rejected_claim(&mut contract, |contract| {
+        contract.create_account_and_claim(
+            Some(account("alice")),
+            keys("alice", 1).remove(0),
+            None,
+            None,
+            None,
+            None,
+        )
+    });
+
+    assert_eq!(
+        message,
+        "claims must be signed by the drop key being claimed"
+    );
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 1);
+    assert_eq!(registered_uses(&contract, drop_id), 1);
+}
@@ -0,0 +1,57 @@
+/*
+    Access keys can only call the claim surface methods marked as key callable. The build fails if one of those is
+    ever payable, touches balances or requires 1 yoctoNEAR, and the same checks are run here on made up methods.
+*/
+use super::*;
+
+fn key_callable(name: &'static str, payable: bool, mutates_balances: bool) -> ClaimSurfaceMethod {
+    ClaimSurfaceMethod {
+        name,
+        key_permission: Some(ClaimPermissions::Claim),
+        payable,
+        mutates_balances,
+    }
+}
+
+#[test]
+fn access_keys_only_get_key_callable_methods() {
+    let key_callable: Vec<&str> = CLAIM_SURFACE
+        .iter()
+        .filter(|method| method.key_permission.is_some())
+        .map(|method| method.name)
+        .collect();
+
+    assert_eq!(
+        access_key_method_names(&ClaimPermissions::Both),
+        key_callable.join(",")
+    );
+    for method in CLAIM_SURFACE.iter().filter(|m| m.key_permission.is_some()) {
+        assert!(!method.payable && !method.mutates_balances);
+        assert!(!ONE_YOCTO_METHODS.contains(&method.name));
+    }
+}
+
+#[test]
+#[should_panic(
+    expected = "drop keys cannot be allowed to call payable or balance mutating methods"
+)]
+fn payable_key_callable_method_is_rejected() {
+    assert_key_callable_methods_are_safe(&[key_callable("claim_signed", true, false)]);
+}
+
+#[test]
+#[should_panic(
+    expected = "drop keys cannot be allowed to call payable or balance mutating methods"
+)]
+fn balance_mutating_key_callable_method_is_rejected() {
+    assert_key_callable_methods_are_safe(&[key_callable("redeem_pending", false, true)]);
+}
+
+#[test]
+#[should_panic(expected = "methods that require 1 yoctoNEAR cannot be called by drop keys")]
+fn one_yocto_key_callable_method_is_rejected() {
+    assert_one_yocto_methods_not_key_callable(
+        &[key_callable("withdraw_from_balance", false, false)],
+        ONE_YOCTO_METHODS,
+    );
+}
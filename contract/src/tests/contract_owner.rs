@@ -0,0 +1,40 @@
+/*
+    Every owner method checks against the owner set with `set_owner`, so transferring ownership moves the admin role
+*/
+use super::*;
+
+fn transfer_ownership(contract: &mut Keypom, new_owner: AccountId) {
+    set_caller(&owner_id(), 1);
+    contract.set_owner(new_owner);
+}
+
+#[test]
+fn new_owner_can_call_owner_methods() {
+    let mut contract = setup();
+    transfer_ownership(&mut contract, account("new-owner"));
+    assert_eq!(contract.get_owner(), account("new-owner"));
+
+    set_caller(&account("new-owner"), 1);
+    contract.set_fees_per_user(funder_id(), U128(0), U128(0));
+
+    assert_eq!(contract.get_effective_fees(funder_id()), (U128(0), U128(0)));
+}
+
+#[test]
+#[should_panic(expected = "predecessor != owner")]
+fn old_owner_cannot_call_owner_methods() {
+    let mut contract = setup();
+    transfer_ownership(&mut contract, account("new-owner"));
+
+    set_caller(&owner_id(), 1);
+    contract.set_fees_per_user(funder_id(), U128(0), U128(0));
+}
+
+#[test]
+#[should_panic(expected = "predecessor != owner")]
+fn only_owner_can_transfer_ownership() {
+    let mut contract = setup();
+
+    set_caller(&funder_id(), 1);
+    contract.set_owner(funder_id());
+}
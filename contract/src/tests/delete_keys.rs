@@ -0,0 +1,24 @@
+/*
+    Funders delete keys they no longer need with `delete_keys`, either by public key or a batch at a time
+*/
+use super::*;
+
+fn delete_keys(contract: &mut Keypom, drop_id: DropId, limit: u64) {
+    set_caller(&funder_id(), 1);
+    contract.delete_keys(drop_id, None, Some(limit));
+}
+
+#[test]
+fn keys_are_deleted_up_to_the_limit() {
+    let mut contract = setup();
+    let pks = keys("delete", 3);
+    let drop_id = create_drop(&mut contract, pks, DropArgs::simple());
+
+    delete_keys(&mut contract, drop_id, 1);
+    assert_eq!(contract.get_key_supply_for_drop(drop_id), 2);
+
+    // Limits aren't capped at what fits in a byte
+    delete_keys(&mut contract, drop_id, 300);
+    // The drop goes with its last key
+    assert!(contract.drop_for_id.get(&drop_id).is_none());
+}
@@ -0,0 +1,111 @@
+/*
+    Drops with a deploy config create accounts through the root's `create_account_advanced` with the contract's
+    storage on top of the balance, then initialize the contract once the account exists.
+*/
+use super::*;
+use near_sdk::json_types::Base64VecU8;
+
+const CONTRACT_SIZE: usize = 100;
+
+fn deploy_config() -> DeployConfig {
+    DeployConfig {
+        contract_bytes: Base64VecU8(vec![0; CONTRACT_SIZE]),
+        init_method: Some("new".to_string()),
+        init_args: Some(r#"{"owner_id":"alice.near"}"#.to_string()),
+    }
+}
+
+fn deploy_cost() -> Balance {
+    CONTRACT_SIZE as u128 * env::storage_byte_cost()
+}
+
+fn deploy_drop_args() -> DropArgs {
+    DropArgs {
+        config: Some(DropConfig::builder().deploy(deploy_config()).build()),
+        ..DropArgs::simple()
+    }
+}
+
+#[test]
+fn deploy_storage_is_reserved_for_every_key() {
+    let contract = setup();
+    let args = deploy_drop_args();
+    let args = CreateDropArgs {
+        public_keys: key_args(keys("deploy-price", 2)),
+        deposit_per_use: U128(args.deposit_per_use),
+        config: args.config,
+        metadata: None,
+        ft_data: None,
+        nft_data: None,
+        fc_data: None,
+        campaign_id: None,
+        partner_id: None,
+    };
+
+    match contract.validate_drop(funder_id(), None, args) {
+        ValidationResult::Ok { price } => assert_eq!(price.extra_costs.0, 2 * deploy_cost()),
+        ValidationResult::Invalid { .. } => panic!("drop is invalid"),
+    }
+}
+
+#[test]
+fn create_account_and_claim_deploys_contract() {
+    let mut contract = setup();
+    let pks = keys("deploy", 1);
+    create_drop(&mut contract, pks.clone(), deploy_drop_args());
+
+    set_create_account_signer(&contract, &pks[0]);
+    contract.create_account_and_claim(
+        Some(account("alice")),
+        keys("alice", 1).remove(0),
+        None,
+        None,
+        None,
+        None,
+    );
+
+    let receipts = get_created_receipts();
+    let create_account = receipts
+        .iter()
+        .find(|receipt| receipt.receiver_id == root_id())
+        .expect("no receipt to the root account");
+    assert!(matches!(
+        &create_account.actions[0],
+        VmAction::FunctionCall { function_name, deposit, .. }
+            if function_name == "create_account_advanced" && *deposit == ONE_NEAR + deploy_cost()
+    ));
+    assert!(receipts
+        .iter()
+        .any(|receipt| receipt.actions.iter().any(|action| matches!(
+            action,
+            VmAction::FunctionCall { function_name, .. } if function_name == "on_account_deployed"
+        ))));
+}
+
+#[test]
+fn deployed_contract_is_initialized() {
+    let mut contract = setup();
+    let deploy = deploy_config();
+
+    set_callback(vec![PromiseResult::Successful(b"true".to_vec())]);
+    assert!(contract.on_account_deployed(account("alice"), deploy.init_method, deploy.init_args));
+
+    let init = get_created_receipts()
+        .into_iter()
+        .find(|receipt| receipt.receiver_id == account("alice"))
+        .expect("no receipt to the new account");
+    assert!(matches!(
+        &init.actions[0],
+        VmAction::FunctionCall { function_name, args, .. }
+            if function_name == "new" && args == br#"{"owner_id":"alice.near"}"#
+    ));
+}
+
+#[test]
+#[should_panic(expected = "creating alice.near with a contract failed")]
+fn failed_deploy_fails_the_claim() {
+    let mut contract = setup();
+
+    set_callback(vec![PromiseResult::Successful(b"false".to_vec())]);
+    contract.on_account_deployed(account("alice"), None, None);
+}
@@ -0,0 +1,59 @@
+/*
+    `create_drop` returns the drop ID and emits it in a `drop_creation` event for every drop type, including FT
+    drops whose keys are only added once the storage check resolves.
+*/
+use super::*;
+use near_sdk::serde_json::{self, Value};
+use near_sdk::test_utils::get_logs;
+
+/// Drop ID and type of the `drop_creation` event the last call emitted
+fn creation_event() -> (DropId, String) {
+    let event = get_logs()
+        .iter()
+        .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+        .map(|json| serde_json::from_str::<Value>(json).unwrap())
+        .find(|event| event["event"] == "drop_creation")
+        .expect("no drop_creation event");
+    let data = &event["data"][0];
+    (
+        data["drop_id"].as_u64().unwrap() as DropId,
+        data["drop_type"].as_str().unwrap().to_string(),
+    )
+}
+
+#[test]
+fn create_drop_returns_drop_id_for_every_type() {
+    let mut contract = setup();
+    let drops = vec![
+        ("simple", DropArgs::simple()),
+        (
+            "nft",
+            DropArgs {
+                nft_data: Some(NFTDataConfig::fixture(account("nft"), funder_id())),
+                ..DropArgs::simple()
+            },
+        ),
+        (
+            "ft",
+            DropArgs {
+                ft_data: Some(FTDataConfig::fixture(account("token"), funder_id())),
+                ..DropArgs::simple()
+            },
+        ),
+        (
+            "fc",
+            DropArgs {
+                fc_data: Some(FCData::fixture(account("receiver"), "nft_mint")),
+                ..DropArgs::simple()
+            },
+        ),
+    ];
+
+    for (expected_id, (drop_type, args)) in drops.into_iter().enumerate() {
+        let drop_id = create_drop(&mut contract, keys(drop_type, 1), args);
+
+        assert_eq!(drop_id, expected_id as DropId);
+        assert!(contract.drop_for_id.get(&drop_id).is_some());
+        assert_eq!(creation_event(), (drop_id, drop_type.to_string()));
+    }
+}
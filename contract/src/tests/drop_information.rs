@@ -0,0 +1,78 @@
+/*
+    `get_drop_information` returns a drop by its ID or by one of its keys. Balances are returned as strings so that
+    they make it through JSON without losing precision.
+*/
+use super::*;
+use near_sdk::serde_json::{self, json, Value};
+
+/// The drop as a frontend would get it, i.e serialized to JSON the way `near_bindgen` returns it
+fn drop_json(contract: &Keypom, drop_id: Option<DropId>, key: Option<PublicKey>) -> Value {
+    let drop = contract.get_drop_information(drop_id, key);
+    serde_json::from_str(&serde_json::to_string(&drop).unwrap()).unwrap()
+}
+
+#[test]
+fn drop_is_found_by_id_or_key() {
+    let mut contract = setup();
+    let pks = keys("info", 3);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+
+    let by_key = drop_json(&contract, None, Some(pks[1].clone()));
+    assert_eq!(by_key, drop_json(&contract, Some(drop_id), None));
+    assert_eq!(by_key["drop_id"].as_u64(), Some(drop_id as u64));
+    assert_eq!(by_key["owner_id"], json!("funder.near"));
+    assert_eq!(by_key["drop_type"], json!("Simple"));
+}
+
+#[test]
+fn key_counts_follow_claims() {
+    let mut contract = setup();
+    let pks = keys("info", 3);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+    let drop = contract.get_drop_information(Some(drop_id), None);
+    assert_eq!(drop.num_keys, 3);
+    assert_eq!(drop.next_key_id, 3);
+    assert_eq!(drop.registered_uses, 3);
+
+    claim(&mut contract, &pks[0], account("alice"));
+
+    let drop = contract.get_drop_information(Some(drop_id), None);
+    assert_eq!(drop.num_keys, 2);
+    assert_eq!(drop.next_key_id, 3);
+    assert_eq!(drop.registered_uses, 2);
+}
+
+#[test]
+fn balances_round_trip_through_json_as_strings() {
+    let mut contract = setup();
+    let pks = keys("info", 1);
+    // Above 2^53, where JSON numbers would lose precision
+    let deposit_per_use = ONE_NEAR + 1;
+    let args = DropArgs {
+        deposit_per_use,
+        ft_data: Some(FTDataConfig::fixture(account("token"), funder_id())),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks, args);
+
+    let drop = drop_json(&contract, Some(drop_id), None);
+    assert_eq!(drop["deposit_per_use"], json!(deposit_per_use.to_string()));
+    let ft_data = &drop["drop_type"]["FungibleToken"];
+    assert!(ft_data["balance_per_use"].is_string());
+    assert_eq!(ft_data["dust"], json!("0"));
+
+    let deposit: U128 = serde_json::from_value(drop["deposit_per_use"].clone()).unwrap();
+    assert_eq!(deposit.0, deposit_per_use);
+    let balance_per_use: U128 = serde_json::from_value(ft_data["balance_per_use"].clone()).unwrap();
+    assert_eq!(
+        balance_per_use,
+        FTDataConfig::fixture(account("token"), funder_id()).balance_per_use
+    );
+}
+
+#[test]
+#[should_panic(expected = "no drop ID for PK")]
+fn unknown_key_is_rejected() {
+    let contract = setup();
+    contract.get_drop_information(None, Some(keys("unknown", 1).remove(0)));
+}
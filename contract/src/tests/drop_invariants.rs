@@ -0,0 +1,98 @@
+/*
+    `add_keys` re-checks the rules `create_drop` enforces against the stored drop. Drops that break them can't be
+    created so they're built by writing the stored drop directly, like one created before a rule existed would be.
+*/
+use super::*;
+
+fn fc_drop_with_attached_gas(contract: &mut Keypom, public_keys: Vec<PublicKey>) -> DropId {
+    let mut fc_data = FCData::fixture(account("receiver"), "nft_mint");
+    fc_data.config = Some(FCConfig {
+        account_id_field: None,
+        drop_id_field: None,
+        key_id_field: None,
+        attached_gas: Some(Gas(20_000_000_000_000)),
+    });
+    let args = DropArgs {
+        deposit_per_use: 0,
+        fc_data: Some(fc_data),
+        ..Default::default()
+    };
+    create_drop(contract, public_keys, args)
+}
+
+fn add_key(contract: &mut Keypom, drop_id: DropId, public_key: PublicKey) {
+    set_caller(&funder_id(), 0);
+    contract.add_keys(key_args(vec![public_key]), drop_id, None, None);
+}
+
+#[test]
+fn add_keys_to_fc_drop_with_attached_gas() {
+    let mut contract = setup();
+    let pks = keys("attached-gas", 2);
+    let drop_id = fc_drop_with_attached_gas(&mut contract, vec![pks[0].clone()]);
+
+    add_key(&mut contract, drop_id, pks[1].clone());
+
+    assert_eq!(contract.get_key_supply_for_drop(drop_id), 2);
+}
+
+#[test]
+#[should_panic(
+    expected = "drop invariant violated: cannot specify gas to attach and have a balance in the linkdrop"
+)]
+fn add_keys_rejects_attached_gas_with_balance() {
+    let mut contract = setup();
+    let pks = keys("attached-gas-balance", 2);
+    let drop_id = fc_drop_with_attached_gas(&mut contract, vec![pks[0].clone()]);
+
+    let mut drop = contract.drop_for_id.get(&drop_id).unwrap();
+    drop.deposit_per_use = ONE_NEAR;
+    contract.drop_for_id.insert(&drop_id, &drop);
+
+    add_key(&mut contract, drop_id, pks[1].clone());
+}
+
+#[test]
+#[should_panic(expected = "drop invariant violated: Cannot have a simple drop with zero balance")]
+fn add_keys_rejects_simple_drop_without_balance() {
+    let mut contract = setup();
+    let pks = keys("zero-balance", 2);
+    let drop_id = create_drop(&mut contract, vec![pks[0].clone()], DropArgs::simple());
+
+    let mut drop = contract.drop_for_id.get(&drop_id).unwrap();
+    drop.deposit_per_use = 0;
+    contract.drop_for_id.insert(&drop_id, &drop);
+
+    add_key(&mut contract, drop_id, pks[1].clone());
+}
+
+#[test]
+#[should_panic(
+    expected = "drop invariant violated: FC drops with attached GAS can only be claimed with claim"
+)]
+fn add_keys_rejects_attached_gas_with_create_account_permission() {
+    let mut contract = setup();
+    let pks = keys("attached-gas-permission", 2);
+    let drop_id = fc_drop_with_attached_gas(&mut contract, vec![pks[0].clone()]);
+
+    let mut drop = contract.drop_for_id.get(&drop_id).unwrap();
+    drop.claim_permission = ClaimPermissions::Both;
+    contract.drop_for_id.insert(&drop_id, &drop);
+
+    add_key(&mut contract, drop_id, pks[1].clone());
+}
+
+#[test]
+#[should_panic(expected = "drop invariant violated: Cannot have a simple drop with zero balance")]
+fn update_drop_rejects_drop_breaking_invariants() {
+    let mut contract = setup();
+    let pks = keys("update-invariants", 1);
+    let drop_id = create_drop(&mut contract, pks, DropArgs::simple());
+
+    let mut drop = contract.drop_for_id.get(&drop_id).unwrap();
+    drop.deposit_per_use = 0;
+    contract.drop_for_id.insert(&drop_id, &drop);
+
+    set_caller(&funder_id(), 1);
+    contract.update_drop(drop_id, Some(DropConfig::builder().build()), None);
+}
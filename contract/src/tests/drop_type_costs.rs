@@ -0,0 +1,151 @@
+/*
+    What each drop type charges, refunds and requires for claims, pinned to exact amounts so that changes to how
+    drop types are dispatched can't quietly change what funders pay. An intended change to any of these amounts
+    should update the snapshot along with it.
+*/
+use super::*;
+
+#[derive(Debug, PartialEq)]
+struct Snapshot {
+    create_drop: Balance,
+    add_key: Balance,
+    delete_key: Balance,
+    claim_required_gas: u64,
+    claim_used_gas: u64,
+    delete_drop: Balance,
+}
+
+fn funder_balance(contract: &Keypom) -> Balance {
+    user_balance(contract, &funder_id())
+}
+
+/// Run a drop with 2 uses per key through its lifecycle, registering the assets for the one use that's claimed
+fn snapshot(args: DropArgs, register_use: impl Fn(&mut Keypom, DropId)) -> Snapshot {
+    let mut contract = setup();
+    let pks = keys("snapshot", 3);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().uses_per_key(2).build()),
+        ..args
+    };
+    let is_ft = args.ft_data.is_some();
+
+    let balance = funder_balance(&contract);
+    let drop_id = create_drop(&mut contract, pks[..2].to_vec(), args);
+    if is_ft {
+        activate_ft_drop(&mut contract, drop_id, pks[..2].to_vec());
+    }
+    let create_drop = balance - funder_balance(&contract);
+
+    let balance = funder_balance(&contract);
+    set_caller(&funder_id(), 0);
+    contract.add_keys(key_args(vec![pks[2].clone()]), drop_id, None, None);
+    let add_key = balance - funder_balance(&contract);
+
+    let balance = funder_balance(&contract);
+    set_caller(&funder_id(), 1);
+    contract.delete_keys(drop_id, Some(vec![pks[2].clone()]), None);
+    let delete_key = funder_balance(&contract) - balance;
+
+    // Keys can only be deleted from FT and NFT drops with no assets registered
+    register_use(&mut contract, drop_id);
+
+    let claim_required_gas = required_gas_for_key(&contract, &pks[0]).0;
+    claim(&mut contract, &pks[0], account("alice"));
+    let claim_used_gas = env::used_gas().0;
+
+    let balance = funder_balance(&contract);
+    set_caller(&funder_id(), 1);
+    contract.delete_drop(drop_id, Some(true));
+    let delete_drop = funder_balance(&contract) - balance;
+
+    Snapshot {
+        create_drop,
+        add_key,
+        delete_key,
+        claim_required_gas,
+        claim_used_gas,
+        delete_drop,
+    }
+}
+
+#[test]
+fn simple_drop() {
+    assert_eq!(
+        snapshot(DropArgs::simple(), |_, _| {}),
+        Snapshot {
+            create_drop: 5113720520254873600000000,
+            add_key: 2050895260127436800000000,
+            delete_key: 2045895260127436800000000,
+            claim_required_gas: 100000000000000,
+            claim_used_gas: 72336043684118,
+            delete_drop: 3093720520254873600000000,
+        }
+    );
+}
+
+#[test]
+fn ft_drop() {
+    let args = DropArgs {
+        ft_data: Some(FTDataConfig::fixture(account("token"), funder_id())),
+        ..DropArgs::simple()
+    };
+    let register_use = |contract: &mut Keypom, drop_id: DropId| {
+        set_caller(&account("token"), 0);
+        contract.ft_on_transfer(funder_id(), U128(ONE_NEAR), drop_id.to_string());
+    };
+    assert_eq!(
+        snapshot(args, register_use),
+        Snapshot {
+            create_drop: 5124490520254873600000000,
+            add_key: 2053395260127436800000000,
+            delete_key: 2048395260127436800000000,
+            claim_required_gas: 100000000000000,
+            claim_used_gas: 72346689500100,
+            delete_drop: 3103000520254873600000000,
+        }
+    );
+}
+
+#[test]
+fn nft_drop() {
+    let args = DropArgs {
+        nft_data: Some(NFTDataConfig::fixture(account("nft"), funder_id())),
+        ..DropArgs::simple()
+    };
+    let register_use = |contract: &mut Keypom, drop_id: DropId| {
+        send_nfts(contract, drop_id, &["token-1"]);
+    };
+    assert_eq!(
+        snapshot(args, register_use),
+        Snapshot {
+            create_drop: 5125860520254873600000000,
+            add_key: 2052385260127436800000000,
+            delete_key: 2047385260127436800000000,
+            claim_required_gas: 100000000000000,
+            claim_used_gas: 72419127388583,
+            delete_drop: 3102880520254873600000000,
+        }
+    );
+}
+
+#[test]
+fn fc_drop() {
+    let mut fc_data = FCData::fixture(account("receiver"), "ping");
+    fc_data.methods[0].as_mut().unwrap()[0].attached_deposit = U128(ONE_NEAR / 10);
+    let args = DropArgs {
+        deposit_per_use: 0,
+        fc_data: Some(fc_data),
+        ..Default::default()
+    };
+    assert_eq!(
+        snapshot(args, |_, _| {}),
+        Snapshot {
+            create_drop: 1514290520254873600000000,
+            add_key: 250895260127436800000000,
+            delete_key: 245895260127436800000000,
+            claim_required_gas: 100000000000000,
+            claim_used_gas: 8489959870718,
+            delete_drop: 394290520254873600000000,
+        }
+    );
+}
@@ -0,0 +1,45 @@
+/*
+    Indexers bootstrap by walking `get_drops_paginated` until it stops returning a cursor. Every drop must come back
+    exactly once, even across deleted drops and gaps in the IDs wider than a single page scans.
+*/
+use super::*;
+
+#[test]
+fn walking_every_page_returns_each_drop_once() {
+    let mut contract = setup();
+    let mut expected = vec![];
+    for i in 0..300 {
+        // Leave a gap in the IDs wider than what a single page looks at
+        if i == 150 {
+            contract.next_drop_id += 600;
+        }
+        let drop_id = create_drop(
+            &mut contract,
+            keys(&format!("drop-{}", i), 1),
+            DropArgs::simple(),
+        );
+        expected.push(drop_id);
+    }
+
+    // Drops go with their last key
+    for drop_id in expected.iter().step_by(10) {
+        set_caller(&funder_id(), 1);
+        contract.delete_keys(*drop_id, None, None);
+    }
+    expected.retain(|id| contract.drop_for_id.contains_key(id));
+    assert_eq!(expected.len(), 270);
+
+    let mut walked = vec![];
+    let mut cursor = None;
+    loop {
+        let (drops, next) = contract.get_drops_paginated(cursor, 7);
+        assert!(drops.len() <= 7);
+        walked.extend(drops.into_iter().map(|drop| drop.drop_id));
+        match next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    assert_eq!(walked, expected);
+}
@@ -0,0 +1,53 @@
+/*
+    Drops with an early bonus pay the first claims extra on top of the deposit per use
+*/
+use super::*;
+
+const BONUS: Balance = ONE_NEAR / 2;
+
+fn drop_with_bonus(
+    contract: &mut Keypom,
+    seed: &str,
+    num_bonus_claims: u64,
+) -> (DropId, Vec<PublicKey>) {
+    let pks = keys(seed, 3);
+    let args = DropArgs {
+        config: Some(
+            DropConfig::builder()
+                .early_bonus(num_bonus_claims, U128(BONUS))
+                .build(),
+        ),
+        ..DropArgs::simple()
+    };
+    (create_drop(contract, pks.clone(), args), pks)
+}
+
+#[test]
+fn only_the_first_claims_get_the_bonus() {
+    let mut contract = setup();
+    let (_, pks) = drop_with_bonus(&mut contract, "early", 2);
+
+    claim(&mut contract, &pks[0], account("alice"));
+    assert_eq!(near_sent_to(&account("alice")), ONE_NEAR + BONUS);
+    claim(&mut contract, &pks[1], account("bob"));
+    assert_eq!(near_sent_to(&account("bob")), ONE_NEAR + BONUS);
+    claim(&mut contract, &pks[2], account("carol"));
+    assert_eq!(near_sent_to(&account("carol")), ONE_NEAR);
+}
+
+#[test]
+fn unspent_bonus_is_refunded_when_the_drop_is_deleted() {
+    // Both drops pay out a single bonus. The one that reserved more should get the rest back.
+    let mut contract = setup();
+    let mut spent = vec![];
+    for (seed, num_bonus_claims) in [("spent", 1), ("unspent", 3)] {
+        let before = user_balance(&contract, &funder_id());
+        let (drop_id, pks) = drop_with_bonus(&mut contract, seed, num_bonus_claims);
+        claim(&mut contract, &pks[0], account("alice"));
+        set_caller(&funder_id(), 1);
+        contract.delete_drop(drop_id, Some(true));
+        spent.push(before - user_balance(&contract, &funder_id()));
+    }
+
+    assert_eq!(spent[0], spent[1]);
+}
@@ -0,0 +1,64 @@
+/*
+    Keys can't be claimed once their end timestamp has passed. The claim fails and the key is left as it was so the
+    funder can still refund it.
+*/
+use super::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+const END: u64 = 1_000_000;
+
+fn claim_at(contract: &mut Keypom, public_key: &PublicKey, block_timestamp: u64) {
+    testing_env!(context(&keypom_id())
+        .signer_account_pk(public_key.clone())
+        .prepaid_gas(required_gas_for_key(contract, public_key))
+        .block_timestamp(block_timestamp)
+        .build());
+    contract.claim(account("alice"), None, None, None, None, None);
+}
+
+fn expiring_drop(contract: &mut Keypom, count: u64) -> Vec<PublicKey> {
+    let pks = keys("expiring", count);
+    let args = DropArgs {
+        config: Some(
+            DropConfig::builder()
+                .uses_per_key(2)
+                .end_timestamp(END)
+                .build(),
+        ),
+        ..DropArgs::simple()
+    };
+    create_drop(contract, pks.clone(), args);
+    pks
+}
+
+#[test]
+fn keys_can_be_claimed_until_the_end_timestamp() {
+    let mut contract = setup();
+    let pks = expiring_drop(&mut contract, 1);
+
+    claim_at(&mut contract, &pks[0], END);
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 1);
+}
+
+#[test]
+fn claims_after_the_end_timestamp_fail_and_leave_the_key_intact() {
+    let mut contract = setup();
+    let pks = expiring_drop(&mut contract, 1);
+    let before = key_info(&contract, &pks[0]);
+    let balance_before = user_balance(&contract, &funder_id());
+
+    let panic = catch_unwind(AssertUnwindSafe(|| {
+        claim_at(&mut contract, &pks[0], END + 1)
+    }))
+    .expect_err("expired key was claimed");
+    assert_eq!(
+        panic.downcast_ref::<String>().map(String::as_str),
+        Some(format!("Drop has expired. Current timestamp is {}", END + 1).as_str())
+    );
+
+    let after = key_info(&contract, &pks[0]);
+    assert_eq!(after.remaining_uses, before.remaining_uses);
+    assert_eq!(after.allowance, before.allowance);
+    assert_eq!(user_balance(&contract, &funder_id()), balance_before);
+    assert!(get_created_receipts().is_empty());
+}
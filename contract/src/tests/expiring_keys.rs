@@ -0,0 +1,142 @@
+/*
+    Keys about to expire can have their access keys re-added with an allowance for a single claim attempt. The
+    allowance they no longer need goes back to the funder once the access key has been replaced.
+*/
+use super::*;
+
+const END: u64 = 10 * DEFAULT_EXPIRING_KEY_WINDOW;
+
+fn tighten_at(contract: &mut Keypom, drop_id: DropId, block_timestamp: u64) {
+    testing_env!(context(&funder_id())
+        .attached_deposit(1)
+        .block_timestamp(block_timestamp)
+        .build());
+    contract.tighten_expiring_keys(drop_id, None);
+}
+
+fn resolve(
+    contract: &mut Keypom,
+    drop_id: DropId,
+    reclaimed: Vec<(PublicKey, U128)>,
+    results: Vec<PromiseResult>,
+) {
+    set_callback(results);
+    contract.resolve_tightened_keys(U128(drop_id), funder_id(), reclaimed);
+}
+
+/// Allowance that covers a single claim of the drop
+fn one_attempt(contract: &Keypom, drop_id: DropId) -> Balance {
+    contract.drop_for_id.get(&drop_id).unwrap().required_gas.0 as u128 * contract.yocto_per_gas
+}
+
+#[test]
+fn keys_in_the_window_are_readded_with_one_attempt() {
+    let mut contract = setup();
+    let pks = keys("expiring", 2);
+    let args = DropArgs {
+        config: Some(
+            DropConfig::builder()
+                .uses_per_key(2)
+                .end_timestamp(END)
+                .build(),
+        ),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+    let allowance = key_info(&contract, &pks[0]).allowance;
+    let one_attempt = one_attempt(&contract, drop_id);
+    assert!(allowance > one_attempt);
+
+    let balance_before = user_balance(&contract, &funder_id());
+    tighten_at(&mut contract, drop_id, END - DEFAULT_EXPIRING_KEY_WINDOW);
+    for pk in &pks {
+        assert_eq!(key_info(&contract, pk).allowance, one_attempt);
+    }
+    // Each key's access key is replaced in its own batch
+    let receipts = get_created_receipts();
+    for pk in &pks {
+        let batch = receipts
+            .iter()
+            .find(|receipt| {
+                matches!(&receipt.actions[0], VmAction::DeleteKey { public_key } if public_key == pk)
+            })
+            .expect("no batch for key");
+        assert!(matches!(
+            &batch.actions[1],
+            VmAction::AddKeyWithFunctionCall { public_key, allowance: Some(allowance), .. }
+                if public_key == pk && *allowance == one_attempt
+        ));
+    }
+    // The funder is only credited once the access keys have been replaced
+    assert_eq!(user_balance(&contract, &funder_id()), balance_before);
+
+    let reclaimed: Vec<(PublicKey, U128)> = pks
+        .iter()
+        .map(|pk| (pk.clone(), U128(allowance - one_attempt)))
+        .collect();
+    resolve(
+        &mut contract,
+        drop_id,
+        reclaimed,
+        vec![
+            PromiseResult::Successful(vec![]),
+            PromiseResult::Successful(vec![]),
+        ],
+    );
+    assert_eq!(
+        user_balance(&contract, &funder_id()),
+        balance_before + 2 * (allowance - one_attempt)
+    );
+}
+
+#[test]
+fn keys_outside_the_window_are_left_alone() {
+    let mut contract = setup();
+    let pks = keys("far", 1);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().end_timestamp(END).build()),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+
+    for timestamp in [END - DEFAULT_EXPIRING_KEY_WINDOW - 1, END + 1] {
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            tighten_at(&mut contract, drop_id, timestamp)
+        }))
+        .expect_err("keys were tightened");
+        assert_eq!(
+            panic.downcast_ref::<String>().map(String::as_str),
+            Some("no expiring keys to tighten in drop")
+        );
+    }
+}
+
+#[test]
+fn failed_batches_keep_their_allowance() {
+    let mut contract = setup();
+    let pks = keys("failed", 2);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().end_timestamp(END).build()),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+    let allowance = key_info(&contract, &pks[0]).allowance;
+    let surplus = allowance - one_attempt(&contract, drop_id);
+
+    let balance_before = user_balance(&contract, &funder_id());
+    tighten_at(&mut contract, drop_id, END);
+    let reclaimed = pks.iter().map(|pk| (pk.clone(), U128(surplus))).collect();
+    resolve(
+        &mut contract,
+        drop_id,
+        reclaimed,
+        vec![PromiseResult::Successful(vec![]), PromiseResult::Failed],
+    );
+
+    assert_eq!(key_info(&contract, &pks[0]).allowance, allowance - surplus);
+    assert_eq!(key_info(&contract, &pks[1]).allowance, allowance);
+    assert_eq!(
+        user_balance(&contract, &funder_id()),
+        balance_before + surplus
+    );
+}
@@ -0,0 +1,106 @@
+/*
+    Accounts without a balance on the contract can create their first drop by attaching the deposit to the call.
+    Balances that can't cover a drop are rejected with the exact shortfall.
+*/
+use super::*;
+
+/// Same length as `funder.near` so that both accounts' drops take up the same storage
+fn newcomer_id() -> AccountId {
+    account("newbie")
+}
+
+fn create_drop_with_deposit(
+    contract: &mut Keypom,
+    caller: &AccountId,
+    public_keys: Vec<PublicKey>,
+    attached_deposit: Balance,
+) -> DropId {
+    set_caller(caller, attached_deposit);
+    contract.create_drop(
+        key_args(public_keys),
+        U128(ONE_NEAR),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+#[test]
+fn new_account_creates_first_drop_with_attached_deposit() {
+    let mut contract = setup();
+    assert_eq!(user_balance(&contract, &newcomer_id()), 0);
+
+    let drop_id = create_drop_with_deposit(
+        &mut contract,
+        &newcomer_id(),
+        keys("newcomer", 2),
+        10 * ONE_NEAR,
+    );
+    assert_eq!(
+        contract.drop_for_id.get(&drop_id).unwrap().owner_id,
+        newcomer_id()
+    );
+
+    // The newcomer pays exactly what a funder with an existing balance pays for the same drop
+    create_drop_with_deposit(&mut contract, &funder_id(), keys("funder", 2), 0);
+    let spent = FUNDER_BALANCE - user_balance(&contract, &funder_id());
+    assert_eq!(
+        user_balance(&contract, &newcomer_id()),
+        10 * ONE_NEAR - spent
+    );
+}
+
+#[test]
+fn attached_deposit_is_added_to_existing_balance() {
+    let mut contract = setup();
+    // The funder's first drop also pays for storage that later drops reuse
+    create_drop_with_deposit(&mut contract, &funder_id(), keys("first", 1), 0);
+
+    let balance_before = user_balance(&contract, &funder_id());
+    create_drop_with_deposit(&mut contract, &funder_id(), keys("second", 1), 0);
+    let spent = balance_before - user_balance(&contract, &funder_id());
+
+    let balance_before = user_balance(&contract, &funder_id());
+    create_drop_with_deposit(&mut contract, &funder_id(), keys("third", 1), spent);
+    assert_eq!(user_balance(&contract, &funder_id()), balance_before);
+}
+
+#[test]
+fn shortfall_is_reported_for_new_accounts() {
+    let contract = setup();
+    let args = CreateDropArgs {
+        public_keys: key_args(keys("short", 1)),
+        deposit_per_use: U128(ONE_NEAR),
+        config: None,
+        metadata: None,
+        ft_data: None,
+        nft_data: None,
+        fc_data: None,
+        campaign_id: None,
+        partner_id: None,
+    };
+
+    let errors = match contract.validate_drop(newcomer_id(), None, args) {
+        ValidationResult::Invalid { errors } => errors,
+        ValidationResult::Ok { .. } => panic!("drop is valid"),
+    };
+    assert_eq!(errors[0].field, "balance");
+    assert!(errors[0].message.starts_with("Insufficient balance, need "));
+    assert_eq!(
+        insufficient_balance_message(ONE_NEAR, 3 * ONE_NEAR, ONE_NEAR),
+        format!(
+            "Insufficient balance, need {} more yoctoNEAR. Balance: {} (including {} attached), required: {}",
+            2 * ONE_NEAR,
+            ONE_NEAR,
+            ONE_NEAR,
+            3 * ONE_NEAR
+        )
+    );
+}
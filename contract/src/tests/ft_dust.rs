@@ -0,0 +1,87 @@
+/*
+    Tokens that don't divide evenly into uses are returned to the sender, or kept on drops that accumulate dust
+    until they add up to another use
+*/
+use super::*;
+
+fn ft_contract() -> AccountId {
+    account("token")
+}
+
+fn dust_drop(contract: &mut Keypom, accumulate_dust: bool) -> DropId {
+    let pks = keys("dust", 4);
+    let mut ft_data = FTDataConfig::fixture(ft_contract(), funder_id());
+    ft_data.balance_per_use = U128(333);
+    ft_data.accumulate_dust = accumulate_dust;
+    let args = DropArgs {
+        ft_data: Some(ft_data),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(contract, pks.clone(), args);
+    activate_ft_drop(contract, drop_id, pks);
+    drop_id
+}
+
+/// Send tokens to the drop and return how many the token contract should refund to the sender
+fn send_tokens(contract: &mut Keypom, drop_id: DropId, amount: u128) -> u128 {
+    set_caller(&ft_contract(), 0);
+    match contract.ft_on_transfer(funder_id(), U128(amount), drop_id.to_string()) {
+        PromiseOrValue::Value(returned) => returned.0,
+        PromiseOrValue::Promise(_) => panic!("tokens weren't resolved right away"),
+    }
+}
+
+fn dust(contract: &Keypom, drop_id: DropId) -> u128 {
+    match contract.get_drop_information(Some(drop_id), None).drop_type {
+        JsonDropType::FungibleToken(data) => data.dust.0,
+        _ => panic!("drop isn't FT"),
+    }
+}
+
+#[test]
+fn remainder_is_returned_by_default() {
+    let mut contract = setup();
+    let drop_id = dust_drop(&mut contract, false);
+
+    assert_eq!(send_tokens(&mut contract, drop_id, 1000), 1);
+    assert_eq!(registered_uses(&contract, drop_id), 3);
+    assert_eq!(dust(&contract, drop_id), 0);
+}
+
+#[test]
+fn accumulated_dust_registers_another_use() {
+    let mut contract = setup();
+    let drop_id = dust_drop(&mut contract, true);
+
+    assert_eq!(send_tokens(&mut contract, drop_id, 1000), 0);
+    assert_eq!(registered_uses(&contract, drop_id), 3);
+    assert_eq!(dust(&contract, drop_id), 1);
+
+    // 332 tokens with the 1 kept from before add up to one more use
+    assert_eq!(send_tokens(&mut contract, drop_id, 332), 0);
+    assert_eq!(registered_uses(&contract, drop_id), 4);
+    assert_eq!(dust(&contract, drop_id), 0);
+}
+
+#[test]
+fn refunded_assets_include_dust() {
+    let mut contract = setup();
+    let drop_id = dust_drop(&mut contract, true);
+    send_tokens(&mut contract, drop_id, 1000);
+
+    set_caller(&funder_id(), 1);
+    contract.refund_assets(drop_id, None);
+
+    let transfer = get_created_receipts()
+        .into_iter()
+        .find(|receipt| receipt.receiver_id == ft_contract())
+        .expect("no transfer to the token contract");
+    assert!(matches!(
+        &transfer.actions[0],
+        VmAction::FunctionCall { function_name, args, .. }
+            if function_name == "ft_transfer"
+                && String::from_utf8_lossy(args).contains(r#""amount":"1000""#)
+    ));
+    assert_eq!(registered_uses(&contract, drop_id), 0);
+    assert_eq!(dust(&contract, drop_id), 0);
+}
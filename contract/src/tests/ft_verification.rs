@@ -0,0 +1,79 @@
+/*
+    Transfers to drops with `verify_received_balance` only register what the token contract says actually arrived
+*/
+use super::*;
+
+fn verified_ft_drop(contract: &mut Keypom, pks: Vec<PublicKey>) -> DropId {
+    let mut ft_data = FTDataConfig::fixture(account("token"), funder_id());
+    ft_data.verify_received_balance = true;
+    // Nothing to send with each claim so the FT transfer is scheduled right away
+    let args = DropArgs {
+        deposit_per_use: 0,
+        ft_data: Some(ft_data),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(contract, pks.clone(), args);
+    activate_ft_drop(contract, drop_id, pks);
+    drop_id
+}
+
+/// Send tokens to the drop and resolve the balance check with the balance the token contract reports
+fn send_verified(
+    contract: &mut Keypom,
+    drop_id: DropId,
+    amount: u128,
+    balance: Option<u128>,
+) -> U128 {
+    set_caller(&account("token"), 0);
+    contract.ft_on_transfer(funder_id(), U128(amount), drop_id.to_string());
+    set_callback(vec![match balance {
+        Some(balance) => PromiseResult::Successful(format!("\"{}\"", balance).into_bytes()),
+        None => PromiseResult::Failed,
+    }]);
+    contract.resolve_received_balance(account("token"))
+}
+
+#[test]
+fn failed_balance_query_refunds_everything() {
+    let mut contract = setup();
+    let drop_id = verified_ft_drop(&mut contract, keys("verify", 2));
+
+    let refunded = send_verified(&mut contract, drop_id, 2 * ONE_NEAR, None);
+
+    assert_eq!(refunded.0, 2 * ONE_NEAR);
+    assert_eq!(registered_uses(&contract, drop_id), 0);
+    assert_eq!(contract.get_ft_held(account("token")).0, 0);
+}
+
+#[test]
+#[should_panic(expected = "claim transfers for this token are in flight")]
+fn verification_waits_for_claims_in_flight() {
+    let mut contract = setup();
+    let pks = keys("verify", 2);
+    let drop_id = verified_ft_drop(&mut contract, pks.clone());
+    send_verified(&mut contract, drop_id, 2 * ONE_NEAR, Some(2 * ONE_NEAR));
+
+    // The claim's transfer hasn't resolved so its token may or may not have left the balance yet
+    claim(&mut contract, &pks[0], account("alice"));
+    set_caller(&account("token"), 0);
+    contract.ft_on_transfer(funder_id(), U128(ONE_NEAR), drop_id.to_string());
+}
+
+#[test]
+fn resolved_claims_dont_undercount_deposits() {
+    let mut contract = setup();
+    let pks = keys("verify", 2);
+    let drop_id = verified_ft_drop(&mut contract, pks.clone());
+    send_verified(&mut contract, drop_id, 2 * ONE_NEAR, Some(2 * ONE_NEAR));
+    assert_eq!(registered_uses(&contract, drop_id), 2);
+
+    claim(&mut contract, &pks[0], account("alice"));
+    set_callback(vec![PromiseResult::Successful(vec![])]);
+    contract.ft_resolve_batch(U128(ONE_NEAR), funder_id(), account("token"));
+    assert_eq!(contract.get_ft_held(account("token")).0, ONE_NEAR);
+
+    // One token left for the drop plus the one just sent
+    let refunded = send_verified(&mut contract, drop_id, ONE_NEAR, Some(2 * ONE_NEAR));
+    assert_eq!(refunded.0, 0);
+    assert_eq!(registered_uses(&contract, drop_id), 2);
+}
@@ -0,0 +1,187 @@
+/*
+    Claims with an idempotency key leave a record behind so that retries of the same transaction are caught
+*/
+use super::*;
+use near_sdk::test_utils::get_logs;
+
+fn claim_with_key(contract: &mut Keypom, public_key: &PublicKey, idempotency_key: &str) {
+    set_key_signer(public_key, required_gas_for_key(contract, public_key));
+    contract.claim(
+        account("alice"),
+        Some(idempotency_key.to_string()),
+        None,
+        None,
+        None,
+        None,
+    );
+}
+
+fn two_use_keys(contract: &mut Keypom, count: u64) -> Vec<PublicKey> {
+    let pks = keys("retry", count);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().uses_per_key(2).build()),
+        ..DropArgs::simple()
+    };
+    create_drop(contract, pks.clone(), args);
+    pks
+}
+
+fn resolve_claim(contract: &mut Keypom, guard: ClaimGuard, result: PromiseResult) {
+    set_callback(vec![result]);
+    contract.on_claim_simple(
+        funder_id(),
+        U128(ONE_NEAR),
+        0,
+        guard.drop_id,
+        guard,
+        PromiseTarget {
+            method_name: "transfer".to_string(),
+            receiver_id: account("alice"),
+        },
+        vec![],
+    );
+}
+
+fn record(expires_at: u64) -> ClaimRecord {
+    ClaimRecord {
+        drop_id: 0,
+        key_id: 0,
+        remaining_uses: 1,
+        account_id: account("alice"),
+        outcome: ClaimOutcome::Succeeded,
+        claimed_at: 0,
+        expires_at,
+    }
+}
+
+#[test]
+fn retries_are_rejected() {
+    let mut contract = setup();
+    let pks = two_use_keys(&mut contract, 1);
+
+    claim_with_key(&mut contract, &pks[0], "retry-1");
+    claim_with_key(&mut contract, &pks[0], "retry-1");
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 1);
+
+    // A different idempotency key is a different claim
+    claim_with_key(&mut contract, &pks[0], "retry-2");
+    assert!(contract.get_key_information(pks[0].clone()).is_none());
+}
+
+#[test]
+fn funder_pays_for_the_claim_record() {
+    let mut contract = setup();
+    let pks = two_use_keys(&mut contract, 2);
+
+    let before = user_balance(&contract, &funder_id());
+    claim(&mut contract, &pks[0], account("alice"));
+    let without_record = before - user_balance(&contract, &funder_id());
+
+    let before = user_balance(&contract, &funder_id());
+    let initial_storage = env::storage_usage();
+    claim_with_key(&mut contract, &pks[1], "retry-1");
+    // The entry pointing the claim's use at its record is covered by the contract until the claim resolves
+    let guard = ClaimGuard {
+        drop_id: 0,
+        key_id: 1,
+        use_number: 1,
+    };
+    let tracking_storage = {
+        let storage = env::storage_usage();
+        let hash = contract.claim_record_for_guard.remove(&guard).unwrap();
+        let tracking_storage = storage - env::storage_usage();
+        contract.claim_record_for_guard.insert(&guard, &hash);
+        tracking_storage
+    };
+    let record_storage = env::storage_usage() - initial_storage - tracking_storage;
+    let with_record = before - user_balance(&contract, &funder_id());
+
+    assert!(record_storage > 0);
+    assert_eq!(
+        with_record - without_record,
+        record_storage as u128 * env::storage_byte_cost()
+    );
+}
+
+#[test]
+fn sweep_skips_records_that_havent_expired() {
+    let mut contract = setup();
+    contract.claim_records.insert(&[1; 32], &record(u64::MAX));
+    contract.claim_records.insert(&[2; 32], &record(0));
+    contract.claim_records.insert(&[3; 32], &record(0));
+
+    set_caller(&account("alice"), 0);
+    assert_eq!(contract.sweep_claim_records(None), 2);
+    assert_eq!(contract.sweep_claim_records(None), 0);
+    assert!(contract.claim_records.get(&[1; 32]).is_some());
+}
+
+#[test]
+fn sweeps_scan_at_most_the_limit_and_pick_up_where_they_left_off() {
+    let mut contract = setup();
+    contract.claim_records.insert(&[1; 32], &record(u64::MAX));
+    contract.claim_records.insert(&[2; 32], &record(0));
+    contract.claim_records.insert(&[3; 32], &record(u64::MAX));
+    contract.claim_records.insert(&[4; 32], &record(0));
+
+    set_caller(&account("alice"), 0);
+    // Only the live record at the start is looked at
+    assert_eq!(contract.sweep_claim_records(Some(1)), 0);
+    assert_eq!(contract.claim_records.len(), 4);
+    // The next sweep starts after it
+    assert_eq!(contract.sweep_claim_records(Some(1)), 1);
+    assert!(contract.claim_records.get(&[2; 32]).is_none());
+    assert_eq!(contract.sweep_claim_records(Some(2)), 1);
+    assert!(contract.claim_records.get(&[4; 32]).is_none());
+
+    // Once the end is reached the sweep starts over and finds the first record now that it has expired
+    contract.claim_records.insert(&[1; 32], &record(0));
+    assert_eq!(contract.sweep_claim_records(Some(1)), 1);
+    assert_eq!(contract.claim_records.len(), 1);
+}
+
+#[test]
+fn record_keeps_the_outcome_of_the_claim() {
+    let mut contract = setup();
+    let pks = two_use_keys(&mut contract, 2);
+    let guard = |key_id| ClaimGuard {
+        drop_id: 0,
+        key_id,
+        use_number: 1,
+    };
+
+    claim_with_key(&mut contract, &pks[0], "retry-1");
+    claim_with_key(&mut contract, &pks[1], "retry-1");
+    let hash = |pk: &PublicKey| hash_claim_record(pk, "retry-1");
+    let record = contract.claim_records.get(&hash(&pks[0])).unwrap();
+    assert_eq!(record.account_id, account("alice"));
+    assert_eq!(record.outcome, ClaimOutcome::Pending);
+
+    resolve_claim(&mut contract, guard(0), PromiseResult::Successful(vec![]));
+    resolve_claim(&mut contract, guard(1), PromiseResult::Failed);
+    let outcome = |pk| contract.claim_records.get(&hash(pk)).unwrap().outcome;
+    assert_eq!(outcome(&pks[0]), ClaimOutcome::Succeeded);
+    assert_eq!(outcome(&pks[1]), ClaimOutcome::Failed);
+    assert!(contract.claim_record_for_guard.get(&guard(0)).is_none());
+}
+
+#[test]
+fn retries_are_told_the_outcome_of_the_original_claim() {
+    let mut contract = setup();
+    let pks = two_use_keys(&mut contract, 1);
+    claim_with_key(&mut contract, &pks[0], "retry-1");
+    let guard = ClaimGuard {
+        drop_id: 0,
+        key_id: 0,
+        use_number: 1,
+    };
+    resolve_claim(&mut contract, guard, PromiseResult::Successful(vec![]));
+
+    claim_with_key(&mut contract, &pks[0], "retry-1");
+    let event = get_logs()
+        .into_iter()
+        .find(|log| log.contains("duplicate_claim"))
+        .expect("no duplicate claim event");
+    assert!(event.contains(r#""account_id":"alice.near""#), "{}", event);
+    assert!(event.contains(r#""outcome":"succeeded""#), "{}", event);
+}
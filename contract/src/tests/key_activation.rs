@@ -0,0 +1,109 @@
+/*
+    Access keys that don't fit in the GAS of the call adding them are kept as pending activation and added by
+    `finish_key_creation` follow ups. Funders pay the same whether or not some of their keys were pending.
+*/
+use super::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+const MAX_GAS: Gas = Gas(300_000_000_000_000);
+/// Only enough for a few of a 20 key drop's access keys, the rest are left pending
+const GAS_FOR_SOME_KEYS: Gas = Gas(75_000_000_000_000);
+/// Not enough for any access key but still enough to schedule the follow up
+const GAS_FOR_NO_KEYS: Gas = Gas(50_000_000_000_000);
+
+/// Simple drop created with `prepaid_gas`, returning what the funder's balance went down by
+fn create_drop_with_gas(
+    contract: &mut Keypom,
+    public_keys: Vec<PublicKey>,
+    prepaid_gas: Gas,
+) -> (DropId, Balance) {
+    let balance_before = user_balance(contract, &funder_id());
+    testing_env!(context(&funder_id()).prepaid_gas(prepaid_gas).build());
+    let drop_id = contract.create_drop(
+        key_args(public_keys),
+        U128(ONE_NEAR),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+    (
+        drop_id,
+        balance_before - user_balance(contract, &funder_id()),
+    )
+}
+
+/// Whether the last call scheduled another `finish_key_creation`
+fn follow_up_scheduled() -> bool {
+    get_created_receipts()
+        .into_iter()
+        .flat_map(|receipt| receipt.actions)
+        .any(|action| {
+            matches!(action, VmAction::FunctionCall { function_name, .. } if function_name == "finish_key_creation")
+        })
+}
+
+fn finish_key_creation(contract: &mut Keypom, drop_id: DropId, prepaid_gas: Gas) {
+    testing_env!(context(&keypom_id()).prepaid_gas(prepaid_gas).build());
+    contract.finish_key_creation(drop_id);
+}
+
+#[test]
+fn pending_keys_cost_the_funder_nothing_extra() {
+    let mut contract = setup();
+    // The funder's first drop also pays for storage that later drops reuse
+    create_drop_with_gas(&mut contract, keys("first", 1), MAX_GAS);
+    let (_, full_cost) = create_drop_with_gas(&mut contract, keys("full", 20), MAX_GAS);
+
+    let (drop_id, cost) =
+        create_drop_with_gas(&mut contract, keys("pending", 20), GAS_FOR_SOME_KEYS);
+    let pending = contract.get_pending_key_activations(drop_id).len();
+    assert!(pending > 0 && pending < 20, "{} keys pending", pending);
+    assert!(follow_up_scheduled());
+    assert_eq!(cost, full_cost);
+
+    // Activating the keys doesn't change the funder's balance either
+    let balance_before = user_balance(&contract, &funder_id());
+    finish_key_creation(&mut contract, drop_id, MAX_GAS);
+    assert!(contract.get_pending_key_activations(drop_id).is_empty());
+    assert!(!follow_up_scheduled());
+    assert_eq!(user_balance(&contract, &funder_id()), balance_before);
+}
+
+#[test]
+fn follow_ups_are_rescheduled_until_every_key_is_added() {
+    let mut contract = setup();
+    let (drop_id, _) = create_drop_with_gas(&mut contract, keys("pending", 20), GAS_FOR_NO_KEYS);
+
+    let mut pending = contract.get_pending_key_activations(drop_id).len();
+    assert_eq!(pending, 20);
+    assert!(follow_up_scheduled());
+    while pending > 0 {
+        finish_key_creation(&mut contract, drop_id, GAS_FOR_SOME_KEYS);
+        let left = contract.get_pending_key_activations(drop_id).len();
+        assert!(left < pending);
+        assert_eq!(follow_up_scheduled(), left > 0);
+        pending = left;
+    }
+}
+
+#[test]
+fn follow_up_without_gas_for_a_single_key_fails() {
+    let mut contract = setup();
+    let (drop_id, _) = create_drop_with_gas(&mut contract, keys("pending", 20), GAS_FOR_NO_KEYS);
+
+    let panic = catch_unwind(AssertUnwindSafe(|| {
+        finish_key_creation(&mut contract, drop_id, GAS_FOR_NO_KEYS)
+    }))
+    .expect_err("keys were added");
+    assert_eq!(
+        panic.downcast_ref::<String>().map(String::as_str),
+        Some("Not enough GAS attached to add any of the keys pending activation")
+    );
+}
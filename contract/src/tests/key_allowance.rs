@@ -0,0 +1,45 @@
+use super::*;
+
+const EXTRA_ALLOWANCE: Balance = ONE_NEAR / 10;
+
+fn top_up(contract: &mut Keypom, drop_id: DropId, public_keys: Vec<PublicKey>) {
+    set_caller(&funder_id(), 1);
+    contract.add_allowance_to_keys(drop_id, public_keys, U128(EXTRA_ALLOWANCE));
+}
+
+#[test]
+fn top_up_raises_allowance_and_reserves() {
+    let mut contract = setup();
+    let pks = keys("top-up", 2);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+    let allowance_before = key_info(&contract, &pks[0]).allowance;
+    let reserves_before = contract.get_solvency_report().key_reserves.0;
+    let balance_before = user_balance(&contract, &funder_id());
+
+    top_up(&mut contract, drop_id, pks.clone());
+
+    assert_eq!(
+        key_info(&contract, &pks[0]).allowance,
+        allowance_before + EXTRA_ALLOWANCE
+    );
+    assert_eq!(
+        contract.get_solvency_report().key_reserves.0,
+        reserves_before + 2 * EXTRA_ALLOWANCE
+    );
+    assert_eq!(
+        user_balance(&contract, &funder_id()),
+        balance_before - 2 * EXTRA_ALLOWANCE
+    );
+}
+
+#[test]
+#[should_panic(expected = "key doesn't belong to the drop")]
+fn top_up_rejects_keys_of_other_drops() {
+    let mut contract = setup();
+    let pks = keys("top-up", 1);
+    let other_pks = keys("other", 1);
+    let drop_id = create_drop(&mut contract, pks, DropArgs::simple());
+    create_drop(&mut contract, other_pks.clone(), DropArgs::simple());
+
+    top_up(&mut contract, drop_id, other_pks);
+}
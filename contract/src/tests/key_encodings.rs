@@ -0,0 +1,165 @@
+/*
+    Public keys are parsed into their curve and raw bytes, so every encoding of a key is the same key whether it's
+    being added, claimed or looked up. Keys can be added as strings or byte arrays and malformed ones are reported
+    with their index.
+*/
+use super::*;
+use near_sdk::serde_json;
+
+/// The key passed in as `ed25519:<base58>`, `ED25519:<base58>` and bare `<base58>`
+fn encodings(public_key: &PublicKey) -> Vec<PublicKey> {
+    let prefixed: String =
+        serde_json::from_value(serde_json::to_value(public_key).unwrap()).unwrap();
+    let bare = prefixed.trim_start_matches("ed25519:").to_string();
+    vec![
+        format!("ed25519:{}", bare),
+        format!("ED25519:{}", bare),
+        bare,
+    ]
+    .into_iter()
+    .map(|encoded| serde_json::from_value(serde_json::Value::String(encoded)).unwrap())
+    .collect()
+}
+
+#[test]
+fn same_key_in_two_encodings_is_a_duplicate() {
+    let contract = setup();
+    let pk = keys("encoding", 1).remove(0);
+    let encoded = encodings(&pk);
+
+    let errors = validation_errors(
+        &contract,
+        vec![encoded[0].clone(), encoded[2].clone()],
+        DropArgs::simple(),
+    );
+    assert_eq!(errors[0].field, "public_keys");
+    assert_eq!(
+        errors[0].message,
+        "public_keys[1] is the same key as public_keys[0]"
+    );
+
+    let errors = validation_errors(
+        &contract,
+        vec![encoded[1].clone(), encoded[2].clone()],
+        DropArgs::simple(),
+    );
+    assert_eq!(
+        errors[0].message,
+        "public_keys[1] is the same key as public_keys[0]"
+    );
+}
+
+/// Keys as clients send them in the JSON arguments
+fn key_args_from_json(public_keys: serde_json::Value) -> Vec<PublicKeyArg> {
+    serde_json::from_value(public_keys).unwrap()
+}
+
+#[test]
+fn same_key_as_bytes_and_bare_base58_is_a_duplicate() {
+    let contract = setup();
+    let pk = keys("bytes", 1).remove(0);
+    let bare = String::from(&pk).trim_start_matches("ed25519:").to_string();
+
+    // With and without the leading curve byte
+    for bytes in [pk.as_bytes(), &pk.as_bytes()[1..]] {
+        let errors = validation_errors(
+            &contract,
+            key_args_from_json(serde_json::json!([bare, bytes])),
+            DropArgs::simple(),
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "public_keys");
+        assert_eq!(
+            errors[0].message,
+            "public_keys[1] is the same key as public_keys[0]"
+        );
+    }
+}
+
+#[test]
+fn keys_added_as_bytes_are_stored_as_the_same_key() {
+    let mut contract = setup();
+    let pk = keys("bytes", 1).remove(0);
+    set_caller(&funder_id(), 0);
+    let drop_id = contract.create_drop(
+        vec![PublicKeyArg::Bytes(pk.as_bytes()[1..].to_vec())],
+        U128(ONE_NEAR),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+
+    assert_eq!(contract.drop_id_for_pk.get(&pk), Some(drop_id));
+}
+
+#[test]
+fn malformed_keys_are_reported_with_their_index() {
+    let contract = setup();
+    let pk = keys("malformed", 1).remove(0);
+    let cases = [
+        serde_json::json!([pk, "ed25519:not-base58"]),
+        serde_json::json!([pk, "ed25519:"]),
+        serde_json::json!([pk, "rsa:3yMApqCuCjXDWPrbjfR5mjCPTHqFG8Pux1TxQrEM35jj"]),
+        serde_json::json!([pk, [1, 2, 3]]),
+    ];
+
+    for public_keys in cases {
+        let errors = validation_errors(
+            &contract,
+            key_args_from_json(public_keys.clone()),
+            DropArgs::simple(),
+        );
+        assert_eq!(errors.len(), 1, "{}", public_keys);
+        assert_eq!(errors[0].field, "public_keys");
+        assert!(
+            errors[0].message.starts_with("index 1: invalid public key"),
+            "{}",
+            errors[0].message
+        );
+    }
+}
+
+#[test]
+fn key_in_another_encoding_already_belongs_to_drop() {
+    let mut contract = setup();
+    let pks = keys("taken", 2);
+    let drop_id = create_drop(&mut contract, vec![pks[0].clone()], DropArgs::simple());
+    let bare = encodings(&pks[0]).remove(2);
+
+    let errors = validation_errors(
+        &contract,
+        vec![pks[1].clone(), bare.clone()],
+        DropArgs::simple(),
+    );
+    assert_eq!(
+        errors[0].message,
+        format!("public_keys[1] already belongs to drop {}", drop_id)
+    );
+    // add_keys runs the same check
+    assert_eq!(
+        contract.internal_check_new_keys(&[bare]),
+        Err(format!(
+            "public_keys[0] already belongs to drop {}",
+            drop_id
+        ))
+    );
+}
+
+#[test]
+fn stored_keys_are_found_through_any_encoding() {
+    let mut contract = setup();
+    let pks = keys("lookup", 1);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+
+    for encoded in encodings(&pks[0]) {
+        assert_eq!(contract.drop_id_for_pk.get(&encoded), Some(drop_id));
+        assert!(contract.get_key_information(encoded).is_some());
+    }
+}
@@ -0,0 +1,82 @@
+/*
+    Legacy wallets call the claim methods with only the arguments they've always passed in. Every argument added
+    since is optional so these payloads still work, and keys keep the method names they were created with.
+*/
+use super::*;
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::serde_json::{self, Value};
+
+/// Argument from a JSON payload the way `near_bindgen` deserializes it. Missing optional arguments are `None`.
+fn arg<T: DeserializeOwned>(payload: &Value, name: &str) -> T {
+    serde_json::from_value(payload.get(name).cloned().unwrap_or(Value::Null)).unwrap()
+}
+
+#[test]
+fn claim_with_legacy_payload() {
+    let mut contract = setup();
+    let pks = keys("legacy-claim", 1);
+    create_drop(&mut contract, pks.clone(), DropArgs::simple());
+
+    let payload: Value = serde_json::from_str(r#"{"account_id":"alice.near"}"#).unwrap();
+    set_key_signer(&pks[0], required_gas_for_key(&contract, &pks[0]));
+    contract.claim(
+        arg(&payload, "account_id"),
+        arg(&payload, "idempotency_key"),
+        arg(&payload, "identifier"),
+        arg(&payload, "payout_token"),
+        arg(&payload, "pow_nonce"),
+        arg(&payload, "password"),
+    );
+
+    assert!(contract.get_key_information(pks[0].clone()).is_none());
+    assert_eq!(near_sent_to(&account("alice")), ONE_NEAR);
+}
+
+#[test]
+fn create_account_and_claim_with_legacy_payload() {
+    let mut contract = setup();
+    let pks = keys("legacy-create-account", 1);
+    create_drop(&mut contract, pks.clone(), DropArgs::simple());
+
+    let new_public_key = keys("alice", 1).remove(0);
+    let payload = near_sdk::serde_json::json!({
+        "new_account_id": "alice.near",
+        "new_public_key": new_public_key,
+    });
+    set_create_account_signer(&contract, &pks[0]);
+    contract.create_account_and_claim(
+        arg(&payload, "new_account_id"),
+        arg(&payload, "new_public_key"),
+        arg(&payload, "idempotency_key"),
+        arg(&payload, "identifier"),
+        arg(&payload, "pow_nonce"),
+        arg(&payload, "password"),
+    );
+
+    assert!(contract.get_key_information(pks[0].clone()).is_none());
+}
+
+#[test]
+fn keys_can_call_the_legacy_method_names() {
+    assert_eq!(
+        access_key_method_names(&ClaimPermissions::Both),
+        "claim,create_account_and_claim"
+    );
+    assert_eq!(access_key_method_names(&ClaimPermissions::Claim), "claim");
+    assert_eq!(
+        access_key_method_names(&ClaimPermissions::CreateAccountAndClaim),
+        "create_account_and_claim"
+    );
+
+    let mut contract = setup();
+    create_drop(&mut contract, keys("method-names", 1), DropArgs::simple());
+    let function_names = get_created_receipts()
+        .into_iter()
+        .flat_map(|receipt| receipt.actions)
+        .find_map(|action| match action {
+            VmAction::AddKeyWithFunctionCall { function_names, .. } => Some(function_names),
+            _ => None,
+        })
+        .expect("no access key added");
+    assert_eq!(function_names, vec!["claim", "create_account_and_claim"]);
+}
@@ -0,0 +1,57 @@
+/*
+    `migrate` reads state written in the layout the contract was first deployed with and carries it over, starting
+    everything added since the same way a new contract would.
+*/
+use super::*;
+
+/// Write `contract`'s state the way a contract deployed before the current layout would have it and migrate it
+fn migrate_from_old_layout(mut contract: Keypom) -> Keypom {
+    // Collections added since weren't in storage yet
+    contract.known_roots.clear();
+
+    // The old layout is the start of the current one so reading it stops before the newer fields
+    let state = contract.try_to_vec().unwrap();
+    let old = OldKeypom::deserialize(&mut state.as_slice()).unwrap();
+    env::state_write(&old);
+
+    set_caller(&keypom_id(), 0);
+    Keypom::migrate()
+}
+
+#[test]
+fn migrated_contract_keeps_drops_balances_and_fees() {
+    let mut contract = setup();
+    let pks = keys("migrate", 2);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+    set_caller(&owner_id(), 1);
+    contract.set_gas_price(50_000_000);
+    let balance = user_balance(&contract, &funder_id());
+    let fees_collected = contract.get_fees_collected();
+
+    let mut contract = migrate_from_old_layout(contract);
+    assert_eq!(contract.get_owner(), owner_id());
+    assert_eq!(contract.get_next_drop_id(), drop_id + 1);
+    assert_eq!(contract.get_gas_price(), U128(50_000_000));
+    assert_eq!(contract.get_fees_collected(), fees_collected);
+    assert_eq!(user_balance(&contract, &funder_id()), balance);
+    assert_eq!(contract.get_key_supply_for_drop(drop_id), 2);
+
+    // Keys of drops created before the migration can still be claimed
+    claim(&mut contract, &pks[0], account("alice"));
+    assert_eq!(contract.get_key_supply_for_drop(drop_id), 1);
+}
+
+#[test]
+fn fields_added_since_start_out_as_for_a_new_contract() {
+    let mut contract = setup();
+    set_caller(&owner_id(), 1);
+    contract.set_claim_record_ttl(1);
+
+    let contract = migrate_from_old_layout(contract);
+    assert_eq!(contract.get_claim_record_ttl(), DEFAULT_CLAIM_RECORD_TTL);
+    assert_eq!(
+        contract.get_expiring_key_window(),
+        DEFAULT_EXPIRING_KEY_WINDOW
+    );
+    assert_eq!(contract.get_known_roots(None, None), vec![root_id()]);
+}
@@ -0,0 +1,295 @@
+/*
+    Unit tests run against near-sdk's mocked blockchain. Promises are never executed so callbacks are
+    called directly with the promise results they would have received. The helpers below build on the
+    fixtures in `testing` so that each scenario only spells out what it's actually testing.
+*/
+use crate::testing::*;
+use crate::*;
+use near_sdk::mock::VmAction;
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+
+mod account_claims;
+mod account_names;
+mod attached_deposits;
+mod backup;
+mod claim_gas;
+mod claim_guard;
+mod claim_origin;
+mod claim_surface;
+mod contract_owner;
+mod delete_keys;
+mod deploy;
+mod drop_creation;
+mod drop_information;
+mod drop_invariants;
+mod drop_type_costs;
+mod drops_paginated;
+mod early_bonus;
+mod end_timestamp;
+mod expiring_keys;
+mod first_drop;
+mod ft_dust;
+mod ft_verification;
+mod idempotency;
+mod key_activation;
+mod key_allowance;
+mod key_encodings;
+mod legacy_wallets;
+mod migration;
+mod nft_packs;
+mod nft_registration;
+mod one_yocto;
+mod operational_buffer;
+mod ownership;
+mod passwords;
+mod post_claim;
+mod reference;
+mod registered_uses;
+mod solvency;
+mod storage_prefixes;
+mod storage_price;
+mod throttle;
+mod unused_keys;
+mod update_drop;
+mod zero_fees;
+
+pub(crate) const ONE_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
+pub(crate) const FUNDER_BALANCE: u128 = 1_000 * ONE_NEAR;
+
+pub(crate) fn keypom_id() -> AccountId {
+    "keypom.near".parse().unwrap()
+}
+
+pub(crate) fn owner_id() -> AccountId {
+    "owner.near".parse().unwrap()
+}
+
+pub(crate) fn funder_id() -> AccountId {
+    "funder.near".parse().unwrap()
+}
+
+pub(crate) fn root_id() -> AccountId {
+    "near".parse().unwrap()
+}
+
+pub(crate) fn account(name: &str) -> AccountId {
+    format!("{}.near", name).parse().unwrap()
+}
+
+/// Context for a call made by `predecessor` directly (i.e not through a drop key)
+pub(crate) fn context(predecessor: &AccountId) -> VMContextBuilder {
+    let mut builder = VMContextBuilder::new();
+    builder
+        .current_account_id(keypom_id())
+        .signer_account_id(predecessor.clone())
+        .predecessor_account_id(predecessor.clone())
+        .account_balance(1_000_000 * ONE_NEAR);
+    builder
+}
+
+/// Keys as they're passed into `create_drop`, `add_keys` and `validate_drop`
+pub(crate) fn key_args(public_keys: Vec<PublicKey>) -> Vec<PublicKeyArg> {
+    public_keys.into_iter().map(PublicKeyArg::from).collect()
+}
+
+pub(crate) fn set_caller(predecessor: &AccountId, attached_deposit: Balance) {
+    testing_env!(context(predecessor)
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+/// Call a self callback with the results of the promises it was waiting on
+pub(crate) fn set_callback(promise_results: Vec<PromiseResult>) {
+    testing_env!(
+        context(&keypom_id()).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        promise_results
+    );
+}
+
+/// Contract owned by `owner.near` with `funder.near` holding `FUNDER_BALANCE`
+pub(crate) fn setup() -> Keypom {
+    set_caller(&owner_id(), 0);
+    Keypom::test_new_with_balances(
+        root_id(),
+        owner_id(),
+        vec![(funder_id(), U128(FUNDER_BALANCE))],
+    )
+}
+
+pub(crate) fn keys(seed: &str, count: u64) -> Vec<PublicKey> {
+    deterministic_public_keys(seed, count)
+}
+
+/// Arguments of `create_drop` that most scenarios don't care about
+#[derive(Default)]
+pub(crate) struct DropArgs {
+    pub deposit_per_use: Balance,
+    pub config: Option<DropConfig>,
+    pub metadata: Option<DropMetadata>,
+    pub ft_data: Option<FTDataConfig>,
+    pub nft_data: Option<NFTDataConfig>,
+    pub fc_data: Option<FCData>,
+    pub password_hashes: Option<Vec<Option<Base64VecU8>>>,
+}
+
+impl DropArgs {
+    pub(crate) fn simple() -> Self {
+        DropArgs {
+            deposit_per_use: ONE_NEAR,
+            ..Default::default()
+        }
+    }
+}
+
+pub(crate) fn create_drop(
+    contract: &mut Keypom,
+    public_keys: Vec<PublicKey>,
+    args: DropArgs,
+) -> DropId {
+    set_caller(&funder_id(), 0);
+    contract.create_drop(
+        key_args(public_keys),
+        U128(args.deposit_per_use),
+        args.config,
+        args.metadata,
+        args.ft_data,
+        args.nft_data,
+        args.fc_data,
+        None,
+        None,
+        None,
+        args.password_hashes,
+        None,
+    )
+}
+
+/// Errors `create_drop` would fail with, found through `validate_drop` so that nothing panics
+pub(crate) fn validation_errors<K: Into<PublicKeyArg>>(
+    contract: &Keypom,
+    public_keys: Vec<K>,
+    args: DropArgs,
+) -> Vec<DropValidationError> {
+    let args = CreateDropArgs {
+        public_keys: public_keys.into_iter().map(Into::into).collect(),
+        deposit_per_use: U128(args.deposit_per_use),
+        config: args.config,
+        metadata: args.metadata,
+        ft_data: args.ft_data,
+        nft_data: args.nft_data,
+        fc_data: args.fc_data,
+        campaign_id: None,
+        partner_id: None,
+    };
+    match contract.validate_drop(funder_id(), None, args) {
+        ValidationResult::Ok { .. } => vec![],
+        ValidationResult::Invalid { errors } => errors,
+    }
+}
+
+/// Send token IDs to an NFT drop from its sender through the drop's NFT contract
+pub(crate) fn send_nfts(contract: &mut Keypom, drop_id: DropId, token_ids: &[&str]) {
+    let nft_data = match contract.drop_for_id.get(&drop_id).unwrap().drop_type {
+        DropType::NonFungibleToken(data) => data,
+        _ => panic!("drop isn't NFT"),
+    };
+    set_caller(&nft_data.contract_id, 0);
+    for token_id in token_ids {
+        contract.nft_on_transfer(
+            nft_data.sender_id.clone(),
+            nft_data.sender_id.clone(),
+            token_id.to_string(),
+            drop_id.to_string(),
+        );
+    }
+}
+
+/// Resolve the FT storage check of a pending drop as if the token contract returned `min` storage
+pub(crate) fn activate_ft_drop(
+    contract: &mut Keypom,
+    drop_id: DropId,
+    public_keys: Vec<PublicKey>,
+) {
+    let required_deposit = match contract.drop_for_id.get(&drop_id).unwrap().state {
+        DropState::Pending {
+            required_deposit, ..
+        } => required_deposit,
+        _ => panic!("drop isn't pending"),
+    };
+    let bounds = near_sdk::serde_json::json!({ "min": "1250000000000000000000", "max": null });
+    set_callback(vec![PromiseResult::Successful(
+        bounds.to_string().into_bytes(),
+    )]);
+    assert!(contract.resolve_storage_check(public_keys, drop_id, required_deposit.0, None));
+}
+
+/// Claim to an existing account with a transaction signed by the drop key and the GAS the drop requires
+pub(crate) fn claim(contract: &mut Keypom, public_key: &PublicKey, account_id: AccountId) {
+    let gas = required_gas_for_key(contract, public_key);
+    claim_with_gas(contract, public_key, account_id, gas);
+}
+
+pub(crate) fn claim_with_gas(
+    contract: &mut Keypom,
+    public_key: &PublicKey,
+    account_id: AccountId,
+    prepaid_gas: Gas,
+) {
+    set_key_signer(public_key, prepaid_gas);
+    contract.claim(account_id, None, None, None, None, None);
+}
+
+/// Context for a transaction signed by a drop key on the contract account
+pub(crate) fn set_key_signer(public_key: &PublicKey, prepaid_gas: Gas) {
+    testing_env!(context(&keypom_id())
+        .signer_account_pk(public_key.clone())
+        .prepaid_gas(prepaid_gas)
+        .build());
+}
+
+pub(crate) fn required_gas_for_key(contract: &Keypom, public_key: &PublicKey) -> Gas {
+    let drop_id = contract.drop_id_for_pk.get(public_key).unwrap();
+    contract.drop_for_id.get(&drop_id).unwrap().required_gas
+}
+
+/// Context for `create_account_and_claim` signed by a drop key. Host calls cost more on the mocked chain than the
+/// GAS drops require accounts for, so 50 TGas (still covered by the key's allowance) is added on top.
+pub(crate) fn set_create_account_signer(contract: &Keypom, public_key: &PublicKey) {
+    let gas = required_gas_for_key(contract, public_key) + Gas(50_000_000_000_000);
+    set_key_signer(public_key, gas);
+}
+
+/// $NEAR transferred to an account by the promises the last call created
+pub(crate) fn near_sent_to(receiver_id: &AccountId) -> Balance {
+    get_created_receipts()
+        .into_iter()
+        .filter(|receipt| &receipt.receiver_id == receiver_id)
+        .flat_map(|receipt| receipt.actions)
+        .map(|action| match action {
+            VmAction::Transfer { deposit } => deposit,
+            _ => 0,
+        })
+        .sum()
+}
+
+pub(crate) fn user_balance(contract: &Keypom, account_id: &AccountId) -> Balance {
+    contract.get_user_balance(account_id.clone()).0
+}
+
+pub(crate) fn key_info(contract: &Keypom, public_key: &PublicKey) -> KeyInfo {
+    let drop_id = contract.drop_id_for_pk.get(public_key).unwrap();
+    contract
+        .drop_for_id
+        .get(&drop_id)
+        .unwrap()
+        .pks
+        .get(public_key)
+        .unwrap()
+}
+
+pub(crate) fn registered_uses(contract: &Keypom, drop_id: DropId) -> u64 {
+    contract.drop_for_id.get(&drop_id).unwrap().registered_uses
+}
@@ -0,0 +1,135 @@
+use super::*;
+
+fn pack_drop_args(tokens_per_use: u64) -> DropArgs {
+    DropArgs {
+        nft_data: Some(
+            NFTDataConfig::fixture(account("nft"), funder_id()).with_tokens_per_use(tokens_per_use),
+        ),
+        ..DropArgs::simple()
+    }
+}
+
+fn nft_data(contract: &Keypom, drop_id: DropId) -> NFTData {
+    match contract.drop_for_id.get(&drop_id).unwrap().drop_type {
+        DropType::NonFungibleToken(data) => data,
+        _ => panic!("drop isn't NFT"),
+    }
+}
+
+#[test]
+fn packs_too_big_for_a_claim_are_rejected() {
+    let contract = setup();
+
+    let errors = validation_errors(&contract, keys("pack", 1), pack_drop_args(20));
+    assert_eq!(errors[0].field, "nft_data.tokens_per_use");
+    assert!(errors[0]
+        .message
+        .contains("a claim can attach at most 300 TGas"));
+
+    let errors = validation_errors(&contract, keys("pack", 1), pack_drop_args(0));
+    assert_eq!(errors[0].message, "tokens_per_use must be more than 0");
+
+    assert!(validation_errors(&contract, keys("pack", 1), pack_drop_args(5)).is_empty());
+}
+
+#[test]
+fn uses_are_registered_once_a_pack_is_complete() {
+    let mut contract = setup();
+    let drop_id = create_drop(&mut contract, keys("pack", 2), pack_drop_args(3));
+
+    send_nfts(&mut contract, drop_id, &["a", "b"]);
+    assert_eq!(registered_uses(&contract, drop_id), 0);
+    send_nfts(&mut contract, drop_id, &["c", "d"]);
+    assert_eq!(registered_uses(&contract, drop_id), 1);
+    assert_eq!(nft_data(&contract, drop_id).leftover_tokens(), 1);
+}
+
+#[test]
+fn claim_hands_over_a_full_pack() {
+    let mut contract = setup();
+    let pks = keys("pack", 2);
+    let drop_id = create_drop(&mut contract, pks.clone(), pack_drop_args(3));
+    send_nfts(&mut contract, drop_id, &["a", "b", "c", "d"]);
+
+    claim(&mut contract, &pks[0], account("alice"));
+
+    assert_eq!(
+        contract.get_nft_token_ids_for_drop(drop_id, None, None),
+        vec!["a".to_string()]
+    );
+    assert_eq!(registered_uses(&contract, drop_id), 0);
+}
+
+#[test]
+fn partial_pack_is_never_claimed() {
+    let mut contract = setup();
+    let pks = keys("pack", 2);
+    let drop_id = create_drop(&mut contract, pks.clone(), pack_drop_args(3));
+    send_nfts(&mut contract, drop_id, &["a", "b", "c"]);
+
+    // Leave the drop with a registered use but only part of a pack
+    let mut drop = contract.drop_for_id.get(&drop_id).unwrap();
+    if let DropType::NonFungibleToken(data) = &mut drop.drop_type {
+        data.token_ids.pop();
+    }
+    contract.drop_for_id.insert(&drop_id, &drop);
+
+    claim(&mut contract, &pks[0], account("alice"));
+
+    assert_eq!(nft_data(&contract, drop_id).token_ids.len(), 2);
+    assert_eq!(registered_uses(&contract, drop_id), 1);
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 1);
+}
+
+#[test]
+fn failed_pack_transfers_are_registered_again() {
+    let mut contract = setup();
+    let pks = keys("pack", 2);
+    let drop_id = create_drop(&mut contract, pks.clone(), pack_drop_args(2));
+    send_nfts(&mut contract, drop_id, &["a", "b"]);
+    claim(&mut contract, &pks[0], account("alice"));
+    assert_eq!(registered_uses(&contract, drop_id), 0);
+
+    set_callback(vec![
+        PromiseResult::Successful(vec![]),
+        PromiseResult::Failed,
+    ]);
+    let all_sent = contract.nft_resolve_pack_transfer(
+        U128(drop_id),
+        vec!["b".to_string(), "a".to_string()],
+        funder_id(),
+        account("nft"),
+    );
+
+    assert!(!all_sent);
+    assert_eq!(
+        contract.get_nft_token_ids_for_drop(drop_id, None, None),
+        vec!["a".to_string()]
+    );
+    // A single token doesn't make up a pack of 2
+    assert_eq!(registered_uses(&contract, drop_id), 0);
+}
+
+#[test]
+fn pack_storage_is_priced_at_the_drops_byte_cost() {
+    let mut contract = setup();
+    let drop_id = create_drop(&mut contract, keys("pack", 1), pack_drop_args(3));
+    let data = nft_data(&contract, drop_id);
+    let recorded_cost = contract
+        .drop_for_id
+        .get(&drop_id)
+        .unwrap()
+        .storage_byte_cost;
+
+    // A protocol change to the byte cost doesn't change what the drop's NFT storage is worth
+    let drop_type = DropType::NonFungibleToken(data);
+    let behavior = drop_type.behavior();
+    assert_eq!(
+        behavior.asset_storage_per_use(recorded_cost),
+        nft_data(&contract, drop_id).storage_for_longest * 3 * recorded_cost
+    );
+    assert_eq!(
+        behavior.asset_storage_per_use(recorded_cost * 2),
+        2 * behavior.asset_storage_per_use(recorded_cost)
+    );
+}
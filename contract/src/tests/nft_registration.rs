@@ -0,0 +1,144 @@
+/*
+    NFTs are registered to a drop by transferring them to the contract with the drop ID as the `msg`. Tokens that
+    can't be registered are returned to their owner by the NFT contract instead of being lost.
+*/
+use super::*;
+use near_sdk::test_utils::get_logs;
+
+fn nft_drop(contract: &mut Keypom) -> DropId {
+    let args = DropArgs {
+        nft_data: Some(NFTDataConfig::fixture(account("nft"), funder_id())),
+        ..DropArgs::simple()
+    };
+    create_drop(contract, keys("nft", 2), args)
+}
+
+/// Transfer a token to the contract as `nft_contract` would and return whether it asked for the token back
+fn transfer_nft(
+    contract: &mut Keypom,
+    nft_contract: &AccountId,
+    sender_id: AccountId,
+    token_id: &str,
+    msg: &str,
+) -> bool {
+    set_caller(nft_contract, 0);
+    match contract.nft_on_transfer(
+        sender_id.clone(),
+        sender_id,
+        token_id.to_string(),
+        msg.to_string(),
+    ) {
+        PromiseOrValue::Value(return_token) => return_token,
+        PromiseOrValue::Promise(_) => panic!("nft_on_transfer returned a promise"),
+    }
+}
+
+/// Check the last transfer was returned for `reason` without registering anything
+fn assert_returned(contract: &Keypom, drop_id: DropId, returned: bool, reason: &str) {
+    assert!(returned);
+    assert!(
+        get_logs().iter().any(|log| log.ends_with(reason)),
+        "{:?}",
+        get_logs()
+    );
+    assert_eq!(registered_uses(contract, drop_id), 0);
+    assert!(contract
+        .get_nft_token_ids_for_drop(drop_id, None, None)
+        .is_empty());
+}
+
+#[test]
+fn token_is_registered_to_the_drop() {
+    let mut contract = setup();
+    let drop_id = nft_drop(&mut contract);
+
+    let returned = transfer_nft(
+        &mut contract,
+        &account("nft"),
+        funder_id(),
+        &"a".repeat(64),
+        &drop_id.to_string(),
+    );
+
+    assert!(!returned);
+    assert_eq!(registered_uses(&contract, drop_id), 1);
+}
+
+#[test]
+fn over_long_token_id_is_returned() {
+    let mut contract = setup();
+    let drop_id = nft_drop(&mut contract);
+
+    let returned = transfer_nft(
+        &mut contract,
+        &account("nft"),
+        funder_id(),
+        &"a".repeat(65),
+        &drop_id.to_string(),
+    );
+
+    assert_returned(
+        &contract,
+        drop_id,
+        returned,
+        "token ID must be less than largest token specified",
+    );
+}
+
+#[test]
+fn token_from_another_contract_is_returned() {
+    let mut contract = setup();
+    let drop_id = nft_drop(&mut contract);
+
+    let returned = transfer_nft(
+        &mut contract,
+        &account("fake-nft"),
+        funder_id(),
+        "token-1",
+        &drop_id.to_string(),
+    );
+
+    assert_returned(
+        &contract,
+        drop_id,
+        returned,
+        "NFT contract doesn't match the drop's contract",
+    );
+}
+
+#[test]
+fn token_from_another_sender_is_returned() {
+    let mut contract = setup();
+    let drop_id = nft_drop(&mut contract);
+
+    let returned = transfer_nft(
+        &mut contract,
+        &account("nft"),
+        account("alice"),
+        "token-1",
+        &drop_id.to_string(),
+    );
+
+    assert_returned(
+        &contract,
+        drop_id,
+        returned,
+        "sender doesn't match the drop's sender",
+    );
+}
+
+#[test]
+fn token_for_an_unknown_or_non_nft_drop_is_returned() {
+    let mut contract = setup();
+    let drop_id = nft_drop(&mut contract);
+    let simple_drop_id = create_drop(&mut contract, keys("simple", 1), DropArgs::simple());
+
+    for (msg, reason) in [
+        ("not-a-drop".to_string(), "msg must be a drop ID"),
+        ((simple_drop_id + 1).to_string(), "No drop found for ID"),
+        (simple_drop_id.to_string(), "drop type isn't NFT"),
+    ] {
+        let returned = transfer_nft(&mut contract, &account("nft"), funder_id(), "token-1", &msg);
+        assert_returned(&contract, drop_id, returned, reason);
+    }
+}
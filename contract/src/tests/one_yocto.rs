@@ -0,0 +1,217 @@
+/*
+    Every method in `ONE_YOCTO_METHODS` must be called with exactly 1 yoctoNEAR attached. Function call access
+    keys can't attach deposits, so these can only ever be called with a full access key.
+*/
+use super::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+const ONE_YOCTO_MESSAGE: &str = "Requires attached deposit of exactly 1 yoctoNEAR";
+const EXPIRY: u64 = 1_000_000_000;
+
+/// Methods called by the drop's funder (or for their own balance). The rest are called by the contract owner.
+const FUNDER_METHODS: &[&str] = &[
+    "delete_keys",
+    "refund_expired_keys",
+    "tighten_expiring_keys",
+    "refund_assets",
+    "delete_drop",
+    "update_drop",
+    "update_asset_sender",
+    "add_allowance_to_keys",
+    "transfer_drop_ownership",
+    "cancel_pending_drop",
+    "add_allowed_hashes",
+    "remove_allowed_hashes",
+    "delete_campaign",
+    "withdraw_from_balance",
+    "veto_admin_action",
+];
+
+fn caller_for(method: &str) -> AccountId {
+    if FUNDER_METHODS.contains(&method) {
+        funder_id()
+    } else {
+        owner_id()
+    }
+}
+
+/// Call `method` with arguments that are valid for the drop `drop_args` creates for it
+fn call(contract: &mut Keypom, method: &str, drop_id: DropId, pks: &[PublicKey]) {
+    let hashes = vec![Base64VecU8(vec![0; 32])];
+    match method {
+        "delete_keys" => contract.delete_keys(drop_id, None, None),
+        "refund_expired_keys" => contract.refund_expired_keys(drop_id, None),
+        "tighten_expiring_keys" => contract.tighten_expiring_keys(drop_id, None),
+        "refund_assets" => contract.refund_assets(drop_id, None),
+        "delete_drop" => contract.delete_drop(drop_id, Some(true)),
+        "update_drop" => contract.update_drop(drop_id, None, Some(None)),
+        "update_asset_sender" => contract.update_asset_sender(drop_id, account("sender")),
+        "add_allowance_to_keys" => contract.add_allowance_to_keys(drop_id, pks.to_vec(), U128(1)),
+        "transfer_drop_ownership" => contract.transfer_drop_ownership(drop_id, account("alice")),
+        "cancel_pending_drop" => {
+            contract.cancel_pending_drop(drop_id);
+        }
+        "add_allowed_hashes" => {
+            contract.add_allowed_hashes(drop_id, hashes);
+        }
+        "remove_allowed_hashes" => {
+            contract.remove_allowed_hashes(drop_id, hashes);
+        }
+        "delete_campaign" => contract.delete_campaign(0),
+        "withdraw_from_balance" => contract.withdraw_from_balance(Some(U128(ONE_NEAR))),
+        "veto_admin_action" => contract.veto_admin_action(drop_id),
+        "restrict_fc_receivers" => {
+            contract.restrict_fc_receivers(account("dao"), ".dao.near".to_string());
+        }
+        "unrestrict_fc_receivers" => {
+            contract.unrestrict_fc_receivers(account("dao"), ".dao.near".to_string());
+        }
+        "set_fc_receiver_reputation" => {
+            contract.set_fc_receiver_reputation(account("receiver"), FcReceiverReputation::Denied);
+        }
+        "set_owner" => contract.set_owner(owner_id()),
+        "set_root_account" => contract.set_root_account(root_id()),
+        "register_known_root" => {
+            contract.register_known_root("testnet".parse().unwrap());
+        }
+        "unregister_known_root" => {
+            contract.unregister_known_root("testnet".parse().unwrap());
+        }
+        "set_fees_per_user" => contract.set_fees_per_user(funder_id(), U128(0), U128(0)),
+        "set_gas_price" => contract.set_gas_price(100_000_000),
+        "set_account_creation_cost" => contract.set_account_creation_cost(U128(ONE_NEAR / 100)),
+        "set_claim_record_ttl" => contract.set_claim_record_ttl(60),
+        "set_expiring_key_window" => contract.set_expiring_key_window(60),
+        "set_operational_buffer" => contract.set_operational_buffer(U128(ONE_NEAR)),
+        "admin_refund_drop" => contract.admin_refund_drop(drop_id),
+        "admin_force_refund" => contract.admin_force_refund(drop_id, false),
+        "withdraw_fees" => {
+            contract.withdraw_fees(owner_id());
+        }
+        "set_badge_minter" => contract.set_badge_minter(Some(account("badges"))),
+        "set_wrap_contract" => contract.set_wrap_contract(Some(account("wrap"))),
+        "set_access_key_cap" => contract.set_access_key_cap(Some(100)),
+        "import_drop" => {
+            // The keys are still used by the exported drop
+            let mut bundle = contract.export_drop(drop_id, None, None);
+            bundle.keys.clear();
+            contract.import_drop(bundle, None);
+        }
+        "register_partner" => {
+            contract.register_partner(account("partner"), 100);
+        }
+        "unregister_partner" => {
+            contract.unregister_partner(account("partner"));
+        }
+        "register_swap_route" => {
+            let route = SwapRoute {
+                exchange: account("exchange"),
+                wrap_contract: account("wrap"),
+                pool_id: 0,
+                min_amount_out_per_near: U128(1),
+            };
+            contract.register_swap_route(account("token"), route);
+        }
+        "unregister_swap_route" => {
+            contract.unregister_swap_route(account("token"));
+        }
+        _ => panic!("no test call for {}", method),
+    }
+}
+
+/// Drop that `method` can be called on successfully
+fn drop_args(method: &str) -> DropArgs {
+    match method {
+        "refund_expired_keys" => DropArgs {
+            config: Some(DropConfig::builder().end_timestamp(EXPIRY).build()),
+            ..DropArgs::simple()
+        },
+        "tighten_expiring_keys" => DropArgs {
+            config: Some(DropConfig::builder().end_timestamp(EXPIRY + 2).build()),
+            ..DropArgs::simple()
+        },
+        "refund_assets" | "update_asset_sender" => DropArgs {
+            nft_data: Some(NFTDataConfig::fixture(account("nft"), funder_id())),
+            ..DropArgs::simple()
+        },
+        "cancel_pending_drop" => DropArgs {
+            ft_data: Some(FTDataConfig::fixture(account("token"), funder_id())),
+            ..DropArgs::simple()
+        },
+        "add_allowed_hashes" | "remove_allowed_hashes" => {
+            let mut config = DropConfig::builder().build();
+            config.identifier_allowlist = Some(IdentifierAllowlist::Reusable);
+            DropArgs {
+                config: Some(config),
+                ..DropArgs::simple()
+            }
+        }
+        _ => DropArgs::simple(),
+    }
+}
+
+/// Context for `method` with `deposit` attached, after the keys of the `refund_expired_keys` drop have expired and
+/// while the keys of the `tighten_expiring_keys` drop are about to
+fn set_method_caller(method: &str, deposit: Balance) {
+    testing_env!(context(&caller_for(method))
+        .attached_deposit(deposit)
+        .block_timestamp(EXPIRY + 1)
+        .build());
+}
+
+/// Message the call panicked with, if any
+fn panic_message(
+    contract: &mut Keypom,
+    method: &str,
+    drop_id: DropId,
+    pks: &[PublicKey],
+) -> Option<String> {
+    catch_unwind(AssertUnwindSafe(|| call(contract, method, drop_id, pks)))
+        .err()
+        .map(|panic| panic.downcast_ref::<String>().cloned().unwrap_or_default())
+}
+
+#[test]
+fn methods_without_one_yocto_are_rejected() {
+    let mut contract = setup();
+    for method in ONE_YOCTO_METHODS {
+        let pks = keys(method, 1);
+        let drop_id = create_drop(&mut contract, pks.clone(), drop_args(method));
+
+        for deposit in [0, 2] {
+            set_method_caller(method, deposit);
+            assert_eq!(
+                panic_message(&mut contract, method, drop_id, &pks).as_deref(),
+                Some(ONE_YOCTO_MESSAGE),
+                "{} called with {} yocto",
+                method,
+                deposit
+            );
+        }
+        assert!(contract.drop_for_id.get(&drop_id).is_some(), "{}", method);
+    }
+}
+
+#[test]
+fn methods_with_one_yocto_go_through() {
+    let mut contract = setup();
+    set_caller(&funder_id(), 0);
+    contract.create_campaign("campaign".to_string(), None);
+
+    // Methods that undo another one (i.e unregister_partner) are listed after it
+    for method in ONE_YOCTO_METHODS {
+        let pks = keys(method, 1);
+        let drop_id = create_drop(&mut contract, pks.clone(), drop_args(method));
+        if method == &"refund_assets" {
+            send_nfts(&mut contract, drop_id, &["token-1"]);
+        }
+        // Only force refunds the owner announced can be vetoed
+        if method == &"veto_admin_action" {
+            set_method_caller("admin_force_refund", 1);
+            contract.admin_force_refund(drop_id, false);
+        }
+
+        set_method_caller(method, 1);
+        call(&mut contract, method, drop_id, &pks);
+    }
+}
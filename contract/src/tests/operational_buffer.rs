@@ -0,0 +1,66 @@
+/*
+    Outbound transfers never take the contract's balance below its storage staking requirement plus the
+    operational buffer. Payouts that would are credited to the recipient's balance instead.
+*/
+use super::*;
+
+/// Call made when the contract's balance only covers its storage staking and `free_balance` on top of it
+fn set_caller_with_free_balance(predecessor: &AccountId, free_balance: Balance) {
+    set_caller(predecessor, 1);
+    let storage_staked = Balance::from(env::storage_usage()) * env::storage_byte_cost();
+    testing_env!(context(predecessor)
+        .attached_deposit(1)
+        .account_balance(storage_staked + free_balance)
+        .build());
+}
+
+fn set_buffer(contract: &mut Keypom, buffer: Balance) {
+    set_caller(&owner_id(), 1);
+    contract.set_operational_buffer(U128(buffer));
+    assert_eq!(contract.get_operational_buffer().0, buffer);
+}
+
+#[test]
+fn withdrawal_is_sent_when_contract_can_spare_it() {
+    let mut contract = setup();
+    set_buffer(&mut contract, ONE_NEAR);
+
+    set_caller_with_free_balance(&funder_id(), 20 * ONE_NEAR);
+    contract.withdraw_from_balance(Some(U128(10 * ONE_NEAR)));
+
+    assert_eq!(near_sent_to(&funder_id()), 10 * ONE_NEAR);
+    assert_eq!(
+        user_balance(&contract, &funder_id()),
+        FUNDER_BALANCE - 10 * ONE_NEAR
+    );
+}
+
+#[test]
+fn withdrawal_is_deferred_when_it_would_dip_into_the_buffer() {
+    let mut contract = setup();
+    set_buffer(&mut contract, ONE_NEAR);
+
+    // 10 $NEAR plus the 1 $NEAR buffer is more than the 5 $NEAR the contract can spare
+    set_caller_with_free_balance(&funder_id(), 5 * ONE_NEAR);
+    contract.withdraw_from_balance(Some(U128(10 * ONE_NEAR)));
+
+    assert_eq!(near_sent_to(&funder_id()), 0);
+    assert_eq!(user_balance(&contract, &funder_id()), FUNDER_BALANCE);
+}
+
+#[test]
+fn fee_withdrawal_is_deferred_when_it_would_dip_into_the_buffer() {
+    let mut contract = setup();
+    create_drop(&mut contract, keys("fees", 1), DropArgs::simple());
+    let fees = contract.get_fees_collected().0;
+    assert!(fees > 0);
+    set_buffer(&mut contract, 2_000_000 * ONE_NEAR);
+
+    set_caller(&owner_id(), 1);
+    let withdrawn = contract.withdraw_fees(owner_id());
+
+    assert!(matches!(withdrawn, PromiseOrValue::Value(false)));
+    assert_eq!(near_sent_to(&owner_id()), 0);
+    assert_eq!(contract.get_fees_collected().0, 0);
+    assert_eq!(user_balance(&contract, &owner_id()), fees);
+}
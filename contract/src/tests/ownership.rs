@@ -0,0 +1,79 @@
+/*
+    Drops handed over with `transfer_drop_ownership` behave as if the new owner had created them
+*/
+use super::*;
+
+fn transfer(contract: &mut Keypom, drop_id: DropId, new_owner_id: AccountId) {
+    set_caller(&funder_id(), 1);
+    contract.transfer_drop_ownership(drop_id, new_owner_id);
+}
+
+fn restrict(contract: &mut Keypom, account_id: AccountId, suffix: &str) {
+    set_caller(&account_id, 1);
+    contract.restrict_fc_receivers(account_id.clone(), suffix.to_string());
+}
+
+#[test]
+fn claims_in_flight_refund_the_new_owner() {
+    let mut contract = setup();
+    let pks = keys("owner", 2);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+    claim(&mut contract, &pks[0], account("alice"));
+    transfer(&mut contract, drop_id, account("client"));
+    let funder_balance = user_balance(&contract, &funder_id());
+
+    // The transfer to the claimer failed so the whole deposit comes back
+    set_callback(vec![PromiseResult::Failed]);
+    contract.on_claim_simple(
+        funder_id(),
+        U128(ONE_NEAR),
+        0,
+        drop_id,
+        ClaimGuard {
+            drop_id,
+            key_id: 0,
+            use_number: 1,
+        },
+        PromiseTarget {
+            method_name: "transfer".to_string(),
+            receiver_id: account("alice"),
+        },
+        vec![],
+    );
+
+    assert_eq!(user_balance(&contract, &funder_id()), funder_balance);
+    assert_eq!(
+        user_balance(&contract, &account("client")),
+        ONE_NEAR + ACCESS_KEY_STORAGE
+    );
+}
+
+#[test]
+#[should_panic(expected = "drop calls FC receivers that the new owner is restricted from")]
+fn fc_drops_respect_the_new_owners_restrictions() {
+    let mut contract = setup();
+    let args = DropArgs {
+        fc_data: Some(FCData::fixture(account("receiver"), "nft_mint")),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, keys("owner", 1), args);
+    restrict(&mut contract, account("client"), ".client.near");
+
+    transfer(&mut contract, drop_id, account("client"));
+}
+
+#[test]
+fn fc_drops_calling_allowed_receivers_can_be_transferred() {
+    let mut contract = setup();
+    let args = DropArgs {
+        fc_data: Some(FCData::fixture(account("nft.client"), "nft_mint")),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, keys("owner", 1), args);
+    restrict(&mut contract, account("client"), ".client.near");
+
+    transfer(&mut contract, drop_id, account("client"));
+
+    let drop = contract.drop_for_id.get(&drop_id).unwrap();
+    assert_eq!(drop.owner_id, account("client"));
+}
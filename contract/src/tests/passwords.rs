@@ -0,0 +1,111 @@
+/*
+    Password protected keys can only be claimed with the password whose sha256 hash the funder set for them. Claims
+    with a missing or wrong password are rejected without consuming a use.
+*/
+use super::*;
+
+fn password_hash(password: &str) -> Base64VecU8 {
+    Base64VecU8(env::sha256(password.as_bytes()))
+}
+
+/// Simple drop with one key per password, where `None` leaves the key unprotected
+fn protected_drop(contract: &mut Keypom, pks: &[PublicKey], passwords: &[Option<&str>]) -> DropId {
+    let args = DropArgs {
+        password_hashes: Some(passwords.iter().map(|p| p.map(password_hash)).collect()),
+        ..DropArgs::simple()
+    };
+    create_drop(contract, pks.to_vec(), args)
+}
+
+fn claim_with_password(contract: &mut Keypom, public_key: &PublicKey, password: Option<&str>) {
+    set_key_signer(public_key, required_gas_for_key(contract, public_key));
+    contract.claim(
+        account("alice"),
+        None,
+        None,
+        None,
+        None,
+        password.map(str::to_string),
+    );
+}
+
+fn assert_rejected(contract: &Keypom, public_key: &PublicKey, drop_id: DropId) {
+    assert_eq!(key_info(contract, public_key).remaining_uses, 1);
+    assert_eq!(registered_uses(contract, drop_id), 1);
+    assert_eq!(near_sent_to(&account("alice")), 0);
+}
+
+#[test]
+fn correct_password_claims_the_key() {
+    let mut contract = setup();
+    let pks = keys("password", 1);
+    protected_drop(&mut contract, &pks, &[Some("hunter2")]);
+
+    claim_with_password(&mut contract, &pks[0], Some("hunter2"));
+
+    assert!(contract.get_key_information(pks[0].clone()).is_none());
+    assert_eq!(near_sent_to(&account("alice")), ONE_NEAR);
+}
+
+#[test]
+fn wrong_password_is_rejected_without_consuming_a_use() {
+    let mut contract = setup();
+    let pks = keys("password", 1);
+    let drop_id = protected_drop(&mut contract, &pks, &[Some("hunter2")]);
+    let allowance_before = key_info(&contract, &pks[0]).allowance;
+
+    claim_with_password(&mut contract, &pks[0], Some("hunter3"));
+
+    assert_rejected(&contract, &pks[0], drop_id);
+    // The key still pays for the GAS it burnt
+    assert!(key_info(&contract, &pks[0]).allowance < allowance_before);
+}
+
+#[test]
+fn missing_password_is_rejected_without_consuming_a_use() {
+    let mut contract = setup();
+    let pks = keys("password", 1);
+    let drop_id = protected_drop(&mut contract, &pks, &[Some("hunter2")]);
+
+    claim_with_password(&mut contract, &pks[0], None);
+
+    assert_rejected(&contract, &pks[0], drop_id);
+}
+
+#[test]
+fn password_is_ignored_for_unprotected_keys() {
+    let mut contract = setup();
+    let pks = keys("password", 2);
+    protected_drop(&mut contract, &pks, &[Some("hunter2"), None]);
+
+    claim_with_password(&mut contract, &pks[1], Some("hunter2"));
+
+    assert!(contract.get_key_information(pks[1].clone()).is_none());
+    assert_eq!(near_sent_to(&account("alice")), ONE_NEAR);
+}
+
+#[test]
+fn password_hashes_added_with_keys_are_stored_and_charged() {
+    let mut contract = setup();
+    let pks = keys("password", 3);
+    let drop_id = protected_drop(&mut contract, &pks[..1], &[None]);
+
+    let mut charged = vec![];
+    for (pk, hash) in [(&pks[1], None), (&pks[2], Some(password_hash("hunter2")))] {
+        let balance_before = user_balance(&contract, &funder_id());
+        set_caller(&funder_id(), 0);
+        contract.add_keys(key_args(vec![pk.clone()]), drop_id, None, Some(vec![hash]));
+        charged.push(balance_before - user_balance(&contract, &funder_id()));
+    }
+
+    assert_eq!(key_info(&contract, &pks[1]).password_hash, None);
+    assert_eq!(
+        key_info(&contract, &pks[2]).password_hash,
+        Some(password_hash("hunter2").0)
+    );
+    // The hash takes up at least its 32 bytes of storage
+    assert!(charged[1] >= charged[0] + 32 * env::storage_byte_cost());
+
+    claim_with_password(&mut contract, &pks[2], Some("hunter2"));
+    assert!(contract.get_key_information(pks[2].clone()).is_none());
+}
@@ -0,0 +1,100 @@
+/*
+    A drop's post claim info is validated when the drop is created and surfaced to wallets through the claim page
+    view and the claim event
+*/
+use super::*;
+use near_sdk::test_utils::get_logs;
+
+fn post_claim_info() -> PostClaimInfo {
+    PostClaimInfo {
+        redirect_url: Some("https://app.example.com/welcome".to_string()),
+        message: Some("Welcome aboard!".to_string()),
+        cta_label: Some("Open app".to_string()),
+    }
+}
+
+fn post_claim_args(post_claim: PostClaimInfo) -> DropArgs {
+    DropArgs {
+        config: Some(DropConfig::builder().post_claim(post_claim).build()),
+        ..DropArgs::simple()
+    }
+}
+
+fn post_claim_error(contract: &Keypom, post_claim: PostClaimInfo) -> String {
+    let errors = validation_errors(contract, keys("invalid", 1), post_claim_args(post_claim));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].field, "config.post_claim");
+    errors[0].message.clone()
+}
+
+#[test]
+fn invalid_post_claim_info_is_rejected() {
+    let contract = setup();
+
+    let http = PostClaimInfo {
+        redirect_url: Some("http://app.example.com".to_string()),
+        ..post_claim_info()
+    };
+    assert_eq!(
+        post_claim_error(&contract, http),
+        "redirect URL must be an https URL"
+    );
+
+    let label_without_url = PostClaimInfo {
+        redirect_url: None,
+        ..post_claim_info()
+    };
+    assert_eq!(
+        post_claim_error(&contract, label_without_url),
+        "cta_label requires a redirect URL"
+    );
+
+    let long_message = PostClaimInfo {
+        message: Some("a".repeat(281)),
+        ..post_claim_info()
+    };
+    assert_eq!(
+        post_claim_error(&contract, long_message),
+        "post claim message must be between 1 and 280 characters"
+    );
+
+    let empty = PostClaimInfo {
+        redirect_url: None,
+        message: None,
+        cta_label: None,
+    };
+    assert_eq!(
+        post_claim_error(&contract, empty),
+        "post claim info needs a redirect URL or a message"
+    );
+
+    assert!(validation_errors(
+        &contract,
+        keys("valid", 1),
+        post_claim_args(post_claim_info())
+    )
+    .is_empty());
+}
+
+#[test]
+fn post_claim_info_is_surfaced_to_wallets() {
+    let mut contract = setup();
+    let pks = keys("post-claim", 2);
+    create_drop(
+        &mut contract,
+        pks.clone(),
+        post_claim_args(post_claim_info()),
+    );
+
+    let page = contract.get_claim_page_data(pks[0].clone(), None).unwrap();
+    assert!(page.post_claim == Some(post_claim_info()));
+
+    claim(&mut contract, &pks[0], account("alice"));
+    let claim_event = get_logs()
+        .into_iter()
+        .find(|log| log.starts_with("EVENT_JSON:") && log.contains(r#""event":"claim""#))
+        .expect("no claim event");
+    assert!(claim_event.contains(
+        r#""post_claim":{"redirect_url":"https://app.example.com/welcome","message":"Welcome aboard!","cta_label":"Open app"}"#
+    ));
+}
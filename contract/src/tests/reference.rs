@@ -0,0 +1,105 @@
+/*
+    Happy paths for each drop type. These are meant to be copied and extended when testing anything
+    that builds on a whole drop's lifecycle.
+*/
+use super::*;
+
+#[test]
+fn simple_drop_claim() {
+    let mut contract = setup();
+    let pks = keys("simple", 2);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+    assert_eq!(registered_uses(&contract, drop_id), 2);
+
+    claim(&mut contract, &pks[0], account("alice"));
+
+    assert!(contract.get_key_information(pks[0].clone()).is_none());
+    assert!(contract.get_key_information(pks[1].clone()).is_some());
+    assert_eq!(registered_uses(&contract, drop_id), 1);
+}
+
+#[test]
+fn multi_use_simple_drop_claim() {
+    let mut contract = setup();
+    let pks = keys("multi", 1);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().uses_per_key(3).build()),
+        ..DropArgs::simple()
+    };
+    create_drop(&mut contract, pks.clone(), args);
+
+    claim(&mut contract, &pks[0], account("alice"));
+    claim(&mut contract, &pks[0], account("alice"));
+
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 1);
+}
+
+#[test]
+fn ft_drop_claim() {
+    let mut contract = setup();
+    let pks = keys("ft", 2);
+    let ft_contract = account("token");
+    let args = DropArgs {
+        ft_data: Some(FTDataConfig::fixture(ft_contract.clone(), funder_id())),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+    assert_eq!(registered_uses(&contract, drop_id), 0);
+    activate_ft_drop(&mut contract, drop_id, pks.clone());
+
+    // 2.5 tokens register 2 uses and half a token is returned
+    set_caller(&ft_contract, 0);
+    let returned =
+        contract.ft_on_transfer(funder_id(), U128(5 * ONE_NEAR / 2), drop_id.to_string());
+    assert!(matches!(returned, PromiseOrValue::Value(U128(amount)) if amount == ONE_NEAR / 2));
+    assert_eq!(registered_uses(&contract, drop_id), 2);
+
+    claim(&mut contract, &pks[0], account("alice"));
+    assert_eq!(registered_uses(&contract, drop_id), 1);
+}
+
+#[test]
+fn nft_drop_claim() {
+    let mut contract = setup();
+    let pks = keys("nft", 2);
+    let nft_contract = account("nft");
+    let args = DropArgs {
+        nft_data: Some(NFTDataConfig::fixture(nft_contract.clone(), funder_id())),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+
+    set_caller(&nft_contract, 0);
+    for token_id in ["token-1", "token-2"] {
+        let returned = contract.nft_on_transfer(
+            funder_id(),
+            funder_id(),
+            token_id.to_string(),
+            drop_id.to_string(),
+        );
+        assert!(matches!(returned, PromiseOrValue::Value(false)));
+    }
+    assert_eq!(registered_uses(&contract, drop_id), 2);
+
+    // Tokens are handed out last in, first out
+    claim(&mut contract, &pks[0], account("alice"));
+    assert_eq!(
+        contract.get_nft_token_ids_for_drop(drop_id, None, None),
+        vec!["token-1".to_string()]
+    );
+}
+
+#[test]
+fn fc_drop_claim() {
+    let mut contract = setup();
+    let pks = keys("fc", 1);
+    let args = DropArgs {
+        fc_data: Some(FCData::fixture(account("receiver"), "nft_mint")),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+    assert_eq!(registered_uses(&contract, drop_id), 1);
+
+    claim(&mut contract, &pks[0], account("alice"));
+    assert!(contract.get_key_information(pks[0].clone()).is_none());
+}
@@ -0,0 +1,86 @@
+/*
+    For FC and Simple drops, `registered_uses` counts every remaining use across the drop's keys, including uses
+    whose method is None. Every claim consumes one, deleted keys release theirs and claims stop once it hits 0.
+*/
+use super::*;
+
+fn registered_uses_view(contract: &Keypom, drop_id: DropId) -> u64 {
+    contract
+        .get_drop_information(Some(drop_id), None)
+        .registered_uses
+}
+
+#[test]
+fn simple_drop_uses_follow_claims_and_deletions() {
+    let mut contract = setup();
+    let pks = keys("simple-uses", 2);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().uses_per_key(3).build()),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+    assert_eq!(registered_uses_view(&contract, drop_id), 6);
+
+    claim(&mut contract, &pks[0], account("alice"));
+    assert_eq!(registered_uses_view(&contract, drop_id), 5);
+
+    // The second key's 3 uses go with it
+    set_caller(&funder_id(), 1);
+    contract.delete_keys(drop_id, Some(vec![pks[1].clone()]), None);
+    assert_eq!(registered_uses_view(&contract, drop_id), 2);
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 2);
+}
+
+#[test]
+fn fc_drop_uses_include_none_methods() {
+    let mut contract = setup();
+    let pks = keys("fc-uses", 1);
+    let method = FCData::fixture(account("receiver"), "nft_mint").methods[0].clone();
+    let fc_data = FCData {
+        methods: vec![method.clone(), None, method],
+        config: None,
+    };
+    let args = DropArgs {
+        config: Some(DropConfig::builder().uses_per_key(3).build()),
+        fc_data: Some(fc_data),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+    assert_eq!(registered_uses_view(&contract, drop_id), 3);
+
+    claim(&mut contract, &pks[0], account("alice"));
+    assert_eq!(registered_uses_view(&contract, drop_id), 2);
+
+    // The use without a method is consumed like any other
+    claim(&mut contract, &pks[0], account("alice"));
+    assert_eq!(registered_uses_view(&contract, drop_id), 1);
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 1);
+}
+
+#[test]
+fn claims_stop_once_no_uses_are_registered() {
+    let mut contract = setup();
+    let pks = keys("no-uses", 1);
+    let drop_id = create_drop(&mut contract, pks.clone(), DropArgs::simple());
+
+    let mut drop = contract.drop_for_id.get(&drop_id).unwrap();
+    drop.registered_uses = 0;
+    contract.drop_for_id.insert(&drop_id, &drop);
+
+    claim(&mut contract, &pks[0], account("alice"));
+
+    assert_eq!(near_sent_to(&account("alice")), 0);
+    assert_eq!(key_info(&contract, &pks[0]).remaining_uses, 1);
+    assert_eq!(registered_uses_view(&contract, drop_id), 0);
+}
+
+#[test]
+fn releasing_uses_saturates_at_zero() {
+    let mut contract = setup();
+    let drop_id = create_drop(&mut contract, keys("saturate", 2), DropArgs::simple());
+    let mut drop = contract.drop_for_id.get(&drop_id).unwrap();
+
+    release_registered_uses(&mut drop, 5);
+
+    assert_eq!(drop.registered_uses, 0);
+}
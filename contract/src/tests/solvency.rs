@@ -0,0 +1,105 @@
+/*
+    The solvency report is built from running totals. After every balance or key change, the totals must match
+    what's actually stored on the contract.
+*/
+use super::*;
+use crate::internals::solvency::key_reserve;
+use near_sdk::test_utils::get_logs;
+
+/// Recompute the totals from storage and check them against the running counters
+fn assert_counters_exact(contract: &Keypom, accounts: &[AccountId]) {
+    let user_balances: Balance = accounts
+        .iter()
+        .map(|account_id| user_balance(contract, account_id))
+        .sum();
+    let key_reserves: Balance = (0..contract.next_drop_id)
+        .filter_map(|drop_id| contract.drop_for_id.get(&drop_id))
+        .flat_map(|drop| {
+            let deposit_per_use = drop.deposit_per_use;
+            drop.pks
+                .values()
+                .map(|key_info| key_reserve(deposit_per_use, &key_info))
+                .collect::<Vec<_>>()
+        })
+        .sum();
+
+    let report = contract.get_solvency_report();
+    assert_eq!(report.user_balances.0, user_balances);
+    assert_eq!(report.key_reserves.0, key_reserves);
+    assert_eq!(report.fees_collected.0, contract.get_fees_collected().0);
+    assert_eq!(
+        report.liabilities.0,
+        user_balances + report.vesting_refunds.0 + key_reserves + report.fees_collected.0
+    );
+}
+
+#[test]
+fn counters_stay_exact_through_the_drop_lifecycle() {
+    let mut contract = setup();
+    let accounts = [funder_id(), owner_id(), account("alice")];
+    assert_counters_exact(&contract, &accounts);
+
+    let pks = keys("lifecycle", 4);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().uses_per_key(2).build()),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks[..3].to_vec(), args);
+    assert_counters_exact(&contract, &accounts);
+
+    set_caller(&funder_id(), 0);
+    contract.add_keys(key_args(vec![pks[3].clone()]), drop_id, None, None);
+    assert_counters_exact(&contract, &accounts);
+
+    claim(&mut contract, &pks[0], account("alice"));
+    assert_counters_exact(&contract, &accounts);
+
+    set_caller(&funder_id(), 1);
+    contract.delete_keys(drop_id, Some(vec![pks[1].clone()]), None);
+    assert_counters_exact(&contract, &accounts);
+
+    set_caller(&funder_id(), 1);
+    contract.withdraw_from_balance(Some(U128(10 * ONE_NEAR)));
+    assert_counters_exact(&contract, &accounts);
+
+    set_caller(&owner_id(), 1);
+    contract.withdraw_fees(owner_id());
+    assert_counters_exact(&contract, &accounts);
+
+    // Deleting the rest of the keys releases every reserve
+    set_caller(&funder_id(), 1);
+    contract.delete_keys(drop_id, None, None);
+    assert_counters_exact(&contract, &accounts);
+    assert_eq!(contract.get_solvency_report().key_reserves.0, 0);
+}
+
+#[test]
+fn report_is_backed_by_the_account_balance() {
+    let mut contract = setup();
+    create_drop(&mut contract, keys("backed", 2), DropArgs::simple());
+
+    let report = contract.get_solvency_report();
+    assert!(report.fully_backed);
+    assert!(report.liabilities.0 <= report.account_balance.0 - report.storage_staked.0);
+}
+
+#[test]
+fn warning_is_emitted_once_liabilities_exceed_assets() {
+    let mut contract = setup();
+
+    // The contract's balance only covers its storage, not the funder's balance
+    let storage_staked = Balance::from(env::storage_usage()) * env::storage_byte_cost();
+    testing_env!(context(&funder_id())
+        .attached_deposit(ONE_NEAR)
+        .account_balance(storage_staked + ONE_NEAR)
+        .build());
+    contract.add_to_balance();
+
+    assert!(!contract.get_solvency_report().fully_backed);
+    assert!(
+        get_logs()
+            .iter()
+            .any(|log| log.starts_with("EVENT_JSON:")
+                && log.contains(r#""event":"solvency_warning""#))
+    );
+}
@@ -0,0 +1,41 @@
+/*
+    Every drop's collections live under prefixes salted with a value that's never handed out twice, so a drop
+    recreated with the same ID and owner starts out empty
+*/
+use super::*;
+
+#[test]
+fn recreated_drop_with_same_id_and_owner_starts_empty() {
+    let mut contract = setup();
+    let old_keys = keys("old", 2);
+    let drop_id = create_drop(&mut contract, old_keys.clone(), DropArgs::simple());
+    set_caller(&funder_id(), 1);
+    contract.delete_keys(drop_id, None, None);
+    assert!(contract.drop_for_id.get(&drop_id).is_none());
+
+    // Hand out the same drop ID to the same owner again
+    contract.next_drop_id = drop_id;
+    let new_keys = keys("new", 1);
+    assert_eq!(
+        create_drop(&mut contract, new_keys.clone(), DropArgs::simple()),
+        drop_id
+    );
+
+    let drop = contract.drop_for_id.get(&drop_id).unwrap();
+    assert_eq!(drop.pks.keys().collect::<Vec<_>>(), new_keys);
+    assert_eq!(drop.pk_for_key_id.get(&0), Some(new_keys[0].clone()));
+    for pk in old_keys {
+        assert!(drop.pks.get(&pk).is_none());
+    }
+}
+
+#[test]
+#[should_panic(expected = "storage prefix for the new drop is already in use")]
+fn reused_prefix_is_rejected() {
+    let mut contract = setup();
+    create_drop(&mut contract, keys("first", 1), DropArgs::simple());
+
+    // The first drop's collections are still under salt 0
+    contract.next_storage_salt = 0;
+    create_drop(&mut contract, keys("second", 1), DropArgs::simple());
+}
@@ -0,0 +1,102 @@
+/*
+    A drop's storage is charged and refunded at the byte cost recorded when it was created. These tests record a
+    different cost on the drop than the protocol's current one, as if the price changed after the drop was created.
+*/
+use super::*;
+
+/// Simple drop with one key, created when storage cost `multiplier` times what it does now
+fn drop_priced_at(contract: &mut Keypom, seed: &str, multiplier: u128) -> DropId {
+    let drop_id = create_drop(contract, keys(seed, 1), DropArgs::simple());
+    let mut drop = contract.drop_for_id.get(&drop_id).unwrap();
+    drop.storage_byte_cost = multiplier * env::storage_byte_cost();
+    contract.drop_for_id.insert(&drop_id, &drop);
+    drop_id
+}
+
+/// What the funder is charged to add `public_key` to the drop
+fn add_key(contract: &mut Keypom, drop_id: DropId, public_key: &PublicKey) -> Balance {
+    let balance_before = user_balance(contract, &funder_id());
+    set_caller(&funder_id(), 0);
+    contract.add_keys(key_args(vec![public_key.clone()]), drop_id, None, None);
+    balance_before - user_balance(contract, &funder_id())
+}
+
+/// Each step up in price adds the same amount, i.e the key's storage at the current price
+fn assert_scales_with_price(amounts: [Balance; 3]) {
+    let [at_1x, at_2x, at_3x] = amounts;
+    assert!(at_2x > at_1x);
+    assert_eq!(at_3x - at_2x, at_2x - at_1x);
+}
+
+#[test]
+fn keys_added_later_are_charged_at_the_recorded_price() {
+    let mut contract = setup();
+    let pks = keys("added", 3);
+
+    let mut charged = [0; 3];
+    for (i, pk) in pks.iter().enumerate() {
+        let drop_id = drop_priced_at(&mut contract, &format!("drop-{}", i), i as u128 + 1);
+        charged[i] = add_key(&mut contract, drop_id, pk);
+    }
+
+    assert_scales_with_price(charged);
+}
+
+#[test]
+fn deleted_keys_are_refunded_what_they_were_charged() {
+    let mut contract = setup();
+    // Fees aren't refunded so they're waived to compare the charge with the refund
+    set_caller(&owner_id(), 1);
+    contract.set_fees_per_user(funder_id(), U128(0), U128(0));
+    let drop_id = drop_priced_at(&mut contract, "deleted", 3);
+    let pk = keys("deleted-added", 1).remove(0);
+    let balance_before = user_balance(&contract, &funder_id());
+
+    let charged = add_key(&mut contract, drop_id, &pk);
+    set_caller(&funder_id(), 1);
+    contract.delete_keys(drop_id, Some(vec![pk]), None);
+
+    assert!(charged > 0);
+    assert_eq!(user_balance(&contract, &funder_id()), balance_before);
+}
+
+/// Storage the claim's callback will refund to the funder
+fn storage_refunded_on_claim() -> Balance {
+    let args = get_created_receipts()
+        .into_iter()
+        .flat_map(|receipt| receipt.actions)
+        .find_map(|action| match action {
+            VmAction::FunctionCall {
+                function_name,
+                args,
+                ..
+            } if function_name == "on_claim_simple" => Some(String::from_utf8(args).unwrap()),
+            _ => None,
+        })
+        .expect("no claim callback");
+    let storage_used = args.split(r#""storage_used":"#).nth(1).unwrap();
+    storage_used
+        .split(|c: char| !c.is_ascii_digit())
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn claims_refund_freed_storage_at_the_recorded_price() {
+    let mut contract = setup();
+
+    let mut refunded = [0; 3];
+    for (i, refund) in refunded.iter_mut().enumerate() {
+        let seed = format!("claimed-{}", i);
+        drop_priced_at(&mut contract, &seed, i as u128 + 1);
+        claim(&mut contract, &keys(&seed, 1)[0], account("alice"));
+        *refund = storage_refunded_on_claim();
+    }
+
+    let [at_1x, at_2x, at_3x] = refunded;
+    assert!(at_1x > 0);
+    assert_eq!(at_2x, 2 * at_1x);
+    assert_eq!(at_3x, 3 * at_1x);
+}
@@ -0,0 +1,100 @@
+/*
+    Throttled drops only let each key be used once per interval, whether it was added with the drop or later on
+*/
+use super::*;
+
+const INTERVAL: u64 = 60_000_000_000;
+const CREATED_AT: u64 = 1_000 * INTERVAL;
+const ADDED_AT: u64 = CREATED_AT + INTERVAL / 2;
+
+fn throttled_drop(contract: &mut Keypom, from_addition: bool) -> DropId {
+    let config = DropConfig::builder()
+        .uses_per_key(3)
+        .throttle_timestamp(INTERVAL)
+        .throttle_from_addition(from_addition)
+        .build();
+    let args = DropArgs {
+        config: Some(config),
+        ..DropArgs::simple()
+    };
+    testing_env!(context(&funder_id()).block_timestamp(CREATED_AT).build());
+    create_drop_now(contract, keys("original", 1), args)
+}
+
+/// Create the drop in the context that was already set so that its block timestamp is kept
+fn create_drop_now(contract: &mut Keypom, public_keys: Vec<PublicKey>, args: DropArgs) -> DropId {
+    contract.create_drop(
+        key_args(public_keys),
+        U128(args.deposit_per_use),
+        args.config,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+fn add_key_at(contract: &mut Keypom, drop_id: DropId, timestamp: u64) -> PublicKey {
+    let pk = keys("added", 1).remove(0);
+    testing_env!(context(&funder_id()).block_timestamp(timestamp).build());
+    contract.add_keys(key_args(vec![pk.clone()]), drop_id, None, None);
+    pk
+}
+
+fn claim_at(contract: &mut Keypom, public_key: &PublicKey, timestamp: u64) {
+    testing_env!(context(&keypom_id())
+        .signer_account_pk(public_key.clone())
+        .prepaid_gas(required_gas_for_key(contract, public_key))
+        .block_timestamp(timestamp)
+        .build());
+    contract.claim(account("alice"), None, None, None, None, None);
+}
+
+#[test]
+fn added_keys_are_throttled_from_their_own_addition() {
+    let mut contract = setup();
+    let drop_id = throttled_drop(&mut contract, true);
+    let original = keys("original", 1).remove(0);
+    let added = add_key_at(&mut contract, drop_id, ADDED_AT);
+    assert_eq!(key_info(&contract, &added).last_used, ADDED_AT);
+
+    // A full interval after creation only the original key has waited long enough
+    claim_at(&mut contract, &original, CREATED_AT + INTERVAL);
+    claim_at(&mut contract, &added, CREATED_AT + INTERVAL);
+    assert_eq!(key_info(&contract, &original).remaining_uses, 2);
+    assert_eq!(key_info(&contract, &added).remaining_uses, 3);
+
+    claim_at(&mut contract, &added, ADDED_AT + INTERVAL);
+    assert_eq!(key_info(&contract, &added).remaining_uses, 2);
+}
+
+#[test]
+fn added_keys_can_be_used_right_away_when_not_throttled_from_addition() {
+    let mut contract = setup();
+    let drop_id = throttled_drop(&mut contract, false);
+    let added = add_key_at(&mut contract, drop_id, ADDED_AT);
+    assert_eq!(key_info(&contract, &added).last_used, 0);
+
+    claim_at(&mut contract, &added, ADDED_AT);
+    assert_eq!(key_info(&contract, &added).remaining_uses, 2);
+
+    // Every key waits a full interval between uses
+    claim_at(&mut contract, &added, ADDED_AT + INTERVAL - 1);
+    assert_eq!(key_info(&contract, &added).remaining_uses, 2);
+}
+
+#[test]
+fn last_used_is_returned_as_a_string_of_nanoseconds() {
+    let mut contract = setup();
+    let drop_id = throttled_drop(&mut contract, true);
+    let added = add_key_at(&mut contract, drop_id, ADDED_AT);
+
+    let info = contract.get_key_information(added).unwrap();
+    let json = near_sdk::serde_json::to_string(&info).unwrap();
+    assert!(json.contains(&format!("\"last_used\":\"{}\"", ADDED_AT)));
+}
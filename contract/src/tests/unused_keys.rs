@@ -0,0 +1,47 @@
+/*
+    Wallets page through the keys of a drop that haven't been handed out yet with `get_next_unused_keys`
+*/
+use super::*;
+
+fn key_ids(page: &[JsonKeyInfo]) -> Vec<u64> {
+    page.iter().map(|key| key.key_info.key_id).collect()
+}
+
+#[test]
+fn pages_skip_used_keys_and_resume_from_the_cursor() {
+    let mut contract = setup();
+    let pks = keys("unused", 4);
+    let args = DropArgs {
+        config: Some(DropConfig::builder().uses_per_key(2).build()),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, pks.clone(), args);
+    claim(&mut contract, &pks[1], account("alice"));
+
+    let (page, cursor) = contract.get_next_unused_keys(drop_id, None, 2);
+    assert_eq!(key_ids(&page), vec![0, 2]);
+    assert_eq!(cursor, Some(2));
+
+    let (page, cursor) = contract.get_next_unused_keys(drop_id, cursor, 2);
+    assert_eq!(key_ids(&page), vec![3]);
+    assert_eq!(cursor, None);
+}
+
+#[test]
+fn pages_stop_scanning_after_a_bounded_number_of_key_ids() {
+    let mut contract = setup();
+    let pks = keys("unused", 1);
+    let drop_id = create_drop(&mut contract, pks, DropArgs::simple());
+    // Leave a long run of deleted keys after the first one
+    let mut drop = contract.drop_for_id.get(&drop_id).unwrap();
+    drop.next_key_id = 1_000;
+    contract.drop_for_id.insert(&drop_id, &drop);
+
+    let (page, cursor) = contract.get_next_unused_keys(drop_id, None, 10);
+    assert_eq!(key_ids(&page), vec![0]);
+    assert_eq!(cursor, Some(499));
+
+    let (page, cursor) = contract.get_next_unused_keys(drop_id, cursor, 10);
+    assert!(page.is_empty());
+    assert_eq!(cursor, None);
+}
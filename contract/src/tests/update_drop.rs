@@ -0,0 +1,90 @@
+/*
+    `update_drop` changes a drop's config and metadata in one call as long as nothing baked into its keys changes
+*/
+use super::*;
+
+fn update_drop(
+    contract: &mut Keypom,
+    drop_id: DropId,
+    config: Option<DropConfig>,
+    metadata: Option<Option<DropMetadata>>,
+) {
+    set_caller(&funder_id(), 1);
+    contract.update_drop(drop_id, config, metadata);
+}
+
+#[test]
+fn config_and_metadata_are_updated_together() {
+    let mut contract = setup();
+    let pks = keys("update", 2);
+    let drop_id = create_drop(&mut contract, pks, DropArgs::simple());
+
+    let config = DropConfig::builder()
+        .claim_permission(ClaimPermissions::Claim)
+        .build();
+    update_drop(
+        &mut contract,
+        drop_id,
+        Some(config),
+        Some(Some("{\"title\":\"Updated\"}".to_string())),
+    );
+
+    let drop = contract.drop_for_id.get(&drop_id).unwrap();
+    assert!(drop.claim_permission == ClaimPermissions::Claim);
+    assert_eq!(
+        drop.metadata.get(),
+        Some("{\"title\":\"Updated\"}".to_string())
+    );
+}
+
+#[test]
+fn clearing_metadata_refunds_its_storage() {
+    let mut contract = setup();
+    let args = DropArgs {
+        metadata: Some("x".repeat(1_000)),
+        ..DropArgs::simple()
+    };
+    let drop_id = create_drop(&mut contract, keys("update", 1), args);
+    let balance = user_balance(&contract, &funder_id());
+
+    update_drop(&mut contract, drop_id, None, Some(None));
+
+    assert!(contract
+        .drop_for_id
+        .get(&drop_id)
+        .unwrap()
+        .metadata
+        .get()
+        .is_none());
+    assert!(user_balance(&contract, &funder_id()) > balance);
+}
+
+#[test]
+#[should_panic(expected = "uses_per_key cannot be changed once a drop is created")]
+fn uses_per_key_cannot_change() {
+    let mut contract = setup();
+    let drop_id = create_drop(&mut contract, keys("update", 1), DropArgs::simple());
+
+    let config = DropConfig::builder().uses_per_key(2).build();
+    update_drop(&mut contract, drop_id, Some(config), None);
+}
+
+#[test]
+#[should_panic(expected = "early_bonus cannot be changed once a drop is created")]
+fn reserved_fields_cannot_change() {
+    let mut contract = setup();
+    let drop_id = create_drop(&mut contract, keys("update", 1), DropArgs::simple());
+
+    let config = DropConfig::builder().early_bonus(1, U128(ONE_NEAR)).build();
+    update_drop(&mut contract, drop_id, Some(config), None);
+}
+
+#[test]
+#[should_panic(expected = "only funder can update drops")]
+fn only_the_funder_can_update() {
+    let mut contract = setup();
+    let drop_id = create_drop(&mut contract, keys("update", 1), DropArgs::simple());
+
+    set_caller(&account("alice"), 1);
+    contract.update_drop(drop_id, None, Some(None));
+}
@@ -0,0 +1,60 @@
+/*
+    Accounts the owner set to zero fees pay for their drops exactly what an account with the global fees pays,
+    minus the fees, and nothing is added to the fees collected.
+*/
+use super::*;
+
+/// Same length as `funder.near` so that both accounts' drops take up the same storage
+fn payer_id() -> AccountId {
+    account("payers")
+}
+
+/// Simple drop with 2 keys and a third added afterwards, returning what the caller spent on it
+fn drop_with_three_keys(contract: &mut Keypom, caller: &AccountId, seed: &str) -> Balance {
+    let pks = keys(seed, 3);
+    let balance_before = user_balance(contract, caller);
+
+    set_caller(caller, 0);
+    let drop_id = contract.create_drop(
+        key_args(pks[..2].to_vec()),
+        U128(ONE_NEAR),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+    set_caller(caller, 0);
+    contract.add_keys(key_args(vec![pks[2].clone()]), drop_id, None, None);
+
+    balance_before - user_balance(contract, caller)
+}
+
+#[test]
+fn zero_fee_user_pays_no_fees() {
+    set_caller(&owner_id(), 0);
+    let mut contract = Keypom::test_new_with_balances(
+        root_id(),
+        owner_id(),
+        vec![
+            (funder_id(), U128(FUNDER_BALANCE)),
+            (payer_id(), U128(FUNDER_BALANCE)),
+        ],
+    );
+    set_caller(&owner_id(), 1);
+    contract.set_fees_per_user(funder_id(), U128(0), U128(0));
+    assert_eq!(contract.get_effective_fees(funder_id()), (U128(0), U128(0)));
+
+    let spent_without_fees = drop_with_three_keys(&mut contract, &funder_id(), "zero-fees");
+    assert_eq!(contract.get_fees_collected().0, 0);
+
+    let spent_with_fees = drop_with_three_keys(&mut contract, &payer_id(), "global-fees");
+    let fees = contract.drop_fee + 3 * contract.key_fee;
+    assert_eq!(contract.get_fees_collected().0, fees);
+    assert_eq!(spent_without_fees + fees, spent_with_fees);
+}
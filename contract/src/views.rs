@@ -1,4 +1,12 @@
 use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+/// Maximum number of drops returned by a single page of `get_drops_paginated`
+const MAX_DROPS_PER_PAGE: u64 = 100;
+/// Maximum number of drop IDs looked at by a single page of `get_drops_paginated`. Deleted drops leave gaps in the IDs.
+const MAX_DROP_IDS_SCANNED: u128 = 500;
+/// Maximum number of key IDs looked at by a single call to `get_next_unused_keys`. Used and deleted keys are skipped.
+const MAX_KEY_IDS_SCANNED: u64 = 500;
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
@@ -26,18 +34,107 @@ pub struct JsonDrop {
 
     // The drop as a whole can have a config as well
     pub config: Option<DropConfig>,
+    // Best effort human readable versions of the config's start timestamp, throttle and end timestamp (ISO-8601 in UTC)
+    pub start_time_human: Option<String>,
+    pub throttle_human: Option<String>,
+    pub end_time_human: Option<String>,
+
+    // Which claim methods the keys in this drop are allowed to call
+    pub claim_permission: ClaimPermissions,
+
+    // Where the drop is in its lifecycle
+    pub status: DropStatus,
+    // Stored state of the drop's setup. Includes the deposit and block for pending FT drops.
+    pub state: DropState,
+
+    // Cached metadata for the token if this is an FT drop
+    pub ft_metadata: Option<FTMetadataInfo>,
 
     // Metadata for the drop
     pub metadata: Option<DropMetadata>,
 
-    // How many claims
+    // How many uses are registered. For FC and Simple drops, this equals the remaining uses across all keys.
     pub registered_uses: u64,
 
     // Ensure this drop can only be used when the function has the required gas to attach
     pub required_gas: Gas,
 
+    // How many claims have received the early bonus so far
+    pub early_claims: u64,
+
+    // How many claims have been confirmed successful and how many are still resolving
+    pub total_claims: u64,
+    pub pending_claims: u64,
+
     // Keep track of the next nonce to give out to a key
     pub next_key_id: u64,
+    // How many keys are currently part of the drop
+    pub num_keys: u64,
+
+    // Campaign the drop is part of
+    pub campaign_id: Option<CampaignId>,
+
+    // Contract that badges are minted on if the drop mints them
+    pub badge_minter: Option<AccountId>,
+
+    // Whether the drop calls an FC receiver the contract owner hasn't allowed. Wallets should warn before claiming.
+    pub unverified: bool,
+}
+
+/// Struct to return in views to query for a campaign along with stats aggregated across its drops
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonCampaign {
+    pub campaign_id: CampaignId,
+    pub owner_id: AccountId,
+    pub name: String,
+    pub metadata: Option<String>,
+    pub drop_ids: Vec<DropId>,
+
+    // Number of keys left across all drops
+    pub total_keys: u64,
+    // Number of claims confirmed successful and still resolving across all drops
+    pub total_claims: u64,
+    pub pending_claims: u64,
+    // $NEAR reserved to pay out the deposit per use for every use left across all drops
+    pub near_locked: U128,
+}
+
+/// Everything a wallet needs to render the claim page for a key and its success screen
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonClaimPage {
+    pub drop_id: DropId,
+    pub owner_id: AccountId,
+    // Where the drop is in its lifecycle and whether the key can be used right now
+    pub status: DropStatus,
+    pub can_claim: bool,
+    // How many uses the key has left
+    pub remaining_uses: u64,
+    // Which claim methods the key can call
+    pub claim_permission: ClaimPermissions,
+    // Metadata for the drop. None if it wasn't asked for.
+    pub metadata: Option<DropMetadata>,
+    // Abstract or first 256 bytes of the metadata. Always included.
+    pub metadata_summary: Option<String>,
+    // What to show once the key is claimed
+    pub post_claim: Option<PostClaimInfo>,
+    // Leading zero bits the claim's pow_nonce must produce. 0 if no proof of work is needed.
+    pub pow_difficulty: u8,
+    // Whether the drop calls an FC receiver the contract owner hasn't allowed
+    pub unverified: bool,
+}
+
+/// A drop's metadata, possibly cut short so that large blobs don't have to be read in full
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonDropMetadata {
+    // Metadata, or the start of it if it was longer than the bytes asked for. None if the drop has no metadata.
+    pub metadata: Option<DropMetadata>,
+    // Size of the full metadata in bytes
+    pub total_bytes: u64,
+    // Whether `metadata` was cut short
+    pub truncated: bool,
 }
 
 /// Keep track of nft data
@@ -48,6 +145,14 @@ pub struct JsonNFTData {
     pub contract_id: AccountId,
     pub longest_token_id: String,
     pub storage_for_longest: U128,
+    // Number of token IDs handed over by each claim
+    #[serde(default = "one_token_per_use")]
+    pub tokens_per_use: u64,
+}
+
+/// Drops exported before packs existed hand over a single token ID per use
+fn one_token_per_use() -> u64 {
+    1
 }
 
 /// Struct to return in views to query for specific data related to an access key.
@@ -57,7 +162,65 @@ pub struct JsonKeyInfo {
     // Drop ID for the specific drop
     pub drop_id: DropId,
     pub pk: PublicKey,
-    pub key_info: KeyInfo,
+    pub key_info: JsonKeyUsage,
+    // Every use of the key with its label and methods. The next use is the one at `len - remaining_uses`.
+    pub schedule: Vec<JsonUseStep>,
+}
+
+/// Key info returned in views. The last used timestamp is returned as a string of nanoseconds.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonKeyUsage {
+    // How many uses this key has left. Once 0 is reached, the key is deleted
+    pub remaining_uses: u64,
+    // When was the last time the key was used. 0 if it has never been used and isn't throttled from addition.
+    pub last_used: U64,
+    // How much allowance does the key have left. When the key is deleted, this is refunded to the funder's balance.
+    pub allowance: u128,
+    // Nonce for the current key.
+    pub key_id: u64,
+    // Allowance spent by the key's claims so far. Defaults to 0 for backups exported before it was tracked.
+    #[serde(default)]
+    pub allowance_used: u128,
+    // Label set by the funder when adding the key. Only used for analytics.
+    #[serde(default)]
+    pub context_tag: Option<String>,
+    // Overrides of the drop config given to the key when the drop was created
+    #[serde(default)]
+    pub config: Option<KeyConfig>,
+    // sha256 hash of the password needed to claim the key, if it has one
+    #[serde(default)]
+    pub password_hash: Option<Base64VecU8>,
+}
+
+impl From<KeyInfo> for JsonKeyUsage {
+    fn from(key_info: KeyInfo) -> Self {
+        JsonKeyUsage {
+            remaining_uses: key_info.remaining_uses,
+            last_used: U64(key_info.last_used),
+            allowance: key_info.allowance,
+            key_id: key_info.key_id,
+            allowance_used: key_info.allowance_used,
+            context_tag: key_info.context_tag,
+            config: key_info.config,
+            password_hash: key_info.password_hash.map(Base64VecU8),
+        }
+    }
+}
+
+impl From<JsonKeyUsage> for KeyInfo {
+    fn from(key_usage: JsonKeyUsage) -> Self {
+        KeyInfo {
+            remaining_uses: key_usage.remaining_uses,
+            last_used: key_usage.last_used.0,
+            allowance: key_usage.allowance,
+            key_id: key_usage.key_id,
+            allowance_used: key_usage.allowance_used,
+            context_tag: key_usage.context_tag,
+            config: key_usage.config,
+            password_hash: key_usage.password_hash.map(|hash| hash.0),
+        }
+    }
 }
 
 #[near_bindgen]
@@ -84,7 +247,9 @@ impl Keypom {
         U128(self.drop_id_for_pk.len() as u128)
     }
 
-    /// Paginate through all active keys on the contract and return a vector of key info.
+    /// Paginate through all active keys on the contract and return a vector of key info. Keys are returned in the
+    /// order they're stored which stays the same between calls. Removing a key moves the last key into its slot so a
+    /// snapshot taken while keys are being claimed or deleted can miss keys that were moved behind the cursor.
     pub fn get_keys(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<JsonKeyInfo> {
         //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
         let start = u128::from(from_index.unwrap_or(U128(0)));
@@ -97,30 +262,130 @@ impl Keypom {
             //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
             .take(limit.unwrap_or(50) as usize)
             //we'll map the public key which are strings into Drops
-            .map(|pk| self.get_key_information(pk.clone()))
+            .filter_map(|pk| self.get_key_information(pk.clone()))
             //since we turned the keys into an iterator, we need to turn it back into a vector to return
             .collect()
     }
 
-    /// Returns the JsonKeyInfo corresponding to a specific key
-    pub fn get_key_information(&self, key: PublicKey) -> JsonKeyInfo {
-        let drop_id = self
-            .drop_id_for_pk
-            .get(&key)
-            .expect("no drop ID found for key");
+    /// Returns the JsonKeyInfo corresponding to a specific key. Returns None if the key isn't part of a drop.
+    pub fn get_key_information(&self, key: PublicKey) -> Option<JsonKeyInfo> {
+        // The key may still point to a drop that's since been removed so none of these lookups panic
+        let drop_id = self.drop_id_for_pk.get(&key)?;
+        let drop = self.drop_for_id.get(&drop_id)?;
+        let key_info = drop.pks.get(&key)?;
+
+        Some(JsonKeyInfo {
+            schedule: use_schedule(&drop, &key_info),
+            key_info: key_info.into(),
+            drop_id,
+            pk: key,
+        })
+    }
+
+    /// Returns whether a key can currently be used to claim. This mirrors the checks done when claiming
+    /// (registered assets, start and end timestamps, throttle and max total claims) except for the attached GAS.
+    pub fn can_key_claim(&self, key: PublicKey) -> bool {
+        let drop_id = match self.drop_id_for_pk.get(&key) {
+            Some(drop_id) => drop_id,
+            None => return false,
+        };
         let drop = self
             .drop_for_id
             .get(&drop_id)
             .expect("no drop found for drop ID");
         let key_info = drop.pks.get(&key).unwrap();
+        let current_timestamp = env::block_timestamp();
+
+        // There must be a use registered for the claim. For FT and NFT drops, this means an asset must be registered.
+        if drop.registered_uses < 1 {
+            return false;
+        }
 
-        JsonKeyInfo {
-            key_info,
+        // Keys can have their own timestamps even if the drop has no config
+        if !key_has_started(&drop, &key_info, current_timestamp)
+            || key_has_ended(&drop, &key_info, current_timestamp)
+        {
+            return false;
+        }
+
+        let config = match &drop.config {
+            Some(config) => config,
+            None => return true,
+        };
+
+        if let Some(interval) = config.throttle_timestamp {
+            if current_timestamp - key_info.last_used < interval {
+                return false;
+            }
+        }
+
+        !drop_reached_max_claims(&drop)
+    }
+
+    /*
+        Returns the data for a key's claim page. None if the key isn't part of a drop (i.e it was fully claimed).
+        The full metadata is only read if `include_metadata` isn't false. The summary is always returned.
+    */
+    pub fn get_claim_page_data(
+        &self,
+        key: PublicKey,
+        include_metadata: Option<bool>,
+    ) -> Option<JsonClaimPage> {
+        let drop_id = self.drop_id_for_pk.get(&key)?;
+        let drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("no drop found for drop ID");
+        let key_info = drop.pks.get(&key).unwrap();
+
+        Some(JsonClaimPage {
             drop_id,
-            pk: key,
+            owner_id: drop.owner_id.clone(),
+            status: drop_status(&drop),
+            can_claim: self.can_key_claim(key),
+            remaining_uses: key_info.remaining_uses,
+            claim_permission: drop.claim_permission.clone(),
+            metadata: if include_metadata.unwrap_or(true) {
+                drop.metadata.get()
+            } else {
+                None
+            },
+            metadata_summary: drop.metadata_summary,
+            unverified: self.internal_is_drop_unverified(&drop.drop_type),
+            pow_difficulty: pow_difficulty(&drop.config),
+            post_claim: drop.config.and_then(|c| c.post_claim),
+        })
+    }
+
+    /// Returns a drop's metadata cut down to at most `max_bytes` (without splitting a character) along with its full size
+    pub fn get_drop_metadata(&self, drop_id: DropId, max_bytes: Option<u32>) -> JsonDropMetadata {
+        let drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("no drop found for drop ID");
+        let metadata = drop.metadata.get();
+        let total_bytes = metadata.as_ref().map_or(0, |m| m.len() as u64);
+        let metadata = match (metadata, max_bytes) {
+            (Some(m), Some(max_bytes)) => Some(truncate_utf8(&m, max_bytes as usize).to_string()),
+            (metadata, _) => metadata,
+        };
+
+        JsonDropMetadata {
+            truncated: metadata.as_ref().map_or(0, |m| m.len() as u64) < total_bytes,
+            metadata,
+            total_bytes,
         }
     }
 
+    /// Returns where a drop is in its lifecycle
+    pub fn get_drop_status(&self, drop_id: DropId) -> DropStatus {
+        let drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("no drop found for drop ID");
+        drop_status(&drop)
+    }
+
     /// Returns the JsonDrop corresponding to a drop ID. If a key is specified, it will return the drop info for that key.
     pub fn get_drop_information(
         &self,
@@ -145,6 +410,18 @@ impl Keypom {
             .get(&drop_id)
             .expect("no drop found for drop ID");
 
+        let status = drop_status(&drop);
+        let badge_minter = if mints_badge(&drop.config) {
+            self.badge_minter.clone()
+        } else {
+            None
+        };
+        let unverified = self.internal_is_drop_unverified(&drop.drop_type);
+        let ft_metadata = match &drop.drop_type {
+            DropType::FungibleToken(data) => self.ft_metadata.get(&data.contract_id),
+            _ => None,
+        };
+
         let drop_type: JsonDropType = match drop.drop_type {
             DropType::FunctionCall(data) => JsonDropType::FunctionCall(data),
             DropType::NonFungibleToken(data) => JsonDropType::NonFungibleToken(JsonNFTData {
@@ -152,22 +429,83 @@ impl Keypom {
                 sender_id: data.sender_id,
                 longest_token_id: data.longest_token_id,
                 storage_for_longest: U128(data.storage_for_longest),
+                tokens_per_use: data.tokens_per_use,
             }),
             DropType::FungibleToken(data) => JsonDropType::FungibleToken(data),
             _simple => JsonDropType::Simple,
         };
 
+        let start_time_human = drop
+            .config
+            .as_ref()
+            .and_then(|c| c.start_timestamp)
+            .map(format_iso_timestamp);
+        let throttle_human = drop
+            .config
+            .as_ref()
+            .and_then(|c| c.throttle_timestamp)
+            .map(format_iso_duration);
+        let end_time_human = drop
+            .config
+            .as_ref()
+            .and_then(|c| c.end_timestamp)
+            .map(format_iso_timestamp);
+
         JsonDrop {
             drop_id,
             owner_id: drop.owner_id,
             deposit_per_use: U128(drop.deposit_per_use),
             drop_type,
             config: drop.config,
+            start_time_human,
+            throttle_human,
+            end_time_human,
+            claim_permission: drop.claim_permission,
+            status,
+            state: drop.state,
+            ft_metadata,
             registered_uses: drop.registered_uses,
             required_gas: drop.required_gas,
+            early_claims: drop.early_claims,
+            total_claims: drop.total_claims,
+            pending_claims: drop.pending_claims,
             metadata: drop.metadata.get(),
             next_key_id: drop.next_key_id,
+            num_keys: drop.pks.len(),
+            campaign_id: self.campaign_for_drop.get(&drop_id),
+            badge_minter,
+            unverified,
+        }
+    }
+
+    /// Returns a campaign along with stats aggregated across its drops. This iterates through every key in the campaign.
+    pub fn get_campaign(&self, campaign_id: CampaignId) -> Option<JsonCampaign> {
+        let campaign = self.campaigns.get(&campaign_id)?;
+
+        let mut total_keys = 0;
+        let mut total_claims = 0;
+        let mut pending_claims = 0;
+        let mut near_locked = 0;
+        for drop_id in &campaign.drop_ids {
+            let drop = self.drop_for_id.get(drop_id).expect("no drop found");
+            total_keys += drop.pks.len();
+            total_claims += drop.total_claims;
+            pending_claims += drop.pending_claims;
+            let remaining_uses: u64 = drop.pks.values().map(|k| k.remaining_uses).sum();
+            near_locked += drop.deposit_per_use * remaining_uses as u128;
         }
+
+        Some(JsonCampaign {
+            campaign_id,
+            owner_id: campaign.owner_id,
+            name: campaign.name,
+            metadata: campaign.metadata,
+            drop_ids: campaign.drop_ids,
+            total_keys,
+            total_claims,
+            pending_claims,
+            near_locked: U128(near_locked),
+        })
     }
 
     /// Returns the total supply of active keys for a given drop
@@ -201,11 +539,81 @@ impl Keypom {
             //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
             .take(limit.unwrap_or(50) as usize)
             //we'll map the public key which are strings into Drops
-            .map(|pk| self.get_key_information(pk.clone()))
+            .filter_map(|pk| self.get_key_information(pk.clone()))
             //since we turned the keys into an iterator, we need to turn it back into a vector to return
             .collect()
     }
 
+    /*
+        Paginate through keys in a drop that haven't been used yet. Keys are always returned in
+        ascending key ID order since key IDs are handed out sequentially as keys are added. Along with
+        the keys, returns the key ID to pass in as `after_key_id` for the next batch, or None once every
+        key has been looked at. If `after_key_id` is not specified, start from the very first key in the
+        drop. Only a bounded number of key IDs are looked at per call so a batch can come back short or
+        even empty while there are still unused keys left.
+    */
+    pub fn get_next_unused_keys(
+        &self,
+        drop_id: DropId,
+        after_key_id: Option<u64>,
+        limit: u64,
+    ) -> (Vec<JsonKeyInfo>, Option<u64>) {
+        require!(limit > 0, "limit must be greater than 0");
+        let drop = self
+            .drop_for_id
+            .get(&drop_id)
+            .expect("No drop for given ID");
+        let mut key_id = after_key_id.map(|id| id + 1).unwrap_or(0);
+        let last_key_id = drop
+            .next_key_id
+            .min(key_id.saturating_add(MAX_KEY_IDS_SCANNED));
+
+        let mut unused_keys = vec![];
+        while key_id < last_key_id && (unused_keys.len() as u64) < limit {
+            // Keys that have been deleted will no longer be in the index
+            if let Some(pk) = drop.pk_for_key_id.get(&key_id) {
+                let key_info = drop.pks.get(&pk).unwrap();
+
+                // Last used can be seeded when the key is added so only check that no uses have been consumed
+                if key_info.remaining_uses == key_uses_per_key(&drop.config, &key_info) {
+                    unused_keys.push(JsonKeyInfo {
+                        drop_id,
+                        pk,
+                        schedule: use_schedule(&drop, &key_info),
+                        key_info: key_info.into(),
+                    });
+                }
+            }
+            key_id += 1;
+        }
+
+        // At least one key ID is always looked at so the last one can be resumed from
+        let next_after_key_id = if key_id < drop.next_key_id {
+            Some(key_id - 1)
+        } else {
+            None
+        };
+        (unused_keys, next_after_key_id)
+    }
+
+    /// Returns whether the root is known to accept `create_account` calls from this contract.
+    /// Frontends should check this before offering account creation for a drop.
+    pub fn can_create_account_under(&self, root: AccountId) -> bool {
+        self.known_roots.contains(&root)
+    }
+
+    /// Paginate through the roots known to accept `create_account` calls from this contract
+    pub fn get_known_roots(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<AccountId> {
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.known_roots
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .collect()
+    }
+
     /// Returns the total supply of active drops for a given owner
     pub fn get_drop_supply_for_owner(&self, account_id: AccountId) -> u64 {
         //get the set of drops for the passed in owner
@@ -220,7 +628,8 @@ impl Keypom {
         }
     }
 
-    /// Return a vector of drop information for a owner
+    /// Return a vector of drop information for a owner. Drops are returned in the order they were created so that
+    /// pages stay in place when one of the owner's drops is removed.
     pub fn get_drops_for_owner(
         &self,
         account_id: AccountId,
@@ -235,7 +644,11 @@ impl Keypom {
             // Where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
             let start = u128::from(from_index.unwrap_or(U128(0)));
 
-            ids.iter()
+            // The set's order changes whenever a drop is removed so the IDs are sorted first
+            let mut ids = ids.to_vec();
+            ids.sort_unstable();
+
+            ids.into_iter()
                 // Skip to the index we specified in the start variable
                 .skip(start as usize)
                 // Take the first "limit" elements in the vector. If we didn't specify a limit, use 50
@@ -249,6 +662,72 @@ impl Keypom {
         }
     }
 
+    /*
+        Walk every drop on the contract in drop ID order so indexers can rebuild their state without replaying history.
+        Returns up to `limit` drops starting at `from_drop_id` along with the drop ID to pass in for the next page, or
+        None once every drop has been returned. IDs of deleted drops are skipped. Only a bounded number of IDs are
+        looked at per call so a page can come back short or even empty while there are still drops left.
+    */
+    pub fn get_drops_paginated(
+        &self,
+        from_drop_id: Option<U128>,
+        limit: u64,
+    ) -> (Vec<JsonDrop>, Option<U128>) {
+        let limit = limit.min(MAX_DROPS_PER_PAGE) as usize;
+        let mut drop_id = from_drop_id.map_or(0, |id| id.0);
+        let last_drop_id = self
+            .next_drop_id
+            .min(drop_id.saturating_add(MAX_DROP_IDS_SCANNED));
+
+        let mut drops = vec![];
+        while drop_id < last_drop_id && drops.len() < limit {
+            if self.drop_for_id.contains_key(&drop_id) {
+                drops.push(self.get_drop_information(Some(drop_id), None));
+            }
+            drop_id += 1;
+        }
+
+        let next_drop_id = if drop_id < self.next_drop_id {
+            Some(U128(drop_id))
+        } else {
+            None
+        };
+        (drops, next_drop_id)
+    }
+
+    /// Paginate through the listed FT and NFT drops distributing an asset contract's tokens
+    pub fn get_drops_for_asset_contract(
+        &self,
+        contract_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<JsonDrop> {
+        let drop_ids = match self.drop_ids_for_asset.get(&contract_id) {
+            Some(drop_ids) => drop_ids,
+            None => return vec![],
+        };
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        drop_ids
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|id| self.get_drop_information(Some(id), None))
+            .collect()
+    }
+
+    /// Return how many identifier hashes are in a drop's allowlist
+    pub fn get_allowed_hash_supply_for_drop(&self, drop_id: DropId) -> u64 {
+        let drop = self.drop_for_id.get(&drop_id).expect("no drop found");
+        drop.allowed_hashes.len()
+    }
+
+    /// Check whether the sha256 hash of an identifier is in a drop's allowlist
+    pub fn is_hash_allowed(&self, drop_id: DropId, hash: Base64VecU8) -> bool {
+        let drop = self.drop_for_id.get(&drop_id).expect("no drop found");
+        drop.allowed_hashes.contains(&hash.0)
+    }
+
     /// Return the total supply of token IDs for a given drop
     pub fn get_nft_supply_for_drop(&self, drop_id: DropId) -> u64 {
         let drop = self.drop_for_id.get(&drop_id).expect("no drop found");
@@ -287,6 +766,30 @@ impl Keypom {
         }
     }
 
+    /// Returns whether a drop exists without loading it
+    pub fn drop_exists(&self, drop_id: DropId) -> bool {
+        self.drop_for_id.contains_key(&drop_id)
+    }
+
+    /// Returns whether a key is part of a drop. Only the key's drop ID is read.
+    pub fn key_exists(&self, key: PublicKey) -> bool {
+        self.drop_id_for_pk.get(&key).is_some()
+    }
+
+    /*
+        Returns the contract's balance left over after the storage staking requirement and the fees collected.
+        User balances and key reserves aren't subtracted so the headroom is an upper bound on what's actually
+        free. `get_solvency_report` compares the balance against everything the contract owes.
+    */
+    pub fn get_balance_headroom(&self) -> U128 {
+        let storage_staked = Balance::from(env::storage_usage()) * env::storage_byte_cost();
+        U128(
+            env::account_balance()
+                .saturating_sub(storage_staked)
+                .saturating_sub(self.fees_collected),
+        )
+    }
+
     /// Returns the current nonce on the contract
     pub fn get_next_drop_id(&self) -> u128 {
         self.next_drop_id
@@ -302,11 +805,133 @@ impl Keypom {
         U128(self.yocto_per_gas)
     }
 
+    /// Returns the minimum balance a new account must have when created
+    pub fn get_account_creation_cost(&self) -> U128 {
+        U128(self.new_account_base)
+    }
+
+    /// Returns the balance kept on top of the storage staking requirement that outbound transfers can't dip into
+    pub fn get_operational_buffer(&self) -> U128 {
+        U128(self.operational_buffer)
+    }
+
+    /// Returns the contract that mints badges for drops with `mint_badge`, if one has been registered
+    pub fn get_badge_minter(&self) -> Option<AccountId> {
+        self.badge_minter.clone()
+    }
+
+    /// Returns the account that can call the owner methods on the contract
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
     /// Returns the current linkdrop contract
     pub fn get_root_account(&self) -> String {
         self.root_account.to_string()
     }
 
+    /// Returns the outcome of a claim that was processed with the given public key and idempotency key
+    pub fn get_claim_record(&self, key: PublicKey, idempotency_key: String) -> Option<ClaimRecord> {
+        self.claim_records
+            .get(&hash_claim_record(&key, &idempotency_key))
+            // Expired records are treated as if they don't exist since a retry would be executed again
+            .filter(|record| record.expires_at > env::block_timestamp())
+    }
+
+    /// Returns how long claim records are kept before they can be swept
+    pub fn get_claim_record_ttl(&self) -> u64 {
+        self.claim_record_ttl
+    }
+
+    /// Returns how long before their end timestamp keys can be tightened with `tighten_expiring_keys`
+    pub fn get_expiring_key_window(&self) -> u64 {
+        self.expiring_key_window
+    }
+
+    /// Returns the suffixes that FC receivers must end with for drops created by an account. Empty if unrestricted.
+    pub fn get_fc_receiver_restrictions(&self, account_id: AccountId) -> Vec<String> {
+        self.fc_receiver_restrictions
+            .get(&account_id)
+            .unwrap_or_default()
+    }
+
+    /// Returns the reputation of an FC receiver. Receivers that aren't on the list are unknown.
+    pub fn get_fc_receiver_reputation(&self, receiver_id: AccountId) -> FcReceiverReputation {
+        self.internal_fc_receiver_reputation(&receiver_id)
+    }
+
+    /// Paginate through the allowed and denied FC receivers
+    pub fn get_fc_receiver_reputations(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<JsonFcReceiverReputation> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.fc_receiver_reputations
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(receiver_id, reputation)| JsonFcReceiverReputation {
+                receiver_id,
+                reputation,
+            })
+            .collect()
+    }
+
+    /// Returns up to `limit` of the most recent claim promise failures for a drop, newest first
+    pub fn get_recent_failures(&self, drop_id: DropId, limit: Option<u64>) -> Vec<PromiseFailure> {
+        self.recent_failures
+            .get(&drop_id)
+            .unwrap_or_default()
+            .into_iter()
+            .rev()
+            .take(limit.unwrap_or(MAX_RECENT_FAILURES as u64) as usize)
+            .collect()
+    }
+
+    /// Returns the keys of a drop whose access keys haven't been added yet
+    pub fn get_pending_key_activations(&self, drop_id: DropId) -> Vec<PublicKey> {
+        self.pending_key_activations
+            .get(&drop_id)
+            .unwrap_or_default()
+    }
+
+    /// Returns the route used to swap $NEAR into an alternative payout token
+    pub fn get_swap_route(&self, token_id: AccountId) -> Option<SwapRoute> {
+        self.swap_routes.get(&token_id)
+    }
+
+    /// Returns the last time an FT drop's registration on its token contract was refreshed (in nanoseconds). None if it never was.
+    pub fn get_last_ft_refresh(&self, drop_id: DropId) -> Option<U64> {
+        self.last_ft_refresh.get(&drop_id).map(U64)
+    }
+
+    /// Returns the cached metadata for an FT contract. None if no FT drop has been created for it yet
+    pub fn get_ft_metadata(&self, contract_id: AccountId) -> Option<FTMetadataInfo> {
+        self.ft_metadata.get(&contract_id)
+    }
+
+    /// Paginate through the registered partners and their current share of the fees (in basis points)
+    pub fn get_partners(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<PartnerShare> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.partners
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(partner_id, share_bps)| PartnerShare {
+                partner_id,
+                share_bps,
+            })
+            .collect()
+    }
+
+    /// Returns the partner a drop was created through along with the share locked in for it
+    pub fn get_partner_for_drop(&self, drop_id: DropId) -> Option<PartnerShare> {
+        self.partner_for_drop.get(&drop_id)
+    }
+
     /// Returns the current fees associated with an account
     pub fn get_fees_per_user(&self, account_id: AccountId) -> Option<(U128, U128)> {
         // return fees per user as a U128 tuple
@@ -314,4 +939,11 @@ impl Keypom {
             .get(&account_id)
             .map(|fees| (U128(fees.0), U128(fees.1)))
     }
+
+    /// Returns the fees that will actually be charged to an account as (drop fee, key fee).
+    /// This is the account's specific fees if set by the owner, otherwise the global fees.
+    pub fn get_effective_fees(&self, account_id: AccountId) -> (U128, U128) {
+        let fees = self.internal_fees_for_user(&account_id);
+        (U128(fees.0), U128(fees.1))
+    }
 }